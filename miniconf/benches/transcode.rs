@@ -0,0 +1,66 @@
+//! Compare `TreeKey::transcode_all()` with `Transcode::transcode()` in a loop.
+//!
+//! The loop reuses the path buffer to not measure allocation.
+//!
+//! Run with `cargo bench --features derive --bench transcode`.
+
+use std::{hint::black_box, time::Instant};
+
+use miniconf::{Leaf, Packed, Path, Transcode, Tree, TreeKey};
+
+#[derive(Tree, Default)]
+struct Inner {
+    gain: Leaf<f32>,
+    offset: Leaf<i32>,
+    enable: Leaf<bool>,
+}
+
+#[derive(Tree, Default)]
+struct Settings {
+    channel: [[Inner; 4]; 4],
+    mode: Leaf<u8>,
+    pair: (Leaf<u8>, Inner),
+}
+
+const ITERATIONS: u32 = 10_000;
+
+fn bench(name: &str, mut f: impl FnMut() -> usize) {
+    // Warm up
+    for _ in 0..ITERATIONS / 10 {
+        black_box(f());
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    println!("{name}: {:?}/iter", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    let keys: Vec<Packed> = Settings::nodes::<Packed, 4>()
+        .map(|p| p.unwrap().0)
+        .collect();
+    let mut buf = vec![0; 2048];
+
+    let mut path = Path::<String, '/'>::default();
+    bench("transcode loop", || {
+        let mut len = 0;
+        for (i, key) in keys.iter().enumerate() {
+            path.0.clear();
+            path.transcode::<Settings, _>(black_box(*key)).unwrap();
+            if i > 0 {
+                buf[len] = b'\n';
+                len += 1;
+            }
+            buf[len..len + path.len()].copy_from_slice(path.as_bytes());
+            len += path.len();
+        }
+        len
+    });
+
+    bench("transcode_all", || {
+        Settings::transcode_all::<'/'>(black_box(&keys), '\n', &mut buf)
+            .unwrap()
+            .0
+    });
+}
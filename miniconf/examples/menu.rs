@@ -4,7 +4,7 @@ use core::marker::PhantomData;
 use ::postcard::{de_flavors::Slice as DeSlice, ser_flavors::Slice as SerSlice};
 use anyhow::{Context, Result};
 use embedded_io::Write;
-use embedded_io_async::Write as AWrite;
+use embedded_io_async::{Read as ARead, Write as AWrite};
 use tokio::io::AsyncBufReadExt;
 
 use miniconf::{
@@ -33,6 +33,8 @@ pub enum Error<I> {
     Fmt(core::fmt::Error),
     Traversal(miniconf::Traversal),
     Serialize(usize, serde_json_core::ser::Error),
+    Deserialize(usize, serde_json_core::de::Error),
+    BufferFull,
     Io(I),
 }
 
@@ -52,6 +54,16 @@ impl<I> From<miniconf::Error<serde_json_core::ser::Error>> for Error<I> {
     }
 }
 
+impl<I> From<miniconf::Error<serde_json_core::de::Error>> for Error<I> {
+    fn from(value: miniconf::Error<serde_json_core::de::Error>) -> Self {
+        match value {
+            miniconf::Error::Inner(depth, e) => Self::Deserialize(depth, e),
+            miniconf::Error::Traversal(e) => Self::Traversal(e),
+            miniconf::Error::Finalization(e) => Self::Deserialize(0, e),
+        }
+    }
+}
+
 impl<I> From<usize> for Error<I> {
     fn from(value: usize) -> Self {
         Traversal::TooLong(value).into()
@@ -139,7 +151,60 @@ where
         instance: &mut M,
         buf: &[u8],
     ) -> Result<usize, miniconf::Error<serde_json_core::de::Error>> {
-        json::set_by_key(instance, self.key, buf)
+        json::set_by_key(instance, self.key, buf).map(|(len, _node)| len)
+    }
+
+    /// Read a value in chunks from `read` into `buf` until EOF and then set it.
+    ///
+    /// `on_progress` is called with the total number of bytes read after each chunk.
+    /// `buf` must be larger than the value.
+    pub async fn set_stream<R>(
+        &mut self,
+        instance: &mut M,
+        mut read: R,
+        buf: &mut [u8],
+        mut on_progress: impl FnMut(usize),
+    ) -> Result<usize, Error<R::Error>>
+    where
+        R: ARead,
+    {
+        let mut len = 0;
+        loop {
+            let chunk = &mut buf[len..];
+            if chunk.is_empty() {
+                return Err(Error::BufferFull);
+            }
+            let n = read.read(chunk).await.map_err(Error::Io)?;
+            if n == 0 {
+                break;
+            }
+            len += n;
+            on_progress(len);
+        }
+        self.set(instance, &buf[..len])?;
+        Ok(len)
+    }
+
+    /// Edit the leaf at `path` (relative to the current key) in place.
+    ///
+    /// The current value is serialized into `buf` and handed to `editor`
+    /// (e.g. [`external_editor`]) which may modify it. The edited value is then set.
+    /// If it fails to deserialize, an error is returned and the leaf is unchanged.
+    pub fn edit<E>(
+        &self,
+        instance: &mut M,
+        path: &str,
+        buf: &mut [u8],
+        editor: E,
+    ) -> Result<usize, Error<std::io::Error>>
+    where
+        E: FnOnce(&mut Vec<u8>) -> std::io::Result<()>,
+    {
+        let (mut menu, _node) = self.push(path)?;
+        let len = menu.get(instance, buf)?;
+        let mut value = buf[..len].to_vec();
+        editor(&mut value).map_err(Error::Io)?;
+        Ok(menu.set(instance, &value)?)
     }
 
     pub fn reset(
@@ -208,6 +273,10 @@ where
             let path_len = rl - sl.len();
             awrite(&mut write, &rest[root_len..path_len]).await?;
             awrite(&mut write, ": ".as_bytes()).await?;
+            if node.is_secret() {
+                awrite(&mut write, "\"***\" [secret]\n".as_bytes()).await?;
+                continue;
+            }
             awrite(&mut write, val).await?;
             let def = match json::get_by_key(&def, keys, &mut buf[..]) {
                 Err(miniconf::Error::Traversal(Traversal::TooShort(_depth))) => {
@@ -228,6 +297,118 @@ where
         Ok(())
     }
 
+    /// Describe the immediate children of the current node.
+    ///
+    /// Leaves are listed with their type, access, and default as JSON (see `json::describe()`),
+    /// internal nodes with a trailing separator. A leaf describes itself.
+    /// The first line of the node documentation (see `TreeKey::doc()`) is appended.
+    pub async fn help<W>(
+        &self,
+        instance: &M,
+        mut write: W,
+        buf: &mut [u8],
+    ) -> Result<(), Error<W::Error>>
+    where
+        W: AWrite,
+    {
+        let (path, node) = M::transcode::<Path<String, SEPARATOR>, _>(self.key)?;
+        if node.is_leaf() {
+            let len = json::describe(instance, &path, buf)?;
+            awrite(&mut write, path.as_bytes()).await?;
+            awrite(&mut write, ": ".as_bytes()).await?;
+            awrite(&mut write, &buf[..len]).await?;
+            Self::write_doc(&mut write, M::doc(self.key)?).await?;
+            awrite(&mut write, "\n".as_bytes()).await?;
+            return Ok(());
+        }
+        awrite(&mut write, path.as_bytes()).await?;
+        awrite(&mut write, ">\n".as_bytes()).await?;
+        let depth = node.depth();
+        let mut last = None;
+        for idx in M::nodes::<Indices<[usize; D]>, D>().root(self.key)? {
+            let (idx, _node) = idx?;
+            // Skip the remaining leaves below the previous child
+            if last == Some(idx[depth]) {
+                continue;
+            }
+            last = Some(idx[depth]);
+            let (path, node) = M::transcode::<Path<String, SEPARATOR>, _>(&idx[..=depth])?;
+            let name = path.rsplit(SEPARATOR).next().unwrap_or_default();
+            awrite(&mut write, "  ".as_bytes()).await?;
+            awrite(&mut write, name.as_bytes()).await?;
+            if node.is_leaf() {
+                let len = json::describe(instance, &path, buf)?;
+                awrite(&mut write, ": ".as_bytes()).await?;
+                awrite(&mut write, &buf[..len]).await?;
+            } else {
+                awrite(&mut write, SEPARATOR.encode_utf8(&mut [0; 4]).as_bytes()).await?;
+            }
+            Self::write_doc(&mut write, M::doc(&idx[..=depth])?).await?;
+            awrite(&mut write, "\n".as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_doc<W>(mut write: W, doc: Option<&str>) -> Result<(), Error<W::Error>>
+    where
+        W: AWrite,
+    {
+        if let Some(line) = doc.and_then(|doc| doc.lines().next()) {
+            awrite(&mut write, "  // ".as_bytes()).await?;
+            awrite(&mut write, line.as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    /// Poll the leaves at `paths` (relative to the current key) and write those
+    /// whose value changed since the previous poll.
+    ///
+    /// All leaves are written on the first poll.
+    /// `tick` is awaited after each poll. Watching ends once it returns `false`.
+    pub async fn watch<'a, W, T, F>(
+        &self,
+        instance: impl Fn() -> &'a M,
+        paths: &[&str],
+        mut write: W,
+        buf: &mut [u8],
+        mut tick: T,
+    ) -> Result<(), Error<W::Error>>
+    where
+        M: 'a,
+        W: AWrite,
+        T: FnMut() -> F,
+        F: core::future::Future<Output = bool>,
+    {
+        let keys = paths
+            .iter()
+            .map(|path| Ok(self.push(path)?.0.key))
+            .collect::<Result<Vec<_>, Traversal>>()?;
+        let mut checks = vec![None; keys.len()];
+        loop {
+            let instance = instance();
+            for ((path, keys), check) in paths.iter().zip(&keys).zip(checks.iter_mut()) {
+                let val = match json::get_by_key(instance, *keys, &mut buf[..]) {
+                    Err(miniconf::Error::Traversal(Traversal::Absent(_depth))) => {
+                        "absent".as_bytes()
+                    }
+                    ret => &buf[..ret?],
+                };
+                let new = Some(yafnv::fnv1a::<u32>(val));
+                if *check != new {
+                    *check = new;
+                    awrite(&mut write, path.as_bytes()).await?;
+                    awrite(&mut write, ": ".as_bytes()).await?;
+                    awrite(&mut write, val).await?;
+                    awrite(&mut write, "\n".as_bytes()).await?;
+                }
+            }
+            if !tick().await {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     async fn handle_cmd(
         &mut self,
         line: &str,
@@ -257,11 +438,20 @@ where
                 .set(instance, args.next().context("value")?.as_bytes())
                 .map_err(anyhow::Error::msg)
                 .and(Ok("".to_owned()))?,
+            "help" => self
+                .help(instance, &mut stdout, buf)
+                .await
+                .map_err(|err| anyhow::Error::msg(format!("{err:?}")))
+                .and(Ok("".to_owned()))?,
             "dump" => self
                 .dump(instance, &mut stdout, buf)
                 .await
                 .map_err(|err| anyhow::Error::msg(format!("{err:?}")))
                 .and(Ok("".to_owned()))?,
+            "edit" => self
+                .edit(instance, args.next().unwrap_or(""), buf, external_editor)
+                .map_err(|err| anyhow::Error::msg(format!("{err:?}")))
+                .and(Ok("".to_owned()))?,
             "reset" => self
                 .reset(instance, buf)
                 .map_err(anyhow::Error::msg)
@@ -271,6 +461,29 @@ where
     }
 }
 
+/// Edit `value` using the editor in `$EDITOR` (default `vi`) through a temporary file.
+///
+/// The file is created exclusively with a random name and removed afterwards.
+/// An unsuccessful editor exit status is returned as an error and `value` is unchanged.
+pub fn external_editor(value: &mut Vec<u8>) -> std::io::Result<()> {
+    let file = tempfile::Builder::new()
+        .prefix("miniconf-")
+        .suffix(".json")
+        .tempfile()?;
+    std::fs::write(file.path(), &value)?;
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_owned());
+    let status = std::process::Command::new(editor)
+        .arg(file.path())
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("editor failed: {status}")));
+    }
+    *value = std::fs::read(file.path())?;
+    // Editors commonly append a newline
+    value.truncate(value.trim_ascii_end().len());
+    Ok(())
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
     let mut buf = vec![0; 1024];
@@ -305,7 +518,7 @@ mod test {
         s.enable();
 
         let mut stdout = embedded_io_adapters::tokio_1::FromTokio::new(tokio::io::stdout());
-        let mut menu = Menu::default();
+        let mut menu = Menu::<_, 4>::default();
 
         menu.enter("/option_tree2").unwrap();
         menu.enter("/b").unwrap();
@@ -316,7 +529,8 @@ mod test {
             .0
             .set(&mut s, b"9")
             .unwrap();
-        let paths: Vec<heapless::String<128>> = menu.list().unwrap().collect().unwrap();
+        let paths: Vec<heapless::String<128>> =
+            menu.list().unwrap().collect::<Result<_, _>>().unwrap();
         stdout
             .write_all(format!("{:?}\n", paths).as_bytes())
             .await
@@ -337,4 +551,166 @@ mod test {
             .unwrap();
         menu.dump(&s, &mut stdout, &mut buf).await.unwrap();
     }
+
+    /// Reader yielding at most `N` bytes per `read()`
+    struct Chunked<'a, const N: usize>(&'a [u8]);
+
+    impl<const N: usize> embedded_io_async::ErrorType for Chunked<'_, N> {
+        type Error = core::convert::Infallible;
+    }
+
+    impl<const N: usize> ARead for Chunked<'_, N> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(N).min(self.0.len());
+            let (chunk, rest) = self.0.split_at(n);
+            buf[..n].copy_from_slice(chunk);
+            self.0 = rest;
+            Ok(n)
+        }
+    }
+
+    #[tokio::test]
+    async fn stream() {
+        let mut buf = vec![0; 1024];
+        let mut s = common::Settings::default();
+        let mut menu = Menu::<_, 4>::default();
+        menu.enter("/array").unwrap();
+
+        let value = b"[123456789,-987654321]";
+        let mut progress = vec![];
+        let len = menu
+            .set_stream(&mut s, Chunked::<4>(value), &mut buf, |n| progress.push(n))
+            .await
+            .unwrap();
+        assert_eq!(len, value.len());
+        assert_eq!(progress, [4, 8, 12, 16, 20, 22]);
+
+        let len = menu.get(&s, &mut buf).unwrap();
+        assert_eq!(&buf[..len], value);
+
+        assert_eq!(
+            menu.set_stream(&mut s, Chunked::<4>(value), &mut buf[..8], |_| ())
+                .await,
+            Err(Error::BufferFull)
+        );
+    }
+
+    #[test]
+    fn edit() {
+        let mut buf = vec![0; 1024];
+        let mut s = common::Settings::default();
+        let mut menu = Menu::<_, 4>::default();
+        menu.enter("/array_tree").unwrap();
+
+        let len = menu
+            .edit(&mut s, "/1", &mut buf, |value| {
+                assert_eq!(value, b"0");
+                *value = b"42".to_vec();
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(len, 2);
+        assert_eq!(json::get(&s, "/array_tree/1", &mut buf), Ok(2));
+        assert_eq!(&buf[..2], b"42");
+
+        assert!(matches!(
+            menu.edit(&mut s, "/1", &mut buf, |value| {
+                *value = b"foo".to_vec();
+                Ok(())
+            }),
+            Err(Error::Deserialize(..))
+        ));
+        assert!(matches!(
+            menu.edit(&mut s, "/1", &mut buf, |_value| Err(std::io::Error::other(
+                "aborted"
+            ))),
+            Err(Error::Io(_))
+        ));
+        assert_eq!(json::get(&s, "/array_tree/1", &mut buf), Ok(2));
+        assert_eq!(&buf[..2], b"42");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn external_editor_status() {
+        // No other test uses `EDITOR`
+        std::env::set_var("EDITOR", "false");
+        let mut value = b"1".to_vec();
+        assert!(external_editor(&mut value).is_err());
+        std::env::set_var("EDITOR", "true");
+        external_editor(&mut value).unwrap();
+        assert_eq!(value, b"1");
+    }
+
+    /// Writer collecting into a `Vec`
+    #[derive(Default)]
+    struct Collect(Vec<u8>);
+
+    impl embedded_io_async::ErrorType for Collect {
+        type Error = core::convert::Infallible;
+    }
+
+    impl AWrite for Collect {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn help() {
+        let mut buf = vec![0; 1024];
+        let mut s = common::Settings::default();
+        s.enable();
+        let mut menu = Menu::<_, 4>::default();
+        menu.enter("/tuple_tree").unwrap();
+        let mut out = Collect::default();
+        menu.help(&s, &mut out, &mut buf).await.unwrap();
+        assert_eq!(
+            String::from_utf8(out.0).unwrap(),
+            "/tuple_tree>\n  0: {\"type\":\"number\",\"access\":\"rw\",\"default\":0}\n  1/\n"
+        );
+        menu.enter("/1/a").unwrap();
+        let mut out = Collect::default();
+        menu.help(&s, &mut out, &mut buf).await.unwrap();
+        assert_eq!(
+            String::from_utf8(out.0).unwrap(),
+            "/tuple_tree/1/a: {\"type\":\"number\",\"access\":\"rw\",\"default\":0}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn watch() {
+        let mut buf = vec![0; 1024];
+        let states = [(0, true), (1, true), (1, false), (5, true)].map(|(i, enable)| {
+            let mut s = common::Settings::default();
+            if enable {
+                s.enable();
+            }
+            json::set(&mut s, "/array_tree/0", format!("{i}").as_bytes()).unwrap();
+            s
+        });
+        let menu = Menu::<_, 4>::default();
+        let poll = core::cell::Cell::new(0);
+        let mut out = Collect::default();
+        menu.watch(
+            || &states[poll.get()],
+            &["/array_tree/0", "/array_tree/1", "/option_tree"],
+            &mut out,
+            &mut buf,
+            || {
+                poll.set(poll.get() + 1);
+                core::future::ready(poll.get() < states.len())
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out.0).unwrap(),
+            "/array_tree/0: 0\n/array_tree/1: 0\n/option_tree: 8\n\
+             /array_tree/0: 1\n\
+             /option_tree: absent\n\
+             /array_tree/0: 5\n/option_tree: 8\n"
+        );
+    }
 }
@@ -22,7 +22,7 @@ impl<T: AsRef<str> + ?Sized> Key for ScpiKey<T> {
     fn find(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
         let s = self.0.as_ref();
         match lookup {
-            KeyLookup::Named(names) => {
+            KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => {
                 let mut truncated = None;
                 let mut ambiguous = false;
                 for (i, name) in names.iter().enumerate() {
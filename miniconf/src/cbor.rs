@@ -0,0 +1,170 @@
+//! `TreeSerialize`/`TreeDeserialize` with "CBOR and `/`".
+//!
+//! Access items with `'/'` as path separator and CBOR (RFC 8949, from `cbor4ii`)
+//! as serialization/deserialization payload format.
+//!
+//! Paths used here are reciprocal to `TreeKey::lookup::<Path<_, '/'>, _>(...)`/
+//! `TreeKey::nodes::<Path<_, '/'>>()`.
+//!
+//! ```
+//! use miniconf::{cbor, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct S {
+//!     foo: Leaf<u32>,
+//!     bar: [Leaf<u16>; 2],
+//! };
+//! let mut s = S::default();
+//! cbor::set(&mut s, "/bar/1", &[0x19, 0x01, 0x00]).unwrap();
+//! assert_eq!(*s.bar[1], 256);
+//! let mut buf = [0u8; 10];
+//! let len = cbor::get(&s, "/bar/1", &mut buf[..]).unwrap();
+//! assert_eq!(&buf[..len], [0x19, 0x01, 0x00]);
+//! ```
+
+use cbor4ii::{
+    core::{dec, enc, error::Never},
+    serde::{DecodeError, Deserializer, EncodeError, Serializer},
+};
+
+use crate::{Error, IntoKeys, Node, Path, TreeDeserialize, TreeSerialize};
+
+/// The buffer is too small for the serialized value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("buffer full")]
+pub struct BufferFull;
+
+/// CBOR deserialization error
+pub type DeError = DecodeError<Never>;
+
+/// CBOR serialization error
+pub type SerError = EncodeError<BufferFull>;
+
+/// Maximum nesting depth of a value
+const DEPTH: usize = 256;
+
+struct Reader<'de> {
+    buf: &'de [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'de> dec::Read<'de> for Reader<'de> {
+    type Error = Never;
+
+    #[inline]
+    fn fill<'short>(&'short mut self, want: usize) -> Result<dec::Reference<'de, 'short>, Never> {
+        let rest = &self.buf[self.pos..];
+        Ok(dec::Reference::Long(&rest[..want.min(rest.len())]))
+    }
+
+    #[inline]
+    fn advance(&mut self, n: usize) {
+        self.pos = (self.pos + n).min(self.buf.len());
+    }
+
+    #[inline]
+    fn step_in(&mut self) -> bool {
+        self.depth
+            .checked_sub(1)
+            .map(|depth| self.depth = depth)
+            .is_some()
+    }
+
+    #[inline]
+    fn step_out(&mut self) {
+        self.depth += 1;
+    }
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl enc::Write for Writer<'_> {
+    type Error = BufferFull;
+
+    #[inline]
+    fn push(&mut self, input: &[u8]) -> Result<(), BufferFull> {
+        let end = self.len + input.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(BufferFull)?
+            .copy_from_slice(input);
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Update a node by path.
+///
+/// # Args
+/// * `tree` - The `TreeDeserialize` to operate on.
+/// * `path` - The path to the node. Everything before the first `'/'` is ignored.
+/// * `data` - The serialized data making up the content.
+///
+/// # Returns
+/// The number of bytes consumed from `data` or an [Error].
+#[inline]
+pub fn set<'de, T: TreeDeserialize<'de> + ?Sized>(
+    tree: &mut T,
+    path: &str,
+    data: &'de [u8],
+) -> Result<usize, Error<DeError>> {
+    set_by_key(tree, Path::<_, '/'>::from(path), data).map(|(len, _node)| len)
+}
+
+/// Retrieve a serialized value by path.
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `path` - The path to the node. Everything before the first `'/'` is ignored.
+/// * `data` - The buffer to serialize the data into.
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer or an [Error].
+#[inline]
+pub fn get<T: TreeSerialize + ?Sized>(
+    tree: &T,
+    path: &str,
+    data: &mut [u8],
+) -> Result<usize, Error<SerError>> {
+    get_by_key(tree, Path::<_, '/'>::from(path), data)
+}
+
+/// Update a node by key.
+///
+/// A single CBOR data item is consumed from `data`. Trailing bytes are not an error.
+///
+/// # Returns
+/// The number of bytes consumed from `data` and the node that was updated or an [Error].
+/// See also `json::set_by_key()`.
+#[inline]
+pub fn set_by_key<'de, T: TreeDeserialize<'de> + ?Sized, K: IntoKeys>(
+    tree: &mut T,
+    keys: K,
+    data: &'de [u8],
+) -> Result<(usize, Node), Error<DeError>> {
+    let mut de = Deserializer::new(Reader {
+        buf: data,
+        pos: 0,
+        depth: DEPTH,
+    });
+    let depth = tree.deserialize_by_key(keys.into_keys(), &mut de)?;
+    Ok((de.into_inner().pos, Node::leaf(depth)))
+}
+
+/// Retrieve a serialized value by key.
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer or an [Error].
+#[inline]
+pub fn get_by_key<T: TreeSerialize + ?Sized, K: IntoKeys>(
+    tree: &T,
+    keys: K,
+    data: &mut [u8],
+) -> Result<usize, Error<SerError>> {
+    let mut ser = Serializer::new(Writer { buf: data, len: 0 });
+    tree.serialize_by_key(keys.into_keys(), &mut ser)?;
+    Ok(ser.into_inner().len)
+}
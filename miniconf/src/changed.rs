@@ -0,0 +1,322 @@
+use core::{marker::PhantomData, ops::Deref};
+
+use serde::{
+    ser::{Impossible, SerializeStruct},
+    Serialize, Serializer,
+};
+
+use crate::{
+    tree::{probe, ProbeError},
+    Error, IntoKeys, Traversal, TreeSerialize,
+};
+
+/// Field value serialization format
+///
+/// See [`FieldValues`], `json::Format`, and `postcard::Format`.
+pub trait FieldFormat {
+    /// Serialize a value into `buf` and return the number of bytes used or `None` if it
+    /// can not be serialized (e.g. if `buf` is too small).
+    fn to_slice<T: Serialize + ?Sized>(value: &T, buf: &mut [u8]) -> Option<usize>;
+}
+
+/// Serialized values of the fields of a struct leaf
+///
+/// This captures the serialized value of each field of an
+/// atomic struct leaf (e.g. `Leaf<T>` where `T` is a `Serialize` struct) in a buffer.
+/// Comparing the captured values to those after an update by field name yields the names
+/// of the fields that changed, appeared, or disappeared (see [`FieldValues::changed()`]).
+/// Only the first `N` serialized fields are captured.
+/// Leaves that are not structs have no fields.
+/// Fields that do not fit into the buffer are always reported as changed.
+/// The part of the buffer not used by the captured values serves as scratch space
+/// for the comparison and must fit each field value after the update.
+///
+/// See also `json::set_by_key_changed()` and `postcard::set_by_key_changed()`.
+///
+/// ```
+/// # #[cfg(feature = "json-core")] {
+/// use miniconf::{json, FieldValues, Leaf, Serialize};
+/// #[derive(Serialize, Default)]
+/// struct Inner {
+///     a: i32,
+///     b: bool,
+/// }
+/// #[derive(miniconf::TreeKey, miniconf::TreeSerialize, Default)]
+/// struct S {
+///     inner: Leaf<Inner>,
+/// };
+/// let mut s = S::default();
+/// let mut buf = [0u8; 16];
+/// let mut before = FieldValues::<json::Format, 2>::new(&s, ["inner"], &mut buf).unwrap();
+/// s.inner.b = true;
+/// assert_eq!(*before.changed(&s, ["inner"]).unwrap(), ["b"]);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FieldValues<'a, F, const N: usize> {
+    fields: [(&'static str, Option<(usize, usize)>); N],
+    len: usize,
+    buf: &'a mut [u8],
+    used: usize,
+    _format: PhantomData<F>,
+}
+
+impl<'a, F: FieldFormat, const N: usize> FieldValues<'a, F, N> {
+    /// Capture the field values of a leaf by key into a buffer.
+    pub fn new<T: TreeSerialize + ?Sized, K: IntoKeys>(
+        tree: &T,
+        keys: K,
+        buf: &'a mut [u8],
+    ) -> Result<Self, Traversal> {
+        let mut values = Self {
+            fields: [("", None); N],
+            len: 0,
+            buf,
+            used: 0,
+            _format: PhantomData,
+        };
+        visit::<F, _, _, _>(tree, keys, &mut values)?;
+        Ok(values)
+    }
+
+    /// Iterate over the names and captured values of the fields.
+    ///
+    /// The value is `None` if it did not fit into the buffer.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Option<&[u8]>)> + '_ {
+        self.fields[..self.len]
+            .iter()
+            .map(|(name, range)| (*name, range.map(|(start, end)| &self.buf[start..end])))
+    }
+
+    /// The names of the fields of a leaf by key that differ from the captured values.
+    ///
+    /// Fields are matched by name. The fields changed or present in the leaf are listed
+    /// first, followed by the fields only captured.
+    /// At most `N` names are reported.
+    pub fn changed<T: TreeSerialize + ?Sized, K: IntoKeys>(
+        &mut self,
+        tree: &T,
+        keys: K,
+    ) -> Result<Changed<N>, Traversal> {
+        let (captured, scratch) = self.buf.split_at_mut(self.used);
+        let mut compare = Compare {
+            fields: &self.fields[..self.len],
+            captured,
+            scratch,
+            seen: [false; N],
+            changed: Changed::default(),
+        };
+        visit::<F, _, _, _>(tree, keys, &mut compare)?;
+        let Compare {
+            mut changed, seen, ..
+        } = compare;
+        for ((name, _value), seen) in self.fields[..self.len].iter().zip(seen) {
+            if !seen {
+                changed.push(name);
+            }
+        }
+        Ok(changed)
+    }
+}
+
+impl<F: FieldFormat, const N: usize> Visit for FieldValues<'_, F, N> {
+    fn field<G: FieldFormat, T: Serialize + ?Sized>(&mut self, name: &'static str, value: &T) {
+        if let Some(field) = self.fields.get_mut(self.len) {
+            let start = self.used;
+            let range = G::to_slice(value, &mut self.buf[start..]).map(|len| (start, start + len));
+            if let Some((_start, end)) = range {
+                self.used = end;
+            }
+            *field = (name, range);
+            self.len += 1;
+        }
+    }
+}
+
+/// Comparison of field values to captured values
+struct Compare<'a, const N: usize> {
+    fields: &'a [(&'static str, Option<(usize, usize)>)],
+    captured: &'a [u8],
+    scratch: &'a mut [u8],
+    seen: [bool; N],
+    changed: Changed<N>,
+}
+
+impl<const N: usize> Visit for Compare<'_, N> {
+    fn field<F: FieldFormat, T: Serialize + ?Sized>(&mut self, name: &'static str, value: &T) {
+        let before = self
+            .fields
+            .iter()
+            .zip(self.seen.iter_mut())
+            .find(|((n, _), _)| *n == name)
+            .and_then(|((_, range), seen)| {
+                *seen = true;
+                *range
+            })
+            .map(|(start, end)| &self.captured[start..end]);
+        let after = F::to_slice(value, self.scratch).map(|len| &self.scratch[..len]);
+        if before.is_none() || before != after {
+            self.changed.push(name);
+        }
+    }
+}
+
+/// Receive the serialized fields of a struct
+trait Visit {
+    fn field<F: FieldFormat, T: Serialize + ?Sized>(&mut self, name: &'static str, value: &T);
+}
+
+fn visit<F: FieldFormat, T: TreeSerialize + ?Sized, K: IntoKeys, V: Visit>(
+    tree: &T,
+    keys: K,
+    visit: &mut V,
+) -> Result<(), Traversal> {
+    match tree.serialize_by_key(
+        keys.into_keys(),
+        Fields {
+            visit,
+            _format: PhantomData::<F>,
+        },
+    ) {
+        Err(Error::Traversal(err)) => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Names of changed fields
+///
+/// See [`FieldValues::changed()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Changed<const N: usize> {
+    names: [&'static str; N],
+    len: usize,
+}
+
+impl<const N: usize> Changed<N> {
+    fn push(&mut self, name: &'static str) {
+        if let Some(slot) = self.names.get_mut(self.len) {
+            *slot = name;
+            self.len += 1;
+        }
+    }
+}
+
+impl<const N: usize> Default for Changed<N> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            names: [""; N],
+            len: 0,
+        }
+    }
+}
+
+impl<const N: usize> Deref for Changed<N> {
+    type Target = [&'static str];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.names[..self.len]
+    }
+}
+
+/// `Serializer` visiting the fields of a struct
+///
+/// Any other value fails like `Probe`.
+struct Fields<'a, V, F> {
+    visit: &'a mut V,
+    _format: PhantomData<F>,
+}
+
+impl<V: Visit, F: FieldFormat> Serializer for Fields<'_, V, F> {
+    type Ok = ();
+    type Error = ProbeError;
+    type SerializeSeq = Impossible<(), ProbeError>;
+    type SerializeTuple = Impossible<(), ProbeError>;
+    type SerializeTupleStruct = Impossible<(), ProbeError>;
+    type SerializeTupleVariant = Impossible<(), ProbeError>;
+    type SerializeMap = Impossible<(), ProbeError>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), ProbeError>;
+
+    probe! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u8(u8) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    #[inline]
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), ProbeError> {
+        Err(ProbeError)
+    }
+
+    #[inline]
+    fn collect_str<T: ?Sized + core::fmt::Display>(self, _value: &T) -> Result<(), ProbeError> {
+        Err(ProbeError)
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), ProbeError> {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), ProbeError> {
+        Err(ProbeError)
+    }
+
+    #[inline]
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, ProbeError> {
+        Ok(self)
+    }
+}
+
+impl<V: Visit, F: FieldFormat> SerializeStruct for Fields<'_, V, F> {
+    type Ok = ();
+    type Error = ProbeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ProbeError> {
+        self.visit.field::<F, _>(key, value);
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<(), ProbeError> {
+        Ok(())
+    }
+}
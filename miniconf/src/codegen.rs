@@ -0,0 +1,390 @@
+//! Code generation for non-Rust consumers
+//!
+//! These helpers are intended to be called from a `build.rs` script to emit
+//! artifacts describing the key schema of a tree, e.g. for other languages
+//! or for documentation.
+//!
+//! ```
+//! use miniconf::{codegen, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct Inner {
+//!     val: Leaf<f32>,
+//! };
+//! #[derive(Tree, Default)]
+//! struct Settings {
+//!     i: [Inner; 2],
+//! };
+//! let header = codegen::c_header::<Settings>("SETTINGS_");
+//! assert!(header.contains("#define SETTINGS_I_1_VAL \"/i/1/val\"\n"));
+//! ```
+
+use core::fmt::Write;
+
+#[cfg(feature = "json-core")]
+use crate::{json, jsontype::JsonType, IntoKeys, TreeSerialize};
+use crate::{Shape, TreeKey};
+
+fn c_defines(shape: &Shape, name: &mut String, path: &mut String, out: &mut String) {
+    if matches!(shape, Shape::Leaf) {
+        writeln!(out, "#define {} \"{}\"", name.trim_end_matches('_'), path).unwrap();
+    }
+    let (name_len, path_len) = (name.len(), path.len());
+    for (_index, key, child) in shape.children() {
+        if !name.ends_with('_') && !name.is_empty() {
+            name.push('_');
+        }
+        name.extend(key.chars().map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        }));
+        path.push('/');
+        path.push_str(&key);
+        c_defines(child, name, path, out);
+        name.truncate(name_len);
+        path.truncate(path_len);
+    }
+}
+
+fn dot_nodes(shape: &Shape, label: &str, id: usize, next: &mut usize, out: &mut String) {
+    let kind = match shape {
+        Shape::Leaf => "leaf",
+        Shape::Named(_) => "named",
+        Shape::Numbered(_) => "numbered",
+        Shape::Homogeneous(..) => "homogeneous",
+    };
+    writeln!(out, "    n{id} [label=\"{label}\\n{kind}\"];").unwrap();
+    for (_index, label, child_shape) in shape.children() {
+        let label = match shape {
+            // A single node represents all homogeneous children
+            Shape::Homogeneous(len, _) if _index > 0 => break,
+            Shape::Homogeneous(len, _) => format!("0..{len}").into(),
+            _ => label,
+        };
+        let child = *next;
+        *next += 1;
+        writeln!(out, "    n{id} -> n{child};").unwrap();
+        dot_nodes(child_shape, &label, child, next, out);
+    }
+}
+
+/// Generate a Graphviz DOT graph of the tree schema.
+///
+/// Each node is labeled with its name (or index) and its kind
+/// (`leaf`, `named`, `numbered`, or `homogeneous`). The root node is labeled `/`.
+/// The children of homogeneous nodes are represented by a single node
+/// labeled with the index range, e.g. `0..4`.
+///
+/// ```
+/// use miniconf::{codegen, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: [Leaf<u32>; 4],
+/// };
+/// let dot = codegen::dot::<S>();
+/// assert!(dot.contains("n1 [label=\"foo\\nhomogeneous\"];"));
+/// assert!(dot.contains("n1 -> n2;"));
+/// ```
+///
+/// # Returns
+/// The DOT source.
+pub fn dot<T: TreeKey + ?Sized>() -> String {
+    let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+    let mut out = String::from("digraph {\n    node [shape=box];\n");
+    dot_nodes(&shape, "/", 0, &mut 1, &mut out);
+    out.push_str("}\n");
+    out
+}
+
+/// Generate a C header with path string constants for all leaves.
+///
+/// Each leaf `/i/1/val` is emitted as `#define <PREFIX>I_1_VAL "/i/1/val"`.
+/// Names are upper-cased and characters that are not ASCII alphanumeric are
+/// replaced by `'_'`.
+///
+/// # Args
+/// * `prefix`: The prefix of the macro names, e.g. `"SETTINGS_"`.
+///
+/// # Returns
+/// The header source.
+pub fn c_header<T: TreeKey + ?Sized>(prefix: &str) -> String {
+    let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+    let mut out = String::from("/* Generated by miniconf. Do not edit. */\n#pragma once\n\n");
+    c_defines(&shape, &mut prefix.into(), &mut String::new(), &mut out);
+    out
+}
+
+/// Serialize a leaf of `default`, `None` if it is absent or not accessible
+#[cfg(feature = "json-core")]
+fn leaf_json<T: TreeSerialize>(default: &T, keys: &[usize]) -> Option<Vec<u8>> {
+    let mut data = vec![0; 256];
+    loop {
+        match json::get_by_key(default, keys.iter().into_keys(), &mut data) {
+            Err(crate::Error::Inner(_, _)) if data.len() < 1 << 16 => {
+                data.resize(data.len() * 2, 0);
+            }
+            ret => {
+                data.truncate(ret.ok()?);
+                return Some(data);
+            }
+        }
+    }
+}
+
+/// TypeScript type of a JSON value of type `typ`
+#[cfg(feature = "json-core")]
+fn ts_scalar(typ: Option<JsonType>) -> &'static str {
+    match typ {
+        Some(JsonType::Null) => "null",
+        Some(JsonType::Boolean) => "boolean",
+        Some(JsonType::Integer | JsonType::Number) => "number",
+        Some(JsonType::String) => "string",
+        Some(JsonType::Array) => "unknown[]",
+        Some(JsonType::Object) => "Record<string, unknown>",
+        None => "unknown",
+    }
+}
+
+/// TypeScript type of a node and whether it is present in `default`
+#[cfg(feature = "json-core")]
+fn ts_type<T: TreeSerialize>(
+    shape: &Shape,
+    keys: &mut Vec<usize>,
+    default: &T,
+    indent: usize,
+) -> (String, bool) {
+    let mut child = |i: usize, shape: &Shape, indent: usize| {
+        keys.push(i);
+        let ret = ts_type(shape, keys, default, indent);
+        keys.pop();
+        ret
+    };
+    match shape {
+        Shape::Leaf => {
+            let Some(data) = leaf_json(default, keys) else {
+                return ("unknown".into(), false);
+            };
+            let typ = match JsonType::of(&data) {
+                Some(JsonType::Array) => format!("{}[]", ts_scalar(JsonType::item(&data))),
+                typ => ts_scalar(typ).into(),
+            };
+            (typ, true)
+        }
+        Shape::Named(_) => {
+            let pad = "  ".repeat(indent);
+            let mut out = String::from("{\n");
+            let mut present = false;
+            for (i, name, shape) in shape.children() {
+                let (typ, p) = child(i, shape, indent + 1);
+                present |= p;
+                let opt = if p { "" } else { "?" };
+                writeln!(out, "{pad}  {name}{opt}: {typ};").unwrap();
+            }
+            out.push_str(&pad);
+            out.push('}');
+            (out, present)
+        }
+        Shape::Numbered(_) => {
+            let mut present = false;
+            let items: Vec<_> = shape
+                .children()
+                .map(|(i, _name, shape)| {
+                    let (typ, p) = child(i, shape, indent);
+                    present |= p;
+                    typ
+                })
+                .collect();
+            (format!("[{}]", items.join(", ")), present)
+        }
+        Shape::Homogeneous(_len, item) => {
+            let (typ, present) = child(0, item, indent);
+            (format!("{typ}[]"), present)
+        }
+    }
+}
+
+/// Generate a TypeScript interface for the nested JSON representation of a tree.
+///
+/// Internal nodes with named children become object types,
+/// numbered internal nodes become tuples, and homogeneous internal nodes become arrays.
+/// The leaf types are `number`, `string`, `boolean`, or arrays thereof and are determined by
+/// serializing the leaf of `T::default()`. Leaves that are absent or not
+/// accessible in `T::default()` are optional and `unknown`.
+///
+/// ```
+/// use miniconf::{codegen, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<bool>; 2],
+/// };
+/// let ts = codegen::typescript::<S>("Settings");
+/// assert!(ts.contains("export interface Settings {\n  foo: number;\n  bar: boolean[];\n}\n"));
+/// ```
+///
+/// # Args
+/// * `name`: The name of the interface.
+///
+/// # Returns
+/// The TypeScript source.
+#[cfg(feature = "json-core")]
+pub fn typescript<T: TreeKey + TreeSerialize + Default>(name: &str) -> String {
+    let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+    let (typ, _present) = ts_type(&shape, &mut vec![], &T::default(), 0);
+    let mut out = String::from("/* Generated by miniconf. Do not edit. */\n\n");
+    if matches!(shape, Shape::Named(_)) {
+        writeln!(out, "export interface {name} {typ}").unwrap();
+    } else {
+        writeln!(out, "export type {name} = {typ};").unwrap();
+    }
+    out
+}
+
+/// Python type of a JSON value of type `typ`
+#[cfg(feature = "json-core")]
+fn py_scalar(typ: Option<JsonType>) -> &'static str {
+    match typ {
+        Some(JsonType::Boolean) => "bool",
+        Some(JsonType::Integer) => "int",
+        Some(JsonType::Number) => "float",
+        Some(JsonType::String) => "str",
+        Some(JsonType::Array) => "list[Any]",
+        Some(JsonType::Object) => "dict[str, Any]",
+        Some(JsonType::Null) | None => "Any",
+    }
+}
+
+/// Python keywords that can not be used as field names
+#[cfg(feature = "json-core")]
+const PY_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Python type of a node and whether it is present in `default`.
+///
+/// The dataclasses for named internal nodes are appended to `classes`
+/// (children before parents) and named `class`.
+#[cfg(feature = "json-core")]
+fn py_type<T: TreeSerialize>(
+    shape: &Shape,
+    keys: &mut Vec<usize>,
+    default: &T,
+    class: &str,
+    classes: &mut String,
+) -> (String, bool) {
+    let mut child = |i: usize, shape: &Shape, class: &str, classes: &mut String| {
+        keys.push(i);
+        let ret = py_type(shape, keys, default, class, classes);
+        keys.pop();
+        ret
+    };
+    match shape {
+        Shape::Leaf => match leaf_json(default, keys) {
+            Some(data) => match JsonType::of(&data) {
+                Some(JsonType::Array) => {
+                    (format!("list[{}]", py_scalar(JsonType::item(&data))), true)
+                }
+                typ => (py_scalar(typ).into(), true),
+            },
+            None => ("Any".into(), false),
+        },
+        Shape::Named(_) => {
+            let mut body = String::new();
+            let mut present = false;
+            for (i, name, shape) in shape.children() {
+                let pascal: String = name
+                    .split(|c: char| !c.is_ascii_alphanumeric())
+                    .flat_map(|w| {
+                        let mut c = w.chars();
+                        c.next()
+                            .map(|f| f.to_ascii_uppercase())
+                            .into_iter()
+                            .chain(c)
+                    })
+                    .collect();
+                let (typ, p) = child(i, shape, &format!("{class}{pascal}"), classes);
+                present |= p;
+                let mut field: String = name
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect();
+                if PY_KEYWORDS.contains(&field.as_str()) {
+                    field.push('_');
+                }
+                if p {
+                    writeln!(body, "    {field}: {typ}").unwrap();
+                } else {
+                    writeln!(body, "    {field}: Optional[{typ}]").unwrap();
+                }
+            }
+            write!(classes, "\n\n@dataclass\nclass {class}:\n{body}").unwrap();
+            (class.into(), present)
+        }
+        Shape::Numbered(_) => {
+            let mut present = false;
+            let items: Vec<_> = shape
+                .children()
+                .map(|(i, _name, shape)| {
+                    let (typ, p) = child(i, shape, &format!("{class}{i}"), classes);
+                    present |= p;
+                    typ
+                })
+                .collect();
+            (format!("tuple[{}]", items.join(", ")), present)
+        }
+        Shape::Homogeneous(_len, item) => {
+            let (typ, present) = child(0, item, class, classes);
+            (format!("list[{typ}]"), present)
+        }
+    }
+}
+
+/// Generate Python dataclasses for the nested JSON representation of a tree.
+///
+/// Internal nodes with named children become dataclasses,
+/// numbered internal nodes become tuples, and homogeneous internal nodes become lists.
+/// Nested dataclasses are named by appending the capitalized field name to the name
+/// of the parent class and are emitted before their parents.
+/// The leaf types are `int`, `float`, `str`, `bool`, or lists thereof and are determined
+/// by serializing the leaf of `T::default()`. Leaves that are absent or not
+/// accessible in `T::default()` are `Optional[Any]`.
+/// Characters in field names that are not valid in Python identifiers are replaced by `_`.
+/// Field names that are Python keywords get a trailing `_` (e.g. `class_`).
+///
+/// ```
+/// use miniconf::{codegen, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<bool>; 2],
+/// };
+/// let py = codegen::python::<S>("Settings");
+/// assert!(py.contains("@dataclass\nclass Settings:\n    foo: int\n    bar: list[bool]\n"));
+/// ```
+///
+/// # Args
+/// * `class_name`: The name of the top level class.
+///
+/// # Returns
+/// The Python source.
+#[cfg(feature = "json-core")]
+pub fn python<T: TreeKey + TreeSerialize + Default>(class_name: &str) -> String {
+    let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+    let mut classes = String::new();
+    let (typ, _present) = py_type(&shape, &mut vec![], &T::default(), class_name, &mut classes);
+    let mut out = String::from(
+        "# Generated by miniconf. Do not edit.\n\n\
+        from dataclasses import dataclass\n\
+        from typing import Any, Optional\n",
+    );
+    out.push_str(&classes);
+    if !matches!(shape, Shape::Named(_)) {
+        write!(out, "\n\n{class_name} = {typ}\n").unwrap();
+    }
+    out
+}
@@ -0,0 +1,508 @@
+use core::{
+    any::Any,
+    borrow::Borrow,
+    num::NonZero,
+    ops::{Deref, DerefMut},
+};
+
+extern crate alloc;
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    Error, KeyLookup, Keys, Schema, Traversal, TreeAny, TreeDeserialize, TreeKey, TreeSerialize,
+    Walk,
+};
+
+/// Per-element access to a `Vec`
+///
+/// This turns a [`Vec<T>`] into a homogeneous internal node with up to `N` children.
+/// `N` is the maximum length used for the key schema.
+/// Elements at indices beyond the current length of the `Vec` are
+/// [`Traversal::Absent`], indices at or beyond `N` are [`Traversal::NotFound`].
+/// The length of the `Vec` is not changed.
+///
+/// Use `Leaf<Vec<T>>` to access the `Vec` as a whole.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree, VecElements};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     buf: VecElements<Leaf<u8>, 8>,
+/// };
+/// let mut s = S::default();
+/// s.buf.extend([1, 2, 3].map(Leaf));
+/// json::set(&mut s, "/buf/2", b"9").unwrap();
+/// assert_eq!(*s.buf[2], 9);
+/// assert!(json::set(&mut s, "/buf/3", b"9").is_err()); // absent
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct VecElements<T, const N: usize>(pub Vec<T>);
+
+impl<T, const N: usize> Deref for VecElements<T, N> {
+    type Target = Vec<T>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> DerefMut for VecElements<T, N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T, const N: usize> VecElements<T, N> {
+    /// Extract just the inner
+    #[inline]
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const N: usize> From<Vec<T>> for VecElements<T, N> {
+    #[inline]
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: TreeKey, const N: usize> TreeKey for VecElements<T, N> {
+    const SCHEMA: Schema = <[T; N]>::SCHEMA;
+
+    #[inline]
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        <[T; N]>::traverse_all()
+    }
+
+    #[inline]
+    fn traverse_by_key<K, F, E>(keys: K, func: F) -> Result<usize, Error<E>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+    {
+        <[T; N]>::traverse_by_key(keys, func)
+    }
+}
+
+impl<T: TreeSerialize, const N: usize> TreeSerialize for VecElements<T, N> {
+    fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    where
+        K: Keys,
+        S: Serializer,
+    {
+        let index = keys.next(&KeyLookup::homogeneous(N))?;
+        let item = self.0.get(index).ok_or(Traversal::Absent(1))?;
+        Error::increment_result(item.serialize_by_key(keys, ser))
+    }
+}
+
+impl<'de, T: TreeDeserialize<'de>, const N: usize> TreeDeserialize<'de> for VecElements<T, N> {
+    fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
+    where
+        K: Keys,
+        D: Deserializer<'de>,
+    {
+        let index = keys.next(&KeyLookup::homogeneous(N))?;
+        let item = self.0.get_mut(index).ok_or(Traversal::Absent(1))?;
+        Error::increment_result(item.deserialize_by_key(keys, de))
+    }
+}
+
+impl<T: TreeAny, const N: usize> TreeAny for VecElements<T, N> {
+    fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        let index = keys.next(&KeyLookup::homogeneous(N))?;
+        let item = self.0.get(index).ok_or(Traversal::Absent(1))?;
+        item.ref_any_by_key(keys).map_err(Traversal::increment)
+    }
+
+    fn mut_any_by_key<K>(&mut self, mut keys: K) -> Result<&mut dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        let index = keys.next(&KeyLookup::homogeneous(N))?;
+        let item = self.0.get_mut(index).ok_or(Traversal::Absent(1))?;
+        item.mut_any_by_key(keys).map_err(Traversal::increment)
+    }
+
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        let index = keys.next(&KeyLookup::homogeneous(N))?;
+        let item = self.0.get(index).ok_or(Traversal::Absent(1))?;
+        item.with_any_by_key(keys, func)
+            .map_err(Traversal::increment)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// A map with string keys for [`MapElements`]
+///
+/// This is implemented for [`BTreeMap`] and (with the `std` feature) `HashMap`
+/// with keys that borrow as `str`.
+pub trait StrMap {
+    /// The value type
+    type Value;
+
+    /// Iterate over the keys and values in iteration order
+    fn entries(&self) -> impl Iterator<Item = (&str, &Self::Value)>;
+
+    /// Iterate mutably over the keys and values in iteration order
+    fn entries_mut(&mut self) -> impl Iterator<Item = (&str, &mut Self::Value)>;
+}
+
+impl<K: Borrow<str>, T> StrMap for BTreeMap<K, T> {
+    type Value = T;
+
+    #[inline]
+    fn entries(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.iter().map(|(k, v)| (k.borrow(), v))
+    }
+
+    #[inline]
+    fn entries_mut(&mut self) -> impl Iterator<Item = (&str, &mut T)> {
+        self.iter_mut().map(|(k, v)| (k.borrow(), v))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Borrow<str>, T, S> StrMap for std::collections::HashMap<K, T, S> {
+    type Value = T;
+
+    #[inline]
+    fn entries(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.iter().map(|(k, v)| (k.borrow(), v))
+    }
+
+    #[inline]
+    fn entries_mut(&mut self) -> impl Iterator<Item = (&str, &mut T)> {
+        self.iter_mut().map(|(k, v)| (k.borrow(), v))
+    }
+}
+
+/// Per-entry access to a map with string keys
+///
+/// This turns a [`StrMap`] (e.g. `BTreeMap<String, T>` or `HashMap<String, T>`) into a
+/// homogeneous internal node with up to `N` children.
+/// `N` is the maximum number of entries used for the key schema.
+///
+/// The children are addressed by map key, e.g. `/channels/<name>`.
+/// Map keys that are not present are [`Traversal::Absent`].
+/// The children can also be addressed by the index of the entry in the iteration order
+/// of the map (sorted for `BTreeMap`, unspecified for `HashMap`).
+/// This is how the static key schema of [`TreeKey`] (e.g. [`TreeKey::nodes()`] and
+/// [`crate::Packed`] keys) refers to them. A map key takes precedence over an index.
+/// Entries at indices beyond the current number of entries are [`Traversal::Absent`],
+/// entries at or beyond `N` can only be accessed as a whole.
+/// The entries of the map are not changed (no entries are inserted or removed).
+///
+/// Use `Leaf<BTreeMap<K, T>>` to access the map as a whole.
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use miniconf::{json, Leaf, MapElements, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     channels: MapElements<BTreeMap<String, Leaf<u8>>, 8>,
+/// };
+/// let mut s = S::default();
+/// s.channels.extend([("b".to_string(), 1.into()), ("a".to_string(), 2.into())]);
+/// json::set(&mut s, "/channels/b", b"9").unwrap();
+/// assert_eq!(*s.channels["b"], 9);
+/// assert_eq!(s.channels.position("b"), Some(1));
+/// json::set(&mut s, "/channels/1", b"7").unwrap();
+/// assert_eq!(*s.channels["b"], 7);
+/// assert!(json::set(&mut s, "/channels/c", b"9").is_err()); // absent
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct MapElements<M, const N: usize>(pub M);
+
+impl<M, const N: usize> Deref for MapElements<M, N> {
+    type Target = M;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<M, const N: usize> DerefMut for MapElements<M, N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<M, const N: usize> MapElements<M, N> {
+    /// Extract just the inner
+    #[inline]
+    pub fn into_inner(self) -> M {
+        self.0
+    }
+}
+
+impl<M: StrMap, const N: usize> MapElements<M, N> {
+    /// Return the node index of the entry with the given map key
+    ///
+    /// Returns `None` if the key is not in the map or if its index is at or
+    /// beyond `N`.
+    #[inline]
+    pub fn position(&self, key: &str) -> Option<usize> {
+        self.find(&|name| name == key)
+    }
+
+    fn find(&self, matches: &dyn Fn(&str) -> bool) -> Option<usize> {
+        self.0
+            .entries()
+            .position(|(name, _)| matches(name))
+            .filter(|index| *index < N)
+    }
+}
+
+impl<M, const N: usize> From<M> for MapElements<M, N> {
+    #[inline]
+    fn from(value: M) -> Self {
+        Self(value)
+    }
+}
+
+impl<M: StrMap, const N: usize> TreeKey for MapElements<M, N>
+where
+    M::Value: TreeKey,
+{
+    const SCHEMA: Schema = <[M::Value; N]>::SCHEMA;
+
+    #[inline]
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        <[M::Value; N]>::traverse_all()
+    }
+
+    #[inline]
+    fn traverse_by_key<K, F, E>(keys: K, func: F) -> Result<usize, Error<E>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+    {
+        <[M::Value; N]>::traverse_by_key(keys, func)
+    }
+}
+
+impl<M: StrMap, const N: usize> TreeSerialize for MapElements<M, N>
+where
+    M::Value: TreeSerialize,
+{
+    fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    where
+        K: Keys,
+        S: Serializer,
+    {
+        let index = keys.next_named(&KeyLookup::homogeneous(N), &|m| self.find(m))?;
+        let (_, item) = self.0.entries().nth(index).ok_or(Traversal::Absent(1))?;
+        Error::increment_result(item.serialize_by_key(keys, ser))
+    }
+}
+
+impl<'de, M: StrMap, const N: usize> TreeDeserialize<'de> for MapElements<M, N>
+where
+    M::Value: TreeDeserialize<'de>,
+{
+    fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
+    where
+        K: Keys,
+        D: Deserializer<'de>,
+    {
+        let index = keys.next_named(&KeyLookup::homogeneous(N), &|m| self.find(m))?;
+        let (_, item) = self
+            .0
+            .entries_mut()
+            .nth(index)
+            .ok_or(Traversal::Absent(1))?;
+        Error::increment_result(item.deserialize_by_key(keys, de))
+    }
+}
+
+impl<M: StrMap, const N: usize> TreeAny for MapElements<M, N>
+where
+    M::Value: TreeAny,
+{
+    fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        let index = keys.next_named(&KeyLookup::homogeneous(N), &|m| self.find(m))?;
+        let (_, item) = self.0.entries().nth(index).ok_or(Traversal::Absent(1))?;
+        item.ref_any_by_key(keys).map_err(Traversal::increment)
+    }
+
+    fn mut_any_by_key<K>(&mut self, mut keys: K) -> Result<&mut dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        let index = keys.next_named(&KeyLookup::homogeneous(N), &|m| self.find(m))?;
+        let (_, item) = self
+            .0
+            .entries_mut()
+            .nth(index)
+            .ok_or(Traversal::Absent(1))?;
+        item.mut_any_by_key(keys).map_err(Traversal::increment)
+    }
+
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        let index = keys.next_named(&KeyLookup::homogeneous(N), &|m| self.find(m))?;
+        let (_, item) = self.0.entries().nth(index).ok_or(Traversal::Absent(1))?;
+        item.with_any_by_key(keys, func)
+            .map_err(Traversal::increment)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Per-character access to a `String`
+///
+/// This turns a [`String`] into a homogeneous internal node with up to `N` leaves.
+/// `N` is the maximum number of characters used for the key schema.
+/// Each leaf is a `char`, serialized as a string with a single character.
+/// Characters beyond the current length of the `String` are
+/// [`Traversal::Absent`], indices at or beyond `N` are [`Traversal::NotFound`].
+/// The number of characters is not changed.
+/// [`TreeAny`] is implemented but denied access at runtime.
+///
+/// Use `Leaf<String>` to access the `String` as a whole.
+///
+/// ```
+/// use miniconf::{json, StringChars, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     name: StringChars<8>,
+/// };
+/// let mut s = S::default();
+/// s.name.push_str("foo");
+/// json::set(&mut s, "/name/1", b"\"a\"").unwrap();
+/// assert_eq!(s.name.as_str(), "fao");
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct StringChars<const N: usize>(pub String);
+
+impl<const N: usize> Deref for StringChars<N> {
+    type Target = String;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for StringChars<N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> StringChars<N> {
+    /// Extract just the inner
+    #[inline]
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const N: usize> From<String> for StringChars<N> {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl<const N: usize> TreeKey for StringChars<N> {
+    const SCHEMA: Schema = <[crate::Leaf<char>; N]>::SCHEMA;
+
+    #[inline]
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        <[crate::Leaf<char>; N]>::traverse_all()
+    }
+
+    #[inline]
+    fn traverse_by_key<K, F, E>(keys: K, func: F) -> Result<usize, Error<E>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+    {
+        <[crate::Leaf<char>; N]>::traverse_by_key(keys, func)
+    }
+}
+
+impl<const N: usize> StringChars<N> {
+    /// Byte range of the indexed character
+    fn char_range<K: Keys>(&self, mut keys: K) -> Result<(usize, char), Traversal> {
+        let index = keys.next(&KeyLookup::homogeneous(N))?;
+        keys.finalize().map_err(Traversal::increment)?;
+        self.0.char_indices().nth(index).ok_or(Traversal::Absent(1))
+    }
+}
+
+impl<const N: usize> TreeSerialize for StringChars<N> {
+    fn serialize_by_key<K, S>(&self, keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    where
+        K: Keys,
+        S: Serializer,
+    {
+        let (_, c) = self.char_range(keys)?;
+        ser.serialize_str(c.encode_utf8(&mut [0; 4]))
+            .map_err(|err| Error::Inner(1, err))?;
+        Ok(1)
+    }
+}
+
+impl<'de, const N: usize> TreeDeserialize<'de> for StringChars<N> {
+    fn deserialize_by_key<K, D>(&mut self, keys: K, de: D) -> Result<usize, Error<D::Error>>
+    where
+        K: Keys,
+        D: Deserializer<'de>,
+    {
+        let (start, old) = self.char_range(keys)?;
+        let value: &str = Deserialize::deserialize(de).map_err(|err| Error::Inner(1, err))?;
+        let mut chars = value.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return Err(Traversal::Invalid(1, "Not a single character").into());
+        };
+        self.0
+            .replace_range(start..start + old.len_utf8(), c.encode_utf8(&mut [0; 4]));
+        Ok(1)
+    }
+}
+
+impl<const N: usize> TreeAny for StringChars<N> {
+    fn ref_any_by_key<K>(&self, keys: K) -> Result<&dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        self.char_range(keys)?;
+        Err(Traversal::Access(1, "No Any access for StringChars"))
+    }
+
+    fn mut_any_by_key<K>(&mut self, keys: K) -> Result<&mut dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        self.char_range(keys)?;
+        Err(Traversal::Access(1, "No Any access for StringChars"))
+    }
+}
@@ -0,0 +1,151 @@
+//! Apply overrides from environment variables.
+//!
+//! A variable `<PREFIX><KEY>=<VALUE>` is applied to the leaf identified by `<KEY>`.
+//! `<KEY>` is the sequence of names or indices separated by `'_'`,
+//! e.g. `I_1_VAL` for `/i/1/val`. Names are matched case-insensitively
+//! and may themselves contain `'_'` (the longest matching name is used).
+//! `<VALUE>` is JSON. If it does not deserialize as JSON, it is
+//! retried as a bare (unquoted) JSON string.
+//!
+//! ```
+//! use miniconf::{env, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct S {
+//!     foo: Leaf<u32>,
+//!     bar_baz: [Leaf<u16>; 2],
+//! };
+//! let mut s = S::default();
+//! let vars = [("APP_FOO", "3"), ("APP_BAR_BAZ_1", "9"), ("OTHER", "1")];
+//! assert_eq!(env::apply_vars(&mut s, "APP_", vars), Ok(2));
+//! assert_eq!((*s.foo, *s.bar_baz[1]), (3, 9));
+//! ```
+
+use serde_json_core::de;
+
+use crate::{json, Error, IntoKeys, KeyLookup, Keys, Traversal, TreeDeserializeOwned};
+
+/// `'_'`-separated, case-insensitive [`Keys`]
+///
+/// See the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvKeys<'a>(&'a str);
+
+impl<'a> EnvKeys<'a> {
+    /// Create a new `EnvKeys` from the variable name without the prefix.
+    pub fn new(key: &'a str) -> Self {
+        Self(key)
+    }
+}
+
+impl Keys for EnvKeys<'_> {
+    fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        if self.0.is_empty() {
+            return Err(Traversal::TooShort(0));
+        }
+        let (index, len) = match lookup {
+            KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => names
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| {
+                    self.0
+                        .get(..name.len())
+                        .is_some_and(|k| k.eq_ignore_ascii_case(name))
+                        && matches!(self.0.as_bytes().get(name.len()), None | Some(b'_'))
+                })
+                .max_by_key(|(_, name)| name.len())
+                .map(|(index, name)| (index, name.len())),
+            KeyLookup::Numbered(n) | KeyLookup::Homogeneous(n) => {
+                let len = self.0.find('_').unwrap_or(self.0.len());
+                self.0[..len]
+                    .parse()
+                    .ok()
+                    .filter(|i| i < &n.get())
+                    .map(|i| (i, len))
+            }
+        }
+        .ok_or(Traversal::NotFound(1))?;
+        self.0 = self.0.get(len + 1..).unwrap_or_default();
+        Ok(index)
+    }
+
+    fn finalize(&mut self) -> Result<(), Traversal> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(Traversal::TooLong(0))
+        }
+    }
+}
+
+impl IntoKeys for EnvKeys<'_> {
+    type IntoKeys = Self;
+
+    #[inline]
+    fn into_keys(self) -> Self::IntoKeys {
+        self
+    }
+}
+
+/// Apply a single value by key, retrying as a bare string.
+pub(crate) fn apply<T: TreeDeserializeOwned + ?Sized, K: IntoKeys + Clone>(
+    tree: &mut T,
+    keys: K,
+    value: &str,
+) -> Result<(), Error<de::Error>> {
+    match json::set_by_key(tree, keys.clone(), value.as_bytes()) {
+        Err(Error::Inner(_, _) | Error::Finalization(_)) => {
+            let mut quoted = std::string::String::with_capacity(value.len() + 2);
+            quoted.push('"');
+            for c in value.chars() {
+                if matches!(c, '"' | '\\') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted.push('"');
+            json::set_by_key(tree, keys, quoted.as_bytes()).map(|_| ())
+        }
+        ret => ret.map(|_| ()),
+    }
+}
+
+/// Apply all variables starting with `prefix`.
+///
+/// # Returns
+/// The number of applied variables or the first error.
+pub fn apply_vars<T, K, V>(
+    tree: &mut T,
+    prefix: &str,
+    vars: impl IntoIterator<Item = (K, V)>,
+) -> Result<usize, Error<de::Error>>
+where
+    T: TreeDeserializeOwned + ?Sized,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut count = 0;
+    for (key, value) in vars {
+        if let Some(key) = key.as_ref().strip_prefix(prefix) {
+            apply(tree, EnvKeys::new(key), value.as_ref())?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Apply all process environment variables starting with `prefix`.
+///
+/// Variables with a name or value that is not valid Unicode are ignored.
+///
+/// See [`apply_vars()`].
+pub fn apply_prefix<T: TreeDeserializeOwned + ?Sized>(
+    tree: &mut T,
+    prefix: &str,
+) -> Result<usize, Error<de::Error>> {
+    apply_vars(
+        tree,
+        prefix,
+        std::env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?))),
+    )
+}
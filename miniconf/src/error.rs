@@ -7,7 +7,11 @@
 ///
 /// If multiple errors are applicable simultaneously the precedence
 /// is as per the order in the enum definition (from high to low).
+///
+/// Besides key errors this also covers the errors of accessing and validating
+/// a leaf value ([`Traversal::Absent`], [`Traversal::Access`], and [`Traversal::Invalid`]).
 #[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Traversal {
     /// A node does not exist at runtime.
     ///
@@ -72,6 +76,7 @@ impl Traversal {
 
 /// Compound errors
 #[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error<E> {
     /// Tree traversal error
     #[error(transparent)]
@@ -0,0 +1,274 @@
+use core::fmt::Write;
+
+use crate::{KeyLookup, Packed, Schema, Traversal, TreeKey, Walk};
+
+/// 32 bit FNV-1a hasher
+///
+/// This implements [`Write`] and can be used as a [`Path`] target to hash
+/// a canonical path (e.g. `"/foo/1/bar"`) during [`crate::Transcode`]
+/// without storing the path.
+///
+/// ```
+/// use miniconf::{Fnv1a, Leaf, Path, TreeKey};
+/// #[derive(TreeKey)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let (hash, _node) = S::transcode::<Path<Fnv1a, '/'>, _>([1, 1]).unwrap();
+/// assert_eq!(hash.into_inner().finish(), Fnv1a::hash("/bar/1"));
+/// ```
+///
+/// As a [`Walk`] it computes a fingerprint of the schema (the names and structure of all nodes)
+/// of a `TreeKey`. It can be used to detect schema changes between firmware versions.
+///
+/// ```
+/// use miniconf::{Fnv1a, Leaf, TreeKey};
+/// #[derive(TreeKey)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// #[derive(TreeKey)]
+/// struct T {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 3],
+/// };
+/// let schema = S::traverse_all::<Fnv1a>().unwrap().finish();
+/// assert_ne!(schema, T::traverse_all::<Fnv1a>().unwrap().finish());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Fnv1a(u32);
+
+impl Default for Fnv1a {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fnv1a {
+    /// A new hasher with the FNV offset basis
+    #[inline]
+    pub const fn new() -> Self {
+        Self(0x811c9dc5)
+    }
+
+    /// Update with bytes
+    #[inline]
+    pub const fn update(&mut self, bytes: &[u8]) {
+        let mut i = 0;
+        while i < bytes.len() {
+            self.0 = (self.0 ^ bytes[i] as u32).wrapping_mul(0x01000193);
+            i += 1;
+        }
+    }
+
+    /// Update with the decimal representation of an index
+    const fn update_index(&mut self, index: usize) {
+        let (buf, start) = decimal(index);
+        self.update(buf.split_at(start).1);
+    }
+
+    /// The current hash value
+    #[inline]
+    pub const fn finish(&self) -> u32 {
+        self.0
+    }
+
+    /// Hash a string
+    #[inline]
+    pub const fn hash(value: &str) -> u32 {
+        let mut h = Self::new();
+        h.update(value.as_bytes());
+        h.finish()
+    }
+}
+
+/// Decimal representation of an index in `buf[start..]`, returns `(buf, start)`
+pub(crate) const fn decimal(mut index: usize) -> ([u8; 20], usize) {
+    let mut buf = [0; 20];
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (index % 10) as u8;
+        index /= 10;
+        if index == 0 {
+            break;
+        }
+    }
+    (buf, start)
+}
+
+impl Write for Fnv1a {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.update(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl Walk for Fnv1a {
+    type Error = core::convert::Infallible;
+
+    #[inline]
+    fn leaf() -> Self {
+        let mut h = Self::default();
+        h.update(b"L");
+        h
+    }
+
+    fn internal(children: &[&Self], lookup: &KeyLookup) -> Result<Self, Self::Error> {
+        let mut h = Self::default();
+        match lookup {
+            KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => {
+                h.update(b"N");
+                for (name, child) in names.iter().zip(children) {
+                    h.update(name.as_bytes());
+                    h.update(&child.finish().to_le_bytes());
+                }
+            }
+            KeyLookup::Numbered(_) => {
+                h.update(b"U");
+                for child in children {
+                    h.update(&child.finish().to_le_bytes());
+                }
+            }
+            KeyLookup::Homogeneous(len) => {
+                h.update(b"H");
+                h.update(&(len.get() as u64).to_le_bytes());
+                h.update(&children[0].finish().to_le_bytes());
+            }
+        }
+        Ok(h)
+    }
+}
+
+/// Sorted lookup table from canonical path hash to [`Packed`] key for all leaves.
+///
+/// This provides `O(log N)` lookup of leaves by the [`Fnv1a`] hash of their canonical
+/// `'/'`-separated path (see [`TreeKey::find_by_hash()`] for the linear search).
+///
+/// The table is built from [`TreeKey::SCHEMA`] and can be constructed at compile time.
+/// Construction fails with [`Traversal::Invalid`] on a hash collision between leaves.
+///
+/// ```
+/// use miniconf::{Fnv1a, HashIndex, Leaf, Packed, TreeKey};
+/// #[derive(TreeKey)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// const INDEX: HashIndex<3> = match HashIndex::new::<S>() {
+///     Ok(index) => index,
+///     Err(_) => panic!("S can not be indexed"),
+/// };
+/// let (packed, _node) = S::transcode::<Packed, _>(["bar", "1"]).unwrap();
+/// assert_eq!(INDEX.get(Fnv1a::hash("/bar/1")), Some(packed));
+/// assert_eq!(INDEX.get(Fnv1a::hash("/bar")), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashIndex<const N: usize>([(u32, Packed); N]);
+
+impl<const N: usize> HashIndex<N> {
+    /// Build the table for the leaves of `M`.
+    ///
+    /// `N` must match the number of leaves of `M`.
+    pub const fn new<M: TreeKey + ?Sized>() -> Result<Self, Traversal> {
+        let mut table = [(0, Packed::EMPTY); N];
+        let mut len = 0;
+        if let Err(err) = Self::insert(&M::SCHEMA, Fnv1a::new(), 1, 0, &mut table, &mut len) {
+            return Err(err);
+        }
+        if len < N {
+            return Err(Traversal::Invalid(0, "Too few leaves"));
+        }
+        // Insertion sort by hash
+        let mut i = 1;
+        while i < N {
+            let mut j = i;
+            while j > 0 && table[j - 1].0 > table[j].0 {
+                let t = table[j];
+                table[j] = table[j - 1];
+                table[j - 1] = t;
+                j -= 1;
+            }
+            i += 1;
+        }
+        let mut i = 1;
+        while i < N {
+            if table[i - 1].0 == table[i].0 {
+                return Err(Traversal::Invalid(0, "Path hash collision"));
+            }
+            i += 1;
+        }
+        Ok(Self(table))
+    }
+
+    /// Recursively add the leaves below `schema`.
+    ///
+    /// `hash` is the hash of the path to the node and `lsb` the LSB aligned
+    /// `Packed` representation of its key.
+    const fn insert(
+        schema: &Schema,
+        hash: Fnv1a,
+        lsb: usize,
+        depth: usize,
+        table: &mut [(u32, Packed); N],
+        len: &mut usize,
+    ) -> Result<(), Traversal> {
+        match schema {
+            Schema::Leaf => {
+                if *len >= N {
+                    return Err(Traversal::Invalid(0, "Too many leaves"));
+                }
+                let Some(packed) = Packed::new_from_lsb(lsb) else {
+                    unreachable!()
+                };
+                table[*len] = (hash.finish(), packed);
+                *len += 1;
+                Ok(())
+            }
+            Schema::Internal(lookup, children) => {
+                let bits = Packed::bits_for(lookup.len().get() - 1);
+                if lsb.leading_zeros() < bits {
+                    return Err(Traversal::TooLong(depth));
+                }
+                let mut index = 0;
+                while index < lookup.len().get() {
+                    let mut h = hash;
+                    h.update(b"/");
+                    let child = match lookup {
+                        KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => {
+                            h.update(names[index].as_bytes());
+                            &children[index]
+                        }
+                        KeyLookup::Numbered(_) => {
+                            h.update_index(index);
+                            &children[index]
+                        }
+                        KeyLookup::Homogeneous(_) => {
+                            h.update_index(index);
+                            &children[0]
+                        }
+                    };
+                    if let Err(err) =
+                        Self::insert(child, h, (lsb << bits) | index, depth + 1, table, len)
+                    {
+                        return Err(err);
+                    }
+                    index += 1;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Look up the `Packed` key of the leaf with the given path hash.
+    pub fn get(&self, hash: u32) -> Option<Packed> {
+        self.0
+            .binary_search_by_key(&hash, |(h, _)| *h)
+            .ok()
+            .map(|i| self.0[i].1)
+    }
+}
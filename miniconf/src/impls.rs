@@ -1,11 +1,12 @@
 use core::cell::{Cell, RefCell};
-use core::ops::{Bound, Range, RangeFrom, RangeInclusive, RangeTo};
-use core::{any::Any, num::NonZero};
+use core::ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive};
+use core::{any::Any, marker::PhantomData, num::NonZero};
 
-use serde::{Deserializer, Serializer};
+use serde::{Deserialize, Deserializer, Serializer};
 
 use crate::{
-    Error, KeyLookup, Keys, Traversal, TreeAny, TreeDeserialize, TreeKey, TreeSerialize, Walk,
+    Error, Key, KeyLookup, Keys, Schema, Traversal, TreeAny, TreeDeserialize, TreeKey,
+    TreeSerialize, Walk,
 };
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -14,6 +15,8 @@ macro_rules! impl_tuple {
     ($n:literal $($i:tt $t:ident)+) => {
         #[allow(unreachable_code, unused_mut, unused)]
         impl<$($t: TreeKey),+> TreeKey for ($($t,)+) {
+            const SCHEMA: Schema = Schema::Internal(KeyLookup::numbered($n), &[$($t::SCHEMA, )+]);
+
             fn traverse_all<W: Walk>() -> Result<W, W::Error> {
                 W::internal(&[$(&$t::traverse_all()?, )+], &KeyLookup::numbered($n))
             }
@@ -86,6 +89,19 @@ macro_rules! impl_tuple {
                     _ => unreachable!()
                 }.map_err(Traversal::increment)
             }
+
+
+            fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+            where
+                K: Keys,
+                F: FnOnce(&dyn Any) -> R,
+            {
+                let index = keys.next(&KeyLookup::numbered($n))?;
+                match index {
+                    $($i => self.$i.with_any_by_key(keys, func),)+
+                    _ => unreachable!()
+                }.map_err(Traversal::increment)
+            }
         }
     }
 }
@@ -107,6 +123,8 @@ impl<const L: usize, const R: usize> Assert<L, R> {
 }
 
 impl<T: TreeKey, const N: usize> TreeKey for [T; N] {
+    const SCHEMA: Schema = Schema::Internal(KeyLookup::homogeneous(N), &[T::SCHEMA]);
+
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         let () = Assert::<N, 0>::GREATER; // internal nodes must have at least one leaf
         W::internal(&[&T::traverse_all()?], &KeyLookup::homogeneous(N))
@@ -147,6 +165,91 @@ impl<'de, T: TreeDeserialize<'de>, const N: usize> TreeDeserialize<'de> for [T;
     }
 }
 
+/// Deserialize a sequence of at most `N` elements into a prefix of `array`
+///
+/// The remaining elements are left unchanged.
+/// The sequence is deserialized into a temporary first. On error the array is unchanged.
+/// This is used by the `partial_array` derive macro field attribute.
+#[doc(hidden)]
+pub fn deserialize_prefix<'de, T: Deserialize<'de>, D: Deserializer<'de>, const N: usize>(
+    array: &mut [T; N],
+    de: D,
+) -> Result<usize, D::Error> {
+    struct Prefix<T, const N: usize>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>, const N: usize> serde::de::Visitor<'de> for Prefix<T, N> {
+        type Value = [Option<T>; N];
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(formatter, "a sequence of at most {N} elements")
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(
+            self,
+            mut seq: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut values = core::array::from_fn(|_| None);
+            let mut len = 0;
+            while len < N {
+                let Some(value) = seq.next_element()? else {
+                    return Ok(values);
+                };
+                values[len] = Some(value);
+                len += 1;
+            }
+            if seq.next_element::<serde::de::IgnoredAny>()?.is_some() {
+                return Err(serde::de::Error::invalid_length(N + 1, &self));
+            }
+            Ok(values)
+        }
+    }
+
+    let values = de.deserialize_seq(Prefix::<T, N>(PhantomData))?;
+    let mut len = 0;
+    for (item, value) in array.iter_mut().zip(values) {
+        let Some(value) = value else {
+            break;
+        };
+        *item = value;
+        len += 1;
+    }
+    Ok(len)
+}
+
+/// Deserialize a variant name and return its index in `names`
+///
+/// The name does not need to be borrowed from the input.
+/// This is used by the `tag` derive macro container attribute.
+#[doc(hidden)]
+pub fn deserialize_tag<'de, D: Deserializer<'de>>(
+    names: &'static [&'static str],
+    ignore_case: bool,
+    de: D,
+) -> Result<usize, Error<D::Error>> {
+    struct Tag(KeyLookup, bool);
+
+    impl serde::de::Visitor<'_> for Tag {
+        type Value = Result<usize, Traversal>;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            formatter.write_str("a variant name")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(if self.1 {
+                v.find_ignore_case(&self.0)
+            } else {
+                Key::find(v, &self.0)
+            })
+        }
+    }
+
+    let index = de
+        .deserialize_str(Tag(KeyLookup::Named(names), ignore_case))
+        .map_err(|err| Error::Inner(0, err))?;
+    Ok(index.or(Err(Traversal::Invalid(0, "Unknown variant")))?)
+}
+
 impl<T: TreeAny, const N: usize> TreeAny for [T; N] {
     fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
     where
@@ -167,33 +270,56 @@ impl<T: TreeAny, const N: usize> TreeAny for [T; N] {
             .mut_any_by_key(keys)
             .map_err(Traversal::increment)
     }
+
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        let index = keys.next(&KeyLookup::homogeneous(N))?;
+        self[index]
+            .with_any_by_key(keys, func)
+            .map_err(Traversal::increment)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
 impl<T: TreeKey> TreeKey for Option<T> {
+    const SCHEMA: Schema = T::SCHEMA;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         T::traverse_all()
     }
 
     #[inline]
-    fn traverse_by_key<K, F, E>(keys: K, func: F) -> Result<usize, Error<E>>
+    fn traverse_by_key<K, F, E>(mut keys: K, func: F) -> Result<usize, Error<E>>
     where
         K: Keys,
         F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
     {
+        if keys.take_presence() {
+            keys.finalize()?;
+            return Ok(0);
+        }
         T::traverse_by_key(keys, func)
     }
 }
 
 impl<T: TreeSerialize> TreeSerialize for Option<T> {
     #[inline]
-    fn serialize_by_key<K, S>(&self, keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
     where
         K: Keys,
         S: Serializer,
     {
+        if keys.take_presence() {
+            keys.finalize()?;
+            ser.serialize_bool(self.is_some())
+                .map_err(|err| Error::Inner(0, err))?;
+            return Ok(0);
+        }
         self.as_ref()
             .ok_or(Traversal::Absent(0))?
             .serialize_by_key(keys, ser)
@@ -202,11 +328,21 @@ impl<T: TreeSerialize> TreeSerialize for Option<T> {
 
 impl<'de, T: TreeDeserialize<'de>> TreeDeserialize<'de> for Option<T> {
     #[inline]
-    fn deserialize_by_key<K, D>(&mut self, keys: K, de: D) -> Result<usize, Error<D::Error>>
+    fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
     where
         K: Keys,
         D: Deserializer<'de>,
     {
+        if keys.take_presence() {
+            keys.finalize()?;
+            let present = bool::deserialize(de).map_err(|err| Error::Inner(0, err))?;
+            if !present {
+                *self = None;
+            } else if self.is_none() {
+                return Err(Traversal::Invalid(0, "Can't create absent value").into());
+            }
+            return Ok(0);
+        }
         self.as_mut()
             .ok_or(Traversal::Absent(0))?
             .deserialize_by_key(keys, de)
@@ -233,6 +369,17 @@ impl<T: TreeAny> TreeAny for Option<T> {
             .ok_or(Traversal::Absent(0))?
             .mut_any_by_key(keys)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        self.as_ref()
+            .ok_or(Traversal::Absent(0))?
+            .with_any_by_key(keys, func)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -240,18 +387,23 @@ impl<T: TreeAny> TreeAny for Option<T> {
 const RESULT_LOOKUP: KeyLookup = KeyLookup::Named(&["Ok", "Err"]);
 
 impl<T: TreeKey, E: TreeKey> TreeKey for Result<T, E> {
+    const SCHEMA: Schema = Schema::Internal(RESULT_LOOKUP, &[T::SCHEMA, E::SCHEMA]);
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         W::internal(&[&T::traverse_all()?, &E::traverse_all()?], &RESULT_LOOKUP)
     }
 
     #[inline]
-    fn traverse_by_key<K, F, G>(mut keys: K, func: F) -> Result<usize, Error<G>>
+    fn traverse_by_key<K, F, G>(mut keys: K, mut func: F) -> Result<usize, Error<G>>
     where
         K: Keys,
         F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), G>,
     {
-        Error::increment_result(match keys.next(&RESULT_LOOKUP)? {
+        let index = keys.next(&RESULT_LOOKUP)?;
+        let name = RESULT_LOOKUP.lookup(index)?;
+        func(index, name, RESULT_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+        Error::increment_result(match index {
             0 => T::traverse_by_key(keys, func),
             1 => E::traverse_by_key(keys, func),
             _ => unreachable!(),
@@ -315,6 +467,20 @@ impl<T: TreeAny, E: TreeAny> TreeAny for Result<T, E> {
         }
         .map_err(Traversal::increment)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        match (self, keys.next(&RESULT_LOOKUP)?) {
+            (Ok(value), 0) => value.with_any_by_key(keys, func),
+            (Err(value), 1) => value.with_any_by_key(keys, func),
+            _ => Err(Traversal::Absent(0)),
+        }
+        .map_err(Traversal::increment)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -322,18 +488,23 @@ impl<T: TreeAny, E: TreeAny> TreeAny for Result<T, E> {
 const BOUND_LOOKUP: KeyLookup = KeyLookup::Named(&["Included", "Excluded"]);
 
 impl<T: TreeKey> TreeKey for Bound<T> {
+    const SCHEMA: Schema = Schema::Internal(BOUND_LOOKUP, &[T::SCHEMA; 2]);
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         W::internal(&[&T::traverse_all()?; 2], &BOUND_LOOKUP)
     }
 
     #[inline]
-    fn traverse_by_key<K, F, G>(mut keys: K, func: F) -> Result<usize, Error<G>>
+    fn traverse_by_key<K, F, G>(mut keys: K, mut func: F) -> Result<usize, Error<G>>
     where
         K: Keys,
         F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), G>,
     {
-        Error::increment_result(match keys.next(&BOUND_LOOKUP)? {
+        let index = keys.next(&BOUND_LOOKUP)?;
+        let name = BOUND_LOOKUP.lookup(index)?;
+        func(index, name, BOUND_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+        Error::increment_result(match index {
             0..=1 => T::traverse_by_key(keys, func),
             _ => unreachable!(),
         })
@@ -396,6 +567,21 @@ impl<T: TreeAny> TreeAny for Bound<T> {
         }
         .map_err(Traversal::increment)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        match (self, keys.next(&BOUND_LOOKUP)?) {
+            (Self::Included(value), 0) | (Self::Excluded(value), 1) => {
+                value.with_any_by_key(keys, func)
+            }
+            _ => Err(Traversal::Absent(0)),
+        }
+        .map_err(Traversal::increment)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -403,18 +589,23 @@ impl<T: TreeAny> TreeAny for Bound<T> {
 const RANGE_LOOKUP: KeyLookup = KeyLookup::Named(&["start", "end"]);
 
 impl<T: TreeKey> TreeKey for Range<T> {
+    const SCHEMA: Schema = Schema::Internal(RANGE_LOOKUP, &[T::SCHEMA; 2]);
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         W::internal(&[&T::traverse_all()?; 2], &RANGE_LOOKUP)
     }
 
     #[inline]
-    fn traverse_by_key<K, F, G>(mut keys: K, func: F) -> Result<usize, Error<G>>
+    fn traverse_by_key<K, F, G>(mut keys: K, mut func: F) -> Result<usize, Error<G>>
     where
         K: Keys,
         F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), G>,
     {
-        Error::increment_result(match keys.next(&RANGE_LOOKUP)? {
+        let index = keys.next(&RANGE_LOOKUP)?;
+        let name = RANGE_LOOKUP.lookup(index)?;
+        func(index, name, RANGE_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+        Error::increment_result(match index {
             0..=1 => T::traverse_by_key(keys, func),
             _ => unreachable!(),
         })
@@ -477,23 +668,42 @@ impl<T: TreeAny> TreeAny for Range<T> {
         }
         .map_err(Traversal::increment)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        match keys.next(&RANGE_LOOKUP)? {
+            0 => self.start.with_any_by_key(keys, func),
+            1 => self.end.with_any_by_key(keys, func),
+            _ => unreachable!(),
+        }
+        .map_err(Traversal::increment)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
 impl<T: TreeKey> TreeKey for RangeInclusive<T> {
+    const SCHEMA: Schema = Schema::Internal(RANGE_LOOKUP, &[T::SCHEMA; 2]);
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         W::internal(&[&T::traverse_all()?; 2], &RANGE_LOOKUP)
     }
 
     #[inline]
-    fn traverse_by_key<K, F, G>(mut keys: K, func: F) -> Result<usize, Error<G>>
+    fn traverse_by_key<K, F, G>(mut keys: K, mut func: F) -> Result<usize, Error<G>>
     where
         K: Keys,
         F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), G>,
     {
-        Error::increment_result(match keys.next(&RANGE_LOOKUP)? {
+        let index = keys.next(&RANGE_LOOKUP)?;
+        let name = RANGE_LOOKUP.lookup(index)?;
+        func(index, name, RANGE_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+        Error::increment_result(match index {
             0..=1 => T::traverse_by_key(keys, func),
             _ => unreachable!(),
         })
@@ -520,18 +730,23 @@ impl<T: TreeSerialize> TreeSerialize for RangeInclusive<T> {
 const RANGE_FROM_LOOKUP: KeyLookup = KeyLookup::Named(&["start"]);
 
 impl<T: TreeKey> TreeKey for RangeFrom<T> {
+    const SCHEMA: Schema = Schema::Internal(RANGE_FROM_LOOKUP, &[T::SCHEMA]);
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         W::internal(&[&T::traverse_all()?], &RANGE_FROM_LOOKUP)
     }
 
     #[inline]
-    fn traverse_by_key<K, F, G>(mut keys: K, func: F) -> Result<usize, Error<G>>
+    fn traverse_by_key<K, F, G>(mut keys: K, mut func: F) -> Result<usize, Error<G>>
     where
         K: Keys,
         F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), G>,
     {
-        Error::increment_result(match keys.next(&RANGE_FROM_LOOKUP)? {
+        let index = keys.next(&RANGE_FROM_LOOKUP)?;
+        let name = RANGE_FROM_LOOKUP.lookup(index)?;
+        func(index, name, RANGE_FROM_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+        Error::increment_result(match index {
             0 => T::traverse_by_key(keys, func),
             _ => unreachable!(),
         })
@@ -590,6 +805,19 @@ impl<T: TreeAny> TreeAny for RangeFrom<T> {
         }
         .map_err(Traversal::increment)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        match keys.next(&RANGE_FROM_LOOKUP)? {
+            0 => self.start.with_any_by_key(keys, func),
+            _ => unreachable!(),
+        }
+        .map_err(Traversal::increment)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -597,18 +825,23 @@ impl<T: TreeAny> TreeAny for RangeFrom<T> {
 const RANGE_TO_LOOKUP: KeyLookup = KeyLookup::Named(&["end"]);
 
 impl<T: TreeKey> TreeKey for RangeTo<T> {
+    const SCHEMA: Schema = Schema::Internal(RANGE_TO_LOOKUP, &[T::SCHEMA]);
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         W::internal(&[&T::traverse_all()?], &RANGE_TO_LOOKUP)
     }
 
     #[inline]
-    fn traverse_by_key<K, F, G>(mut keys: K, func: F) -> Result<usize, Error<G>>
+    fn traverse_by_key<K, F, G>(mut keys: K, mut func: F) -> Result<usize, Error<G>>
     where
         K: Keys,
         F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), G>,
     {
-        Error::increment_result(match keys.next(&RANGE_TO_LOOKUP)? {
+        let index = keys.next(&RANGE_TO_LOOKUP)?;
+        let name = RANGE_TO_LOOKUP.lookup(index)?;
+        func(index, name, RANGE_TO_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+        Error::increment_result(match index {
             0 => T::traverse_by_key(keys, func),
             _ => unreachable!(),
         })
@@ -667,11 +900,186 @@ impl<T: TreeAny> TreeAny for RangeTo<T> {
         }
         .map_err(Traversal::increment)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        match keys.next(&RANGE_TO_LOOKUP)? {
+            0 => self.end.with_any_by_key(keys, func),
+            _ => unreachable!(),
+        }
+        .map_err(Traversal::increment)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+impl<T: TreeKey> TreeKey for RangeToInclusive<T> {
+    const SCHEMA: Schema = Schema::Internal(RANGE_TO_LOOKUP, &[T::SCHEMA]);
+
+    #[inline]
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        W::internal(&[&T::traverse_all()?], &RANGE_TO_LOOKUP)
+    }
+
+    #[inline]
+    fn traverse_by_key<K, F, G>(mut keys: K, mut func: F) -> Result<usize, Error<G>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), G>,
+    {
+        let index = keys.next(&RANGE_TO_LOOKUP)?;
+        let name = RANGE_TO_LOOKUP.lookup(index)?;
+        func(index, name, RANGE_TO_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+        Error::increment_result(match index {
+            0 => T::traverse_by_key(keys, func),
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl<T: TreeSerialize> TreeSerialize for RangeToInclusive<T> {
+    #[inline]
+    fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    where
+        K: Keys,
+        S: Serializer,
+    {
+        Error::increment_result(match keys.next(&RANGE_TO_LOOKUP)? {
+            0 => self.end.serialize_by_key(keys, ser),
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl<'de, T: TreeDeserialize<'de>> TreeDeserialize<'de> for RangeToInclusive<T> {
+    #[inline]
+    fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
+    where
+        K: Keys,
+        D: Deserializer<'de>,
+    {
+        Error::increment_result(match keys.next(&RANGE_TO_LOOKUP)? {
+            0 => self.end.deserialize_by_key(keys, de),
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl<T: TreeAny> TreeAny for RangeToInclusive<T> {
+    #[inline]
+    fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        match keys.next(&RANGE_TO_LOOKUP)? {
+            0 => self.end.ref_any_by_key(keys),
+            _ => unreachable!(),
+        }
+        .map_err(Traversal::increment)
+    }
+
+    #[inline]
+    fn mut_any_by_key<K>(&mut self, mut keys: K) -> Result<&mut dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        match keys.next(&RANGE_TO_LOOKUP)? {
+            0 => self.end.mut_any_by_key(keys),
+            _ => unreachable!(),
+        }
+        .map_err(Traversal::increment)
+    }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        match keys.next(&RANGE_TO_LOOKUP)? {
+            0 => self.end.with_any_by_key(keys, func),
+            _ => unreachable!(),
+        }
+        .map_err(Traversal::increment)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+impl TreeKey for RangeFull {
+    const SCHEMA: Schema = Schema::Leaf;
+
+    #[inline]
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        Ok(W::leaf())
+    }
+
+    #[inline]
+    fn traverse_by_key<K, F, E>(mut keys: K, _func: F) -> Result<usize, Error<E>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+    {
+        keys.finalize()?;
+        Ok(0)
+    }
+}
+
+impl TreeSerialize for RangeFull {
+    #[inline]
+    fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    where
+        K: Keys,
+        S: Serializer,
+    {
+        keys.finalize()?;
+        ser.serialize_unit().map_err(|err| Error::Inner(0, err))?;
+        Ok(0)
+    }
+}
+
+impl<'de> TreeDeserialize<'de> for RangeFull {
+    #[inline]
+    fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
+    where
+        K: Keys,
+        D: Deserializer<'de>,
+    {
+        keys.finalize()?;
+        <()>::deserialize(de).map_err(|err| Error::Inner(0, err))?;
+        Ok(0)
+    }
+}
+
+impl TreeAny for RangeFull {
+    #[inline]
+    fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        keys.finalize()?;
+        Ok(self)
+    }
+
+    #[inline]
+    fn mut_any_by_key<K>(&mut self, mut keys: K) -> Result<&mut dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        keys.finalize()?;
+        Ok(self)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
 impl<T: TreeKey> TreeKey for Cell<T> {
+    const SCHEMA: Schema = T::SCHEMA;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         T::traverse_all()
@@ -730,6 +1138,8 @@ impl<T: TreeAny> TreeAny for Cell<T> {
 /////////////////////////////////////////////////////////////////////////////////////////
 
 impl<T: TreeKey> TreeKey for RefCell<T> {
+    const SCHEMA: Schema = T::SCHEMA;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         T::traverse_all()
@@ -798,6 +1208,17 @@ impl<T: TreeAny> TreeAny for RefCell<T> {
     {
         self.get_mut().mut_any_by_key(keys)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        self.try_borrow()
+            .or(Err(Traversal::Access(0, "Borrowed")))?
+            .with_any_by_key(keys, func)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -809,6 +1230,8 @@ mod _alloc {
     use alloc::{borrow::Cow, boxed::Box, rc, rc::Rc, sync, sync::Arc};
 
     impl<T: TreeKey> TreeKey for Box<T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -862,11 +1285,22 @@ mod _alloc {
         {
             (**self).mut_any_by_key(keys)
         }
+
+        #[inline]
+        fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+        where
+            K: Keys,
+            F: FnOnce(&dyn Any) -> R,
+        {
+            (**self).with_any_by_key(keys, func)
+        }
     }
 
     /////////////////////////////////////////////////////////////////////////////////////////
 
     impl<'a, T: TreeKey + Clone> TreeKey for Cow<'a, T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -920,11 +1354,22 @@ mod _alloc {
         {
             self.to_mut().mut_any_by_key(keys)
         }
+
+        #[inline]
+        fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+        where
+            K: Keys,
+            F: FnOnce(&dyn Any) -> R,
+        {
+            (**self).with_any_by_key(keys, func)
+        }
     }
 
     /////////////////////////////////////////////////////////////////////////////////////////
 
     impl<T: TreeKey> TreeKey for Rc<T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -982,11 +1427,22 @@ mod _alloc {
                 .ok_or(Traversal::Access(0, "Reference is taken"))?
                 .mut_any_by_key(keys)
         }
+
+        #[inline]
+        fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+        where
+            K: Keys,
+            F: FnOnce(&dyn Any) -> R,
+        {
+            (**self).with_any_by_key(keys, func)
+        }
     }
 
     /////////////////////////////////////////////////////////////////////////////////////////
 
     impl<T: TreeKey> TreeKey for rc::Weak<T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -1031,6 +1487,8 @@ mod _alloc {
     /////////////////////////////////////////////////////////////////////////////////////////
 
     impl<T: TreeKey> TreeKey for Arc<T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -1088,11 +1546,22 @@ mod _alloc {
                 .ok_or(Traversal::Access(0, "Reference is taken"))?
                 .mut_any_by_key(keys)
         }
+
+        #[inline]
+        fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+        where
+            K: Keys,
+            F: FnOnce(&dyn Any) -> R,
+        {
+            (**self).with_any_by_key(keys, func)
+        }
     }
 
     /////////////////////////////////////////////////////////////////////////////////////////
 
     impl<T: TreeKey> TreeKey for sync::Weak<T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -1143,6 +1612,8 @@ mod _std {
     use std::sync::{Mutex, RwLock};
 
     impl<T: TreeKey> TreeKey for Mutex<T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -1216,11 +1687,24 @@ mod _std {
                 .or(Err(Traversal::Access(0, "Poisoned")))?
                 .mut_any_by_key(keys)
         }
+
+        #[inline]
+        fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+        where
+            K: Keys,
+            F: FnOnce(&dyn Any) -> R,
+        {
+            self.lock()
+                .or(Err(Traversal::Access(0, "Poisoned")))?
+                .with_any_by_key(keys, func)
+        }
     }
 
     /////////////////////////////////////////////////////////////////////////////////////////
 
     impl<T: TreeKey> TreeKey for RwLock<T> {
+        const SCHEMA: Schema = T::SCHEMA;
+
         #[inline]
         fn traverse_all<W: Walk>() -> Result<W, W::Error> {
             T::traverse_all()
@@ -1293,5 +1777,123 @@ mod _std {
                 .or(Err(Traversal::Access(0, "Poisoned")))?
                 .mut_any_by_key(keys)
         }
+
+        #[inline]
+        fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+        where
+            K: Keys,
+            F: FnOnce(&dyn Any) -> R,
+        {
+            self.read()
+                .or(Err(Traversal::Access(0, "Poisoned")))?
+                .with_any_by_key(keys, func)
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "either")]
+mod _either {
+    use super::*;
+    use either::Either;
+
+    const EITHER_LOOKUP: KeyLookup = KeyLookup::Named(&["Left", "Right"]);
+
+    impl<L: TreeKey, R: TreeKey> TreeKey for Either<L, R> {
+        const SCHEMA: Schema = Schema::Internal(EITHER_LOOKUP, &[L::SCHEMA, R::SCHEMA]);
+
+        #[inline]
+        fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+            W::internal(&[&L::traverse_all()?, &R::traverse_all()?], &EITHER_LOOKUP)
+        }
+
+        #[inline]
+        fn traverse_by_key<K, F, E>(mut keys: K, mut func: F) -> Result<usize, Error<E>>
+        where
+            K: Keys,
+            F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+        {
+            let index = keys.next(&EITHER_LOOKUP)?;
+            let name = EITHER_LOOKUP.lookup(index)?;
+            func(index, name, EITHER_LOOKUP.len()).map_err(|err| Error::Inner(1, err))?;
+            Error::increment_result(match index {
+                0 => L::traverse_by_key(keys, func),
+                1 => R::traverse_by_key(keys, func),
+                _ => unreachable!(),
+            })
+        }
+    }
+
+    impl<L: TreeSerialize, R: TreeSerialize> TreeSerialize for Either<L, R> {
+        #[inline]
+        fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
+        where
+            K: Keys,
+            S: Serializer,
+        {
+            Error::increment_result(match (self, keys.next(&EITHER_LOOKUP)?) {
+                (Either::Left(value), 0) => value.serialize_by_key(keys, ser),
+                (Either::Right(value), 1) => value.serialize_by_key(keys, ser),
+                _ => Err(Traversal::Absent(0).into()),
+            })
+        }
+    }
+
+    impl<'de, L: TreeDeserialize<'de>, R: TreeDeserialize<'de>> TreeDeserialize<'de> for Either<L, R> {
+        #[inline]
+        fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
+        where
+            K: Keys,
+            D: Deserializer<'de>,
+        {
+            Error::increment_result(match (self, keys.next(&EITHER_LOOKUP)?) {
+                (Either::Left(value), 0) => value.deserialize_by_key(keys, de),
+                (Either::Right(value), 1) => value.deserialize_by_key(keys, de),
+                _ => Err(Traversal::Absent(0).into()),
+            })
+        }
+    }
+
+    impl<L: TreeAny, R: TreeAny> TreeAny for Either<L, R> {
+        #[inline]
+        fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
+        where
+            K: Keys,
+        {
+            match (self, keys.next(&EITHER_LOOKUP)?) {
+                (Either::Left(value), 0) => value.ref_any_by_key(keys),
+                (Either::Right(value), 1) => value.ref_any_by_key(keys),
+                _ => Err(Traversal::Absent(0)),
+            }
+            .map_err(Traversal::increment)
+        }
+
+        #[inline]
+        fn mut_any_by_key<K>(&mut self, mut keys: K) -> Result<&mut dyn Any, Traversal>
+        where
+            K: Keys,
+        {
+            match (self, keys.next(&EITHER_LOOKUP)?) {
+                (Either::Left(value), 0) => value.mut_any_by_key(keys),
+                (Either::Right(value), 1) => value.mut_any_by_key(keys),
+                _ => Err(Traversal::Absent(0)),
+            }
+            .map_err(Traversal::increment)
+        }
+
+        #[inline]
+        fn with_any_by_key<K, F, T>(&self, mut keys: K, func: F) -> Result<T, Traversal>
+        where
+            K: Keys,
+            F: FnOnce(&dyn Any) -> T,
+        {
+            match (self, keys.next(&EITHER_LOOKUP)?) {
+                (Either::Left(value), 0) => value.with_any_by_key(keys, func),
+                (Either::Right(value), 1) => value.with_any_by_key(keys, func),
+                _ => Err(Traversal::Absent(0)),
+            }
+            .map_err(Traversal::increment)
+        }
     }
 }
@@ -0,0 +1,73 @@
+//! Apply flat INI / `key = value` files.
+//!
+//! Each line `<KEY> = <VALUE>` is applied to the leaf identified by `<KEY>`.
+//! `<KEY>` is the sequence of names or indices separated by `'.'` or `'/'`,
+//! e.g. `i.1.val` or `/i/1/val`.
+//! A `[<SECTION>]` line prefixes the keys of the following lines with `<SECTION>`.
+//! `<VALUE>` is JSON. If it does not deserialize as JSON, it is
+//! retried as a bare (unquoted) JSON string.
+//! Blank lines and lines starting with `'#'` or `';'` are ignored.
+//!
+//! ```
+//! use miniconf::{ini, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct Inner {
+//!     val: Leaf<i32>,
+//! };
+//! #[derive(Tree, Default)]
+//! struct S {
+//!     name: Leaf<String>,
+//!     i: [Inner; 2],
+//! };
+//! let mut s = S::default();
+//! let text = "
+//! ; device
+//! name = dev a
+//! [i]
+//! 1.val = 9
+//! ";
+//! assert_eq!(ini::set_all(&mut s, text), Ok(2));
+//! assert_eq!((s.name.as_str(), *s.i[1].val), ("dev a", 9));
+//! ```
+
+use serde_json_core::de;
+
+use crate::{env, Error, IntoKeys, Traversal, TreeDeserializeOwned};
+
+const SEPARATORS: [char; 2] = ['.', '/'];
+
+/// Apply all `key = value` lines of an INI document.
+///
+/// Values are applied in document order and the tree is updated up to the first error.
+///
+/// # Returns
+/// The number of applied values or the first error.
+/// Lines that are neither blank, comments, sections, nor `key = value` pairs are
+/// rejected with [`Traversal::Invalid`].
+pub fn set_all<T: TreeDeserializeOwned + ?Sized>(
+    tree: &mut T,
+    text: &str,
+) -> Result<usize, Error<de::Error>> {
+    let mut section = "";
+    let mut count = 0;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(s) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            section = s.trim();
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(Traversal::Invalid(0, "Expected `key = value`"))?;
+        let keys = section
+            .split(SEPARATORS)
+            .chain(key.trim().split(SEPARATORS))
+            .filter(|k| !k.is_empty())
+            .into_keys();
+        env::apply(tree, keys, value.trim())?;
+        count += 1;
+    }
+    Ok(count)
+}
@@ -1,6 +1,9 @@
 use core::marker::PhantomData;
 
-use crate::{IntoKeys, KeyLookup, Keys, Metadata, Node, Transcode, Traversal, TreeKey};
+use crate::{
+    IntoKeys, Key, KeyLookup, Keys, Limit, Metadata, Names, Node, NodeKind, Packed, PathIter,
+    Transcode, Traversal, TreeKey,
+};
 
 /// Counting wrapper for iterators with known exact size
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -18,6 +21,7 @@ impl<T: Iterator> Iterator for ExactSize<T> {
             self.count -= 1; // checks for overflow in debug
             Some(v)
         } else {
+            // A mismatch is a `TreeKey` implementation bug, see `NodeIter::verify_count()`
             debug_assert!(self.count == 0);
             None
         }
@@ -55,6 +59,41 @@ impl<T: Keys> Keys for Consume<T> {
         self.0.next(lookup)
     }
 
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        self.0.next_ignore_case(lookup)
+    }
+
+    #[inline]
+    fn next_named(&mut self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        self.0.next_named(lookup, names)
+    }
+
+    #[inline]
+    fn take_presence(&mut self) -> bool {
+        self.0.take_presence()
+    }
+
+    #[inline]
+    fn mark_secret(&mut self) {
+        self.0.mark_secret()
+    }
+
+    #[inline]
+    fn mark_default(&mut self) {
+        self.0.mark_default()
+    }
+
+    #[inline]
+    fn mark_doc(&mut self, doc: &'static str) {
+        self.0.mark_doc(doc)
+    }
+
+    #[inline]
+    fn mark_range(&mut self, min: Option<Limit>, max: Option<Limit>) {
+        self.0.mark_range(min, max)
+    }
+
     #[inline]
     fn finalize(&mut self) -> Result<(), Traversal> {
         Ok(())
@@ -115,6 +154,61 @@ impl<M: TreeKey + ?Sized, N, const D: usize> NodeIter<M, N, D> {
         Ok(self)
     }
 
+    /// Limit iteration to at and below the provided root key and resume
+    /// just after the node identified by `cursor`.
+    ///
+    /// The `cursor` is typically a previously obtained [`NodeIter::position()`]
+    /// that has been persisted.
+    /// `Packed::EMPTY` starts the iteration at the beginning of `root`.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Packed, Path, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let mut iter = S::nodes::<Path<String, '/'>, 2>();
+    /// iter.next();
+    /// let cursor = iter.position().unwrap();
+    /// let rest: Vec<_> = S::nodes::<Path<String, '/'>, 2>()
+    ///     .resume(Packed::EMPTY, cursor)
+    ///     .unwrap()
+    ///     .map(|p| p.unwrap().0.into_inner())
+    ///     .collect();
+    /// assert_eq!(rest, ["/bar/0", "/bar/1"]);
+    /// ```
+    ///
+    /// This requires moving `self` to ensure `FusedIterator`.
+    pub fn resume<K: IntoKeys>(self, root: K, cursor: Packed) -> Result<Self, Traversal> {
+        let mut this = self.root(root)?;
+        if cursor.is_empty() {
+            return Ok(this);
+        }
+        let mut state = [0; D];
+        let node = state.transcode::<M, _>(cursor)?;
+        if node.depth() <= this.root || state[..this.root] != this.state[..this.root] {
+            return Err(Traversal::Invalid(0, "Cursor not below root"));
+        }
+        this.state = state;
+        this.depth = node.depth();
+        Ok(this)
+    }
+
+    /// Return the `Packed` key of the node last returned by `next()`.
+    ///
+    /// This can be persisted and used to [`NodeIter::resume()`] the iteration later.
+    /// Returns `None` if iteration has not started or is complete (or if the key
+    /// exceeds the `Packed` capacity).
+    pub fn position(&self) -> Option<Packed> {
+        if self.depth > D || self.depth == self.root {
+            return None;
+        }
+        M::transcode::<Packed, _>(&self.state[..self.depth])
+            .ok()
+            .map(|(packed, _node)| packed)
+    }
+
     /// Wrap the iterator in an exact size counting iterator that is
     /// `FusedIterator` and `ExactSizeIterator`.
     ///
@@ -137,6 +231,133 @@ impl<M: TreeKey + ?Sized, N, const D: usize> NodeIter<M, N, D> {
         }
     }
 
+    /// Consume the iterator and verify that the number of nodes yielded matches
+    /// `Metadata::count` from `TreeKey::traverse_all()`.
+    ///
+    /// The count from `traverse_all()` backs the exact size of [`ExactSize`].
+    /// A mismatch indicates that `traverse_all()` and `traverse_by_key()` of `M` (or of
+    /// one of its descendants) disagree about the tree structure. For derived types this
+    /// is a bug in the derive macros, otherwise in the manual `TreeKey` implementation.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Path, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// assert_eq!(S::nodes::<Path<String, '/'>, 2>().verify_count(), 3);
+    /// ```
+    ///
+    /// # Returns
+    /// The number of nodes.
+    ///
+    /// Note(panic): Panics on a mismatch and under the same conditions as
+    /// [`NodeIter::exact_size()`].
+    pub fn verify_count(self) -> usize {
+        let iter = NodeIter::<M, (), D> {
+            state: self.state,
+            root: self.root,
+            depth: self.depth,
+            _n: PhantomData,
+            _m: PhantomData,
+        }
+        .exact_size();
+        let expected = iter.len();
+        let count = iter.iter.count();
+        assert_eq!(
+            count, expected,
+            "Node count mismatch: iteration yields {count}, `Metadata::count` is {expected}"
+        );
+        count
+    }
+
+    /// Iterate over all nodes of the given kind below the root in pre-order.
+    ///
+    /// In contrast to the `NodeIter` iteration, internal nodes (up to depth `D`)
+    /// are visited and returned before the nodes below them.
+    /// Internal nodes deeper than `D` are not visited.
+    /// The root node itself is not returned.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, NodeKind, Path, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [[Leaf<u16>; 2]; 2],
+    /// };
+    /// let arrays: Vec<_> = S::nodes::<Path<String, '/'>, 2>()
+    ///     .filter_kind(NodeKind::Homogeneous)
+    ///     .map(|p| p.unwrap().0.into_inner())
+    ///     .collect();
+    /// assert_eq!(arrays, ["/bar", "/bar/0", "/bar/1"]);
+    /// ```
+    ///
+    /// Note(panic): Panics, if the iterator had `next()` called.
+    pub fn filter_kind(self, kind: NodeKind) -> FilterKind<M, N, D> {
+        assert_eq!(self.depth, D + 1, "NodeIter partially consumed");
+        let descend = M::kind(&self.state[..self.root]).unwrap() != NodeKind::Leaf; // Note(unwrap): checked in root()
+        FilterKind {
+            state: self.state,
+            root: self.root,
+            depth: self.root,
+            descend,
+            kind,
+            _n: PhantomData,
+            _m: PhantomData,
+        }
+    }
+
+    /// Iterate over the same nodes in breadth-first (level) order.
+    ///
+    /// The nodes are yielded in order of increasing depth and in index order within
+    /// each depth. This groups siblings together.
+    /// The iteration root (see [`NodeIter::root()`]) is respected.
+    /// The returned iterator is an [`ExactSizeIterator`].
+    ///
+    /// Each level is a separate walk of the tree down to that level, and the
+    /// number of nodes is determined by an additional walk up front.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Path, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: [Leaf<u16>; 2],
+    ///     bar: Leaf<u32>,
+    /// };
+    /// let paths: Vec<_> = S::nodes::<Path<String, '/'>, 2>()
+    ///     .breadth_first()
+    ///     .map(|p| p.unwrap().0.into_inner())
+    ///     .collect();
+    /// assert_eq!(paths, ["/bar", "/foo/0", "/foo/1"]);
+    /// ```
+    ///
+    /// Note(panic): Panics, if the iterator had `next()` called.
+    pub fn breadth_first(self) -> BreadthFirst<M, N, D> {
+        assert_eq!(self.depth, D + 1, "NodeIter partially consumed");
+        let count = NodeIter::<M, (), D> {
+            state: self.state,
+            root: self.root,
+            depth: self.depth,
+            _n: PhantomData,
+            _m: PhantomData,
+        }
+        .count();
+        // A leaf or depth limited root is yielded itself
+        let terminal =
+            self.root == D || M::kind(&self.state[..self.root]).unwrap() == NodeKind::Leaf; // Note(unwrap): checked in root()
+        BreadthFirst {
+            state: self.state,
+            root: self.root,
+            depth: self.root,
+            level: if terminal { self.root } else { self.root + 1 },
+            descend: true,
+            count,
+            _n: PhantomData,
+            _m: PhantomData,
+        }
+    }
+
     /// Return the current iteration depth
     pub fn current_depth(&self) -> usize {
         self.depth
@@ -197,3 +418,323 @@ impl<M: TreeKey + ?Sized, N: Transcode + Default, const D: usize> core::iter::Fu
     for NodeIter<M, N, D>
 {
 }
+
+/// Pre-order iterator over the nodes of a given [`NodeKind`]
+///
+/// See [`NodeIter::filter_kind()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilterKind<M: ?Sized, N, const D: usize> {
+    state: [usize; D],
+    root: usize,
+    depth: usize,
+    descend: bool,
+    kind: NodeKind,
+    _n: PhantomData<N>,
+    _m: PhantomData<M>,
+}
+
+impl<M, N, const D: usize> Iterator for FilterKind<M, N, D>
+where
+    M: TreeKey + ?Sized,
+    N: Transcode + Default,
+{
+    type Item = Result<(N, Node), usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.descend && self.depth < D {
+                // Visit the first child of the last internal node
+                self.state[self.depth] = 0;
+                self.depth += 1;
+            } else if self.depth == self.root {
+                // Iteration done
+                return None;
+            } else {
+                // Visit the next sibling
+                self.state[self.depth - 1] += 1;
+            }
+            self.descend = false;
+            let keys = &self.state[..self.depth];
+            let kind = match M::kind(keys) {
+                Err(Traversal::NotFound(_)) => {
+                    // No more siblings: retry with the next sibling of the parent or terminate
+                    self.depth -= 1;
+                    continue;
+                }
+                Ok(kind) => kind,
+                // The prefix of the keys is valid and the keys are exhausted
+                _ => unreachable!(),
+            };
+            self.descend = kind != NodeKind::Leaf;
+            if kind == self.kind {
+                return Some(M::transcode(keys).map_err(|err| err.depth()));
+            }
+        }
+    }
+}
+
+impl<M: TreeKey + ?Sized, N: Transcode + Default, const D: usize> core::iter::FusedIterator
+    for FilterKind<M, N, D>
+{
+}
+
+/// Breadth-first (level order) node iterator
+///
+/// See [`NodeIter::breadth_first()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreadthFirst<M: ?Sized, N, const D: usize> {
+    state: [usize; D],
+    root: usize,
+    depth: usize,
+    level: usize,
+    descend: bool,
+    count: usize,
+    _n: PhantomData<N>,
+    _m: PhantomData<M>,
+}
+
+impl<M, N, const D: usize> Iterator for BreadthFirst<M, N, D>
+where
+    M: TreeKey + ?Sized,
+    N: Transcode + Default,
+{
+    type Item = Result<(N, Node), usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.level > D {
+                // Iteration done
+                debug_assert_eq!(self.count, 0);
+                return None;
+            }
+            if self.level == self.root {
+                // Leaf or depth limited root
+                self.level = D + 1;
+                self.count -= 1;
+                return Some(M::transcode(&self.state[..self.root]).map_err(|err| err.depth()));
+            }
+            if self.descend && self.depth < self.level {
+                // Visit the first child of the last internal node
+                self.state[self.depth] = 0;
+                self.depth += 1;
+            } else if self.depth == self.root {
+                // Level done: start the next level
+                self.level += 1;
+                self.descend = true;
+                continue;
+            } else {
+                // Visit the next sibling
+                self.state[self.depth - 1] += 1;
+            }
+            self.descend = false;
+            let keys = &self.state[..self.depth];
+            let kind = match M::kind(keys) {
+                Err(Traversal::NotFound(_)) => {
+                    // No more siblings: retry with the next sibling of the parent
+                    self.depth -= 1;
+                    continue;
+                }
+                Ok(kind) => kind,
+                // The prefix of the keys is valid and the keys are exhausted
+                _ => unreachable!(),
+            };
+            if self.depth < self.level {
+                // Leaves above the level have been returned already
+                self.descend = kind != NodeKind::Leaf;
+            } else if kind == NodeKind::Leaf || self.level == D {
+                self.count -= 1; // checks for overflow in debug
+                return Some(M::transcode(keys).map_err(|err| err.depth()));
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.count, Some(self.count))
+    }
+}
+
+impl<M: TreeKey + ?Sized, N: Transcode + Default, const D: usize> ExactSizeIterator
+    for BreadthFirst<M, N, D>
+{
+}
+
+impl<M: TreeKey + ?Sized, N: Transcode + Default, const D: usize> core::iter::FusedIterator
+    for BreadthFirst<M, N, D>
+{
+}
+
+/// Keys of a glob pattern at a given wildcard state
+struct Wildcard<'a, 'b, const S: char> {
+    path: PathIter<'a, S>,
+    state: &'b [usize],
+    lens: &'b mut [usize],
+    depth: usize,
+    presence: bool,
+}
+
+impl<'a, const S: char> Wildcard<'a, '_, S> {
+    #[inline]
+    fn next_key(&mut self) -> Result<&'a str, Traversal> {
+        if self.presence {
+            return Err(Traversal::TooLong(0));
+        }
+        let key = self.path.next().ok_or(Traversal::TooShort(0))?;
+        self.presence = key.is_presence();
+        self.depth += 1;
+        Ok(key)
+    }
+
+    #[inline]
+    fn expand(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        // Arrays with `index_names` (`KeyLookup::IndexNamed`) can be expanded
+        if matches!(lookup, KeyLookup::Named(_)) {
+            return Err(Traversal::Invalid(1, "Wildcard at named node"));
+        }
+        let index = *self
+            .state
+            .get(self.depth - 1)
+            .ok_or(Traversal::TooLong(0))?;
+        // Record the number of children to wrap around
+        self.lens[self.depth - 1] = lookup.len().get();
+        Ok(index)
+    }
+}
+
+impl<const S: char> Keys for Wildcard<'_, '_, S> {
+    #[inline]
+    fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        match self.next_key()? {
+            "*" => self.expand(lookup),
+            key => Key::find(key, lookup),
+        }
+    }
+
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        match self.next_key()? {
+            "*" => self.expand(lookup),
+            key => Key::find_ignore_case(key, lookup),
+        }
+    }
+
+    #[inline]
+    fn next_named(&mut self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        match self.next_key()? {
+            "*" => self.expand(lookup),
+            key => Key::find_named(key, lookup, names),
+        }
+    }
+
+    #[inline]
+    fn take_presence(&mut self) -> bool {
+        core::mem::take(&mut self.presence)
+    }
+
+    #[inline]
+    fn finalize(&mut self) -> Result<(), Traversal> {
+        (!self.presence && self.path.next().is_none())
+            .then_some(())
+            .ok_or(Traversal::TooLong(0))
+    }
+}
+
+impl<const S: char> IntoKeys for Wildcard<'_, '_, S> {
+    type IntoKeys = Self;
+
+    #[inline]
+    fn into_keys(self) -> Self::IntoKeys {
+        self
+    }
+}
+
+/// Glob pattern iterator
+///
+/// Expands `*` path segments across all children of the numbered or homogeneous
+/// node they are applied to and yields the matching concrete keys
+/// in pre-order.
+/// The keys (e.g. [`Packed`]) can be passed to [`NodeIter::root()`] to iterate
+/// the leaves below them.
+///
+/// A `*` segment at a named node yields [`Traversal::Invalid`].
+/// Other errors (e.g. a key that is not found below some of the expansions)
+/// are yielded and iteration continues with the next expansion.
+///
+/// `D` is the maximum depth of the pattern.
+///
+/// ```
+/// use miniconf::{Glob, Leaf, Packed, Path, Tree, TreeKey};
+/// #[derive(Tree)]
+/// struct S {
+///     ch: [[Leaf<f32>; 2]; 3],
+/// };
+/// let keys: Vec<_> = Glob::<S, Path<String, '/'>, 3>::new("/ch/*/1")
+///     .map(|k| k.unwrap().0.into_inner())
+///     .collect();
+/// assert_eq!(keys, ["/ch/0/1", "/ch/1/1", "/ch/2/1"]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glob<'a, M: ?Sized, N, const D: usize, const S: char = '/'> {
+    path: PathIter<'a, S>,
+    state: [usize; D],
+    done: bool,
+    _n: PhantomData<N>,
+    _m: PhantomData<M>,
+}
+
+impl<'a, M: ?Sized, N, const D: usize, const S: char> Glob<'a, M, N, D, S> {
+    /// Create a new glob iterator from a pattern
+    ///
+    /// Everything before the first separator `S` is ignored.
+    pub fn new(pattern: &'a str) -> Self {
+        Self {
+            path: PathIter::root(pattern),
+            state: [0; D],
+            done: false,
+            _n: PhantomData,
+            _m: PhantomData,
+        }
+    }
+}
+
+impl<M, N, const D: usize, const S: char> Iterator for Glob<'_, M, N, D, S>
+where
+    M: TreeKey + ?Sized,
+    N: Transcode + Default,
+{
+    type Item = Result<(N, Node), Traversal>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut lens = [0; D];
+        let ret = M::transcode(Wildcard {
+            path: self.path,
+            state: &self.state,
+            lens: &mut lens,
+            depth: 0,
+            presence: false,
+        });
+        // Increment the deepest wildcard that was expanded, carrying upward.
+        // Wildcards that were not reached (due to an error) are skipped.
+        self.done = true;
+        for (index, len) in self.state.iter_mut().zip(lens).rev() {
+            if len == 0 {
+                continue;
+            }
+            *index += 1;
+            if *index < len {
+                self.done = false;
+                break;
+            }
+            *index = 0;
+        }
+        Some(ret)
+    }
+}
+
+impl<M: TreeKey + ?Sized, N: Transcode + Default, const D: usize, const S: char>
+    core::iter::FusedIterator for Glob<'_, M, N, D, S>
+{
+}
@@ -21,9 +21,15 @@
 //! assert_eq!(&buf[..len], b"9");
 //! ```
 
+use core::fmt::Write;
+
 use serde_json_core::{de, ser};
 
-use crate::{Error, IntoKeys, Path, TreeDeserialize, TreeSerialize};
+use crate::{
+    jsontype::JsonType, Changed, Error, FieldFormat, FieldValues, Fnv1a, IntoKeys, JsonPointer,
+    Keys, Node, Packed, Path, Transcode, Traversal, TreeDeserialize, TreeDeserializeOwned, TreeKey,
+    TreeSerialize,
+};
 
 /// Update a node by path.
 ///
@@ -40,7 +46,7 @@ pub fn set<'de, T: TreeDeserialize<'de> + ?Sized>(
     path: &str,
     data: &'de [u8],
 ) -> Result<usize, Error<de::Error>> {
-    set_by_key(tree, Path::<_, '/'>::from(path), data)
+    set_by_key(tree, Path::<_, '/'>::from(path), data).map(|(len, _node)| len)
 }
 
 /// Retrieve a serialized value by path.
@@ -61,19 +67,226 @@ pub fn get<T: TreeSerialize + ?Sized>(
     get_by_key(tree, Path::<_, '/'>::from(path), data)
 }
 
-/// Update a node by key.
+/// Update a node by JSON Pointer (RFC 6901).
+///
+/// In contrast to [`set()`], reference tokens are un-escaped (`~1` to `/` and `~0` to `~`)
+/// allowing names that contain `/`.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(index_names=["a/b", "c"])]
+///     foo: [Leaf<u32>; 2],
+/// };
+/// let mut s = S::default();
+/// json::set_pointer(&mut s, "/foo/a~1b", b"9").unwrap();
+/// assert_eq!(*s.foo[0], 9);
+/// let mut buf = [0u8; 10];
+/// let len = json::get_pointer(&s, "/foo/a~1b", &mut buf[..]).unwrap();
+/// assert_eq!(&buf[..len], b"9");
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeDeserialize` to operate on.
+/// * `pointer` - The JSON Pointer to the node. Everything before the first `'/'` is ignored.
+/// * `data` - The serialized data making up the content.
 ///
 /// # Returns
 /// The number of bytes consumed from `data` or an [Error].
 #[inline]
+pub fn set_pointer<'de, T: TreeDeserialize<'de> + ?Sized>(
+    tree: &mut T,
+    pointer: &str,
+    data: &'de [u8],
+) -> Result<usize, Error<de::Error>> {
+    set_by_key(tree, &JsonPointer(pointer), data).map(|(len, _node)| len)
+}
+
+/// Retrieve a serialized value by JSON Pointer (RFC 6901).
+///
+/// See [`set_pointer()`].
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `pointer` - The JSON Pointer to the node. Everything before the first `'/'` is ignored.
+/// * `data` - The buffer to serialize the data into.
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer or an [Error].
+#[inline]
+pub fn get_pointer<T: TreeSerialize + ?Sized>(
+    tree: &T,
+    pointer: &str,
+    data: &mut [u8],
+) -> Result<usize, Error<ser::Error>> {
+    get_by_key(tree, &JsonPointer(pointer), data)
+}
+
+/// Update a node by key.
+///
+/// ```
+/// use miniconf::{json, Leaf, Node, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let mut s = S::default();
+/// let (_len, node) = json::set_by_key(&mut s, ["bar", "1"], b"9").unwrap();
+/// assert_eq!(node, Node::leaf(2));
+/// ```
+///
+/// # Returns
+/// The number of bytes consumed from `data` and the node that was updated or an [Error].
+/// The node is a leaf (`atomic` internal nodes are deserialized as a whole and also reported
+/// as leaves).
+#[inline]
 pub fn set_by_key<'de, T: TreeDeserialize<'de> + ?Sized, K: IntoKeys>(
     tree: &mut T,
     keys: K,
     data: &'de [u8],
-) -> Result<usize, Error<de::Error>> {
+) -> Result<(usize, Node), Error<de::Error>> {
     let mut de = de::Deserializer::new(data, None);
-    tree.deserialize_by_key(keys.into_keys(), &mut de)?;
-    de.end().map_err(Error::Finalization)
+    let depth = tree.deserialize_by_key(keys.into_keys(), &mut de)?;
+    let len = de.end().map_err(Error::Finalization)?;
+    Ok((len, Node::leaf(depth)))
+}
+
+/// Update a node by key and report the changed fields of a struct leaf.
+///
+/// ```
+/// use miniconf::{json, Deserialize, Leaf, Serialize, Tree};
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Inner {
+///     a: i32,
+///     b: bool,
+/// }
+/// #[derive(Tree, Default)]
+/// struct S {
+///     inner: Leaf<Inner>,
+/// };
+/// let mut s = S::default();
+/// let mut buf = [0u8; 16];
+/// let (_len, _node, changed) =
+///     json::set_by_key_changed::<_, _, 2>(&mut s, ["inner"], br#"{"a":0,"b":true}"#, &mut buf)
+///         .unwrap();
+/// assert_eq!(*changed, ["b"]);
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeDeserialize` to operate on.
+/// * `keys` - The key of the leaf.
+/// * `data` - The serialized data making up the content.
+/// * `scratch` - The buffer to serialize the field values into.
+///
+/// # Returns
+/// The number of bytes consumed from `data`, the node that was updated, and the names
+/// of the fields that changed or an [Error].
+/// See [`FieldValues`] for details and limitations.
+pub fn set_by_key_changed<
+    'de,
+    T: TreeSerialize + TreeDeserialize<'de> + ?Sized,
+    K: IntoKeys + Clone,
+    const N: usize,
+>(
+    tree: &mut T,
+    keys: K,
+    data: &'de [u8],
+    scratch: &mut [u8],
+) -> Result<(usize, Node, Changed<N>), Error<de::Error>> {
+    let mut before = FieldValues::<Format, N>::new(tree, keys.clone(), scratch)?;
+    let (len, node) = set_by_key(tree, keys.clone(), data)?;
+    let changed = before.changed(tree, keys)?;
+    Ok((len, node, changed))
+}
+
+/// JSON field value format for [`FieldValues`]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Format;
+
+impl FieldFormat for Format {
+    fn to_slice<T: serde::Serialize + ?Sized>(value: &T, buf: &mut [u8]) -> Option<usize> {
+        serde_json_core::to_slice(value, buf).ok()
+    }
+}
+
+/// Object-safe JSON deserialization shim.
+///
+/// [`TreeDeserialize`] is not dyn-compatible as its methods are generic over
+/// the keys and the deserializer. This trait is implemented for all `TreeDeserialize`
+/// types with concrete key and payload types and can be used as a trait object,
+/// e.g. to hold heterogeneous settings in a plugin architecture.
+/// See [`set_dyn()`] and [`set_dyn_by_key()`].
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+/// };
+/// let mut s = S::default();
+/// let d: &mut dyn json::DynSet = &mut s;
+/// json::set_dyn(d, "/foo", b"9").unwrap();
+/// assert_eq!(*s.foo, 9);
+/// ```
+pub trait DynSet<'de> {
+    /// Update a node by path. See [`set()`].
+    fn set_dyn(&mut self, path: &str, data: &'de [u8]) -> Result<usize, Error<de::Error>>;
+
+    /// Update a node by `Packed` key. See [`set_by_key()`].
+    fn set_dyn_by_key(
+        &mut self,
+        key: Packed,
+        data: &'de [u8],
+    ) -> Result<(usize, Node), Error<de::Error>>;
+}
+
+impl<'de, T: TreeDeserialize<'de> + ?Sized> DynSet<'de> for T {
+    #[inline]
+    fn set_dyn(&mut self, path: &str, data: &'de [u8]) -> Result<usize, Error<de::Error>> {
+        set(self, path, data)
+    }
+
+    #[inline]
+    fn set_dyn_by_key(
+        &mut self,
+        key: Packed,
+        data: &'de [u8],
+    ) -> Result<(usize, Node), Error<de::Error>> {
+        set_by_key(self, key, data)
+    }
+}
+
+/// Update a node by path through a trait object.
+///
+/// # Args
+/// * `tree` - The [`DynSet`] trait object to operate on.
+/// * `path` - The path to the node. Everything before the first `'/'` is ignored.
+/// * `data` - The serialized data making up the content.
+///
+/// # Returns
+/// The number of bytes consumed from `data` or an [Error].
+#[inline]
+pub fn set_dyn<'de>(
+    tree: &mut dyn DynSet<'de>,
+    path: &str,
+    data: &'de [u8],
+) -> Result<usize, Error<de::Error>> {
+    tree.set_dyn(path, data)
+}
+
+/// Update a node by `Packed` key through a trait object.
+///
+/// # Returns
+/// The number of bytes consumed from `data` and the node that was updated or an [Error].
+#[inline]
+pub fn set_dyn_by_key<'de>(
+    tree: &mut dyn DynSet<'de>,
+    key: Packed,
+    data: &'de [u8],
+) -> Result<(usize, Node), Error<de::Error>> {
+    tree.set_dyn_by_key(key, data)
 }
 
 /// Retrieve a serialized value by key.
@@ -86,7 +299,1140 @@ pub fn get_by_key<T: TreeSerialize + ?Sized, K: IntoKeys>(
     keys: K,
     data: &mut [u8],
 ) -> Result<usize, Error<ser::Error>> {
+    get_by_key_meta(tree, keys, data).map(|(len, _node)| len)
+}
+
+/// Retrieve a serialized value and the node metadata by key.
+///
+/// This avoids a separate traversal (e.g. through `TreeKey::transcode()`) to
+/// determine the node.
+///
+/// ```
+/// use miniconf::{json, Leaf, Node, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let mut buf = [0; 8];
+/// let (len, node) = json::get_by_key_meta(&S::default(), ["bar", "1"], &mut buf).unwrap();
+/// assert_eq!((&buf[..len], node), (&b"0"[..], Node::leaf(2)));
+/// ```
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer and the node that was serialized
+/// or an [Error].
+/// The node is a leaf (`atomic` internal nodes are serialized as a whole and also reported
+/// as leaves).
+#[inline]
+pub fn get_by_key_meta<T: TreeSerialize + ?Sized, K: IntoKeys>(
+    tree: &T,
+    keys: K,
+    data: &mut [u8],
+) -> Result<(usize, Node), Error<ser::Error>> {
     let mut ser = ser::Serializer::new(data);
-    tree.serialize_by_key(keys.into_keys(), &mut ser)?;
-    Ok(ser.end())
+    let depth = tree.serialize_by_key(keys.into_keys(), &mut ser)?;
+    Ok((ser.end(), Node::leaf(depth)))
+}
+
+/// Retrieve a leaf together with its path.
+///
+/// This is useful for structured logging.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let mut buf = [0; 8];
+/// let (path, value) = json::get_entry(&S::default(), "/bar/1", &mut buf).unwrap();
+/// assert_eq!((path, value), ("/bar/1", &b"0"[..]));
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `path` - The path to the node. Everything before the first `'/'` is ignored.
+/// * `data` - The buffer to serialize the data into.
+///
+/// # Returns
+/// The path (without the ignored part) and the serialized value or an [Error].
+pub fn get_entry<'a, 'b, T: TreeSerialize + ?Sized>(
+    tree: &T,
+    path: &'a str,
+    data: &'b mut [u8],
+) -> Result<(&'a str, &'b [u8]), Error<ser::Error>> {
+    let len = get(tree, path, data)?;
+    let path = path
+        .find('/')
+        .map(|start| &path[start..])
+        .unwrap_or_default();
+    Ok((path, &data[..len]))
+}
+
+/// Retrieve a serialized value by path or a default if the node is absent.
+///
+/// If the path resolves but the node is absent at runtime (e.g. an [`Option`] that is `None`),
+/// the `default` bytes are copied into `data` instead.
+/// All other errors (e.g. the path not being found) are returned.
+/// Note that keys below an absent node are not checked.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Option<Leaf<u32>>,
+/// };
+/// let mut s = S::default();
+/// let mut buf = [0; 8];
+/// let len = json::get_or(&s, "/foo", b"null", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"null");
+/// s.foo = Some(3.into());
+/// let len = json::get_or(&s, "/foo", b"null", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"3");
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `path` - The path to the node. Everything before the first `'/'` is ignored.
+/// * `default` - The serialized default value.
+/// * `data` - The buffer to serialize the data into.
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer or an [Error].
+pub fn get_or<T: TreeSerialize + ?Sized>(
+    tree: &T,
+    path: &str,
+    default: &[u8],
+    data: &mut [u8],
+) -> Result<usize, Error<ser::Error>> {
+    match get(tree, path, data) {
+        Err(Error::Traversal(Traversal::Absent(_))) => {
+            data.get_mut(..default.len())
+                .ok_or(Error::Finalization(ser::Error::BufferFull))?
+                .copy_from_slice(default);
+            Ok(default.len())
+        }
+        ret => ret,
+    }
+}
+
+/// Update a leaf by path only if its current serialized value matches.
+///
+/// The current value is serialized into `buf` and compared byte-wise to `expected`.
+/// On a match, `new` is deserialized into the leaf.
+/// Note that the comparison is on the serialized form: `expected` must match the output
+/// of [`get()`] exactly (e.g. `1.0`, not `1`).
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+/// };
+/// let mut s = S::default();
+/// let mut buf = [0; 8];
+/// assert_eq!(json::compare_and_set(&mut s, "/foo", b"0", b"5", &mut buf), Ok(true));
+/// assert_eq!(json::compare_and_set(&mut s, "/foo", b"0", b"7", &mut buf), Ok(false));
+/// assert_eq!(*s.foo, 5);
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` and `TreeDeserialize` to operate on.
+/// * `path` - The path to the node. Everything before the first `'/'` is ignored.
+/// * `expected` - The expected serialized current value.
+/// * `new` - The serialized new value.
+/// * `buf` - Scratch space for the current value. It must be at least as long as `expected`.
+///
+/// # Returns
+/// Whether the value matched and was updated, or an [Error].
+/// A `buf` shorter than `expected` is rejected with [`Traversal::Invalid`].
+/// A current value that can not be serialized for any other reason than being longer
+/// than `expected` is reported as `de::Error::CustomError`.
+pub fn compare_and_set<'de, T: TreeSerialize + TreeDeserialize<'de> + ?Sized>(
+    tree: &mut T,
+    path: &str,
+    expected: &[u8],
+    new: &'de [u8],
+    buf: &mut [u8],
+) -> Result<bool, Error<de::Error>> {
+    let buf = buf
+        .get_mut(..expected.len())
+        .ok_or(Traversal::Invalid(0, "Buffer too small"))?;
+    let current = match get(tree, path, buf) {
+        Ok(len) => &buf[..len],
+        // The current value is longer than `expected`
+        Err(
+            Error::Inner(_, ser::Error::BufferFull) | Error::Finalization(ser::Error::BufferFull),
+        ) => return Ok(false),
+        Err(Error::Traversal(err)) => return Err(err.into()),
+        // `ser::Error` is non-exhaustive: report any other failure as a custom error
+        Err(Error::Inner(depth, _)) => return Err(Error::Inner(depth, de::Error::CustomError)),
+        Err(Error::Finalization(_)) => return Err(Error::Finalization(de::Error::CustomError)),
+    };
+    if current != expected {
+        return Ok(false);
+    }
+    set(tree, path, new)?;
+    Ok(true)
+}
+
+/// `core::fmt::Write` into a byte slice, tracking the used length
+struct SliceWriter<'a>(&'a mut [u8], usize);
+
+impl SliceWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> Result<(), ser::Error> {
+        self.0
+            .get_mut(self.1..self.1 + data.len())
+            .ok_or(ser::Error::BufferFull)?
+            .copy_from_slice(data);
+        self.1 += data.len();
+        Ok(())
+    }
+}
+
+impl core::fmt::Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.write(s.as_bytes()).or(Err(core::fmt::Error))
+    }
+}
+
+/// Serialize all leaves into a JSON object mapping paths to values.
+///
+/// Absent leaves are skipped.
+/// The values of secret leaves (see [`crate::Node::is_secret()`]) are redacted as `"***"`.
+/// `D` is the maximum key depth.
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `data` - The buffer to serialize the object into.
+/// * `default` - Also skip leaves that serialize equal to the corresponding
+///   leaf of this tree, e.g. `T::default()`. This requires scratch space in `data` to serialize
+///   the default value. Secret leaves are always redacted, never skipped.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let mut s = S::default();
+/// *s.bar[1] = 9;
+/// let mut buf = [0u8; 64];
+/// let len = json::get_all::<_, 2>(&s, &mut buf, None).unwrap();
+/// assert_eq!(&buf[..len], br#"{"/foo":0,"/bar/0":0,"/bar/1":9}"#);
+/// let len = json::get_all::<_, 2>(&s, &mut buf, Some(&S::default())).unwrap();
+/// assert_eq!(&buf[..len], br#"{"/bar/1":9}"#);
+/// ```
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer or an [Error].
+pub fn get_all<T: TreeKey + TreeSerialize + ?Sized, const D: usize>(
+    tree: &T,
+    data: &mut [u8],
+    default: Option<&T>,
+) -> Result<usize, Error<ser::Error>> {
+    get_all_skip::<T, D>(tree, data, default, true)
+}
+
+fn get_all_skip<T: TreeKey + TreeSerialize + ?Sized, const D: usize>(
+    tree: &T,
+    data: &mut [u8],
+    default: Option<&T>,
+    redact: bool,
+) -> Result<usize, Error<ser::Error>> {
+    let mut w = SliceWriter(data, 0);
+    w.write(b"{").map_err(Error::Finalization)?;
+    for key in T::nodes::<Packed, D>() {
+        let (key, node) = key.map_err(Traversal::TooLong)?;
+        let start = w.1;
+        if start > 1 {
+            w.write(b",").map_err(Error::Finalization)?;
+        }
+        w.write(b"\"").map_err(Error::Finalization)?;
+        Path::<_, '/'>::from(&mut w)
+            .transcode::<T, _>(key)
+            .map_err(|_| Error::Finalization(ser::Error::BufferFull))?;
+        w.write(b"\":").map_err(Error::Finalization)?;
+        let len = match get_by_key(tree, key, &mut w.0[w.1..]) {
+            Err(Error::Traversal(Traversal::Absent(_))) => {
+                w.1 = start;
+                continue;
+            }
+            ret => ret?,
+        };
+        if redact && node.is_secret() {
+            // Not compared to the default: that would reveal whether the secret is the default
+            w.write(br#""***""#).map_err(Error::Finalization)?;
+            continue;
+        }
+        let (value, rest) = w.0[w.1..].split_at_mut(len);
+        if let Some(default) = default {
+            match get_by_key(default, key, rest) {
+                Err(Error::Traversal(Traversal::Absent(_))) => {}
+                ret => {
+                    if rest[..ret?] == *value {
+                        w.1 = start;
+                        continue;
+                    }
+                }
+            }
+        }
+        w.1 += len;
+    }
+    w.write(b"}").map_err(Error::Finalization)?;
+    Ok(w.1)
+}
+
+/// Serialize all leaves into a canonical JSON object mapping paths to values.
+///
+/// The canonical form is stable: For a given tree schema and leaf values it is
+/// always byte-identical. The leaves are in schema declaration order
+/// (the [`TreeKey::nodes()`] order), there is no whitespace, and absent leaves are skipped.
+/// This is the same format as [`get_all()`] without skipping defaults and without
+/// redacting secret leaves. It is suitable
+/// for reproducible dumps and as input to hashes and signatures (see [`hash_canonical()`]).
+/// `D` is the maximum key depth.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let s = S::default();
+/// let mut buf = [0u8; 64];
+/// let len = json::get_all_canonical::<_, 2>(&s, &mut buf).unwrap();
+/// assert_eq!(&buf[..len], br#"{"/foo":0,"/bar/0":0,"/bar/1":0}"#);
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `data` - The buffer to serialize the object into.
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer or an [Error].
+pub fn get_all_canonical<T: TreeKey + TreeSerialize + ?Sized, const D: usize>(
+    tree: &T,
+    data: &mut [u8],
+) -> Result<usize, Error<ser::Error>> {
+    get_all_skip::<T, D>(tree, data, None, false)
+}
+
+/// Hash the canonical serialization of all leaves.
+///
+/// This is the [`Fnv1a`] hash of the [`get_all_canonical()`] bytes.
+/// It can be used to detect changes of the tree values or as a compact digest
+/// to be signed. `D` is the maximum key depth.
+///
+/// ```
+/// use miniconf::{json, Fnv1a, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+/// };
+/// let mut s = S::default();
+/// let mut buf = [0u8; 64];
+/// let hash = json::hash_canonical::<_, 1>(&s, &mut buf).unwrap();
+/// assert_eq!(hash, Fnv1a::hash(r#"{"/foo":0}"#));
+/// *s.foo = 1;
+/// assert_ne!(json::hash_canonical::<_, 1>(&s, &mut buf).unwrap(), hash);
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `data` - The scratch buffer to serialize the canonical object into.
+///
+/// # Returns
+/// The hash or an [Error].
+pub fn hash_canonical<T: TreeKey + TreeSerialize + ?Sized, const D: usize>(
+    tree: &T,
+    data: &mut [u8],
+) -> Result<u32, Error<ser::Error>> {
+    let len = get_all_canonical::<T, D>(tree, data)?;
+    let mut hash = Fnv1a::default();
+    hash.update(&data[..len]);
+    Ok(hash.finish())
+}
+
+/// Compute a checksum over all present leaves at and below a root node.
+///
+/// The FNV-1a hash covers the [`Packed`] key and the JSON serialized value of each present
+/// leaf in [`TreeKey::nodes()`] order. It changes when any leaf below the root changes value
+/// or presence and is stable otherwise. It can be used for change detection of subtrees.
+/// `D` is the maximum key depth.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let mut s = S::default();
+/// let mut buf = [0u8; 16];
+/// let bar = json::checksum_by_key::<_, _, 2>(&s, ["bar"], &mut buf).unwrap();
+/// *s.foo = 1;
+/// assert_eq!(json::checksum_by_key::<_, _, 2>(&s, ["bar"], &mut buf), Ok(bar));
+/// *s.bar[1] = 1;
+/// assert_ne!(json::checksum_by_key::<_, _, 2>(&s, ["bar"], &mut buf), Ok(bar));
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `root` - The key of the root node.
+/// * `data` - The scratch buffer to serialize each leaf value into.
+///
+/// # Returns
+/// The checksum or an [Error].
+pub fn checksum_by_key<T, K, const D: usize>(
+    tree: &T,
+    root: K,
+    data: &mut [u8],
+) -> Result<u32, Error<ser::Error>>
+where
+    T: TreeKey + TreeSerialize + ?Sized,
+    K: IntoKeys,
+{
+    let mut hash = Fnv1a::default();
+    for key in T::nodes::<Packed, D>().root(root)? {
+        let (key, _node) = key.map_err(Traversal::TooLong)?;
+        match get_by_key(tree, key, data) {
+            Err(Error::Traversal(Traversal::Absent(_))) => {}
+            ret => {
+                let len = ret?;
+                hash.update(&key.into_lsb().get().to_le_bytes());
+                hash.update(&data[..len]);
+            }
+        }
+    }
+    Ok(hash.finish())
+}
+
+/// Serialize each leaf and pass its path and value to a callback.
+///
+/// This walks all leaves in [`TreeKey::nodes()`] order in a single pass. The path and the
+/// serialized value share the `data` buffer: the path is written first and the value is
+/// serialized into the remainder. Absent leaves are skipped.
+/// The values of secret leaves (see [`crate::Node::is_secret()`]) are redacted as `"***"`.
+/// `D` is the maximum key depth.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let s = S::default();
+/// let mut buf = [0u8; 16];
+/// let mut paths = vec![];
+/// json::for_each_entry::<_, 2>(&s, &mut buf, |path, _value| paths.push(path.to_string()))
+///     .unwrap();
+/// assert_eq!(paths, ["/foo", "/bar/0", "/bar/1"]);
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `data` - The scratch buffer for path and value.
+/// * `func` - The callback receiving the path and the serialized value of each leaf.
+///
+/// # Returns
+/// The number of leaves passed to `func` or an [Error].
+pub fn for_each_entry<T, const D: usize>(
+    tree: &T,
+    data: &mut [u8],
+    mut func: impl FnMut(&str, &[u8]),
+) -> Result<usize, Error<ser::Error>>
+where
+    T: TreeKey + TreeSerialize + ?Sized,
+{
+    let mut count = 0;
+    for key in T::nodes::<Packed, D>() {
+        let (key, node) = key.map_err(Traversal::TooLong)?;
+        let mut w = SliceWriter(data, 0);
+        Path::<_, '/'>::from(&mut w)
+            .transcode::<T, _>(key)
+            .map_err(|_| Error::Finalization(ser::Error::BufferFull))?;
+        let len = w.1;
+        let (path, rest) = data.split_at_mut(len);
+        let len = match get_by_key(tree, key, rest) {
+            Err(Error::Traversal(Traversal::Absent(_))) => continue,
+            ret => ret?,
+        };
+        let value = if node.is_secret() {
+            br#""***""#
+        } else {
+            &rest[..len]
+        };
+        // The path was written from `&str` segments and separators
+        func(core::str::from_utf8(path).unwrap(), value);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Describe a leaf as a JSON object.
+///
+/// The descriptor contains:
+/// * `"type"`: the JSON type of the current value (`"number"`, `"string"`, `"boolean"`,
+///   `"null"`, `"array"`, or `"object"`), or of the default value if the current value
+///   can not be serialized, or the type the leaf deserializes from if neither can
+///   be serialized (e.g. write-only leaves)
+/// * `"access"`: `"rw"`, `"r"`, `"w"`, or `"none"` depending on whether the leaf can be
+///   serialized from `tree` and deserialized into `T::default()`
+/// * `"min"`, `"max"`: the inclusive range limits (see [`TreeKey::range()`])
+/// * `"default"`: the serialized value of the leaf in `T::default()` (if readable)
+///
+/// Keys without available information are omitted.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(max = 10)]
+///     foo: Leaf<u32>,
+///     #[tree(deny(deserialize = "read-only"))]
+///     bar: Leaf<bool>,
+/// };
+/// let s = S::default();
+/// let mut buf = [0u8; 64];
+/// let len = json::describe(&s, "/foo", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], br#"{"type":"number","access":"rw","max":10,"default":0}"#);
+/// let len = json::describe(&s, "/bar", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], br#"{"type":"boolean","access":"r","default":false}"#);
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeSerialize` to operate on.
+/// * `path` - The path to the leaf.
+/// * `data` - The buffer to serialize the descriptor into.
+///
+/// # Returns
+/// The number of bytes used in the `data` buffer or an [Error].
+/// Internal nodes are rejected with [`Traversal::TooShort`].
+pub fn describe<T>(tree: &T, path: &str, data: &mut [u8]) -> Result<usize, Error<ser::Error>>
+where
+    T: TreeKey + TreeSerialize + TreeDeserializeOwned + Default,
+{
+    let (_, node) = T::transcode::<(), _>(Path::<_, '/'>::from(path))?;
+    if !node.is_leaf() {
+        return Err(Traversal::TooShort(node.depth()).into());
+    }
+    let (min, max) = T::range(Path::<_, '/'>::from(path))?;
+    let default = T::default();
+    // Returns whether the leaf is readable and the serialized length if it is present
+    let read = |tree: &T, data: &mut [u8]| match get(tree, path, data) {
+        Ok(len) => Ok((true, Some(len))),
+        Err(Error::Traversal(Traversal::Absent(_))) => Ok((true, None)),
+        Err(Error::Traversal(Traversal::Access(..))) => Ok((false, None)),
+        Err(err) => Err(err),
+    };
+    let (readable, mut value) = read(tree, data)?;
+    if value.is_none() {
+        value = read(&default, data)?.1;
+    }
+    let typ = value
+        .and_then(|len| JsonType::of(&data[..len]))
+        .or_else(|| JsonType::probe(&mut T::default(), Path::<_, '/'>::from(path)))
+        .map(|typ| match typ {
+            JsonType::Integer => "number",
+            typ => typ.name(),
+        });
+    let writable = !matches!(
+        set(&mut T::default(), path, &[]),
+        Err(Error::Traversal(Traversal::Access(..)))
+    );
+    let access = match (readable, writable) {
+        (true, true) => "rw",
+        (true, false) => "r",
+        (false, true) => "w",
+        (false, false) => "none",
+    };
+
+    let mut w = SliceWriter(data, 0);
+    w.write(b"{").map_err(Error::Finalization)?;
+    if let Some(typ) = typ {
+        w.write(b"\"type\":\"").map_err(Error::Finalization)?;
+        w.write(typ.as_bytes()).map_err(Error::Finalization)?;
+        w.write(b"\",").map_err(Error::Finalization)?;
+    }
+    w.write(b"\"access\":\"").map_err(Error::Finalization)?;
+    w.write(access.as_bytes()).map_err(Error::Finalization)?;
+    w.write(b"\"").map_err(Error::Finalization)?;
+    for (key, limit) in [("min", min), ("max", max)] {
+        if let Some(limit) = limit {
+            write!(w, ",\"{key}\":{limit}")
+                .map_err(|_| Error::Finalization(ser::Error::BufferFull))?;
+        }
+    }
+    let start = w.1;
+    w.write(b",\"default\":").map_err(Error::Finalization)?;
+    match read(&default, &mut w.0[w.1..])?.1 {
+        Some(len) => w.1 += len,
+        None => w.1 = start,
+    }
+    w.write(b"}").map_err(Error::Finalization)?;
+    Ok(w.1)
+}
+
+/// Parse a `{"<directive>": <value>}` object into the directive and the raw value
+fn directive(data: &[u8]) -> Option<(&str, &[u8])> {
+    let data = data.trim_ascii().strip_prefix(b"{")?.strip_suffix(b"}")?;
+    let data = data.trim_ascii().strip_prefix(b"\"")?;
+    let end = data.iter().position(|c| *c == b'"')?;
+    let (key, rest) = data.split_at(end);
+    let value = rest[1..].trim_ascii().strip_prefix(b":")?;
+    Some((core::str::from_utf8(key).ok()?, value))
+}
+
+/// Update multiple elements of an internal node with the same value.
+///
+/// `data` is an object with a single directive:
+/// * `{"fill": <value>}` updates all children of the node at `path`.
+/// * `{"<start>..<end>": <value>}` updates the children with indices in the
+///   (exclusive) range.
+///
+/// The range is checked against the schema before any child is updated.
+/// Absent children are skipped.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     arr: [Leaf<u16>; 6],
+/// };
+/// let mut s = S::default();
+/// json::set_fill(&mut s, "/arr", br#"{"fill": 7}"#).unwrap();
+/// json::set_fill(&mut s, "/arr", br#"{"1..3": 9}"#).unwrap();
+/// assert_eq!(s.arr.map(|v| *v), [7, 9, 9, 7, 7, 7]);
+/// ```
+///
+/// # Args
+/// * `tree` - The `TreeDeserialize` to operate on.
+/// * `path` - The path to the internal node.
+/// * `data` - The serialized fill directive.
+///
+/// # Returns
+/// The number of updated children or an [Error].
+/// Malformed directives are rejected with [`Traversal::Invalid`].
+pub fn set_fill<'de, T: TreeKey + TreeDeserialize<'de> + ?Sized>(
+    tree: &mut T,
+    path: &str,
+    data: &'de [u8],
+) -> Result<usize, Error<de::Error>> {
+    const INVALID: Traversal = Traversal::Invalid(0, "Invalid fill directive");
+    let (key, value) = directive(data).ok_or(INVALID)?;
+    let keys = || Path::<_, '/'>::from(path).into_keys();
+    // Obtain the number of children from the lookup of the first child
+    let mut len = 0;
+    let node: Node = T::traverse_by_key(keys().chain([0usize]), |_index, _name, l| {
+        len = l.get();
+        Ok(())
+    })
+    .try_into()?;
+    let range = if key == "fill" {
+        0..len
+    } else {
+        let (start, end) = key.split_once("..").ok_or(INVALID)?;
+        start.parse().or(Err(INVALID))?..end.parse().or(Err(INVALID))?
+    };
+    if range.end > len || range.is_empty() {
+        return Err(Traversal::NotFound(node.depth()).into());
+    }
+    let mut count = 0;
+    for index in range {
+        match set_by_key(tree, keys().chain([index]), value) {
+            Err(Error::Traversal(Traversal::Absent(_))) => {}
+            ret => {
+                ret?;
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(feature = "serde_json")]
+mod _serde_json {
+    use serde_json::{Map, Value};
+
+    use super::*;
+    use crate::{Indices, Shape};
+
+    fn get<T: TreeKey + TreeSerialize + ?Sized>(
+        tree: &T,
+        shape: &Shape,
+        keys: &mut Vec<usize>,
+        redact: bool,
+    ) -> Result<Option<Value>, Error<serde_json::Error>> {
+        Ok(match shape {
+            Shape::Leaf => {
+                let mut data = Vec::new();
+                match tree.serialize_by_key(
+                    keys.iter().into_keys(),
+                    &mut serde_json::Serializer::new(&mut data),
+                ) {
+                    Err(Error::Traversal(Traversal::Absent(_))) => None,
+                    ret => {
+                        ret?;
+                        if redact && T::transcode::<(), _>(keys.iter())?.1.is_secret() {
+                            Some(Value::from("***"))
+                        } else {
+                            let value = serde_json::from_slice(&data)
+                                .map_err(|err| Error::Inner(keys.len(), err))?;
+                            Some(value)
+                        }
+                    }
+                }
+            }
+            Shape::Named(_) => {
+                let mut map = Map::new();
+                for (i, name, child) in shape.children() {
+                    keys.push(i);
+                    let value = get(tree, child, keys, redact)?;
+                    keys.pop();
+                    if let Some(value) = value {
+                        map.insert(name.into_owned(), value);
+                    }
+                }
+                (!map.is_empty()).then_some(Value::Object(map))
+            }
+            Shape::Numbered(_) | Shape::Homogeneous(..) => {
+                array(shape.children().map(|(i, _name, child)| {
+                    keys.push(i);
+                    let value = get(tree, child, keys, redact);
+                    keys.pop();
+                    value
+                }))?
+            }
+        })
+    }
+
+    fn array<E>(items: impl Iterator<Item = Result<Option<Value>, E>>) -> Result<Option<Value>, E> {
+        let mut present = false;
+        let items = items
+            .map(|item| {
+                let item = item?;
+                present |= item.is_some();
+                Ok(item.unwrap_or_else(|| Value::Object(Map::new())))
+            })
+            .collect::<Result<Vec<_>, E>>()?;
+        Ok(present.then_some(Value::Array(items)))
+    }
+
+    /// Serialize all leaves into an indented JSON document with nested objects and arrays.
+    ///
+    /// Internal nodes with named children become objects, numbered and homogeneous
+    /// internal nodes become arrays.
+    /// Absent leaves are omitted from objects and represented by empty objects in arrays.
+    /// Secret leaves are redacted as `"***"`.
+    /// Object keys are in tree order with the `preserve_order` feature and sorted by name otherwise.
+    /// See [`get_all()`] for the compact `no_std` variant mapping paths to values.
+    ///
+    /// ```
+    /// use miniconf::{json, Leaf, Tree};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     bar: [Leaf<u16>; 2],
+    ///     foo: Leaf<u32>,
+    /// };
+    /// let mut buf = [0u8; 128];
+    /// let len = json::get_all_pretty(&S::default(), &mut buf).unwrap();
+    /// assert_eq!(
+    ///     &buf[..len],
+    ///     b"{\n  \"bar\": [\n    0,\n    0\n  ],\n  \"foo\": 0\n}"
+    /// );
+    /// ```
+    ///
+    /// # Returns
+    /// The number of bytes used in the `data` buffer or an [Error].
+    pub fn get_all_pretty<T: TreeKey + TreeSerialize + ?Sized>(
+        tree: &T,
+        data: &mut [u8],
+    ) -> Result<usize, Error<serde_json::Error>> {
+        let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+        let value = get(tree, &shape, &mut vec![], true)?.unwrap_or(Value::Object(Map::new()));
+        let mut w = &mut data[..];
+        serde_json::to_writer_pretty(&mut w, &value).map_err(Error::Finalization)?;
+        let rest = w.len();
+        Ok(data.len() - rest)
+    }
+
+    /// Serialize the leaves of a subtree into a nested JSON [`Value`].
+    ///
+    /// The value mirrors the subtree hierarchy like [`get_all_pretty()`]:
+    /// Internal nodes with named children become objects, numbered and homogeneous
+    /// internal nodes become arrays. A leaf root becomes a scalar value.
+    /// Object keys are ordered as in [`get_all_pretty()`].
+    ///
+    /// ```
+    /// use miniconf::{json, Leaf, Tree};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let value = json::get_subtree_value(&S::default(), "/bar").unwrap();
+    /// assert_eq!(value, serde_json::json!([0, 0]));
+    /// ```
+    ///
+    /// # Args
+    /// * `tree` - The `TreeSerialize` to operate on.
+    /// * `root` - The path to the root node of the subtree. Everything before the first `'/'`
+    ///   is ignored.
+    ///
+    /// # Returns
+    /// The nested value or an [Error].
+    /// If all leaves in the subtree are absent, [`Traversal::Absent`] is returned.
+    pub fn get_subtree_value<T: TreeKey + TreeSerialize + ?Sized>(
+        tree: &T,
+        root: &str,
+    ) -> Result<Value, Error<serde_json::Error>> {
+        let mut indices = Indices(vec![0; T::META.max_depth]);
+        let node = indices.transcode::<T, _>(Path::<_, '/'>::from(root))?;
+        let mut keys = indices.0;
+        keys.truncate(node.depth());
+        let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+        let mut shape = &shape;
+        for &i in keys.iter() {
+            shape = shape.child(i).unwrap(); // Note(unwrap): transcoded above
+        }
+        get(tree, shape, &mut keys, false)?.ok_or(Traversal::Absent(node.depth()).into())
+    }
+
+    fn set<T: TreeDeserializeOwned + ?Sized>(
+        tree: &mut T,
+        shape: &Shape,
+        keys: &mut Vec<usize>,
+        value: Value,
+    ) -> Result<usize, Error<serde_json::Error>> {
+        let depth = keys.len();
+        let mut applied = 0;
+        match (shape, value) {
+            (Shape::Leaf, value) => {
+                match tree.deserialize_by_key(keys.iter().into_keys(), value) {
+                    Err(Error::Traversal(Traversal::Absent(_))) => {}
+                    ret => {
+                        ret?;
+                        applied += 1;
+                    }
+                };
+            }
+            (Shape::Named(_), Value::Object(map)) => {
+                for (name, value) in map {
+                    let (i, _, child) = shape
+                        .children()
+                        .find(|(_, n, _)| *n == name)
+                        .ok_or(Traversal::NotFound(depth + 1))?;
+                    keys.push(i);
+                    applied += set(tree, child, keys, value)?;
+                    keys.pop();
+                }
+            }
+            (Shape::Numbered(_) | Shape::Homogeneous(..), Value::Array(items)) => {
+                for (i, value) in items.into_iter().enumerate() {
+                    let child = shape.child(i).ok_or(Traversal::NotFound(depth + 1))?;
+                    if matches!(&value, Value::Object(m) if m.is_empty()) {
+                        continue;
+                    }
+                    keys.push(i);
+                    applied += set(tree, child, keys, value)?;
+                    keys.pop();
+                }
+            }
+            (Shape::Named(_), _) => return Err(Traversal::Invalid(depth, "Expected object").into()),
+            _ => return Err(Traversal::Invalid(depth, "Expected array").into()),
+        }
+        Ok(applied)
+    }
+
+    /// Deserialize the values in a nested JSON document into a tree.
+    ///
+    /// This is the inverse of [`get_all_pretty()`] and accepts both indented and compact
+    /// documents. The document may contain a subset of the tree.
+    /// Values for absent leaves and empty objects in arrays are skipped.
+    /// Values are applied in document order (object members in sorted order unless the
+    /// `preserve_order` feature is enabled) and the tree is updated up to the first error.
+    ///
+    /// # Returns
+    /// The number of applied leaf values.
+    pub fn set_all<T: TreeKey + TreeDeserializeOwned + ?Sized>(
+        tree: &mut T,
+        data: &[u8],
+    ) -> Result<usize, Error<serde_json::Error>> {
+        let value: Value = serde_json::from_slice(data).map_err(|err| Error::Inner(0, err))?;
+        let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+        set(tree, &shape, &mut vec![], value)
+    }
+
+    /// Blank out comments and trailing commas outside of strings
+    #[cfg(feature = "jsonc")]
+    fn strip_jsonc(text: &str) -> Result<Vec<u8>, serde_json::Error> {
+        let mut data = text.as_bytes().to_vec();
+        // Comments: replaced by spaces to retain error positions
+        let (mut i, mut string) = (0, false);
+        while i < data.len() {
+            match data[i] {
+                b'\\' if string => i += 1,
+                b'"' => string = !string,
+                b'/' if !string && data.get(i + 1) == Some(&b'/') => {
+                    while i < data.len() && data[i] != b'\n' {
+                        data[i] = b' ';
+                        i += 1;
+                    }
+                    continue;
+                }
+                b'/' if !string && data.get(i + 1) == Some(&b'*') => {
+                    let end = data[i + 2..]
+                        .windows(2)
+                        .position(|w| w == b"*/")
+                        .ok_or_else(|| {
+                            <serde_json::Error as serde::de::Error>::custom(
+                                "unterminated block comment",
+                            )
+                        })?
+                        + i
+                        + 4;
+                    data[i..end]
+                        .iter_mut()
+                        .filter(|c| **c != b'\n')
+                        .for_each(|c| *c = b' ');
+                    i = end;
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        // Trailing commas
+        let (mut i, mut string) = (0, false);
+        while i < data.len() {
+            match data[i] {
+                b'\\' if string => i += 1,
+                b'"' => string = !string,
+                b',' if !string => {
+                    if let Some(b'}' | b']') =
+                        data[i + 1..].iter().find(|c| !c.is_ascii_whitespace())
+                    {
+                        data[i] = b' ';
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        Ok(data)
+    }
+
+    /// Deserialize the values in a nested JSONC document into a tree.
+    ///
+    /// Like [`set_all()`] but `//` line comments, `/* */` block comments, and trailing
+    /// commas in objects and arrays are ignored.
+    ///
+    /// ```
+    /// use miniconf::{json, Leaf, Tree};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let mut s = S::default();
+    /// let text = r#"{
+    ///     // line comment
+    ///     "foo": 3, /* block comment */
+    ///     "bar": [1, 2,],
+    /// }"#;
+    /// assert_eq!(json::set_all_jsonc(&mut s, text).unwrap(), 3);
+    /// assert_eq!((*s.foo, *s.bar[1]), (3, 2));
+    /// ```
+    ///
+    /// # Returns
+    /// The number of applied leaf values.
+    /// An unterminated block comment is rejected with [`Error::Inner`].
+    #[cfg(feature = "jsonc")]
+    pub fn set_all_jsonc<T: TreeKey + TreeDeserializeOwned + ?Sized>(
+        tree: &mut T,
+        text: &str,
+    ) -> Result<usize, Error<serde_json::Error>> {
+        set_all(
+            tree,
+            &strip_jsonc(text).map_err(|err| Error::Inner(0, err))?,
+        )
+    }
+
+    /// Apply a newline-delimited JSON (NDJSON) stream of `{"path": ..., "value": ...}` commands.
+    ///
+    /// The lines are read from `reader` and applied in order. Empty lines are ignored.
+    /// Application stops at the first line that fails to read, parse, or apply.
+    ///
+    /// ```
+    /// use miniconf::{json, Leaf, Tree};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let mut s = S::default();
+    /// let stream = b"{\"path\":\"/foo\",\"value\":3}\n{\"path\":\"/bar/1\",\"value\":9}\n";
+    /// assert_eq!(json::apply_ndjson(&mut s, &stream[..]).unwrap(), 2);
+    /// assert_eq!((*s.foo, *s.bar[1]), (3, 9));
+    /// ```
+    ///
+    /// # Returns
+    /// The number of applied commands or the (zero-based) index of the failing line and the
+    /// [Error].
+    pub fn apply_ndjson<T: TreeDeserializeOwned + ?Sized, R: std::io::Read>(
+        tree: &mut T,
+        reader: R,
+    ) -> Result<usize, (usize, Error<serde_json::Error>)> {
+        use std::io::BufRead;
+
+        // Returns whether a command was applied
+        let mut apply = |line: std::io::Result<String>| {
+            let line = line.map_err(|err| Error::Finalization(serde_json::Error::io(err)))?;
+            if line.trim().is_empty() {
+                return Ok(false);
+            }
+            let mut cmd: Map<String, Value> =
+                serde_json::from_str(&line).map_err(|err| Error::Inner(0, err))?;
+            let (Some(Value::String(path)), Some(value)) =
+                (cmd.remove("path"), cmd.remove("value"))
+            else {
+                return Err(Traversal::Invalid(0, "Expected path and value").into());
+            };
+            tree.deserialize_by_key(Path::<_, '/'>::from(path.as_str()).into_keys(), value)?;
+            Ok(true)
+        };
+        let mut applied = 0;
+        for (i, line) in std::io::BufReader::new(reader).lines().enumerate() {
+            if apply(line).map_err(|err| (i, err))? {
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+
+    /// The [`describe()`] descriptor of a leaf as a JSON object with the range limits
+    /// as `"minimum"` and `"maximum"` and without the `"default"` of secret leaves.
+    ///
+    /// `data` is grown as needed up to 64 KiB.
+    pub(crate) fn descriptor<T: TreeKey + TreeSerialize + TreeDeserializeOwned + Default>(
+        tree: &T,
+        path: &str,
+        secret: bool,
+        data: &mut Vec<u8>,
+    ) -> Result<Map<String, Value>, Error<ser::Error>> {
+        let len = loop {
+            match describe(tree, path, data) {
+                Err(
+                    Error::Inner(_, ser::Error::BufferFull)
+                    | Error::Finalization(ser::Error::BufferFull),
+                ) if data.len() < 1 << 16 => {
+                    data.resize(data.len().max(128) * 2, 0);
+                }
+                ret => break ret?,
+            }
+        };
+        // Note(unwrap): `describe()` writes a JSON object
+        let Value::Object(mut desc) = serde_json::from_slice(&data[..len]).unwrap() else {
+            unreachable!()
+        };
+        if secret {
+            desc.remove("default");
+        }
+        for (key, name) in [("min", "minimum"), ("max", "maximum")] {
+            if let Some(limit) = desc.remove(key) {
+                desc.insert(name.into(), limit);
+            }
+        }
+        Ok(desc)
+    }
+
+    fn leaf_keys(shape: &Shape, keys: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        if matches!(shape, Shape::Leaf) {
+            out.push(keys.clone());
+        }
+        for (i, _name, child) in shape.children() {
+            keys.push(i);
+            leaf_keys(child, keys, out);
+            keys.pop();
+        }
+    }
+
+    /// Describe all leaves for building a settings form user interface.
+    ///
+    /// The returned JSON object maps the path of each leaf to its descriptor.
+    /// The descriptor contains the [`describe()`] keys of the leaf in `tree`
+    /// (`"type"`, `"access"`, and `"default"`), the range limits as `"minimum"` and
+    /// `"maximum"`, and additionally:
+    /// * `"description"`: the leaf documentation (see [`TreeKey::doc()`])
+    /// * `"optional"`: whether the leaf is absent in `T::default()`
+    /// * `"secret"`: whether the leaf is secret (see [`crate::Node::is_secret()`]).
+    ///   The `"default"` of secret leaves is omitted.
+    ///
+    /// The `"type"` of leaves that are absent in both `tree` and `T::default()` is unknown
+    /// and omitted.
+    ///
+    /// The paths are in [`TreeKey::nodes()`] order with the `preserve_order` feature
+    /// and sorted otherwise.
+    ///
+    /// ```
+    /// use miniconf::{json, Leaf, Tree};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     #[tree(secret)]
+    ///     key: Leaf<u32>,
+    /// };
+    /// let bundle = json::schema_bundle(&S::default()).unwrap();
+    /// assert_eq!(
+    ///     bundle["/foo"],
+    ///     serde_json::json!({
+    ///         "type": "number",
+    ///         "access": "rw",
+    ///         "default": 0,
+    ///         "optional": false,
+    ///         "secret": false
+    ///     })
+    /// );
+    /// assert_eq!(bundle["/key"]["secret"], true);
+    /// assert!(bundle["/key"].get("default").is_none());
+    /// ```
+    ///
+    /// # Returns
+    /// The bundle or the first [`describe()`] [Error], e.g. if a descriptor does not fit
+    /// into 64 KiB.
+    pub fn schema_bundle<T: TreeKey + TreeSerialize + TreeDeserializeOwned + Default>(
+        tree: &T,
+    ) -> Result<Value, Error<ser::Error>> {
+        let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+        let mut leaves = vec![];
+        leaf_keys(&shape, &mut vec![], &mut leaves);
+        let default = T::default();
+        let mut data = vec![0; 256];
+        let mut out = Map::new();
+        for keys in leaves {
+            let (path, node) = T::transcode::<Path<String, '/'>, _>(&keys).unwrap(); // Note(unwrap): valid keys
+            let path = path.into_inner();
+            let mut desc = descriptor(tree, &path, node.is_secret(), &mut data)?;
+            let optional = matches!(
+                super::get(&default, &path, &mut data),
+                Err(Error::Traversal(Traversal::Absent(_)))
+            );
+            // Note(unwrap): valid keys
+            if let Some(doc) = T::doc(&keys).unwrap() {
+                desc.insert("description".into(), doc.into());
+            }
+            desc.insert("optional".into(), optional.into());
+            desc.insert("secret".into(), node.is_secret().into());
+            out.insert(path, Value::Object(desc));
+        }
+        Ok(Value::Object(out))
+    }
 }
+#[cfg(feature = "serde_json")]
+pub use _serde_json::*;
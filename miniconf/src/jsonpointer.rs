@@ -0,0 +1,192 @@
+use core::{
+    fmt::Write,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{IntoKeys, Key, KeyLookup, KeysIter, Names, Node, Transcode, Traversal, TreeKey};
+
+/// A JSON Pointer (RFC 6901) reference token
+///
+/// The token is stored escaped: `~1` is un-escaped to `/` and `~0` to `~`
+/// when matching names.
+/// Tokens with invalid escape sequences do not match any name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize, Hash)]
+#[repr(transparent)]
+#[serde(transparent)]
+pub struct JsonPointerToken<'a>(pub &'a str);
+
+impl JsonPointerToken<'_> {
+    /// Whether the un-escaped token equals `name`
+    fn matches(&self, name: &str) -> bool {
+        let mut chars = self.0.chars();
+        let unescaped = core::iter::from_fn(|| {
+            Some(match chars.next()? {
+                '~' => match chars.next() {
+                    Some('0') => Some('~'),
+                    Some('1') => Some('/'),
+                    _ => None,
+                },
+                c => Some(c),
+            })
+        });
+        unescaped.eq(name.chars().map(Some))
+    }
+}
+
+impl Key for JsonPointerToken<'_> {
+    fn find(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        match lookup {
+            KeyLookup::Named(names) => names.iter().position(|n| self.matches(n)),
+            KeyLookup::IndexNamed(names) => names
+                .iter()
+                .position(|n| self.matches(n))
+                .or_else(|| self.0.parse().ok().filter(|i| *i < names.len())),
+            KeyLookup::Homogeneous(len) | KeyLookup::Numbered(len) => {
+                self.0.parse().ok().filter(|i| *i < len.get())
+            }
+        }
+        .ok_or(Traversal::NotFound(1))
+    }
+
+    fn find_named(&self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        names(&|name| self.matches(name))
+            .map_or_else(|| self.find(lookup).or(Err(Traversal::Absent(1))), Ok)
+    }
+}
+
+/// JSON Pointer (RFC 6901) reference token iterator
+///
+/// Everything before the first `'/'` is ignored.
+///
+/// ```
+/// use miniconf::{JsonPointerIter, JsonPointerToken};
+/// let tokens: Vec<_> = JsonPointerIter::from("/foo/a~1b/m~0n/0").collect();
+/// assert_eq!(
+///     tokens,
+///     ["foo", "a~1b", "m~0n", "0"].map(JsonPointerToken)
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub struct JsonPointerIter<'a>(Option<&'a str>);
+
+impl<'a, T> From<&'a T> for JsonPointerIter<'a>
+where
+    T: AsRef<str> + ?Sized,
+{
+    #[inline]
+    fn from(value: &'a T) -> Self {
+        let s = value.as_ref();
+        Self(s.find('/').map(|i| &s[i + 1..]))
+    }
+}
+
+impl<'a> Iterator for JsonPointerIter<'a> {
+    type Item = JsonPointerToken<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let s = self.0?;
+        let (token, rest) = s
+            .split_once('/')
+            .map_or((s, None), |(token, rest)| (token, Some(rest)));
+        self.0 = rest;
+        Some(JsonPointerToken(token))
+    }
+}
+
+impl core::iter::FusedIterator for JsonPointerIter<'_> {}
+
+/// JSON Pointer (RFC 6901)
+///
+/// `T` can be `Write` for `Transcode`: Names and indices are written as reference tokens
+/// prefixed by `/`. In names, `~` is escaped as `~0` and `/` as `~1`.
+///
+/// `T` can be `AsRef<str>` for `IntoKeys` with the behavior described in [`JsonPointerIter`].
+/// In contrast to [`crate::Path`] this supports names containing `/`.
+///
+/// ```
+/// use miniconf::{Indices, JsonPointer, Leaf, Tree, TreeKey};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(index_names=["a/b", "m~n"])]
+///     foo: [Leaf<u32>; 2],
+/// };
+/// let (pointer, _node) = S::transcode::<JsonPointer<String>, _>([0, 1]).unwrap();
+/// assert_eq!(pointer.as_str(), "/foo/m~0n");
+/// let (indices, _node) = S::transcode::<Indices<[usize; 2]>, _>(&JsonPointer("/foo/a~1b")).unwrap();
+/// assert_eq!(indices.0, [0, 0]);
+/// ```
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize, Hash,
+)]
+#[repr(transparent)]
+#[serde(transparent)]
+pub struct JsonPointer<T: ?Sized>(pub T);
+
+impl<T> From<T> for JsonPointer<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> JsonPointer<T> {
+    /// Extract the inner value
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for JsonPointer<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for JsonPointer<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<'a, T: AsRef<str> + ?Sized> IntoKeys for &'a JsonPointer<T> {
+    type IntoKeys = KeysIter<JsonPointerIter<'a>>;
+    #[inline]
+    fn into_keys(self) -> Self::IntoKeys {
+        JsonPointerIter::from(&self.0).into_keys()
+    }
+}
+
+impl<T: Write + ?Sized> Transcode for JsonPointer<T> {
+    fn transcode<M, K>(&mut self, keys: K) -> Result<Node, Traversal>
+    where
+        M: TreeKey + ?Sized,
+        K: IntoKeys,
+    {
+        M::traverse_by_key(keys.into_keys(), |index, name, _len| {
+            self.0.write_char('/').or(Err(()))?;
+            match name {
+                Some(name) => name.split_inclusive(['~', '/']).try_for_each(|part| {
+                    match part.strip_suffix('~') {
+                        Some(part) => self.0.write_str(part).and_then(|()| self.0.write_str("~0")),
+                        None => match part.strip_suffix('/') {
+                            Some(part) => {
+                                self.0.write_str(part).and_then(|()| self.0.write_str("~1"))
+                            }
+                            None => self.0.write_str(part),
+                        },
+                    }
+                }),
+                None => self.0.write_str(itoa::Buffer::new().format(index)),
+            }
+            .or(Err(()))
+        })
+        .try_into()
+    }
+}
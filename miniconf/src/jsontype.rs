@@ -0,0 +1,169 @@
+/// The type of a serialized JSON value
+///
+/// This is used to infer the type of a leaf from the serialization of its default value
+/// or by probing its deserialization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JsonType {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    /// The type of the JSON value `data`
+    pub fn of(data: &[u8]) -> Option<Self> {
+        let data = data.trim_ascii();
+        Some(match data.first()? {
+            b'n' => Self::Null,
+            b't' | b'f' => Self::Boolean,
+            b'-' | b'0'..=b'9' if data.iter().any(|c| b".eE".contains(c)) => Self::Number,
+            b'-' | b'0'..=b'9' => Self::Integer,
+            b'"' => Self::String,
+            b'[' => Self::Array,
+            b'{' => Self::Object,
+            _ => return None,
+        })
+    }
+
+    /// The type of the first item of the JSON array `data`
+    #[cfg(all(feature = "std", feature = "json-core"))]
+    pub fn item(data: &[u8]) -> Option<Self> {
+        let rest = data.trim_ascii().strip_prefix(b"[")?.trim_ascii_start();
+        // Scalar items end at the first separator
+        let end = rest
+            .iter()
+            .position(|c| b",]".contains(c))
+            .unwrap_or(rest.len());
+        Self::of(&rest[..end])
+    }
+
+    /// The type a leaf deserializes from, without a value
+    ///
+    /// This probes the `Deserializer` method the leaf calls, e.g. for write-only leaves.
+    /// Returns `None` if the leaf is absent, not accessible, or self-describing.
+    #[cfg(feature = "json-core")]
+    pub fn probe<'de, T: crate::TreeDeserialize<'de> + ?Sized, K: crate::IntoKeys>(
+        tree: &mut T,
+        keys: K,
+    ) -> Option<Self> {
+        let mut typ = None;
+        tree.deserialize_by_key(keys.into_keys(), probe::Probe(&mut typ))
+            .ok();
+        typ
+    }
+
+    /// The JSON schema type name, e.g. `"integer"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Object => "object",
+        }
+    }
+}
+
+/// Serialize a leaf to JSON, `None` if it is absent or not accessible
+#[cfg(feature = "serde_json")]
+pub(crate) fn leaf_json<T: crate::TreeSerialize + ?Sized>(
+    tree: &T,
+    keys: &[usize],
+) -> Option<Vec<u8>> {
+    use crate::IntoKeys;
+    let mut data = Vec::new();
+    let mut ser = serde_json::Serializer::new(&mut data);
+    tree.serialize_by_key(keys.iter().into_keys(), &mut ser)
+        .ok()?;
+    Some(data)
+}
+
+#[cfg(feature = "json-core")]
+mod probe {
+    use serde::{de::Visitor, Deserializer};
+
+    use super::JsonType;
+
+    /// `Deserializer` recording the requested type and failing
+    pub struct Probe<'a>(pub &'a mut Option<JsonType>);
+
+    #[derive(Debug)]
+    pub struct ProbeError;
+
+    impl core::fmt::Display for ProbeError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("Probe")
+        }
+    }
+
+    impl serde::de::StdError for ProbeError {}
+
+    impl serde::de::Error for ProbeError {
+        fn custom<T: core::fmt::Display>(_msg: T) -> Self {
+            Self
+        }
+    }
+
+    macro_rules! probe {
+        ($typ:ident: $($name:ident($($arg:ty),*);)*) => {
+            $(
+                fn $name<V: Visitor<'de>>(self, $(_: $arg,)* _visitor: V) -> Result<V::Value, ProbeError> {
+                    *self.0 = Some(JsonType::$typ);
+                    Err(ProbeError)
+                }
+            )*
+        };
+    }
+
+    impl<'de> Deserializer<'de> for Probe<'_> {
+        type Error = ProbeError;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, ProbeError> {
+            Err(ProbeError)
+        }
+
+        fn deserialize_ignored_any<V: Visitor<'de>>(
+            self,
+            _visitor: V,
+        ) -> Result<V::Value, ProbeError> {
+            Err(ProbeError)
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, ProbeError> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        probe!(Null: deserialize_unit(); deserialize_unit_struct(&'static str););
+        probe!(Boolean: deserialize_bool(););
+        probe!(Integer:
+            deserialize_i8(); deserialize_i16(); deserialize_i32(); deserialize_i64(); deserialize_i128();
+            deserialize_u8(); deserialize_u16(); deserialize_u32(); deserialize_u64(); deserialize_u128();
+        );
+        probe!(Number: deserialize_f32(); deserialize_f64(););
+        probe!(String:
+            deserialize_char(); deserialize_str(); deserialize_string(); deserialize_identifier();
+            deserialize_enum(&'static str, &'static [&'static str]);
+        );
+        probe!(Array:
+            deserialize_bytes(); deserialize_byte_buf(); deserialize_seq(); deserialize_tuple(usize);
+            deserialize_tuple_struct(&'static str, usize);
+        );
+        probe!(Object:
+            deserialize_map(); deserialize_struct(&'static str, &'static [&'static str]);
+        );
+    }
+}
@@ -4,6 +4,47 @@ use serde::Serialize;
 
 use crate::Traversal;
 
+/// A range limit of a leaf
+///
+/// This is the `Display` representation of a `min`/`max` derive macro field attribute,
+/// e.g. a number. See [`crate::TreeKey::range()`].
+pub type Limit = &'static dyn core::fmt::Display;
+
+// Autoref specialization for the derive macros: a `min`/`max` value is a `Limit`
+// if it implements `Display` and is ignored otherwise.
+#[doc(hidden)]
+pub struct LimitProbe<T: 'static>(pub &'static T);
+
+#[doc(hidden)]
+pub trait DisplayLimit {
+    fn limit(&self) -> Option<Limit>;
+}
+
+impl<T: core::fmt::Display> DisplayLimit for &LimitProbe<T> {
+    #[inline]
+    fn limit(&self) -> Option<Limit> {
+        Some(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait OtherLimit {
+    fn limit(&self) -> Option<Limit>;
+}
+
+impl<T> OtherLimit for LimitProbe<T> {
+    #[inline]
+    fn limit(&self) -> Option<Limit> {
+        None
+    }
+}
+
+/// Runtime child names
+///
+/// Returns the index of the first child whose name satisfies the predicate.
+/// See [`Key::find_named()`].
+pub type Names<'a> = &'a dyn Fn(&dyn Fn(&str) -> bool) -> Option<usize>;
+
 /// Data to look up field names and convert to indices
 ///
 /// This struct used together with [`crate::TreeKey`].
@@ -15,6 +56,11 @@ pub enum KeyLookup {
     Numbered(NonZero<usize>),
     /// Homogeneous numbered children
     Homogeneous(NonZero<usize>),
+    /// Homogeneous children with names
+    ///
+    /// The children can be addressed by name or by index.
+    /// This is used for arrays with the `index_names` derive macro attribute.
+    IndexNamed(&'static [&'static str]),
 }
 
 impl KeyLookup {
@@ -40,7 +86,7 @@ impl KeyLookup {
     #[inline]
     pub const fn len(&self) -> NonZero<usize> {
         match self {
-            Self::Named(names) => match NonZero::new(names.len()) {
+            Self::Named(names) | Self::IndexNamed(names) => match NonZero::new(names.len()) {
                 Some(len) => len,
                 None => panic!("Must have at least one child"),
             },
@@ -48,11 +94,19 @@ impl KeyLookup {
         }
     }
 
+    /// Whether the children are homogeneous, e.g. an array or [`crate::VecElements`]
+    ///
+    /// See also [`crate::Node::is_collection()`].
+    #[inline]
+    pub const fn is_collection(&self) -> bool {
+        matches!(self, Self::Homogeneous(_) | Self::IndexNamed(_))
+    }
+
     /// Perform a index-to-name lookup
     #[inline]
     pub fn lookup(&self, index: usize) -> Result<Option<&'static str>, Traversal> {
         match self {
-            Self::Named(names) => match names.get(index) {
+            Self::Named(names) | Self::IndexNamed(names) => match names.get(index) {
                 Some(name) => Ok(Some(name)),
                 None => Err(Traversal::NotFound(1)),
             },
@@ -71,6 +125,36 @@ impl KeyLookup {
 pub trait Key {
     /// Convert the key `self` to a `usize` index
     fn find(&self, lookup: &KeyLookup) -> Result<usize, Traversal>;
+
+    /// Convert the key `self` to a `usize` index matching names ASCII case-insensitively
+    ///
+    /// This is used by nodes with the `case_insensitive` derive macro attribute.
+    /// An exact match takes precedence.
+    /// The default implementation is [`Key::find()`].
+    #[inline]
+    fn find_ignore_case(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        self.find(lookup)
+    }
+
+    /// Convert the key `self` to a `usize` index matching runtime names first
+    ///
+    /// This is used by nodes with runtime child names like [`crate::MapElements`].
+    /// A name key that is not found in `names` falls back to [`Key::find()`] on `lookup`
+    /// and is [`Traversal::Absent`] if that fails.
+    /// The default implementation is [`Key::find()`].
+    #[inline]
+    fn find_named(&self, lookup: &KeyLookup, _names: Names<'_>) -> Result<usize, Traversal> {
+        self.find(lookup)
+    }
+
+    /// Whether the key addresses the presence of an [`Option`] node
+    ///
+    /// See [`Keys::take_presence()`].
+    /// The default implementation returns `false`.
+    #[inline]
+    fn is_presence(&self) -> bool {
+        false
+    }
 }
 
 impl<T: Key> Key for &T
@@ -81,6 +165,21 @@ where
     fn find(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
         (**self).find(lookup)
     }
+
+    #[inline]
+    fn find_ignore_case(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        (**self).find_ignore_case(lookup)
+    }
+
+    #[inline]
+    fn find_named(&self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        (**self).find_named(lookup, names)
+    }
+
+    #[inline]
+    fn is_presence(&self) -> bool {
+        (**self).is_presence()
+    }
 }
 
 impl<T: Key> Key for &mut T
@@ -91,6 +190,21 @@ where
     fn find(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
         (**self).find(lookup)
     }
+
+    #[inline]
+    fn find_ignore_case(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        (**self).find_ignore_case(lookup)
+    }
+
+    #[inline]
+    fn find_named(&self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        (**self).find_named(lookup, names)
+    }
+
+    #[inline]
+    fn is_presence(&self) -> bool {
+        (**self).is_presence()
+    }
 }
 
 // index
@@ -111,17 +225,56 @@ macro_rules! impl_key_integer {
 impl_key_integer!(usize u8 u16 u32 u64 u128 isize i8 i16 i32 i64 i128);
 
 // name
+// A `'?'` suffix addresses the presence of an `Option` node (see `Key::is_presence()`).
 impl Key for str {
     #[inline]
     fn find(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        let key = self
+            .strip_suffix('?')
+            .filter(|_| self.is_presence())
+            .unwrap_or(self);
         match lookup {
-            KeyLookup::Named(names) => names.iter().position(|n| *n == self),
+            KeyLookup::Named(names) => names.iter().position(|n| *n == key),
+            KeyLookup::IndexNamed(names) => names
+                .iter()
+                .position(|n| *n == key)
+                .or_else(|| key.parse().ok().filter(|i| *i < names.len())),
             KeyLookup::Homogeneous(len) | KeyLookup::Numbered(len) => {
-                self.parse().ok().filter(|i| *i < len.get())
+                key.parse().ok().filter(|i| *i < len.get())
             }
         }
         .ok_or(Traversal::NotFound(1))
     }
+
+    #[inline]
+    fn find_ignore_case(&self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        let key = self
+            .strip_suffix('?')
+            .filter(|_| self.is_presence())
+            .unwrap_or(self);
+        Key::find(self, lookup).or_else(|err| match lookup {
+            KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => names
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(key))
+                .ok_or(err),
+            _ => Err(err),
+        })
+    }
+
+    #[inline]
+    fn find_named(&self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        let key = self
+            .strip_suffix('?')
+            .filter(|_| self.is_presence())
+            .unwrap_or(self);
+        names(&|name| name == key)
+            .map_or_else(|| Key::find(self, lookup).or(Err(Traversal::Absent(1))), Ok)
+    }
+
+    #[inline]
+    fn is_presence(&self) -> bool {
+        self.len() > 1 && self.ends_with('?')
+    }
 }
 
 /// Capability to yield and look up [`Key`]s
@@ -131,6 +284,72 @@ pub trait Keys {
     /// This must be fused (like [`core::iter::FusedIterator`]).
     fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal>;
 
+    /// Look up the next key matching names ASCII case-insensitively.
+    ///
+    /// See [`Key::find_ignore_case()`].
+    /// The default implementation is [`Keys::next()`].
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        self.next(lookup)
+    }
+
+    /// Look up the next key matching runtime child names first.
+    ///
+    /// See [`Key::find_named()`].
+    /// The default implementation is [`Keys::next()`].
+    #[inline]
+    fn next_named(&mut self, lookup: &KeyLookup, _names: Names<'_>) -> Result<usize, Traversal> {
+        self.next(lookup)
+    }
+
+    /// Take the presence marker of the previous key.
+    ///
+    /// Returns `true` if the previous key addressed the presence of an [`Option`]
+    /// (e.g. `"feature?"`, see [`Key::is_presence()`]) and the marker has not been taken yet.
+    /// An [`Option`] node then behaves like a `bool` leaf indicating whether it is `Some`.
+    /// An unconsumed marker causes [`Traversal::TooLong`] on the next lookup or on finalization.
+    /// The default implementation returns `false`.
+    #[inline]
+    fn take_presence(&mut self) -> bool {
+        false
+    }
+
+    /// Mark the node being traversed as secret.
+    ///
+    /// This is called by [`crate::TreeKey::traverse_by_key()`] implementations before
+    /// descending into a node marked `#[tree(secret)]`.
+    /// See [`crate::Node::is_secret()`].
+    /// The default implementation does nothing.
+    #[inline]
+    fn mark_secret(&mut self) {}
+
+    /// Mark the node being traversed as having a default.
+    ///
+    /// This is called by [`crate::TreeKey::traverse_by_key()`] implementations before
+    /// descending into a node marked `#[tree(default)]`.
+    /// See [`crate::Node::has_default()`].
+    /// The default implementation does nothing.
+    #[inline]
+    fn mark_default(&mut self) {}
+
+    /// Attach documentation to the node being traversed.
+    ///
+    /// This is called by [`crate::TreeKey::traverse_by_key()`] implementations before
+    /// descending into a node with a doc comment (with the `doc` feature of the derive macros).
+    /// See [`crate::TreeKey::doc()`].
+    /// The default implementation does nothing.
+    #[inline]
+    fn mark_doc(&mut self, _doc: &'static str) {}
+
+    /// Attach inclusive range limits to the node being traversed.
+    ///
+    /// This is called by [`crate::TreeKey::traverse_by_key()`] implementations before
+    /// descending into a node with `min`/`max` field attributes.
+    /// See [`crate::TreeKey::range()`].
+    /// The default implementation does nothing.
+    #[inline]
+    fn mark_range(&mut self, _min: Option<Limit>, _max: Option<Limit>) {}
+
     /// Finalize the keys, ensure there are no more.
     ///
     /// This must be fused.
@@ -155,6 +374,41 @@ where
         (**self).next(lookup)
     }
 
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        (**self).next_ignore_case(lookup)
+    }
+
+    #[inline]
+    fn next_named(&mut self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        (**self).next_named(lookup, names)
+    }
+
+    #[inline]
+    fn take_presence(&mut self) -> bool {
+        (**self).take_presence()
+    }
+
+    #[inline]
+    fn mark_secret(&mut self) {
+        (**self).mark_secret()
+    }
+
+    #[inline]
+    fn mark_default(&mut self) {
+        (**self).mark_default()
+    }
+
+    #[inline]
+    fn mark_doc(&mut self, doc: &'static str) {
+        (**self).mark_doc(doc)
+    }
+
+    #[inline]
+    fn mark_range(&mut self, min: Option<Limit>, max: Option<Limit>) {
+        (**self).mark_range(min, max)
+    }
+
     #[inline]
     fn finalize(&mut self) -> Result<(), Traversal> {
         (**self).finalize()
@@ -163,13 +417,34 @@ where
 
 /// [`Keys`]/[`IntoKeys`] for Iterators of [`Key`]
 #[derive(Debug, Clone)]
-#[repr(transparent)]
-pub struct KeysIter<T>(Fuse<T>);
+pub struct KeysIter<T> {
+    iter: Fuse<T>,
+    presence: bool,
+}
 
 impl<T: Iterator> KeysIter<T> {
     #[inline]
     fn new(inner: T) -> Self {
-        Self(inner.fuse())
+        Self {
+            iter: inner.fuse(),
+            presence: false,
+        }
+    }
+}
+
+impl<T> KeysIter<T>
+where
+    T: Iterator,
+    T::Item: Key,
+{
+    #[inline]
+    fn next_key(&mut self) -> Result<T::Item, Traversal> {
+        if self.presence {
+            return Err(Traversal::TooLong(0));
+        }
+        let key = self.iter.next().ok_or(Traversal::TooShort(0))?;
+        self.presence = key.is_presence();
+        Ok(key)
     }
 }
 
@@ -180,14 +455,27 @@ where
 {
     #[inline]
     fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
-        self.0.next().ok_or(Traversal::TooShort(0))?.find(lookup)
+        self.next_key()?.find(lookup)
+    }
+
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        self.next_key()?.find_ignore_case(lookup)
+    }
+
+    #[inline]
+    fn next_named(&mut self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        self.next_key()?.find_named(lookup, names)
+    }
+
+    #[inline]
+    fn take_presence(&mut self) -> bool {
+        core::mem::take(&mut self.presence)
     }
 
     #[inline]
     fn finalize(&mut self) -> Result<(), Traversal> {
-        self.0
-            .next()
-            .is_none()
+        (!self.presence && self.iter.next().is_none())
             .then_some(())
             .ok_or(Traversal::TooLong(0))
     }
@@ -248,6 +536,51 @@ impl<T: Keys, U: Keys> Keys for Chain<T, U> {
         }
     }
 
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        match self.0.next_ignore_case(lookup) {
+            Err(Traversal::TooShort(_)) => self.1.next_ignore_case(lookup),
+            ret => ret,
+        }
+    }
+
+    #[inline]
+    fn next_named(&mut self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        match self.0.next_named(lookup, names) {
+            Err(Traversal::TooShort(_)) => self.1.next_named(lookup, names),
+            ret => ret,
+        }
+    }
+
+    #[inline]
+    fn take_presence(&mut self) -> bool {
+        self.0.take_presence() || self.1.take_presence()
+    }
+
+    #[inline]
+    fn mark_secret(&mut self) {
+        self.0.mark_secret();
+        self.1.mark_secret();
+    }
+
+    #[inline]
+    fn mark_default(&mut self) {
+        self.0.mark_default();
+        self.1.mark_default();
+    }
+
+    #[inline]
+    fn mark_doc(&mut self, doc: &'static str) {
+        self.0.mark_doc(doc);
+        self.1.mark_doc(doc);
+    }
+
+    #[inline]
+    fn mark_range(&mut self, min: Option<Limit>, max: Option<Limit>) {
+        self.0.mark_range(min, max);
+        self.1.mark_range(min, max);
+    }
+
     #[inline]
     fn finalize(&mut self) -> Result<(), Traversal> {
         self.0.finalize().and_then(|()| self.1.finalize())
@@ -262,3 +595,94 @@ impl<T: Keys, U: Keys> IntoKeys for Chain<T, U> {
         self
     }
 }
+
+/// [`Keys`] for `group:index` addressing
+///
+/// The `group` is looked up as the first key (e.g. a field name) and the
+/// `index` as the second key (e.g. an array element index).
+///
+/// ```
+/// use miniconf::{json, GroupIndexKeys, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     gains: [Leaf<f32>; 4],
+/// };
+/// let mut s = S::default();
+/// json::set_by_key(&mut s, GroupIndexKeys::new("gains:2").unwrap(), b"1.5").unwrap();
+/// assert_eq!(*s.gains[2], 1.5);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GroupIndexKeys<'a> {
+    group: Option<&'a str>,
+    index: Option<usize>,
+}
+
+impl<'a> GroupIndexKeys<'a> {
+    /// Parse a `group:index` string
+    ///
+    /// Returns [`Traversal::Invalid`] if the separator is missing, the group is empty, or the
+    /// index is not a decimal number.
+    pub fn new(s: &'a str) -> Result<Self, Traversal> {
+        let (group, index) = s
+            .split_once(':')
+            .ok_or(Traversal::Invalid(0, "Missing `:` separator"))?;
+        if group.is_empty() {
+            return Err(Traversal::Invalid(0, "Empty group"));
+        }
+        let index = index
+            .parse()
+            .or(Err(Traversal::Invalid(0, "Malformed index")))?;
+        Ok(Self {
+            group: Some(group),
+            index: Some(index),
+        })
+    }
+}
+
+impl Keys for GroupIndexKeys<'_> {
+    #[inline]
+    fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        if let Some(group) = self.group.take() {
+            Key::find(group, lookup)
+        } else {
+            self.index
+                .take()
+                .ok_or(Traversal::TooShort(0))?
+                .find(lookup)
+        }
+    }
+
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        if let Some(group) = self.group.take() {
+            group.find_ignore_case(lookup)
+        } else {
+            self.next(lookup)
+        }
+    }
+
+    #[inline]
+    fn next_named(&mut self, lookup: &KeyLookup, names: Names<'_>) -> Result<usize, Traversal> {
+        if let Some(group) = self.group.take() {
+            group.find_named(lookup, names)
+        } else {
+            self.next(lookup)
+        }
+    }
+
+    #[inline]
+    fn finalize(&mut self) -> Result<(), Traversal> {
+        (self.group.is_none() && self.index.is_none())
+            .then_some(())
+            .ok_or(Traversal::TooLong(0))
+    }
+}
+
+impl IntoKeys for GroupIndexKeys<'_> {
+    type IntoKeys = Self;
+
+    #[inline]
+    fn into_keys(self) -> Self::IntoKeys {
+        self
+    }
+}
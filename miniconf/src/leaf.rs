@@ -6,7 +6,9 @@ use core::{
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{Error, Keys, Traversal, TreeAny, TreeDeserialize, TreeKey, TreeSerialize, Walk};
+use crate::{
+    Error, Keys, Schema, Traversal, TreeAny, TreeDeserialize, TreeKey, TreeSerialize, Walk,
+};
 
 /// `Serialize`/`Deserialize`/`Any` leaf
 ///
@@ -56,6 +58,8 @@ impl<T> From<T> for Leaf<T> {
 }
 
 impl<T: ?Sized> TreeKey for Leaf<T> {
+    const SCHEMA: Schema = Schema::Leaf;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         Ok(W::leaf())
@@ -120,12 +124,87 @@ impl<T: Any> TreeAny for Leaf<T> {
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
+// Borrowed string leaf
+//
+// This allows `&'a str` fields in lifetime-parameterized structs
+// to be deserialized from borrowed data (`'de: 'a`).
+
+impl TreeKey for &str {
+    const SCHEMA: Schema = Schema::Leaf;
+
+    #[inline]
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        Ok(W::leaf())
+    }
+
+    #[inline]
+    fn traverse_by_key<K, F, E>(mut keys: K, _func: F) -> Result<usize, Error<E>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+    {
+        keys.finalize()?;
+        Ok(0)
+    }
+}
+
+impl TreeSerialize for &str {
+    #[inline]
+    fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    where
+        K: Keys,
+        S: Serializer,
+    {
+        keys.finalize()?;
+        ser.serialize_str(self)
+            .map_err(|err| Error::Inner(0, err))?;
+        Ok(0)
+    }
+}
+
+impl<'de: 'a, 'a> TreeDeserialize<'de> for &'a str {
+    #[inline]
+    fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
+    where
+        K: Keys,
+        D: Deserializer<'de>,
+    {
+        keys.finalize()?;
+        *self = Deserialize::deserialize(de).map_err(|err| Error::Inner(0, err))?;
+        Ok(0)
+    }
+}
+
+impl TreeAny for &'static str {
+    #[inline]
+    fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        keys.finalize()?;
+        Ok(self)
+    }
+
+    #[inline]
+    fn mut_any_by_key<K>(&mut self, mut keys: K) -> Result<&mut dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        keys.finalize()?;
+        Ok(self)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+
 /// `TryFrom<&str>`/`AsRef<str>` leaf
 ///
 /// This wraps [`TryFrom<&str>`] and [`AsRef<str>`] into a `Tree*` leaf.
 /// [`TreeAny`] is implemented but denied access at runtime.
 /// It is especially useful to support enum variant switching using `strum`.
 /// Inner enum variant field access can be implemented using `defer`.
+/// See also the `tag` derive attribute on enums (in [`crate::TreeKey`]) which does not
+/// require the companion field.
 ///
 /// ```
 /// use miniconf::{json, Leaf, StrLeaf, Tree};
@@ -186,6 +265,8 @@ impl<T> From<T> for StrLeaf<T> {
 }
 
 impl<T: ?Sized> TreeKey for StrLeaf<T> {
+    const SCHEMA: Schema = Schema::Leaf;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         Ok(W::leaf())
@@ -289,6 +370,8 @@ impl<T> From<T> for Deny<T> {
 }
 
 impl<T: ?Sized> TreeKey for Deny<T> {
+    const SCHEMA: Schema = Schema::Leaf;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         Ok(W::leaf())
@@ -16,25 +16,67 @@ mod packed;
 pub use packed::*;
 mod jsonpath;
 pub use jsonpath::*;
+mod jsonpointer;
+pub use jsonpointer::*;
+#[cfg(any(feature = "json-core", feature = "serde_json"))]
+mod jsontype;
 mod tree;
 pub use tree::*;
 mod iter;
 pub use iter::*;
 mod impls;
+#[doc(hidden)]
+pub use impls::{deserialize_prefix, deserialize_tag};
 mod leaf;
 pub use leaf::*;
+mod unit;
+pub use unit::*;
 mod walk;
 pub use walk::*;
+mod hash;
+pub use hash::*;
+mod table;
+#[doc(hidden)]
+pub use table::KeyTable;
+mod changed;
+pub use changed::*;
+#[cfg(feature = "alloc")]
+mod elements;
+#[cfg(feature = "alloc")]
+pub use elements::*;
 
 #[cfg(feature = "derive")]
 pub use miniconf_derive::*;
 
+pub mod size;
+
 #[cfg(feature = "json-core")]
 pub mod json;
 
 #[cfg(feature = "postcard")]
 pub mod postcard;
 
+#[cfg(feature = "cbor")]
+pub mod cbor;
+
+#[cfg(all(feature = "std", feature = "json-core"))]
+pub mod env;
+
+#[cfg(all(feature = "std", feature = "json-core"))]
+pub mod ini;
+
+#[cfg(all(feature = "serde_json", feature = "json-core"))]
+pub mod openapi;
+
+#[cfg(feature = "serde_json")]
+pub mod migrate;
+
+#[cfg(feature = "toml")]
+pub mod toml;
+
+#[cfg(feature = "std")]
+pub mod codegen;
+
 // re-export for proc-macro
 #[doc(hidden)]
 pub use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -0,0 +1,150 @@
+//! Schema comparison for settings migration.
+//!
+//! A [`Schema`] records the `'/'`-separated path and the JSON type of every leaf
+//! of a tree. It can be persisted (e.g. alongside stored settings) and later compared
+//! with the schema of a newer firmware to obtain a list of [`Change`]s.
+//!
+//! ```
+//! use miniconf::{migrate, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct Old {
+//!     foo: Leaf<u32>,
+//!     bar: Leaf<bool>,
+//! };
+//! #[derive(Tree, Default)]
+//! struct New {
+//!     foo: Leaf<f32>,
+//!     baz: Leaf<bool>,
+//! };
+//! let old = migrate::Schema::new::<Old>();
+//! let changes = migrate::report(&old, &migrate::Schema::new::<New>());
+//! assert_eq!(changes[0], migrate::Change::Removed("/bar".into()));
+//! assert_eq!(changes[1].to_string(), "+ /baz");
+//! assert_eq!(changes[2].to_string(), "~ /foo (integer -> number)");
+//! ```
+
+use core::fmt;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    jsontype::{leaf_json, JsonType},
+    Shape, TreeKey, TreeSerialize,
+};
+
+/// Leaf paths and types of a tree
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schema {
+    /// The JSON schema type (e.g. `"integer"`) of each leaf by `'/'`-separated path.
+    ///
+    /// The type is `None` for leaves that are absent or not accessible in the default.
+    pub leaves: BTreeMap<String, Option<String>>,
+}
+
+impl Schema {
+    /// Obtain the schema of a tree.
+    ///
+    /// The leaf types are determined by serializing the leaves of `T::default()`.
+    pub fn new<T: TreeKey + TreeSerialize + Default>() -> Self {
+        let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+        let mut schema = Self::default();
+        schema.walk(&shape, &mut String::new(), &mut Vec::new(), &T::default());
+        schema
+    }
+
+    fn walk<T: TreeSerialize>(
+        &mut self,
+        shape: &Shape,
+        path: &mut String,
+        keys: &mut Vec<usize>,
+        default: &T,
+    ) {
+        if matches!(shape, Shape::Leaf) {
+            let typ = leaf_json(default, keys)
+                .and_then(|data| JsonType::of(&data))
+                .map(|typ| typ.name().into());
+            self.leaves.insert(path.clone(), typ);
+        }
+        let len = path.len();
+        for (index, name, child) in shape.children() {
+            path.push('/');
+            path.push_str(&name);
+            keys.push(index);
+            self.walk(child, path, keys, default);
+            keys.pop();
+            path.truncate(len);
+        }
+    }
+}
+
+/// A leaf difference between two schemas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The leaf is new
+    Added(String),
+    /// The leaf has been removed
+    Removed(String),
+    /// The leaf type has changed
+    Retyped {
+        /// The leaf path
+        path: String,
+        /// The old leaf type
+        old: String,
+        /// The new leaf type
+        new: String,
+    },
+}
+
+impl Change {
+    /// The path of the changed leaf
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Added(path) | Self::Removed(path) | Self::Retyped { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added(path) => write!(f, "+ {path}"),
+            Self::Removed(path) => write!(f, "- {path}"),
+            Self::Retyped { path, old, new } => write!(f, "~ {path} ({old} -> {new})"),
+        }
+    }
+}
+
+/// Compare two schemas.
+///
+/// A renamed leaf is reported as removed and added.
+/// Leaves with unknown type in either schema are never reported as retyped.
+///
+/// # Args
+/// * `old`: The previous schema, typically deserialized from persistent storage.
+/// * `new`: The current schema.
+///
+/// # Returns
+/// The changes ordered by path.
+pub fn report(old: &Schema, new: &Schema) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (path, typ) in old.leaves.iter() {
+        match (typ, new.leaves.get(path)) {
+            (_, None) => changes.push(Change::Removed(path.clone())),
+            (Some(old), Some(Some(new))) if old != new => changes.push(Change::Retyped {
+                path: path.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            }),
+            _ => {}
+        }
+    }
+    changes.extend(
+        new.leaves
+            .keys()
+            .filter(|path| !old.leaves.contains_key(*path))
+            .map(|path| Change::Added(path.clone())),
+    );
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
@@ -5,7 +5,7 @@ use core::{
 
 use serde::{Deserialize, Serialize};
 
-use crate::{Error, IntoKeys, KeysIter, Traversal, TreeKey};
+use crate::{Error, IntoKeys, KeyLookup, Keys, KeysIter, Limit, Traversal, TreeKey};
 
 /// Type of a node: leaf or internal
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -29,6 +29,33 @@ impl NodeType {
     }
 }
 
+/// Kind of a node: leaf or the kind of children of an internal node
+///
+/// See [`TreeKey::kind()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum NodeKind {
+    /// A leaf node
+    Leaf,
+    /// An internal node with named children ([`KeyLookup::Named`]), e.g. a struct
+    Named,
+    /// An internal node with numbered heterogeneous children ([`KeyLookup::Numbered`]), e.g. a tuple
+    Numbered,
+    /// An internal node with homogeneous children ([`KeyLookup::Homogeneous`] or
+    /// [`KeyLookup::IndexNamed`]), e.g. an array
+    Homogeneous,
+}
+
+impl From<&KeyLookup> for NodeKind {
+    #[inline]
+    fn from(value: &KeyLookup) -> Self {
+        match value {
+            KeyLookup::Named(_) => Self::Named,
+            KeyLookup::Numbered(_) => Self::Numbered,
+            KeyLookup::Homogeneous(_) | KeyLookup::IndexNamed(_) => Self::Homogeneous,
+        }
+    }
+}
+
 /// Type and depth of a node in a `TreeKey`
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Node {
@@ -39,6 +66,26 @@ pub struct Node {
 
     /// Leaf or internal
     pub typ: NodeType,
+
+    /// The node is at or below a node marked `#[tree(secret)]`
+    ///
+    /// This is determined by [`TreeKey::transcode()`] (and thus [`crate::NodeIter`]).
+    /// It is `false` when calling [`Transcode::transcode()`] directly.
+    /// See [`Node::is_secret()`].
+    #[serde(default)]
+    pub(crate) secret: bool,
+
+    /// The node is at or below a node marked `#[tree(default)]`
+    ///
+    /// This is determined like the secret flag. See [`Node::has_default()`].
+    #[serde(default)]
+    pub(crate) default: bool,
+
+    /// The node is an internal node with homogeneous children
+    ///
+    /// See [`Node::is_collection()`].
+    #[serde(default)]
+    pub(crate) collection: bool,
 }
 
 impl Node {
@@ -60,12 +107,77 @@ impl Node {
         self.typ.is_leaf()
     }
 
+    /// The node is secret and its value should be redacted in dumps
+    ///
+    /// ```
+    /// use miniconf::{Leaf, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     user: Leaf<u32>,
+    ///     #[tree(secret)]
+    ///     password: Leaf<u32>,
+    /// };
+    /// assert!(!S::transcode::<(), _>(["user"]).unwrap().1.is_secret());
+    /// assert!(S::transcode::<(), _>(["password"]).unwrap().1.is_secret());
+    /// ```
+    #[inline]
+    pub const fn is_secret(&self) -> bool {
+        self.secret
+    }
+
+    /// The node has a declared default value that it can be reset to
+    ///
+    /// ```
+    /// use miniconf::{Leaf, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     #[tree(default)]
+    ///     gain: Leaf<f32>,
+    ///     serial: Leaf<u32>,
+    /// };
+    /// assert!(S::transcode::<(), _>(["gain"]).unwrap().1.has_default());
+    /// assert!(!S::transcode::<(), _>(["serial"]).unwrap().1.has_default());
+    /// ```
+    #[inline]
+    pub const fn has_default(&self) -> bool {
+        self.default
+    }
+
+    /// The node is a collection
+    ///
+    /// A collection is an internal node with homogeneous children
+    /// (see [`KeyLookup::is_collection()`]), e.g. an array, [`crate::VecElements`], or
+    /// [`crate::MapElements`].
+    /// Leaves and internal nodes with named or numbered heterogeneous children
+    /// (structs, enums, tuples) are not collections.
+    /// This complements [`NodeType`] as a hint for user interfaces.
+    /// Like [`Node::is_secret()`] it is determined by [`TreeKey::transcode()`].
+    ///
+    /// ```
+    /// use miniconf::{Leaf, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// assert!(S::transcode::<(), _>(["bar"]).unwrap().1.is_collection());
+    /// assert!(!S::transcode::<(), _>(["bar", "0"]).unwrap().1.is_collection());
+    /// assert!(!S::transcode::<(), _>([0usize; 0]).unwrap().1.is_collection());
+    /// ```
+    #[inline]
+    pub const fn is_collection(&self) -> bool {
+        self.collection
+    }
+
     /// Create a leaf node
     #[inline]
     pub const fn leaf(depth: usize) -> Self {
         Self {
             depth,
             typ: NodeType::Leaf,
+            secret: false,
+            default: false,
+            collection: false,
         }
     }
 
@@ -75,6 +187,9 @@ impl Node {
         Self {
             depth,
             typ: NodeType::Internal,
+            secret: false,
+            default: false,
+            collection: false,
         }
     }
 }
@@ -86,6 +201,67 @@ impl From<Node> for usize {
     }
 }
 
+/// A `Keys` wrapper recording whether marked (secret or default) nodes were traversed
+/// and whether the keys are exhausted at a collection
+pub(crate) struct Marks<'a, K>(pub(crate) K, pub(crate) &'a mut Node);
+
+impl<K: Keys> Keys for Marks<'_, K> {
+    #[inline]
+    fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        self.0.next(lookup).inspect_err(|err| {
+            self.1.collection = matches!(err, Traversal::TooShort(_)) && lookup.is_collection();
+        })
+    }
+
+    #[inline]
+    fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+        self.0.next_ignore_case(lookup).inspect_err(|err| {
+            self.1.collection = matches!(err, Traversal::TooShort(_)) && lookup.is_collection();
+        })
+    }
+
+    #[inline]
+    fn take_presence(&mut self) -> bool {
+        self.0.take_presence()
+    }
+
+    #[inline]
+    fn mark_secret(&mut self) {
+        self.1.secret = true;
+        self.0.mark_secret()
+    }
+
+    #[inline]
+    fn mark_default(&mut self) {
+        self.1.default = true;
+        self.0.mark_default()
+    }
+
+    #[inline]
+    fn mark_doc(&mut self, doc: &'static str) {
+        self.0.mark_doc(doc)
+    }
+
+    #[inline]
+    fn mark_range(&mut self, min: Option<Limit>, max: Option<Limit>) {
+        self.0.mark_range(min, max)
+    }
+
+    #[inline]
+    fn finalize(&mut self) -> Result<(), Traversal> {
+        self.0.finalize()
+    }
+}
+
+impl<K: Keys> IntoKeys for Marks<'_, K> {
+    type IntoKeys = Self;
+
+    #[inline]
+    fn into_keys(self) -> Self::IntoKeys {
+        self
+    }
+}
+
 /// Map a `TreeKey::traverse_by_key()` `Result` to a `Transcode::transcode()` `Result`.
 impl TryFrom<Result<usize, Error<()>>> for Node {
     type Error = Traversal;
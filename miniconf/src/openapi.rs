@@ -0,0 +1,116 @@
+//! OpenAPI 3.1 schema generation.
+//!
+//! The schema object describes the nested JSON representation of a `TreeSerialize`:
+//! internal nodes with named children become objects, numbered and homogeneous
+//! internal nodes become arrays. The schema of each leaf is obtained from its
+//! [`json::describe()`] descriptor in `T::default()` (like [`json::schema_bundle()`]):
+//! the `type`, the range limits as `minimum` and `maximum`, and the `default` value.
+//! Read-only leaves are `readOnly`, write-only leaves are `writeOnly`.
+//! Leaves of unknown type (e.g. absent in `T::default()`) have an empty schema.
+//! Secret leaves (see [`crate::Node::is_secret()`]) are `writeOnly` and their default
+//! value is omitted.
+//!
+//! ```
+//! use miniconf::{openapi, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct S {
+//!     #[tree(max = 10)]
+//!     foo: Leaf<u32>,
+//!     bar: [Leaf<f32>; 2],
+//! };
+//! let schema = openapi::components::<S>();
+//! assert_eq!(schema["properties"]["foo"]["type"], "number");
+//! assert_eq!(schema["properties"]["foo"]["maximum"], 10);
+//! assert_eq!(schema["properties"]["bar"]["items"]["type"], "number");
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use crate::{json, Path, Shape, TreeDeserializeOwned, TreeKey, TreeSerialize};
+
+fn schema<T: TreeKey + TreeSerialize + TreeDeserializeOwned + Default>(
+    shape: &Shape,
+    keys: &mut Vec<usize>,
+    default: &T,
+) -> Value {
+    match shape {
+        Shape::Leaf => {
+            // Note(unwrap): valid keys
+            let (path, node) = T::transcode::<Path<String, '/'>, _>(keys.iter()).unwrap();
+            let Ok(mut desc) = json::descriptor(default, &path, node.is_secret(), &mut Vec::new())
+            else {
+                return json!({});
+            };
+            if !desc.contains_key("type") {
+                return json!({});
+            }
+            match desc.remove("access").as_ref().and_then(Value::as_str) {
+                Some("r") => {
+                    desc.insert("readOnly".into(), true.into());
+                }
+                Some("w") => {
+                    desc.insert("writeOnly".into(), true.into());
+                }
+                _ => {}
+            }
+            if node.is_secret() {
+                desc.insert("writeOnly".into(), true.into());
+            }
+            Value::Object(desc)
+        }
+        Shape::Named(_) => {
+            let properties: Map<_, _> = shape
+                .children()
+                .map(|(i, name, child)| {
+                    keys.push(i);
+                    let child = schema(child, keys, default);
+                    keys.pop();
+                    (name.into_owned(), child)
+                })
+                .collect();
+            json!({
+                "type": "object",
+                "properties": properties,
+                "additionalProperties": false,
+            })
+        }
+        Shape::Numbered(children) => {
+            let items: Vec<_> = shape
+                .children()
+                .map(|(i, _name, child)| {
+                    keys.push(i);
+                    let child = schema(child, keys, default);
+                    keys.pop();
+                    child
+                })
+                .collect();
+            json!({
+                "type": "array",
+                "prefixItems": items,
+                "items": false,
+                "minItems": children.len(),
+                "maxItems": children.len(),
+            })
+        }
+        Shape::Homogeneous(len, child) => {
+            // The first element is representative for defaults
+            keys.push(0);
+            let items = schema(child, keys, default);
+            keys.pop();
+            json!({
+                "type": "array",
+                "items": items,
+                "minItems": len.get(),
+                "maxItems": len.get(),
+            })
+        }
+    }
+}
+
+/// Generate an OpenAPI 3.1 schema object for the whole tree.
+///
+/// See the module documentation.
+pub fn components<T: TreeKey + TreeSerialize + TreeDeserializeOwned + Default>() -> Value {
+    let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+    schema(&shape, &mut Vec::new(), &T::default())
+}
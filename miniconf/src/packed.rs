@@ -61,6 +61,19 @@ use crate::{IntoKeys, Key, KeyLookup, Keys, Node, Transcode, Traversal, TreeKey}
 /// assert_eq!(p.get(), 0b11_0__101_1 << (Packed::CAPACITY - p.len()));
 /// //                              ^ marker
 /// ```
+///
+/// # Capacity
+///
+/// Each level of a key uses `Packed::bits_for(len - 1)` bits where `len` is the
+/// number of children of the internal node at that level (e.g. 3 bits for an array of
+/// 8 elements). A key fits if the sum of the bits of all its levels is at most
+/// [`Packed::CAPACITY`] (`usize::BITS - 1`). For a tree of arrays of length `N` the
+/// maximum depth is thus `Packed::CAPACITY / Packed::bits_for(N - 1)`.
+/// [`TreeKey::traverse_all()`] determines the maximum for a given tree as
+/// `Metadata::max_bits`.
+///
+/// For trees that exceed the capacity, [`PackedWide`] offers the same API and
+/// semantics backed by a `u128`.
 #[derive(
     Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, serde::Serialize, serde::Deserialize,
 )]
@@ -68,218 +81,247 @@ use crate::{IntoKeys, Key, KeyLookup, Keys, Node, Transcode, Traversal, TreeKey}
 #[serde(transparent)]
 pub struct Packed(NonZero<usize>);
 
-impl Default for Packed {
-    #[inline]
-    fn default() -> Self {
-        Self::EMPTY
-    }
-}
+/// A wide bit-packed representation of multiple indices.
+///
+/// This is the same as [`Packed`] but backed by a `u128` with a
+/// [`PackedWide::CAPACITY`] of 127 bits.
+///
+/// ```
+/// use miniconf::{Leaf, PackedWide, Path, TreeKey};
+/// type T = [[[[Leaf<()>; 1 << 16]; 1 << 16]; 1 << 16]; 1 << 16];
+/// let (p, node) = T::transcode::<PackedWide, _>([1, 2, 3, 4]).unwrap();
+/// assert_eq!(node.depth(), 4);
+/// assert_eq!(p.len(), 64);
+/// assert_eq!(p.into_lsb().get(), 1 << 64 | 1 << 48 | 2 << 32 | 3 << 16 | 4);
+/// let (path, _node) = T::transcode::<Path<String, '/'>, _>(p).unwrap();
+/// assert_eq!(path.as_str(), "/1/2/3/4");
+/// ```
+#[derive(
+    Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[repr(transparent)]
+#[serde(transparent)]
+pub struct PackedWide(NonZero<u128>);
 
-impl From<NonZero<usize>> for Packed {
-    #[inline]
-    fn from(value: NonZero<usize>) -> Self {
-        Self(value)
-    }
-}
+macro_rules! impl_packed {
+    ($p:ident, $t:ty) => {
+        impl Default for $p {
+            #[inline]
+            fn default() -> Self {
+                Self::EMPTY
+            }
+        }
 
-impl From<Packed> for NonZero<usize> {
-    #[inline]
-    fn from(value: Packed) -> Self {
-        value.0
-    }
-}
+        impl From<NonZero<$t>> for $p {
+            #[inline]
+            fn from(value: NonZero<$t>) -> Self {
+                Self(value)
+            }
+        }
 
-impl Deref for Packed {
-    type Target = NonZero<usize>;
+        impl From<$p> for NonZero<$t> {
+            #[inline]
+            fn from(value: $p) -> Self {
+                value.0
+            }
+        }
 
-    #[inline]
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
+        impl Deref for $p {
+            type Target = NonZero<$t>;
 
-impl DerefMut for Packed {
-    #[inline]
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
-}
+            #[inline]
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
 
-impl Packed {
-    /// Number of bits in the representation including the marker bit
-    pub const BITS: u32 = NonZero::<usize>::BITS;
+        impl DerefMut for $p {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
 
-    /// The total number of bits this representation can store.
-    pub const CAPACITY: u32 = Self::BITS - 1;
+        impl $p {
+            /// Number of bits in the representation including the marker bit
+            pub const BITS: u32 = NonZero::<$t>::BITS;
 
-    /// The empty value
-    pub const EMPTY: Self = Self(
-        // Slightly cumbersome to generate it with `const`
-        NonZero::<usize>::MIN
-            .saturating_add(1)
-            .saturating_pow(Self::CAPACITY),
-    );
+            /// The total number of bits this representation can store.
+            pub const CAPACITY: u32 = Self::BITS - 1;
 
-    /// Create a new `Packed` from a `usize`.
-    ///
-    /// The value must not be zero.
-    #[inline]
-    pub const fn new(value: usize) -> Option<Self> {
-        match NonZero::new(value) {
-            Some(value) => Some(Self(value)),
-            None => None,
-        }
-    }
+            /// The empty value
+            pub const EMPTY: Self = Self(
+                // Slightly cumbersome to generate it with `const`
+                NonZero::<$t>::MIN
+                    .saturating_add(1)
+                    .saturating_pow(Self::CAPACITY),
+            );
 
-    /// Create a new `Packed` from LSB aligned `usize`
-    ///
-    /// The value must not be zero.
-    #[inline]
-    pub const fn new_from_lsb(value: usize) -> Option<Self> {
-        match NonZero::new(value) {
-            Some(value) => Some(Self::from_lsb(value)),
-            None => None,
-        }
-    }
+            /// Create a new value from its representation.
+            ///
+            /// The value must not be zero.
+            #[inline]
+            pub const fn new(value: $t) -> Option<Self> {
+                match NonZero::new(value) {
+                    Some(value) => Some(Self(value)),
+                    None => None,
+                }
+            }
 
-    /// The value is empty.
-    #[inline]
-    pub const fn is_empty(&self) -> bool {
-        matches!(*self, Self::EMPTY)
-    }
+            /// Create a new value from its LSB aligned representation
+            ///
+            /// The value must not be zero.
+            #[inline]
+            pub const fn new_from_lsb(value: $t) -> Option<Self> {
+                match NonZero::new(value) {
+                    Some(value) => Some(Self::from_lsb(value)),
+                    None => None,
+                }
+            }
 
-    /// Clear and discard all bits stored.
-    #[inline]
-    pub fn clear(&mut self) {
-        *self = Self::EMPTY;
-    }
+            /// The value is empty.
+            #[inline]
+            pub const fn is_empty(&self) -> bool {
+                matches!(*self, Self::EMPTY)
+            }
 
-    /// Number of bits that can be stored.
-    #[inline]
-    pub const fn capacity(&self) -> u32 {
-        self.0.trailing_zeros()
-    }
+            /// Clear and discard all bits stored.
+            #[inline]
+            pub fn clear(&mut self) {
+                *self = Self::EMPTY;
+            }
 
-    /// Number of bits stored.
-    #[inline]
-    pub const fn len(&self) -> u32 {
-        Self::CAPACITY - self.capacity()
-    }
+            /// Number of bits that can be stored.
+            #[inline]
+            pub const fn capacity(&self) -> u32 {
+                self.0.trailing_zeros()
+            }
 
-    /// Return the representation aligned to the LSB with the marker bit
-    /// moved from the LSB to the MSB.
-    #[inline]
-    pub const fn into_lsb(self) -> NonZero<usize> {
-        match NonZero::new(((self.0.get() >> 1) | (1 << Self::CAPACITY)) >> self.0.trailing_zeros())
-        {
-            Some(v) => v,
-            // We ensure there is at least the marker bit set
-            None => unreachable!(),
-        }
-    }
+            /// Number of bits stored.
+            #[inline]
+            pub const fn len(&self) -> u32 {
+                Self::CAPACITY - self.capacity()
+            }
 
-    /// Build a `Packed` from a LSB-aligned representation with the marker bit
-    /// moved from the MSB the LSB.
-    #[inline]
-    pub const fn from_lsb(value: NonZero<usize>) -> Self {
-        match Self::new(((value.get() << 1) | 1) << value.leading_zeros()) {
-            Some(v) => v,
-            // We ensure there is at least the marker bit set
-            None => unreachable!(),
-        }
-    }
+            /// Return the representation aligned to the LSB with the marker bit
+            /// moved from the LSB to the MSB.
+            #[inline]
+            pub const fn into_lsb(self) -> NonZero<$t> {
+                match NonZero::new(
+                    ((self.0.get() >> 1) | (1 << Self::CAPACITY)) >> self.0.trailing_zeros(),
+                ) {
+                    Some(v) => v,
+                    // We ensure there is at least the marker bit set
+                    None => unreachable!(),
+                }
+            }
 
-    /// Return the number of bits required to represent `num`.
-    ///
-    /// Ensures that at least one bit is allocated.
-    #[inline]
-    pub const fn bits_for(num: usize) -> u32 {
-        match usize::BITS - num.leading_zeros() {
-            0 => 1,
-            v => v,
-        }
-    }
+            /// Build a value from a LSB-aligned representation with the marker bit
+            /// moved from the MSB the LSB.
+            #[inline]
+            pub const fn from_lsb(value: NonZero<$t>) -> Self {
+                match Self::new(((value.get() << 1) | 1) << value.leading_zeros()) {
+                    Some(v) => v,
+                    // We ensure there is at least the marker bit set
+                    None => unreachable!(),
+                }
+            }
 
-    /// Remove the given number of MSBs and return them.
-    ///
-    /// If the value does not contain sufficient bits
-    /// it is left unchanged and `None` is returned.
-    ///
-    /// # Args
-    /// * `bits`: Number of bits to pop. `bits <= Self::CAPACITY`
-    pub fn pop_msb(&mut self, bits: u32) -> Option<usize> {
-        let s = self.get();
-        // Remove value from self
-        Self::new(s << bits).map(|new| {
-            *self = new;
-            // Extract value from old self
-            // Done in two steps as bits + 1 can be Self::BITS which would wrap.
-            (s >> (Self::CAPACITY - bits)) >> 1
-        })
-    }
+            /// Return the number of bits required to represent `num`.
+            ///
+            /// Ensures that at least one bit is allocated.
+            #[inline]
+            pub const fn bits_for(num: usize) -> u32 {
+                match usize::BITS - num.leading_zeros() {
+                    0 => 1,
+                    v => v,
+                }
+            }
 
-    /// Push the given number `bits` of `value` as new LSBs.
-    ///
-    /// Returns the remaining number of unused bits on success.
-    ///
-    /// # Args
-    /// * `bits`: Number of bits to push. `bits <= Self::CAPACITY`
-    /// * `value`: Value to push. `value >> bits == 0`
-    pub fn push_lsb(&mut self, bits: u32, value: usize) -> Option<u32> {
-        debug_assert_eq!(value >> bits, 0);
-        let mut n = self.trailing_zeros();
-        let old_marker = 1 << n;
-        Self::new(old_marker >> bits).map(|new_marker| {
-            n -= bits;
-            // * Remove old marker
-            // * Add value at offset n + 1
-            //   Done in two steps as n + 1 can be Self::BITS, which would wrap.
-            // * Add new marker
-            self.0 = (self.get() ^ old_marker) | ((value << n) << 1) | new_marker.0;
-            n
-        })
-    }
-}
+            /// Remove the given number of MSBs and return them.
+            ///
+            /// If the value does not contain sufficient bits
+            /// it is left unchanged and `None` is returned.
+            ///
+            /// # Args
+            /// * `bits`: Number of bits to pop. `bits <= Self::CAPACITY`
+            pub fn pop_msb(&mut self, bits: u32) -> Option<usize> {
+                let s = self.get();
+                // Remove value from self
+                Self::new(s << bits).map(|new| {
+                    *self = new;
+                    // Extract value from old self
+                    // Done in two steps as bits + 1 can be Self::BITS which would wrap.
+                    ((s >> (Self::CAPACITY - bits)) >> 1) as usize
+                })
+            }
 
-impl Keys for Packed {
-    #[inline]
-    fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
-        let bits = Self::bits_for(lookup.len().get() - 1);
-        let index = self.pop_msb(bits).ok_or(Traversal::TooShort(0))?;
-        index.find(lookup)
-    }
+            /// Push the given number `bits` of `value` as new LSBs.
+            ///
+            /// Returns the remaining number of unused bits on success.
+            ///
+            /// # Args
+            /// * `bits`: Number of bits to push. `bits <= Self::CAPACITY`
+            /// * `value`: Value to push. `value >> bits == 0`
+            pub fn push_lsb(&mut self, bits: u32, value: usize) -> Option<u32> {
+                debug_assert_eq!(value >> bits, 0);
+                let mut n = self.trailing_zeros();
+                let old_marker: $t = 1 << n;
+                Self::new(old_marker >> bits).map(|new_marker| {
+                    n -= bits;
+                    // * Remove old marker
+                    // * Add value at offset n + 1
+                    //   Done in two steps as n + 1 can be Self::BITS, which would wrap.
+                    // * Add new marker
+                    self.0 = (self.get() ^ old_marker) | (((value as $t) << n) << 1) | new_marker.0;
+                    n
+                })
+            }
+        }
 
-    #[inline]
-    fn finalize(&mut self) -> Result<(), Traversal> {
-        self.is_empty().then_some(()).ok_or(Traversal::TooLong(0))
-    }
-}
+        impl Keys for $p {
+            #[inline]
+            fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+                let bits = Self::bits_for(lookup.len().get() - 1);
+                let index = self.pop_msb(bits).ok_or(Traversal::TooShort(0))?;
+                index.find(lookup)
+            }
 
-impl IntoKeys for Packed {
-    type IntoKeys = Self;
+            #[inline]
+            fn finalize(&mut self) -> Result<(), Traversal> {
+                self.is_empty().then_some(()).ok_or(Traversal::TooLong(0))
+            }
+        }
 
-    #[inline]
-    fn into_keys(self) -> Self::IntoKeys {
-        self
-    }
-}
+        impl IntoKeys for $p {
+            type IntoKeys = Self;
 
-impl Transcode for Packed {
-    fn transcode<M, K>(&mut self, keys: K) -> Result<Node, Traversal>
-    where
-        Self: Sized,
-        M: TreeKey + ?Sized,
-        K: IntoKeys,
-    {
-        M::traverse_by_key(keys.into_keys(), |index, _name, len| {
-            match self.push_lsb(Packed::bits_for(len.get() - 1), index) {
-                None => Err(()),
-                Some(_) => Ok(()),
+            #[inline]
+            fn into_keys(self) -> Self::IntoKeys {
+                self
             }
-        })
-        .try_into()
-    }
+        }
+
+        impl Transcode for $p {
+            fn transcode<M, K>(&mut self, keys: K) -> Result<Node, Traversal>
+            where
+                Self: Sized,
+                M: TreeKey + ?Sized,
+                K: IntoKeys,
+            {
+                M::traverse_by_key(keys.into_keys(), |index, _name, len| {
+                    match self.push_lsb(Self::bits_for(len.get() - 1), index) {
+                        None => Err(()),
+                        Some(_) => Ok(()),
+                    }
+                })
+                .try_into()
+            }
+        }
+    };
 }
+impl_packed!(Packed, usize);
+impl_packed!(PackedWide, u128);
 
 #[cfg(test)]
 mod test {
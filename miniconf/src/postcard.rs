@@ -33,9 +33,16 @@
 
 use postcard::{de_flavors, ser_flavors, Deserializer, Serializer};
 
-use crate::{Error, IntoKeys, TreeDeserialize, TreeSerialize};
+use crate::{
+    Changed, Error, FieldFormat, FieldValues, Fnv1a, IntoKeys, Node, Packed, Path, Traversal,
+    TreeDeserialize, TreeKey, TreeSerialize,
+};
 
 /// Deserialize and set a node value from a `postcard` flavor.
+///
+/// # Returns
+/// The remainder of the flavor and the node that was updated.
+/// See also `json::set_by_key()`.
 #[inline]
 pub fn set_by_key<
     'de,
@@ -46,10 +53,99 @@ pub fn set_by_key<
     tree: &mut T,
     keys: K,
     flavor: F,
-) -> Result<F::Remainder, Error<postcard::Error>> {
+) -> Result<(F::Remainder, Node), Error<postcard::Error>> {
     let mut de = Deserializer::from_flavor(flavor);
-    tree.deserialize_by_key(keys.into_keys(), &mut de)?;
-    de.finalize().map_err(Error::Finalization)
+    let depth = tree.deserialize_by_key(keys.into_keys(), &mut de)?;
+    let rest = de.finalize().map_err(Error::Finalization)?;
+    Ok((rest, Node::leaf(depth)))
+}
+
+/// Deserialize and set a node value from a `postcard` flavor and report the changed fields
+/// of a struct leaf.
+///
+/// The field values are serialized into `scratch`.
+///
+/// # Returns
+/// The remainder of the flavor, the node that was updated, and the names of the fields
+/// that changed.
+/// See also `json::set_by_key_changed()` and [`FieldValues`].
+pub fn set_by_key_changed<
+    'de,
+    T: TreeSerialize + TreeDeserialize<'de> + ?Sized,
+    K: IntoKeys + Clone,
+    F: de_flavors::Flavor<'de>,
+    const N: usize,
+>(
+    tree: &mut T,
+    keys: K,
+    flavor: F,
+    scratch: &mut [u8],
+) -> Result<(F::Remainder, Node, Changed<N>), Error<postcard::Error>> {
+    let mut before = FieldValues::<Format, N>::new(tree, keys.clone(), scratch)?;
+    let (rest, node) = set_by_key(tree, keys.clone(), flavor)?;
+    let changed = before.changed(tree, keys)?;
+    Ok((rest, node, changed))
+}
+
+/// `postcard` field value format for [`FieldValues`]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Format;
+
+impl FieldFormat for Format {
+    fn to_slice<T: serde::Serialize + ?Sized>(value: &T, buf: &mut [u8]) -> Option<usize> {
+        postcard::to_slice(value, buf).ok().map(|used| used.len())
+    }
+}
+
+/// Object-safe `postcard` deserialization shim.
+///
+/// [`TreeDeserialize`] is not dyn-compatible.
+/// This trait is implemented for all `TreeDeserialize` types with a concrete key and
+/// slice flavor and can be used as a trait object. See [`set_dyn()`] and `json::DynSet`.
+pub trait DynSet<'de> {
+    /// Deserialize and set a node value from a slice. See [`set_by_key()`].
+    fn set_dyn(
+        &mut self,
+        key: Packed,
+        data: &'de [u8],
+    ) -> Result<(&'de [u8], Node), Error<postcard::Error>>;
+}
+
+impl<'de, T: TreeDeserialize<'de> + ?Sized> DynSet<'de> for T {
+    #[inline]
+    fn set_dyn(
+        &mut self,
+        key: Packed,
+        data: &'de [u8],
+    ) -> Result<(&'de [u8], Node), Error<postcard::Error>> {
+        set_by_key(self, key, de_flavors::Slice::new(data))
+    }
+}
+
+/// Deserialize and set a node value from a slice through a trait object.
+///
+/// ```
+/// use miniconf::{postcard, Leaf, Packed, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     foo: Leaf<u32>,
+/// };
+/// let mut s = S::default();
+/// let d: &mut dyn postcard::DynSet = &mut s;
+/// let (rest, _node) = postcard::set_dyn(d, Packed::new_from_lsb(0b10).unwrap(), &[9]).unwrap();
+/// assert!(rest.is_empty());
+/// assert_eq!(*s.foo, 9);
+/// ```
+///
+/// # Returns
+/// The remainder of `data` and the node that was updated.
+#[inline]
+pub fn set_dyn<'de>(
+    tree: &mut dyn DynSet<'de>,
+    key: Packed,
+    data: &'de [u8],
+) -> Result<(&'de [u8], Node), Error<postcard::Error>> {
+    tree.set_dyn(key, data)
 }
 
 /// Get and serialize a node value into a `postcard` flavor.
@@ -59,7 +155,337 @@ pub fn get_by_key<T: TreeSerialize + ?Sized, K: IntoKeys, F: ser_flavors::Flavor
     keys: K,
     flavor: F,
 ) -> Result<F::Output, Error<postcard::Error>> {
+    get_by_key_meta(tree, keys, flavor).map(|(output, _node)| output)
+}
+
+/// Get and serialize a node value into a `postcard` flavor and return the node.
+///
+/// # Returns
+/// The output of the flavor and the node that was serialized.
+/// See also `json::get_by_key_meta()`.
+#[inline]
+pub fn get_by_key_meta<T: TreeSerialize + ?Sized, K: IntoKeys, F: ser_flavors::Flavor>(
+    tree: &T,
+    keys: K,
+    flavor: F,
+) -> Result<(F::Output, Node), Error<postcard::Error>> {
     let mut ser = Serializer { output: flavor };
-    tree.serialize_by_key(keys.into_keys(), &mut ser)?;
-    ser.output.finalize().map_err(Error::Finalization)
+    let depth = tree.serialize_by_key(keys.into_keys(), &mut ser)?;
+    let output = ser.output.finalize().map_err(Error::Finalization)?;
+    Ok((output, Node::leaf(depth)))
+}
+
+/// CRC-16/CCITT-FALSE
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |mut crc, byte| {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = (crc << 1) ^ if crc & 0x8000 != 0 { 0x1021 } else { 0 };
+        }
+        crc
+    })
+}
+
+/// Snapshot of all leaf values of a tree in `postcard` encoding.
+///
+/// A snapshot is a sequence of records, one per present leaf.
+/// Each record is the [`Packed`] key (as a `postcard` varint),
+/// the value length (`u16` little endian), the `postcard` encoded value, and a
+/// CRC-16/CCITT-FALSE (`u16` little endian) over all of the preceding record bytes.
+///
+/// ```
+/// use miniconf::{postcard::Snapshot, Leaf, Tree};
+///
+/// #[derive(Tree, Default, PartialEq, Debug)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+///
+/// let source = S {
+///     foo: 9.into(),
+///     bar: [7.into(), 11.into()],
+/// };
+/// let mut buf = [0; 32];
+/// let len = Snapshot::to_bytes_crc::<_, 2>(&source, &mut buf).unwrap();
+///
+/// let mut target = S::default();
+/// let dropped = Snapshot::from_bytes_crc(&mut target, &buf[..len]).unwrap();
+/// assert_eq!(dropped, 0);
+/// assert_eq!(source, target);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Snapshot;
+
+impl Snapshot {
+    /// Serialize all present leaves into CRC protected records.
+    ///
+    /// Absent leaves are skipped.
+    /// `D` is the maximum key depth.
+    ///
+    /// # Returns
+    /// The number of bytes written to `buf`.
+    pub fn to_bytes_crc<T: TreeKey + TreeSerialize + ?Sized, const D: usize>(
+        tree: &T,
+        buf: &mut [u8],
+    ) -> Result<usize, Error<postcard::Error>> {
+        const FULL: Error<postcard::Error> =
+            Error::Finalization(postcard::Error::SerializeBufferFull);
+        let mut len = 0;
+        for key in T::nodes::<Packed, D>() {
+            let (key, _node) = key.map_err(Traversal::TooLong)?;
+            let record = &mut buf[len..];
+            let head = postcard::to_slice(&key.into_lsb().get(), record)
+                .map_err(Error::Finalization)?
+                .len();
+            let value = record.get_mut(head + 2..).ok_or(FULL)?;
+            let size = match get_by_key(tree, key, ser_flavors::Slice::new(value)) {
+                Err(Error::Traversal(Traversal::Absent(_))) => continue,
+                ret => ret?.len(),
+            };
+            record[head..head + 2]
+                .copy_from_slice(&u16::try_from(size).or(Err(FULL))?.to_le_bytes());
+            let end = head + 2 + size;
+            let crc = crc16(&record[..end]);
+            record
+                .get_mut(end..end + 2)
+                .ok_or(FULL)?
+                .copy_from_slice(&crc.to_le_bytes());
+            len += end + 2;
+        }
+        Ok(len)
+    }
+
+    /// Deserialize CRC protected records into a tree.
+    ///
+    /// Records with CRC mismatch are skipped.
+    /// If the record framing itself is corrupted, the remainder of `buf` is dropped
+    /// as a single record.
+    /// Records with valid CRC that fail to deserialize return an error.
+    ///
+    /// # Returns
+    /// The number of dropped records.
+    pub fn from_bytes_crc<'de, T: TreeDeserialize<'de> + ?Sized>(
+        tree: &mut T,
+        mut buf: &'de [u8],
+    ) -> Result<usize, Error<postcard::Error>> {
+        let mut dropped = 0;
+        while !buf.is_empty() {
+            let Some((key, value, valid, rest)) = Self::split_record(buf) else {
+                dropped += 1;
+                break;
+            };
+            buf = rest;
+            match Packed::new_from_lsb(key) {
+                Some(key) if valid => {
+                    set_by_key(tree, key, de_flavors::Slice::new(value))?;
+                }
+                _ => {
+                    dropped += 1;
+                }
+            }
+        }
+        Ok(dropped)
+    }
+
+    /// Apply a patch of `(Packed, value)` records to a tree.
+    ///
+    /// Each `postcard` encoded value is deserialized into the leaf identified by the
+    /// [`Packed`] key. Records for absent leaves are skipped.
+    ///
+    /// ```
+    /// use miniconf::{postcard::Snapshot, Leaf, Packed, Tree, TreeKey};
+    ///
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let (key, _node) = S::transcode::<Packed, _>(["bar", "1"]).unwrap();
+    /// let mut s = S::default();
+    /// assert_eq!(Snapshot::apply_patch(&mut s, &[(key, &[7])]), Ok(1));
+    /// assert_eq!(*s.bar[1], 7);
+    /// ```
+    ///
+    /// # Returns
+    /// The number of applied records.
+    pub fn apply_patch<'de, T: TreeDeserialize<'de> + ?Sized>(
+        tree: &mut T,
+        patch: &[(Packed, &'de [u8])],
+    ) -> Result<usize, Error<postcard::Error>> {
+        let mut applied = 0;
+        for (key, value) in patch.iter() {
+            match set_by_key(tree, *key, de_flavors::Slice::new(value)) {
+                Err(Error::Traversal(Traversal::Absent(_))) => {}
+                ret => {
+                    ret?;
+                    applied += 1;
+                }
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Split a record into key, value, CRC validity, and the remainder.
+    fn split_record(buf: &[u8]) -> Option<(usize, &[u8], bool, &[u8])> {
+        let (key, rest) = postcard::take_from_bytes::<usize>(buf).ok()?;
+        let head = buf.len() - rest.len() + 2;
+        let (size, _) = rest.split_first_chunk()?;
+        let size = u16::from_le_bytes(*size) as usize;
+        let (record, rest) = buf.split_at_checked(head + size)?;
+        let (crc, rest) = rest.split_first_chunk()?;
+        let valid = crc16(record) == u16::from_le_bytes(*crc);
+        Some((key, &record[head..], valid, rest))
+    }
+}
+
+/// Counts of a [`load_all()`]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LoadCount {
+    /// Records applied to a leaf
+    pub applied: usize,
+    /// Records skipped because their path is unknown or absent
+    pub skipped: usize,
+    /// Leaves without a record
+    pub missing: usize,
+}
+
+/// Serialize all present leaves into path hash keyed records.
+///
+/// Each record is the [`Fnv1a`] hash of the canonical `'/'`-separated
+/// path (`u32` little endian), the value length (`postcard` varint), and
+/// the `postcard` encoded value.
+/// In contrast to [`Snapshot`] which is keyed by [`Packed`], these records are stable
+/// when leaves are added or removed and can be loaded using [`load_all()`] after schema
+/// changes.
+///
+/// Absent leaves are skipped.
+/// `D` is the maximum key depth.
+///
+/// # Returns
+/// The number of bytes written to `buf`.
+pub fn save_all<T: TreeKey + TreeSerialize + ?Sized, const D: usize>(
+    tree: &T,
+    buf: &mut [u8],
+) -> Result<usize, Error<postcard::Error>> {
+    const FULL: Error<postcard::Error> = Error::Finalization(postcard::Error::SerializeBufferFull);
+    let mut len = 0;
+    let mut value = [0; 10]; // postcard usize varint
+    for key in T::nodes::<Packed, D>() {
+        let (key, _node) = key.map_err(Traversal::TooLong)?;
+        let record = &mut buf[len..];
+        let (head, rest) = record.split_first_chunk_mut::<4>().ok_or(FULL)?;
+        let size = match get_by_key(tree, key, ser_flavors::Slice::new(rest)) {
+            Err(Error::Traversal(Traversal::Absent(_))) => continue,
+            ret => ret?.len(),
+        };
+        let (hash, _node) = T::transcode::<Path<Fnv1a, '/'>, _>(key)?;
+        *head = hash.into_inner().finish().to_le_bytes();
+        let varint = postcard::to_slice(&size, &mut value)
+            .map_err(Error::Finalization)?
+            .len();
+        // Move the value behind the length
+        let end = 4 + varint + size;
+        record
+            .get_mut(..end)
+            .ok_or(FULL)?
+            .copy_within(4..4 + size, 4 + varint);
+        record[4..4 + varint].copy_from_slice(&value[..varint]);
+        len += end;
+    }
+    Ok(len)
+}
+
+/// Deserialize path hash keyed records from [`save_all()`] into a tree.
+///
+/// Records with unknown path hashes (e.g. removed leaves) or for absent leaves
+/// are skipped. Leaves without a record (e.g. new leaves) keep their current value.
+/// Records that fail to deserialize return an error.
+/// `D` is the maximum key depth.
+///
+/// This is a single pass over the leaves. The record of each leaf is searched starting
+/// after the previous match. For records in leaf order (as written by [`save_all()`]
+/// for an unchanged or extended schema) this is a single pass over the records as well.
+///
+/// ```
+/// use miniconf::{postcard, Leaf, Tree};
+///
+/// #[derive(Tree, Default)]
+/// struct Old {
+///     foo: Leaf<u32>,
+///     gone: Leaf<u8>,
+/// };
+/// #[derive(Tree, Default)]
+/// struct New {
+///     added: Leaf<i16>,
+///     foo: Leaf<u32>,
+/// };
+///
+/// let old = Old { foo: 9.into(), gone: 3.into() };
+/// let mut buf = [0; 32];
+/// let len = postcard::save_all::<_, 1>(&old, &mut buf).unwrap();
+///
+/// let mut new = New { added: 5.into(), ..Default::default() };
+/// let count = postcard::load_all::<_, 1>(&mut new, &buf[..len]).unwrap();
+/// assert_eq!((*new.foo, *new.added), (9, 5));
+/// assert_eq!((count.applied, count.skipped, count.missing), (1, 1, 1));
+/// ```
+pub fn load_all<'de, T: TreeKey + TreeDeserialize<'de> + ?Sized, const D: usize>(
+    tree: &mut T,
+    buf: &'de [u8],
+) -> Result<LoadCount, Error<postcard::Error>> {
+    // Hash, value, and remainder
+    type Record<'a> = (u32, &'a [u8], &'a [u8]);
+
+    // Split off the first record
+    fn record(buf: &[u8]) -> Result<Record<'_>, Error<postcard::Error>> {
+        const EOF: Error<postcard::Error> =
+            Error::Finalization(postcard::Error::DeserializeUnexpectedEnd);
+        let (hash, rest) = buf.split_first_chunk::<4>().ok_or(EOF)?;
+        let (size, rest) = postcard::take_from_bytes::<usize>(rest).map_err(Error::Finalization)?;
+        let (value, rest) = rest.split_at_checked(size).ok_or(EOF)?;
+        Ok((u32::from_le_bytes(*hash), value, rest))
+    }
+
+    let mut records = 0;
+    let mut rest = buf;
+    while !rest.is_empty() {
+        (_, _, rest) = record(rest)?;
+        records += 1;
+    }
+    let mut count = LoadCount::default();
+    let mut cursor = buf;
+    for node in T::nodes::<Packed, D>() {
+        let (key, _node) = node.map_err(Traversal::TooLong)?;
+        let (path, _node) = T::transcode::<Path<Fnv1a, '/'>, _>(key)?;
+        let hash = path.into_inner().finish();
+        // Cyclic search starting after the previous match
+        let mut value = None;
+        rest = cursor;
+        for _ in 0..records {
+            if rest.is_empty() {
+                rest = buf;
+            }
+            let (h, v, r) = record(rest)?;
+            rest = r;
+            if h == hash {
+                value = Some(v);
+                break;
+            }
+        }
+        let Some(value) = value else {
+            count.missing += 1;
+            continue;
+        };
+        cursor = rest;
+        match set_by_key(tree, key, de_flavors::Slice::new(value)) {
+            Err(Error::Traversal(Traversal::Absent(_))) => {}
+            ret => {
+                ret?;
+                count.applied += 1;
+            }
+        }
+    }
+    count.skipped = records - count.applied;
+    Ok(count)
 }
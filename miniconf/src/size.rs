@@ -0,0 +1,428 @@
+//! Upper bounds of serialized value sizes.
+//!
+//! The bound for a leaf is determined by the types of its value (as seen by serializing
+//! it), not by the value itself. This allows sizing payload buffers for any value a leaf
+//! may take.
+//! Fixed-size primitives, unit types, newtype structs, tuples, arrays,
+//! structs, and `Some` of bounded values are bounded. Strings, bytes, sequences, maps,
+//! `None` (the type of the inner value is not known), and enum variants are unbounded.
+//!
+//! ```
+//! use miniconf::{size, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct S {
+//!     foo: Leaf<u32>,
+//!     bar: [Leaf<(i16, f32)>; 2],
+//! };
+//! let s = S::default();
+//! assert_eq!(size::value_size::<size::Json, _, _>(&s, ["foo"]), Some(10));
+//! assert_eq!(size::max_value_size::<size::Json, _, 2>(&s), Some(25));
+//! assert_eq!(size::max_value_size::<size::Postcard, _, 2>(&s), Some(7));
+//! ```
+
+use core::marker::PhantomData;
+
+use serde::{
+    ser::{Impossible, SerializeStruct, SerializeTuple, SerializeTupleStruct},
+    Serialize, Serializer,
+};
+
+use crate::{Error, Indices, IntoKeys, Traversal, TreeKey, TreeSerialize};
+
+/// Fixed-size primitive types
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Primitive {
+    /// `bool`
+    Bool,
+    /// `i8`
+    I8,
+    /// `i16`
+    I16,
+    /// `i32`
+    I32,
+    /// `i64`
+    I64,
+    /// `i128`
+    I128,
+    /// `u8`
+    U8,
+    /// `u16`
+    U16,
+    /// `u32`
+    U32,
+    /// `u64`
+    U64,
+    /// `u128`
+    U128,
+    /// `f32`
+    F32,
+    /// `f64`
+    F64,
+    /// `char`
+    Char,
+    /// `()` and unit structs
+    Unit,
+}
+
+/// Serialization format size bounds
+pub trait Format {
+    /// The maximum size of a primitive value
+    fn primitive(primitive: Primitive) -> usize;
+
+    /// The size of the framing of a tuple or array with `len` elements
+    fn tuple(len: usize) -> usize;
+
+    /// The size of the framing of a struct with `len` fields, excluding the field names
+    fn structure(len: usize) -> usize;
+
+    /// The size of a field name
+    fn field(name: &str) -> usize;
+
+    /// The size of `None`
+    fn none() -> usize;
+
+    /// The size of `Some` wrapping a value of size `inner`
+    fn some(inner: usize) -> usize;
+}
+
+/// JSON (`serde-json-core` and `serde_json`)
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Json;
+
+impl Format for Json {
+    fn primitive(primitive: Primitive) -> usize {
+        match primitive {
+            Primitive::Bool => "false".len(),
+            Primitive::I8 => "-128".len(),
+            Primitive::I16 => "-32768".len(),
+            Primitive::I32 => "-2147483648".len(),
+            Primitive::I64 => "-9223372036854775808".len(),
+            Primitive::I128 => "-170141183460469231731687303715884105728".len(),
+            Primitive::U8 => "255".len(),
+            Primitive::U16 => "65535".len(),
+            Primitive::U32 => "4294967295".len(),
+            Primitive::U64 => "18446744073709551615".len(),
+            Primitive::U128 => "340282366920938463463374607431768211455".len(),
+            // Shortest round trip representation (`ryu`)
+            Primitive::F32 => 16,
+            Primitive::F64 => 24,
+            Primitive::Char => r#""\u001f""#.len(),
+            Primitive::Unit => "null".len(),
+        }
+    }
+
+    fn tuple(len: usize) -> usize {
+        // Brackets and commas
+        2 + len.saturating_sub(1)
+    }
+
+    fn structure(len: usize) -> usize {
+        // Braces and commas
+        2 + len.saturating_sub(1)
+    }
+
+    fn field(name: &str) -> usize {
+        // Quotes and colon
+        name.len() + 3
+    }
+
+    fn none() -> usize {
+        "null".len()
+    }
+
+    fn some(inner: usize) -> usize {
+        inner
+    }
+}
+
+/// Postcard
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Postcard;
+
+impl Format for Postcard {
+    fn primitive(primitive: Primitive) -> usize {
+        // Integers wider than a byte are varint encoded
+        match primitive {
+            Primitive::Bool | Primitive::I8 | Primitive::U8 => 1,
+            Primitive::I16 | Primitive::U16 => 3,
+            Primitive::I32 | Primitive::U32 => 5,
+            Primitive::I64 | Primitive::U64 => 10,
+            Primitive::I128 | Primitive::U128 => 19,
+            Primitive::F32 => 4,
+            Primitive::F64 => 8,
+            // Length and UTF-8
+            Primitive::Char => 5,
+            Primitive::Unit => 0,
+        }
+    }
+
+    fn tuple(_len: usize) -> usize {
+        0
+    }
+
+    fn structure(_len: usize) -> usize {
+        0
+    }
+
+    fn field(_name: &str) -> usize {
+        0
+    }
+
+    fn none() -> usize {
+        // Tag
+        1
+    }
+
+    fn some(inner: usize) -> usize {
+        1 + inner
+    }
+}
+
+/// Upper bound of the serialized size of a leaf value.
+///
+/// # Returns
+/// The bound or `None` if the leaf is unbounded, absent, or not accessible.
+pub fn value_size<F: Format, T: TreeSerialize + ?Sized, K: IntoKeys>(
+    tree: &T,
+    keys: K,
+) -> Option<usize> {
+    bound::<F, _, _>(tree, keys).ok()
+}
+
+fn bound<F: Format, T: TreeSerialize + ?Sized, K: IntoKeys>(
+    tree: &T,
+    keys: K,
+) -> Result<usize, Error<Unbounded>> {
+    let mut size = 0;
+    tree.serialize_by_key(keys.into_keys(), Bound::<F>::new(&mut size))?;
+    Ok(size)
+}
+
+/// Upper bound of the serialized size of any leaf value.
+///
+/// `D` is the depth limit (see [`crate::NodeIter`]).
+/// Absent leaves are skipped.
+///
+/// # Returns
+/// The bound or `None` if any present leaf is unbounded or not accessible.
+pub fn max_value_size<F: Format, T: TreeKey + TreeSerialize, const D: usize>(
+    tree: &T,
+) -> Option<usize> {
+    T::nodes::<Indices<[usize; D]>, D>().try_fold(0, |max, node| {
+        let (keys, node) = node.ok()?;
+        match bound::<F, _, _>(tree, &keys[..node.depth()]) {
+            Ok(size) => Some(max.max(size)),
+            Err(Error::Traversal(Traversal::Absent(_))) => Some(max),
+            Err(_) => None,
+        }
+    })
+}
+
+/// Size bound `Serializer`
+struct Bound<'a, F> {
+    size: &'a mut usize,
+    _f: PhantomData<F>,
+}
+
+impl<'a, F: Format> Bound<'a, F> {
+    fn new(size: &'a mut usize) -> Self {
+        Self {
+            size,
+            _f: PhantomData,
+        }
+    }
+
+    fn add(self, size: usize) -> Result<(), Unbounded> {
+        *self.size += size;
+        Ok(())
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Unbounded> {
+        value.serialize(Bound::<F>::new(self.size))
+    }
+}
+
+/// The value is not bounded
+#[derive(Debug)]
+struct Unbounded;
+
+impl core::fmt::Display for Unbounded {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Unbounded")
+    }
+}
+
+impl serde::ser::StdError for Unbounded {}
+
+impl serde::ser::Error for Unbounded {
+    fn custom<T: core::fmt::Display>(_msg: T) -> Self {
+        Self
+    }
+}
+
+macro_rules! primitive {
+    ($($name:ident($arg:ty) -> $primitive:ident;)*) => {
+        $(
+            #[inline]
+            fn $name(self, _: $arg) -> Result<(), Unbounded> {
+                self.add(F::primitive(Primitive::$primitive))
+            }
+        )*
+    };
+}
+
+macro_rules! unbounded {
+    ($($name:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(
+            #[inline]
+            fn $name(self, $(_: $arg),*) -> Result<$ret, Unbounded> {
+                Err(Unbounded)
+            }
+        )*
+    };
+}
+
+impl<'a, F: Format> Serializer for Bound<'a, F> {
+    type Ok = ();
+    type Error = Unbounded;
+    type SerializeSeq = Impossible<(), Unbounded>;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), Unbounded>;
+    type SerializeMap = Impossible<(), Unbounded>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), Unbounded>;
+
+    primitive! {
+        serialize_bool(bool) -> Bool;
+        serialize_i8(i8) -> I8;
+        serialize_i16(i16) -> I16;
+        serialize_i32(i32) -> I32;
+        serialize_i64(i64) -> I64;
+        serialize_i128(i128) -> I128;
+        serialize_u8(u8) -> U8;
+        serialize_u16(u16) -> U16;
+        serialize_u32(u32) -> U32;
+        serialize_u64(u64) -> U64;
+        serialize_u128(u128) -> U128;
+        serialize_f32(f32) -> F32;
+        serialize_f64(f64) -> F64;
+        serialize_char(char) -> Char;
+        serialize_unit_struct(&'static str) -> Unit;
+    }
+
+    unbounded! {
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    #[inline]
+    fn serialize_unit(self) -> Result<(), Unbounded> {
+        self.add(F::primitive(Primitive::Unit))
+    }
+
+    #[inline]
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Unbounded> {
+        let mut inner = 0;
+        value.serialize(Bound::<F>::new(&mut inner))?;
+        self.add(F::some(inner).max(F::none()))
+    }
+
+    #[inline]
+    fn collect_str<T: ?Sized + core::fmt::Display>(self, _value: &T) -> Result<(), Unbounded> {
+        Err(Unbounded)
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Unbounded> {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Unbounded> {
+        Err(Unbounded)
+    }
+
+    #[inline]
+    fn serialize_tuple(self, len: usize) -> Result<Self, Unbounded> {
+        *self.size += F::tuple(len);
+        Ok(self)
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self, Unbounded> {
+        self.serialize_tuple(len)
+    }
+
+    #[inline]
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self, Unbounded> {
+        *self.size += F::structure(len);
+        Ok(self)
+    }
+}
+
+impl<F: Format> SerializeTuple for Bound<'_, F> {
+    type Ok = ();
+    type Error = Unbounded;
+
+    #[inline]
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Unbounded> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<(), Unbounded> {
+        Ok(())
+    }
+}
+
+impl<F: Format> SerializeTupleStruct for Bound<'_, F> {
+    type Ok = ();
+    type Error = Unbounded;
+
+    #[inline]
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Unbounded> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<(), Unbounded> {
+        Ok(())
+    }
+}
+
+impl<F: Format> SerializeStruct for Bound<'_, F> {
+    type Ok = ();
+    type Error = Unbounded;
+
+    #[inline]
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Unbounded> {
+        *self.size += F::field(key);
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<(), Unbounded> {
+        Ok(())
+    }
+}
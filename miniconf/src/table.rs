@@ -0,0 +1,171 @@
+use crate::{hash::decimal, KeyLookup, Packed, Schema, TreeKey};
+
+/// Compile time table of all leaf paths and their [`Packed`] keys.
+///
+/// This backs the `KEYS` table generated by the `key_table` derive macro attribute.
+/// `N` is the number of leaves and `L` the size of the path buffer (see [`KeyTable::len()`]).
+/// Paths are `'/'`-separated (as with [`crate::Path`]) and entries are sorted by path.
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyTable<const N: usize, const L: usize> {
+    paths: [u8; L],
+    entries: [(usize, usize, Packed); N],
+}
+
+/// Total length of the paths of all nodes below `schema` with a path of length `base`
+const fn paths_len(schema: &Schema, base: usize) -> usize {
+    match schema {
+        Schema::Leaf => 0,
+        Schema::Internal(lookup, children) => {
+            let mut len = 0;
+            let mut index = 0;
+            while index < lookup.len().get() {
+                let (name, child) = match lookup {
+                    KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => {
+                        (names[index].len(), &children[index])
+                    }
+                    KeyLookup::Numbered(_) => (20 - decimal(index).1, &children[index]),
+                    KeyLookup::Homogeneous(_) => (20 - decimal(index).1, &children[0]),
+                };
+                let child_len = base + 1 + name;
+                len += child_len + paths_len(child, child_len);
+                index += 1;
+            }
+            len
+        }
+    }
+}
+
+/// Lexicographic byte order
+const fn less(a: &[u8], b: &[u8]) -> bool {
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+        i += 1;
+    }
+    a.len() < b.len()
+}
+
+impl KeyTable<0, 0> {
+    /// Size of the path buffer for `M`
+    pub const fn len<M: TreeKey + ?Sized>() -> usize {
+        paths_len(&M::SCHEMA, 0)
+    }
+}
+
+impl<const N: usize, const L: usize> KeyTable<N, L> {
+    /// Build the table for the leaves of `M`.
+    ///
+    /// # Panics
+    /// If `N`, `L` do not match `M` or if a key does not fit into a `Packed`.
+    pub const fn new<M: TreeKey + ?Sized>() -> Self {
+        let mut table = Self {
+            paths: [0; L],
+            entries: [(0, 0, Packed::EMPTY); N],
+        };
+        let (len, end) = table.insert(&M::SCHEMA, 0, 0, 1, 0, 0);
+        assert!(len == N && end == L);
+        // Insertion sort by path
+        let mut i = 1;
+        while i < N {
+            let mut j = i;
+            while j > 0 && less(table.path(j), table.path(j - 1)) {
+                let t = table.entries[j];
+                table.entries[j] = table.entries[j - 1];
+                table.entries[j - 1] = t;
+                j -= 1;
+            }
+            i += 1;
+        }
+        table
+    }
+
+    const fn path(&self, index: usize) -> &[u8] {
+        let (start, end, _) = self.entries[index];
+        self.paths.split_at(end).0.split_at(start).1
+    }
+
+    /// Recursively add the leaves below `schema`.
+    ///
+    /// The path to the node is `paths[start..end]` and `lsb` is the LSB aligned
+    /// `Packed` representation of its key. `len` is the number of entries so far.
+    /// Returns the new number of entries and the new end of `paths`.
+    const fn insert(
+        &mut self,
+        schema: &Schema,
+        start: usize,
+        end: usize,
+        lsb: usize,
+        mut len: usize,
+        mut next: usize,
+    ) -> (usize, usize) {
+        match schema {
+            Schema::Leaf => {
+                assert!(len < N);
+                let Some(packed) = Packed::new_from_lsb(lsb) else {
+                    unreachable!()
+                };
+                self.entries[len] = (start, end, packed);
+                (len + 1, next)
+            }
+            Schema::Internal(lookup, children) => {
+                let bits = Packed::bits_for(lookup.len().get() - 1);
+                assert!(lsb.leading_zeros() >= bits);
+                let mut index = 0;
+                while index < lookup.len().get() {
+                    // Each child path is a copy of the parent path and the child name.
+                    let child_start = next;
+                    let mut i = start;
+                    while i < end {
+                        self.paths[next] = self.paths[i];
+                        next += 1;
+                        i += 1;
+                    }
+                    self.paths[next] = b'/';
+                    next += 1;
+                    let digits = decimal(index);
+                    let (name, child) = match lookup {
+                        KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => {
+                            (names[index].as_bytes(), &children[index])
+                        }
+                        KeyLookup::Numbered(_) => (digits.0.split_at(digits.1).1, &children[index]),
+                        KeyLookup::Homogeneous(_) => (digits.0.split_at(digits.1).1, &children[0]),
+                    };
+                    let mut i = 0;
+                    while i < name.len() {
+                        self.paths[next] = name[i];
+                        next += 1;
+                        i += 1;
+                    }
+                    let child_end = next;
+                    (len, next) = self.insert(
+                        child,
+                        child_start,
+                        child_end,
+                        (lsb << bits) | index,
+                        len,
+                        next,
+                    );
+                    index += 1;
+                }
+                (len, next)
+            }
+        }
+    }
+
+    /// The sorted `(path, key)` entries.
+    pub const fn entries(&'static self) -> [(&'static str, Packed); N] {
+        let mut entries = [("", Packed::EMPTY); N];
+        let mut i = 0;
+        while i < N {
+            let Ok(path) = core::str::from_utf8(self.path(i)) else {
+                unreachable!()
+            };
+            entries[i] = (path, self.entries[i].2);
+            i += 1;
+        }
+        entries
+    }
+}
@@ -0,0 +1,165 @@
+//! TOML documents
+//!
+//! Map a tree to and from a nested TOML document.
+//! Internal nodes with named children become tables, numbered and homogeneous
+//! internal nodes become arrays.
+//!
+//! Absent leaves and leaves with a `None` value are omitted from tables.
+//! Absent elements of arrays are represented by empty tables and skipped in [`set_all()`].
+//! Table keys are in tree order with the `preserve_order` feature and sorted by name otherwise.
+//!
+//! ```
+//! use miniconf::{toml, Leaf, Tree};
+//! #[derive(Tree, Default)]
+//! struct S {
+//!     bar: [Leaf<f32>; 2],
+//!     foo: Leaf<u32>,
+//! };
+//! let mut s = S::default();
+//! toml::set_all(&mut s, "foo = 9\nbar = [1.5]").unwrap();
+//! assert_eq!(*s.foo, 9);
+//! assert_eq!(toml::get_all(&s).unwrap(), "bar = [1.5, 0.0]\nfoo = 9\n");
+//! ```
+
+use serde::{ser::Error as _, Deserialize};
+use toml::{de, ser, Table, Value};
+
+use crate::{Error, IntoKeys, Shape, Traversal, TreeDeserializeOwned, TreeKey, TreeSerialize};
+
+fn get<T: TreeSerialize + ?Sized>(
+    tree: &T,
+    shape: &Shape,
+    keys: &mut Vec<usize>,
+) -> Result<Option<Value>, Error<ser::Error>> {
+    Ok(match shape {
+        Shape::Leaf => {
+            let mut data = String::new();
+            match tree.serialize_by_key(
+                keys.iter().into_keys(),
+                ser::ValueSerializer::new(&mut data),
+            ) {
+                Err(Error::Traversal(Traversal::Absent(_))) => None,
+                Err(Error::Inner(_, err)) if err == Value::try_from(None::<()>).unwrap_err() => {
+                    None
+                }
+                ret => {
+                    ret?;
+                    let value = Value::deserialize(de::ValueDeserializer::new(&data))
+                        .map_err(|err| Error::Inner(keys.len(), ser::Error::custom(err)))?;
+                    Some(value)
+                }
+            }
+        }
+        Shape::Named(_) => {
+            let mut table = Table::new();
+            for (i, name, child) in shape.children() {
+                keys.push(i);
+                let value = get(tree, child, keys)?;
+                keys.pop();
+                if let Some(value) = value {
+                    table.insert(name.into_owned(), value);
+                }
+            }
+            (!table.is_empty()).then_some(Value::Table(table))
+        }
+        Shape::Numbered(_) | Shape::Homogeneous(..) => {
+            array(shape.children().map(|(i, _name, child)| {
+                keys.push(i);
+                let value = get(tree, child, keys);
+                keys.pop();
+                value
+            }))?
+        }
+    })
+}
+
+fn array<E>(items: impl Iterator<Item = Result<Option<Value>, E>>) -> Result<Option<Value>, E> {
+    let mut present = false;
+    let items = items
+        .map(|item| {
+            let item = item?;
+            present |= item.is_some();
+            Ok(item.unwrap_or_else(|| Value::Table(Table::new())))
+        })
+        .collect::<Result<Vec<_>, E>>()?;
+    Ok(present.then_some(Value::Array(items)))
+}
+
+/// Serialize a tree into a TOML document.
+///
+/// # Returns
+/// The TOML document.
+pub fn get_all<T: TreeKey + TreeSerialize + ?Sized>(tree: &T) -> Result<String, Error<ser::Error>> {
+    let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+    let Some(value) = get(tree, &shape, &mut vec![])? else {
+        return Ok(String::new());
+    };
+    let Value::Table(table) = value else {
+        return Err(Traversal::TooShort(0).into());
+    };
+    toml::to_string(&table).map_err(Error::Finalization)
+}
+
+fn set<T: TreeDeserializeOwned + ?Sized>(
+    tree: &mut T,
+    shape: &Shape,
+    keys: &mut Vec<usize>,
+    value: Value,
+) -> Result<usize, Error<de::Error>> {
+    let depth = keys.len();
+    let mut applied = 0;
+    match (shape, value) {
+        (Shape::Leaf, value) => {
+            match tree.deserialize_by_key(keys.iter().into_keys(), value) {
+                Err(Error::Traversal(Traversal::Absent(_))) => {}
+                ret => {
+                    ret?;
+                    applied += 1;
+                }
+            };
+        }
+        (Shape::Named(_), Value::Table(table)) => {
+            for (name, value) in table {
+                let (i, _, child) = shape
+                    .children()
+                    .find(|(_, n, _)| *n == name)
+                    .ok_or(Traversal::NotFound(depth + 1))?;
+                keys.push(i);
+                applied += set(tree, child, keys, value)?;
+                keys.pop();
+            }
+        }
+        (Shape::Numbered(_) | Shape::Homogeneous(..), Value::Array(items)) => {
+            for (i, value) in items.into_iter().enumerate() {
+                let child = shape.child(i).ok_or(Traversal::NotFound(depth + 1))?;
+                if matches!(&value, Value::Table(t) if t.is_empty()) {
+                    continue;
+                }
+                keys.push(i);
+                applied += set(tree, child, keys, value)?;
+                keys.pop();
+            }
+        }
+        (Shape::Named(_), _) => return Err(Traversal::Invalid(depth, "Expected table").into()),
+        _ => return Err(Traversal::Invalid(depth, "Expected array").into()),
+    }
+    Ok(applied)
+}
+
+/// Deserialize the values in a TOML document into a tree.
+///
+/// The document may contain a subset of the tree.
+/// Values for absent leaves are skipped.
+/// Values are applied in document order (keys in sorted order unless the `preserve_order`
+/// feature is enabled) and the tree is updated up to the first error.
+///
+/// # Returns
+/// The number of applied leaf values.
+pub fn set_all<T: TreeKey + TreeDeserializeOwned + ?Sized>(
+    tree: &mut T,
+    data: &str,
+) -> Result<usize, Error<de::Error>> {
+    let table: Table = data.parse().map_err(|err| Error::Inner(0, err))?;
+    let shape: Shape = T::traverse_all().unwrap(); // Note(unwrap): infallible
+    set(tree, &shape, &mut vec![], Value::Table(table))
+}
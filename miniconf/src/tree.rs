@@ -1,8 +1,14 @@
-use core::{any::Any, num::NonZero};
+use core::{
+    any::{Any, TypeId},
+    num::NonZero,
+};
 
 use serde::{Deserializer, Serializer};
 
-use crate::{Error, IntoKeys, Keys, Node, NodeIter, Transcode, Traversal, Walk};
+use crate::{
+    node::Marks, Error, Fnv1a, IntoKeys, KeyLookup, Keys, Limit, Metadata, Node, NodeIter,
+    NodeKind, Packed, Path, Schema, Transcode, Traversal, Walk,
+};
 
 /// Traversal, iteration of keys in a tree.
 ///
@@ -50,6 +56,21 @@ use crate::{Error, IntoKeys, Keys, Node, NodeIter, Transcode, Traversal, Walk};
 /// assert_eq!(name.as_str(), "/OTHER");
 /// ```
 ///
+/// The `rename_all` container attribute renames all named fields or variants without
+/// an explicit `rename` to `"kebab-case"`, `"snake_case"`, `"camelCase"`, or `"PascalCase"`.
+/// Names that collide after renaming are rejected at compile time.
+///
+/// ```
+/// use miniconf::{Leaf, Path, Tree, TreeKey};
+/// #[derive(Tree, Default)]
+/// #[tree(rename_all = "kebab-case")]
+/// struct S {
+///     max_depth: Leaf<u8>,
+/// };
+/// let (name, _node) = S::transcode::<Path<String, '/'>, _>([0usize]).unwrap();
+/// assert_eq!(name.as_str(), "/max-depth");
+/// ```
+///
 /// ## Skip
 ///
 /// Named fields/variants may be omitted from the derived `Tree` trait implementations using the
@@ -73,6 +94,163 @@ use crate::{Error, IntoKeys, Keys, Node, NodeIter, Transcode, Traversal, Walk};
 /// The type to use when accessing the field through `TreeKey` can be overridden using the `typ`
 /// derive macro attribute (`#[tree(typ="[f32; 4]")]`).
 ///
+/// ## Atomic
+///
+/// A field marked `atomic` keeps its sub-tree for key traversal, serialization,
+/// and `TreeAny` access, but it can only be deserialized as a whole, using its
+/// `serde::Deserialize` implementation.
+/// Deserializing an inner node returns [`Traversal::Access`].
+///
+/// ```
+/// use miniconf::{json, Deserialize, Leaf, Traversal, Tree};
+/// #[derive(Tree, Deserialize, Default)]
+/// struct Span {
+///     start: Leaf<i32>,
+///     end: Leaf<i32>,
+/// };
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(atomic)]
+///     span: Span,
+/// };
+/// let mut s = S::default();
+/// json::set(&mut s, "/span", br#"{"start": 1, "end": 3}"#).unwrap();
+/// assert_eq!((*s.span.start, *s.span.end), (1, 3));
+/// assert_eq!(json::set(&mut s, "/span/end", b"5"), Err(Traversal::Access(1, "Atomic").into()));
+/// ```
+///
+/// ## Skip deserialize
+///
+/// A field marked `skip_deserialize` remains serializable and `TreeAny` accessible but
+/// deserialization into it (or any node below it) is silently ignored:
+/// the value is deserialized into a `Default` instance of the field type which is discarded.
+/// In contrast to `deny(deserialize)` no error is returned. This allows setting
+/// whole documents (e.g. obtained from a dump) that contain read-only values like a serial number.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(skip_deserialize)]
+///     serial: Leaf<u32>,
+///     gain: Leaf<f32>,
+/// };
+/// let mut s = S::default();
+/// json::set(&mut s, "/serial", b"7").unwrap();
+/// assert_eq!(*s.serial, 0);
+/// ```
+///
+/// ## Partial array
+///
+/// An array field marked `partial_array` can additionally be deserialized as a whole
+/// from a sequence that may be shorter than the array. Only the provided prefix of elements
+/// is updated, the remaining elements are left unchanged. Longer sequences are rejected.
+/// The array is only updated if the entire sequence deserializes successfully.
+/// The elements must implement `serde::Deserialize`.
+/// Key traversal, serialization, `TreeAny` access, and deserialization of individual
+/// elements are unchanged.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(partial_array)]
+///     b: [Leaf<i32>; 3],
+/// };
+/// let mut s = S::default();
+/// *s.b[2] = 5;
+/// json::set(&mut s, "/b", b"[1, 2]").unwrap();
+/// assert_eq!(s.b.map(|b| *b), [1, 2, 5]);
+/// json::set(&mut s, "/b/2", b"3").unwrap();
+/// assert!(json::set(&mut s, "/b", b"[7, 8, 9, 10]").is_err());
+/// assert_eq!(s.b.map(|b| *b), [1, 2, 3]);
+/// ```
+///
+/// ## Secret
+///
+/// A field marked `secret` (and all nodes below it) is reported as secret through
+/// [`Node::is_secret()`]. Dumps ([`crate::json::get_all()`], `json::get_all_pretty()`,
+/// [`crate::openapi`]) redact secret values as `"***"`.
+/// Direct access (e.g. [`crate::json::get()`]) is unchanged.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     user: Leaf<u32>,
+///     #[tree(secret)]
+///     key: Leaf<u32>,
+/// };
+/// let s = S { user: 1.into(), key: 42.into() };
+/// let mut buf = [0u8; 64];
+/// let len = json::get_all::<_, 1>(&s, &mut buf, None).unwrap();
+/// assert_eq!(&buf[..len], br#"{"/user":1,"/key":"***"}"#);
+/// let len = json::get(&s, "/key", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"42");
+/// ```
+///
+/// ## Default
+///
+/// A field marked `default` (and all nodes below it) is reported as having a declared
+/// default value through [`Node::has_default()`], e.g. to offer "reset to default" in a UI.
+/// The default value itself is the corresponding node of the container's `Default`.
+/// Whether a field type implements `Default` can not be determined by the derive macro.
+/// The attribute declares it. See also [`TreeKey::subtree_has_defaults()`].
+///
+/// ```
+/// use miniconf::{Leaf, Tree, TreeKey};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(default)]
+///     gain: Leaf<f32>,
+///     serial: Leaf<u32>,
+/// };
+/// assert!(S::transcode::<(), _>(["gain"]).unwrap().1.has_default());
+/// assert!(!S::transcode::<(), _>(["serial"]).unwrap().1.has_default());
+/// ```
+///
+/// ## Range
+///
+/// The `min` and `max` field attributes reject deserialized values outside of the
+/// given inclusive range with [`Traversal::Invalid`] without modifying the field.
+/// They apply to leaf fields that dereference to a `PartialOrd` value (e.g. [`crate::Leaf`])
+/// or to `atomic` fields.
+///
+/// With the additional `clamp` attribute, out-of-range values are instead silently
+/// clamped into the range. This is lossy: the stored value differs from the
+/// deserialized one and no error is returned.
+///
+/// ```
+/// use miniconf::{json, Leaf, Traversal, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(min=0, max=10)]
+///     strict: Leaf<i32>,
+///     #[tree(max=1.0, clamp)]
+///     clamped: Leaf<f32>,
+/// };
+/// let mut s = S::default();
+/// assert_eq!(json::set(&mut s, "/strict", b"11"), Err(Traversal::Invalid(1, "Above maximum").into()));
+/// json::set(&mut s, "/clamped", b"1.5").unwrap();
+/// assert_eq!(*s.clamped, 1.0);
+/// ```
+///
+/// The `finite` attribute on `f32`/`f64` leaves rejects NaN and infinite values with
+/// [`Traversal::Access`] without modifying the field. It is checked before `min`/`max`.
+///
+/// ```
+/// use miniconf::{json, Leaf, Traversal, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(finite)]
+///     gain: Leaf<f32>,
+/// };
+/// let mut s = S::default();
+/// json::set(&mut s, "/gain", b"2.5").unwrap();
+/// assert_eq!(json::set(&mut s, "/gain", b"1e39"), Err(Traversal::Access(1, "Non-finite").into()));
+/// assert_eq!(*s.gain, 2.5);
+/// ```
+///
 /// ## Accessors
 ///
 /// The `get`, `get_mut`, `validate` callbacks can be used to implement accessors,
@@ -120,10 +298,245 @@ use crate::{Error, IntoKeys, Keys, Node, NodeIter, Transcode, Traversal, Walk};
 /// }
 /// ```
 ///
+/// ### `rollback`
+///
+/// With the additional `rollback` flag on a struct field with `validate`, the field is cloned
+/// before deserialization and restored if deserialization or validation fail.
+/// The field is then left unmodified on error. This requires the field type to be `Clone`
+/// and clones it on every `deserialize_by_key()` into the field or any node below it.
+///
+/// ```
+/// use miniconf::{json, Leaf, Traversal, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(validate=self.check, rollback)]
+///     v: Leaf<f32>,
+/// };
+/// impl S {
+///     fn check(&mut self, depth: usize) -> Result<usize, &'static str> {
+///         (*self.v >= 0.0).then_some(depth).ok_or("negative")
+///     }
+/// }
+/// let mut s = S::default();
+/// json::set(&mut s, "/v", b"1.0").unwrap();
+/// assert_eq!(json::set(&mut s, "/v", b"-1.0"), Err(Traversal::Invalid(1, "negative").into()));
+/// assert_eq!(*s.v, 1.0);
+/// ```
+///
 /// ### `defer`
 ///
 /// The `defer` attribute is a shorthand for `get`+`get_mut` of the same owned value.
 ///
+/// ## Flatten
+///
+/// The `flatten` container attribute elides the level of a struct with a single
+/// (non-skipped) field or an enum with a single newtype variant (modulo unit variants).
+/// The node then has the keys of its only child. Using `flatten` on a container
+/// with multiple fields or variants is rejected.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct Inner {
+///     a: Leaf<i32>,
+/// };
+/// #[derive(Tree)]
+/// #[tree(flatten)]
+/// enum Config {
+///     V1(Inner),
+/// };
+/// let mut c = Config::V1(Inner::default());
+/// json::set(&mut c, "/a", b"1").unwrap();
+/// ```
+///
+/// ## Finalize
+///
+/// The `finalize` container attribute is called after any successful
+/// `deserialize_by_key()` into the struct or enum. It can be used to enforce
+/// consistency between multiple fields.
+/// Its signature is `fn(&mut Self) -> Result<(), &'static str>`.
+/// If it returns `Err()`, the leaf value already **has been** updated and
+/// [`Traversal::Invalid`] is returned at the depth of the container.
+/// `finalize` may restore a consistent state before returning an error.
+///
+/// ```
+/// use miniconf::{json, Leaf, Traversal, Tree};
+/// #[derive(Tree, Default)]
+/// #[tree(finalize=Self::check)]
+/// struct S {
+///     a: Leaf<i32>,
+///     b: Leaf<i32>,
+/// };
+/// impl S {
+///     fn check(&mut self) -> Result<(), &'static str> {
+///         (*self.a <= *self.b).then_some(()).ok_or("a > b")
+///     }
+/// }
+/// let mut s = S::default();
+/// json::set(&mut s, "/b", b"1").unwrap();
+/// assert_eq!(json::set(&mut s, "/a", b"2"), Err(Traversal::Invalid(0, "a > b").into()));
+/// ```
+///
+/// ## Key table
+///
+/// The `key_table` container attribute adds an associated
+/// `const KEYS: &[(&str, Packed)]` to types without generics.
+/// It maps the `'/'`-separated paths of all leaves to their [`crate::Packed`] keys
+/// and is sorted by path for lookup with a binary search.
+///
+/// ```
+/// use miniconf::{Leaf, Packed, Tree};
+/// #[derive(Tree, Default)]
+/// #[tree(key_table)]
+/// struct S {
+///     foo: Leaf<i32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// let i = S::KEYS.binary_search_by_key(&"/bar/1", |(path, _)| *path).unwrap();
+/// assert_eq!(S::KEYS[i].1, Packed::new_from_lsb(0b1_1_1).unwrap());
+/// assert_eq!(S::KEYS.len(), 3);
+/// ```
+///
+/// ## Case insensitive
+///
+/// The `case_insensitive` container attribute on structs with named fields and enums
+/// matches the names of the immediate child nodes ASCII case-insensitively when looking
+/// up `&str` keys (see [`crate::Key::find_ignore_case()`]).
+/// An exact match takes precedence. Iteration and transcoding still yield the canonical names.
+/// Names that only differ in case would be ambiguous and are rejected at compile time.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// #[tree(case_insensitive)]
+/// struct S {
+///     gain: Leaf<f32>,
+/// };
+/// let mut s = S::default();
+/// json::set(&mut s, "/GAIN", b"2.0").unwrap();
+/// assert_eq!(*s.gain, 2.0);
+/// ```
+///
+/// ## Order
+///
+/// The `order = "lexicographic"` container attribute on structs with named fields and enums
+/// sorts the immediate child nodes by name at compile time.
+/// Iteration then yields the children in alphabetical (byte-wise) order instead of
+/// declaration order. Note that this also changes the child indices and therefore
+/// the [`crate::Indices`] and [`crate::Packed`] keys with respect to declaration order.
+///
+/// ```
+/// use miniconf::{Leaf, Path, Tree, TreeKey};
+/// #[derive(Tree, Default)]
+/// #[tree(order = "lexicographic")]
+/// struct S {
+///     foo: Leaf<i32>,
+///     bar: Leaf<i32>,
+/// };
+/// let paths: Vec<_> = S::nodes::<Path<String, '/'>, 1>()
+///     .map(|p| p.unwrap().0.into_inner())
+///     .collect();
+/// assert_eq!(paths, ["/bar", "/foo"]);
+/// ```
+///
+/// ## Variant names
+///
+/// The `variant_names` container attribute adds an associated `const VARIANTS: &[&str]` to enums
+/// listing the names (respecting `rename`) of all variants not marked `skip` in declaration order.
+/// In contrast to the child nodes this includes unit variants.
+/// It can be used to build the selector list for a [`crate::StrLeaf`] tag without `strum`.
+///
+/// ```
+/// use miniconf::{Leaf, Tree};
+/// #[derive(Tree)]
+/// #[tree(variant_names)]
+/// enum E {
+///     Off,
+///     #[tree(rename = "on")]
+///     On(Leaf<i32>),
+/// }
+/// assert_eq!(E::VARIANTS, ["Off", "on"]);
+/// ```
+///
+/// ## Tag
+///
+/// The `tag` container attribute on enums exposes the name of the active variant
+/// (see [Variant names](#variant-names)) as a string at the path of the enum node itself
+/// through [`TreeSerialize`] and [`TreeDeserialize`].
+/// Setting it to the name of a different variant switches to that variant with
+/// `Default` field values. Setting it to the name of the active variant does not
+/// modify the value. All variant fields must implement `Default`.
+/// The tag is not part of the iteration and the [`Metadata`] since the node
+/// remains an internal node.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default, PartialEq, Debug)]
+/// #[tree(tag)]
+/// enum E {
+///     #[default]
+///     Off,
+///     On(Leaf<i32>),
+/// }
+/// let mut e = E::default();
+/// json::set(&mut e, "", br#""On""#).unwrap();
+/// assert_eq!(e, E::On(0.into()));
+/// json::set(&mut e, "/On", b"3").unwrap();
+/// let mut buf = [0; 8];
+/// let len = json::get(&e, "", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], br#""On""#);
+/// ```
+///
+/// ## Variant index
+///
+/// With the `variant_by = "index"` container attribute on enums, the variants are
+/// addressed by their numeric index instead of by their name
+/// (see [Variant names](#variant-names)).
+/// The index counts the variants that are part of the tree (i.e. excluding unit
+/// and `skip`ped variants) in declaration order. It is the same for path and integer
+/// keys (e.g. [`crate::Indices`] or [`crate::Packed`]).
+/// Inactive variants are [`Traversal::Absent`] as usual.
+/// The default is `variant_by = "name"`.
+///
+/// ```
+/// use miniconf::{json, Leaf, Traversal, Tree};
+/// #[derive(Tree, Default, PartialEq, Debug)]
+/// #[tree(variant_by = "index")]
+/// enum E {
+///     #[default]
+///     Off,
+///     A(Leaf<i32>),
+///     B(Leaf<bool>),
+/// }
+/// let mut e = E::B(false.into());
+/// json::set(&mut e, "/1", b"true").unwrap();
+/// assert_eq!(e, E::B(true.into()));
+/// json::set_by_key(&mut e, [1], b"false").unwrap();
+/// assert_eq!(e, E::B(false.into()));
+/// assert_eq!(json::set(&mut e, "/0", b"3"), Err(Traversal::Absent(1).into()));
+/// assert_eq!(json::set(&mut e, "/2", b"3"), Err(Traversal::NotFound(1).into()));
+/// ```
+///
+/// ## Index names
+///
+/// The `index_names` attribute on an array field names the array elements.
+/// The names are used when iterating and in addition to the numeric indices
+/// when looking up keys (see [`KeyLookup::IndexNamed`]).
+/// The number of names must match the array length.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     #[tree(index_names = ["left", "right"])]
+///     gain: [Leaf<f32>; 2],
+/// };
+/// let mut s = S::default();
+/// json::set(&mut s, "/gain/right", b"2.0").unwrap();
+/// json::set(&mut s, "/gain/0", b"1.0").unwrap();
+/// assert_eq!(*s.gain[1], 2.0);
+/// ```
+///
 /// # Array
 ///
 /// Blanket implementations of the `Tree*` traits are provided for homogeneous arrays
@@ -142,6 +555,26 @@ use crate::{Error, IntoKeys, Keys, Node, NodeIter, Transcode, Traversal, Walk};
 ///
 /// This is the same behavior as for other `enums` that have the `Tree*` traits derived.
 ///
+/// A `'?'` suffix on the key of an [`Option`] node (e.g. `"feature?"`) addresses its presence
+/// as a synthetic `bool` leaf through [`TreeSerialize`] and [`TreeDeserialize`]
+/// (see [`Keys::take_presence()`]). Setting it to `false` clears the [`Option`].
+/// Setting it to `true` only succeeds if the [`Option`] is already `Some`.
+/// Presence leaves are not part of the iteration and the [`Metadata`].
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+/// #[derive(Tree, Default)]
+/// struct S {
+///     feature: Option<Leaf<u32>>,
+/// };
+/// let mut s = S { feature: Some(3.into()) };
+/// let mut buf = [0; 8];
+/// let len = json::get(&s, "/feature?", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"true");
+/// json::set(&mut s, "/feature?", b"false").unwrap();
+/// assert_eq!(s.feature, None);
+/// ```
+///
 /// # Tuples
 ///
 /// Blanket impementations for the `Tree*` traits are provided for heterogeneous tuples `(T0, T1, ...)`
@@ -152,6 +585,27 @@ use crate::{Error, IntoKeys, Keys, Node, NodeIter, Transcode, Traversal, Walk};
 /// See the [`crate`] documentation for a longer example showing how the traits and the derive
 /// macros work.
 pub trait TreeKey {
+    /// The structure of the namespace for use in `const` context.
+    ///
+    /// This mirrors the nodes visited by [`TreeKey::traverse_all()`].
+    const SCHEMA: Schema;
+
+    /// Metadata about all paths, computed at compile time from [`TreeKey::SCHEMA`].
+    ///
+    /// This is equal to the [`Metadata`] obtained through [`TreeKey::traverse_all()`].
+    ///
+    /// ```
+    /// use miniconf::{Leaf, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// const _: () = assert!(S::META.max_depth <= 2);
+    /// assert_eq!(S::META.count.get(), 3);
+    /// ```
+    const META: Metadata = Self::SCHEMA.metadata();
+
     /// Walk metadata about all paths.
     ///
     /// ```
@@ -255,10 +709,117 @@ pub trait TreeKey {
         N: Transcode + Default,
     {
         let mut target = N::default();
-        let node = target.transcode::<Self, _>(keys)?;
+        let mut marks = Node::leaf(0);
+        let mut node = target.transcode::<Self, _>(Marks(keys.into_keys(), &mut marks))?;
+        node.secret = marks.secret;
+        node.default = marks.default;
+        node.collection = marks.collection;
         Ok((target, node))
     }
 
+    /// Transcode multiple `Packed` keys into delimited paths in a single buffer.
+    ///
+    /// The paths (with separator `S`) are written to `buf` separated by `delimiter`.
+    /// This is meant for bulk export, e.g. of the keys in [`TreeKey::nodes()`] order.
+    /// The keys are decoded using [`TreeKey::SCHEMA`]. The part of a path shared with the
+    /// previous key is copied from the previous path and only the remaining levels are
+    /// decoded and written.
+    /// Writing stops before the first path that does not fit into `buf`.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Packed, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let keys = [["foo"], ["bar"]].map(|k| S::transcode::<Packed, _>(k).unwrap().0);
+    /// let mut buf = [0; 16];
+    /// assert_eq!(S::transcode_all::<'/'>(&keys, '\n', &mut buf), Ok((9, 2)));
+    /// assert_eq!(&buf[..9], b"/foo\n/bar");
+    /// assert_eq!(S::transcode_all::<'/'>(&keys, '\n', &mut buf[..6]), Ok((4, 1)));
+    /// ```
+    ///
+    /// # Returns
+    /// The number of bytes written and the number of paths that fit into `buf`.
+    fn transcode_all<const S: char>(
+        keys: &[Packed],
+        delimiter: char,
+        buf: &mut [u8],
+    ) -> Result<(usize, usize), Traversal> {
+        let mut separator = [0; 4];
+        let separator = S.encode_utf8(&mut separator).as_bytes();
+        let mut delimiter_buf = [0; 4];
+        let delimiter = delimiter.encode_utf8(&mut delimiter_buf).as_bytes();
+        // The levels of the previous path: the node, the number of key bits consumed
+        // to reach it, and the length of the path to it.
+        // Each level consumes at least one bit.
+        let mut levels = [(&Self::SCHEMA, 0, 0); Packed::CAPACITY as usize + 1];
+        // The previous key, its depth, and the start of its path in `buf`
+        let mut prev: Option<(Packed, usize, usize)> = None;
+        let mut end = 0;
+        for (count, key) in keys.iter().enumerate() {
+            let mut start = end;
+            if count > 0 {
+                let Some(dst) = buf.get_mut(start..start + delimiter.len()) else {
+                    return Ok((end, count));
+                };
+                dst.copy_from_slice(delimiter);
+                start += delimiter.len();
+            }
+            // Copy the levels shared with the previous key
+            let mut depth = 0;
+            if let Some((prev_key, prev_depth, prev_start)) = prev {
+                let common = (prev_key.get() ^ key.get()).leading_zeros().min(key.len());
+                while depth < prev_depth && levels[depth + 1].1 <= common {
+                    depth += 1;
+                }
+                let len = levels[depth].2;
+                if start + len > buf.len() {
+                    return Ok((end, count));
+                }
+                buf.copy_within(prev_start..prev_start + len, start);
+            }
+            let (mut schema, mut bits, mut len) = levels[depth];
+            let mut rest = *key;
+            rest.pop_msb(bits);
+            // Decode and write the remaining levels
+            while let Schema::Internal(lookup, children) = schema {
+                let width = Packed::bits_for(lookup.len().get() - 1);
+                let Some(index) = rest.pop_msb(width) else {
+                    break;
+                };
+                if index >= lookup.len().get() {
+                    return Err(Traversal::NotFound(depth + 1));
+                }
+                let mut digits = itoa::Buffer::new();
+                let name = match lookup.lookup(index)? {
+                    Some(name) => name,
+                    None => digits.format(index),
+                };
+                debug_assert!(!name.contains(S));
+                let at = start + len;
+                let Some(dst) = buf.get_mut(at..at + separator.len() + name.len()) else {
+                    return Ok((end, count));
+                };
+                let (sep, dst) = dst.split_at_mut(separator.len());
+                sep.copy_from_slice(separator);
+                dst.copy_from_slice(name.as_bytes());
+                schema = children.get(index).unwrap_or(&children[0]);
+                bits += width;
+                len += separator.len() + name.len();
+                depth += 1;
+                levels[depth] = (schema, bits, len);
+            }
+            if matches!(schema, Schema::Leaf) && !rest.is_empty() {
+                return Err(Traversal::TooLong(depth));
+            }
+            prev = Some((*key, depth, start));
+            end = start + len;
+        }
+        Ok((end, keys.len()))
+    }
+
     /// Return an iterator over nodes of a given type
     ///
     /// This is a walk of all leaf nodes.
@@ -316,6 +877,288 @@ pub trait TreeKey {
     {
         NodeIter::default()
     }
+
+    /// Find a leaf by the [`Fnv1a`] hash of its canonical `'/'`-separated path.
+    ///
+    /// This is a linear search over all leaves. The first match is returned.
+    /// Use [`crate::HashIndex`] for faster lookup and to detect hash collisions.
+    ///
+    /// ```
+    /// use miniconf::{Fnv1a, Leaf, Node, Packed, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let (packed, node) = S::find_by_hash::<2>(Fnv1a::hash("/bar/1")).unwrap();
+    /// assert_eq!(packed, S::transcode::<Packed, _>(["bar", "1"]).unwrap().0);
+    /// assert_eq!(node, Node::leaf(2));
+    /// ```
+    ///
+    /// # Returns
+    /// The `Packed` key and the leaf node or [`Traversal::NotFound`] if there is no match.
+    fn find_by_hash<const D: usize>(hash: u32) -> Result<(Packed, Node), Traversal> {
+        for node in Self::nodes::<Packed, D>() {
+            let (packed, node) = node.map_err(Traversal::TooLong)?;
+            let (path, _node) = Self::transcode::<Path<Fnv1a, '/'>, _>(packed)?;
+            if path.into_inner().finish() == hash {
+                return Ok((packed, node));
+            }
+        }
+        Err(Traversal::NotFound(0))
+    }
+
+    /// The kind of the node identified by `keys`.
+    ///
+    /// For internal nodes this is the kind of its children lookup.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, NodeKind, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// assert_eq!(S::kind([0usize; 0]), Ok(NodeKind::Named));
+    /// assert_eq!(S::kind(["bar"]), Ok(NodeKind::Homogeneous));
+    /// assert_eq!(S::kind(["bar", "0"]), Ok(NodeKind::Leaf));
+    /// ```
+    fn kind<K: IntoKeys>(keys: K) -> Result<NodeKind, Traversal> {
+        // Records the lookup kind where the keys are exhausted
+        struct Probe<K>(K, NodeKind);
+        impl<K: Keys> Keys for Probe<K> {
+            fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+                self.0.next(lookup).inspect_err(|err| {
+                    if matches!(err, Traversal::TooShort(_)) {
+                        self.1 = lookup.into();
+                    }
+                })
+            }
+
+            fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+                self.0.next_ignore_case(lookup).inspect_err(|err| {
+                    if matches!(err, Traversal::TooShort(_)) {
+                        self.1 = lookup.into();
+                    }
+                })
+            }
+
+            fn take_presence(&mut self) -> bool {
+                self.0.take_presence()
+            }
+
+            fn mark_secret(&mut self) {
+                self.0.mark_secret()
+            }
+
+            fn mark_default(&mut self) {
+                self.0.mark_default()
+            }
+
+            fn mark_doc(&mut self, doc: &'static str) {
+                self.0.mark_doc(doc)
+            }
+
+            fn mark_range(&mut self, min: Option<Limit>, max: Option<Limit>) {
+                self.0.mark_range(min, max)
+            }
+
+            fn finalize(&mut self) -> Result<(), Traversal> {
+                self.0.finalize()
+            }
+        }
+        let mut probe = Probe(keys.into_keys(), NodeKind::Leaf);
+        let node: Node = Self::traverse_by_key(&mut probe, |_, _, _| Ok(())).try_into()?;
+        Ok(if node.is_leaf() {
+            NodeKind::Leaf
+        } else {
+            probe.1
+        })
+    }
+
+    /// Whether `keys` address a leaf.
+    ///
+    /// This resolves the keys without side effects. It can be used to check persisted keys
+    /// (e.g. [`Packed`]) after a schema change.
+    /// Keys that are not found, too long, or address an internal node are invalid.
+    ///
+    /// Note that a stale key may still be valid and address a different leaf
+    /// in the changed schema. Use the schema fingerprint (see [`Fnv1a`]) to detect changes.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Packed, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let (packed, _node) = S::transcode::<Packed, _>(["bar", "1"]).unwrap();
+    /// assert!(S::is_valid(packed));
+    /// assert!(!S::is_valid(["bar"]));
+    /// assert!(!S::is_valid(["bar", "2"]));
+    /// ```
+    #[inline]
+    fn is_valid<K: IntoKeys>(keys: K) -> bool {
+        matches!(Self::transcode::<(), _>(keys), Ok(((), node)) if node.is_leaf())
+    }
+
+    /// Whether all leaves at and below a node have a declared default.
+    ///
+    /// See [`Node::has_default()`] and [`TreeKey#default`].
+    /// `D` is the maximum key depth.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     #[tree(default)]
+    ///     foo: [Leaf<u16>; 2],
+    ///     #[tree(default)]
+    ///     bar: Leaf<u32>,
+    ///     baz: Leaf<u32>,
+    /// };
+    /// assert_eq!(S::subtree_has_defaults::<_, 2>(["foo"]), Ok(true));
+    /// assert_eq!(S::subtree_has_defaults::<_, 2>(["baz"]), Ok(false));
+    /// assert_eq!(S::subtree_has_defaults::<_, 2>([0usize; 0]), Ok(false));
+    /// ```
+    fn subtree_has_defaults<K: IntoKeys, const D: usize>(keys: K) -> Result<bool, Traversal> {
+        for node in Self::nodes::<(), D>().root(keys)? {
+            let ((), node) = node.map_err(Traversal::TooLong)?;
+            if !node.has_default() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Return the documentation of a node.
+    ///
+    /// With the `doc` feature the derive macros capture the doc comments of fields
+    /// and enum variants. Without it (or without a doc comment) this returns `None`.
+    /// The doc comments are only stored in the code of [`TreeKey::traverse_by_key()`]
+    /// and do not use RAM.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     /// The output gain
+    ///     gain: Leaf<f32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let doc = cfg!(feature = "doc").then_some("The output gain");
+    /// assert_eq!(S::doc(["gain"]), Ok(doc));
+    /// assert_eq!(S::doc(["bar", "1"]), Ok(None));
+    /// ```
+    fn doc<K: IntoKeys>(keys: K) -> Result<Option<&'static str>, Traversal> {
+        // Records the doc of the last node looked up
+        struct Doc<K>(K, Option<&'static str>);
+        impl<K: Keys> Keys for Doc<K> {
+            fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+                self.0.next(lookup).inspect(|_| self.1 = None)
+            }
+
+            fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+                self.0.next_ignore_case(lookup).inspect(|_| self.1 = None)
+            }
+
+            fn take_presence(&mut self) -> bool {
+                self.0.take_presence()
+            }
+
+            fn mark_secret(&mut self) {
+                self.0.mark_secret()
+            }
+
+            fn mark_default(&mut self) {
+                self.0.mark_default()
+            }
+
+            fn mark_doc(&mut self, doc: &'static str) {
+                self.1 = Some(doc);
+                self.0.mark_doc(doc)
+            }
+
+            fn mark_range(&mut self, min: Option<Limit>, max: Option<Limit>) {
+                self.0.mark_range(min, max)
+            }
+
+            fn finalize(&mut self) -> Result<(), Traversal> {
+                self.0.finalize()
+            }
+        }
+        let mut doc = Doc(keys.into_keys(), None);
+        let _node: Node = Self::traverse_by_key(&mut doc, |_, _, _| Ok(())).try_into()?;
+        Ok(doc.1)
+    }
+
+    /// Return the inclusive range limits of a node.
+    ///
+    /// The derive macros record the `min` and `max` field attributes
+    /// (see [`macro@crate::TreeDeserialize`]). The limits must be constant expressions.
+    /// Limits that do not implement `Display` are not recorded.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, TreeKey};
+    /// #[derive(TreeKey)]
+    /// struct S {
+    ///     #[tree(min = -1.5, max = 10)]
+    ///     gain: Leaf<f32>,
+    ///     #[tree(max = u8::MAX)]
+    ///     count: Leaf<u8>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let (min, max) = S::range(["gain"]).unwrap();
+    /// assert_eq!((min.unwrap().to_string(), max.unwrap().to_string()), ("-1.5".into(), "10".into()));
+    /// let (min, max) = S::range(["count"]).unwrap();
+    /// assert_eq!((min.is_none(), max.unwrap().to_string()), (true, "255".into()));
+    /// assert!(matches!(S::range(["bar", "1"]), Ok((None, None))));
+    /// ```
+    fn range<K: IntoKeys>(keys: K) -> Result<(Option<Limit>, Option<Limit>), Traversal> {
+        // Records the limits of the last node looked up
+        struct Range<K>(K, Option<Limit>, Option<Limit>);
+        impl<K: Keys> Keys for Range<K> {
+            fn next(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+                self.0
+                    .next(lookup)
+                    .inspect(|_| (self.1, self.2) = (None, None))
+            }
+
+            fn next_ignore_case(&mut self, lookup: &KeyLookup) -> Result<usize, Traversal> {
+                self.0
+                    .next_ignore_case(lookup)
+                    .inspect(|_| (self.1, self.2) = (None, None))
+            }
+
+            fn take_presence(&mut self) -> bool {
+                self.0.take_presence()
+            }
+
+            fn mark_secret(&mut self) {
+                self.0.mark_secret()
+            }
+
+            fn mark_default(&mut self) {
+                self.0.mark_default()
+            }
+
+            fn mark_doc(&mut self, doc: &'static str) {
+                self.0.mark_doc(doc)
+            }
+
+            fn mark_range(&mut self, min: Option<Limit>, max: Option<Limit>) {
+                (self.1, self.2) = (min, max);
+                self.0.mark_range(min, max)
+            }
+
+            fn finalize(&mut self) -> Result<(), Traversal> {
+                self.0.finalize()
+            }
+        }
+        let mut range = Range(keys.into_keys(), None, None);
+        let _node: Node = Self::traverse_by_key(&mut range, |_, _, _| Ok(())).try_into()?;
+        Ok((range.1, range.2))
+    }
 }
 
 /// Access any node by keys.
@@ -373,6 +1216,351 @@ pub trait TreeAny {
             .downcast_mut()
             .ok_or(Traversal::Invalid(0, "Incorrect type"))
     }
+
+    /// Swap the values of two leaves of known type by key.
+    ///
+    /// The value at `a` is temporarily replaced by `T::default()`.
+    /// If `b` can not be accessed, the value at `a` is restored.
+    /// If `a` and `b` resolve to the same leaf, its value is unchanged.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Traversal, Tree, TreeAny};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: [Leaf<i32>; 2],
+    ///     bar: Leaf<u8>,
+    /// };
+    /// let mut s = S { foo: [1.into(), 2.into()], ..Default::default() };
+    /// s.swap_by_key::<i32, _, _>(["foo", "0"], ["foo", "1"]).unwrap();
+    /// assert_eq!((*s.foo[0], *s.foo[1]), (2, 1));
+    /// assert_eq!(
+    ///     s.swap_by_key::<i32, _, _>(["foo", "0"], ["bar"]),
+    ///     Err(Traversal::Invalid(0, "Incorrect type"))
+    /// );
+    /// assert_eq!(*s.foo[0], 2);
+    /// ```
+    fn swap_by_key<T, A, B>(&mut self, a: A, b: B) -> Result<(), Traversal>
+    where
+        T: Any + Default,
+        A: IntoKeys + Clone,
+        B: IntoKeys,
+    {
+        let a_ref = self.mut_by_key::<T, _>(a.clone())?;
+        let a_ptr: *const T = a_ref;
+        let mut value = core::mem::take(a_ref);
+        let ret = self.mut_by_key::<T, _>(b).map(|b| {
+            // Swapping with itself would leave the temporary default in place
+            if !core::ptr::eq(a_ptr, b) {
+                core::mem::swap(&mut value, b);
+            }
+        });
+        *self.mut_by_key::<T, _>(a)? = value;
+        ret
+    }
+
+    /// Call a function with a `dyn Any` reference to a leaf node.
+    ///
+    /// In contrast to [`TreeAny::ref_any_by_key()`] this also reaches leaves behind
+    /// `RefCell`, `Mutex`, and `RwLock`: the lock is held for the duration of the call.
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        self.ref_any_by_key(keys).map(func)
+    }
+
+    /// Clone a leaf of known type out of the tree by key.
+    ///
+    /// See [`TreeAny::with_any_by_key()`].
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// use std::sync::Mutex;
+    /// use miniconf::{Leaf, Traversal, Tree, TreeAny};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Mutex<Leaf<i32>>,
+    /// };
+    /// let s = S { foo: Mutex::new(Leaf(3)) };
+    /// assert_eq!(s.try_clone_by_key::<i32, _>(["foo"]), Ok(3));
+    /// assert_eq!(
+    ///     s.try_clone_by_key::<u32, _>(["foo"]),
+    ///     Err(Traversal::Invalid(0, "Incorrect type"))
+    /// );
+    /// # }
+    /// ```
+    #[inline]
+    fn try_clone_by_key<T: Any + Clone, K: IntoKeys>(&self, keys: K) -> Result<T, Traversal> {
+        self.with_any_by_key(keys.into_keys(), |any| any.downcast_ref::<T>().cloned())?
+            .ok_or(Traversal::Invalid(0, "Incorrect type"))
+    }
+
+    /// Get the concrete `TypeId` of a leaf by key.
+    ///
+    /// See [`TreeAny::with_any_by_key()`].
+    /// This can be used to determine castability up front, e.g. for all leaves
+    /// from [`TreeKey::nodes()`].
+    /// Absent leaves return [`Traversal::Absent`].
+    ///
+    /// ```
+    /// use core::any::TypeId;
+    /// use miniconf::{Leaf, Tree, TreeAny};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<i32>,
+    ///     bar: [Leaf<u8>; 2],
+    /// };
+    /// let s = S::default();
+    /// assert_eq!(s.type_id_by_key(["foo"]), Ok(TypeId::of::<i32>()));
+    /// assert_eq!(s.type_id_by_key(["bar", "1"]), Ok(TypeId::of::<u8>()));
+    /// ```
+    #[inline]
+    fn type_id_by_key<K: IntoKeys>(&self, keys: K) -> Result<TypeId, Traversal> {
+        self.with_any_by_key(keys.into_keys(), |any| any.type_id())
+    }
+
+    /// Iterate over the keys and `TypeId`s of all present leaves.
+    ///
+    /// This combines [`TreeKey::nodes()`] and [`TreeAny::type_id_by_key()`]
+    /// in one pass. Leaves that are absent or not accessible are skipped.
+    ///
+    /// ```
+    /// use core::any::TypeId;
+    /// use miniconf::{Leaf, Path, Tree, TreeAny};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<i32>,
+    ///     bar: Option<Leaf<u8>>,
+    /// };
+    /// let s = S::default();
+    /// let typed: Vec<_> = s
+    ///     .present_typed::<Path<String, '/'>, 1>()
+    ///     .map(|p| p.map(|(p, t)| (p.into_inner(), t)))
+    ///     .collect();
+    /// assert_eq!(typed, [Ok(("/foo".to_string(), TypeId::of::<i32>()))]);
+    /// ```
+    ///
+    /// # Returns
+    /// An iterator over the keys and `TypeId`s of the present leaves.
+    /// The `Err(usize)` variant indicates that `N` or the depth `D` do not have
+    /// sufficient capacity (see [`NodeIter`]).
+    fn present_typed<N, const D: usize>(
+        &self,
+    ) -> impl Iterator<Item = Result<(N, TypeId), usize>> + '_
+    where
+        Self: TreeKey,
+        N: Transcode + Default,
+    {
+        Self::nodes::<Packed, D>().filter_map(move |node| {
+            let (keys, _node) = match node {
+                Ok(node) => node,
+                Err(depth) => return Some(Err(depth)),
+            };
+            let type_id = self.type_id_by_key(keys).ok()?;
+            Some(
+                Self::transcode(keys)
+                    .map(|(path, _node)| (path, type_id))
+                    .map_err(|err| err.depth()),
+            )
+        })
+    }
+
+    /// Read a primitive numeric leaf as raw little-endian bytes by key.
+    ///
+    /// This avoids serialization, e.g. for memory-mapped register maps.
+    /// Supported leaf types are the fixed-width integers (`u8` to `u128` and `i8` to `i128`)
+    /// and `f32`, `f64`. The bytes are obtained using `to_le_bytes()`.
+    /// See [`TreeAny::with_any_by_key()`] and [`TreeAny::set_raw_le()`].
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Traversal, Tree, TreeAny};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<u16>,
+    ///     bar: Leaf<bool>,
+    /// };
+    /// let s = S { foo: 0x1234.into(), ..Default::default() };
+    /// let mut buf = [0; 8];
+    /// assert_eq!(s.get_raw_le(["foo"], &mut buf), Ok(2));
+    /// assert_eq!(buf[..2], [0x34, 0x12]);
+    /// assert_eq!(s.get_raw_le(["bar"], &mut buf), Err(Traversal::Invalid(0, "Incorrect type")));
+    /// ```
+    ///
+    /// # Returns
+    /// The number of bytes written to `buf`.
+    fn get_raw_le<K: IntoKeys>(&self, keys: K, buf: &mut [u8]) -> Result<usize, Traversal> {
+        self.with_any_by_key(keys.into_keys(), |any| {
+            macro_rules! get {
+                ($($t:ty)+) => {$(
+                    if let Some(v) = any.downcast_ref::<$t>() {
+                        let bytes = v.to_le_bytes();
+                        buf.get_mut(..bytes.len())
+                            .ok_or(Traversal::Invalid(0, "Buffer too small"))?
+                            .copy_from_slice(&bytes);
+                        return Ok(bytes.len());
+                    }
+                )+};
+            }
+            get!(u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64);
+            Err(Traversal::Invalid(0, "Incorrect type"))
+        })?
+    }
+
+    /// Write a primitive numeric leaf from raw little-endian bytes by key.
+    ///
+    /// The length of `data` must match the size of the leaf type.
+    /// The value is converted using `from_le_bytes()`.
+    /// See [`TreeAny::get_raw_le()`] for the supported types.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Traversal, Tree, TreeAny};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<u16>,
+    /// };
+    /// let mut s = S::default();
+    /// assert_eq!(s.set_raw_le(["foo"], &[0x34, 0x12]), Ok(2));
+    /// assert_eq!(*s.foo, 0x1234);
+    /// assert_eq!(s.set_raw_le(["foo"], &[0]), Err(Traversal::Invalid(0, "Incorrect length")));
+    /// ```
+    ///
+    /// # Returns
+    /// The number of bytes consumed from `data`.
+    fn set_raw_le<K: IntoKeys>(&mut self, keys: K, data: &[u8]) -> Result<usize, Traversal> {
+        let any = self.mut_any_by_key(keys.into_keys())?;
+        macro_rules! set {
+            ($($t:ty)+) => {$(
+                if let Some(v) = any.downcast_mut::<$t>() {
+                    *v = <$t>::from_le_bytes(
+                        data.try_into()
+                            .or(Err(Traversal::Invalid(0, "Incorrect length")))?,
+                    );
+                    return Ok(data.len());
+                }
+            )+};
+        }
+        set!(u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64);
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    }
+
+    /// Read a primitive numeric leaf as `f64` by key.
+    ///
+    /// This is useful for generic numeric processing (e.g. plotting) independent of the leaf type.
+    /// Supported leaf types are the integers (`u8` to `u128`, `usize`, `i8` to `i128`, `isize`)
+    /// and `f32`, `f64`. Integers beyond 53 bits may be rounded.
+    /// Other leaf types are rejected with [`Traversal::Access`].
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Traversal, Tree, TreeAny};
+    /// #[derive(Tree, Default)]
+    /// struct S {
+    ///     foo: Leaf<i16>,
+    ///     bar: Leaf<bool>,
+    /// };
+    /// let s = S { foo: (-3).into(), ..Default::default() };
+    /// assert_eq!(s.get_f64(["foo"]), Ok(-3.0));
+    /// assert_eq!(s.get_f64(["bar"]), Err(Traversal::Access(0, "Not numeric")));
+    /// ```
+    fn get_f64<K: IntoKeys>(&self, keys: K) -> Result<f64, Traversal> {
+        self.with_any_by_key(keys.into_keys(), |any| {
+            macro_rules! get {
+                ($($t:ty)+) => {$(
+                    if let Some(v) = any.downcast_ref::<$t>() {
+                        return Ok(*v as f64);
+                    }
+                )+};
+            }
+            get!(f64 f32 i32 u32 u8 i8 u16 i16 u64 i64 usize isize u128 i128);
+            Err(Traversal::Access(0, "Not numeric"))
+        })?
+    }
+
+    /// Borrow the bytes of a byte slice or string leaf by key.
+    ///
+    /// This avoids serialization for leaves that are already contiguous bytes.
+    /// Supported leaf types are `&'static [u8]` and `&'static str`
+    /// and with the `alloc` feature also `Vec<u8>` and `String`.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Traversal, Tree, TreeAny};
+    /// #[derive(Tree)]
+    /// struct S {
+    ///     foo: Leaf<&'static [u8]>,
+    ///     bar: Leaf<u32>,
+    /// };
+    /// let s = S { foo: Leaf(b"foo"), bar: 3.into() };
+    /// assert_eq!(s.get_bytes(["foo"]), Ok(&b"foo"[..]));
+    /// assert_eq!(s.get_bytes(["bar"]), Err(Traversal::Invalid(0, "Incorrect type")));
+    /// ```
+    fn get_bytes<K: IntoKeys>(&self, keys: K) -> Result<&[u8], Traversal> {
+        let any = self.ref_any_by_key(keys.into_keys())?;
+        if let Some(v) = any.downcast_ref::<&'static [u8]>() {
+            return Ok(v);
+        }
+        if let Some(v) = any.downcast_ref::<&'static str>() {
+            return Ok(v.as_bytes());
+        }
+        #[cfg(feature = "alloc")]
+        {
+            extern crate alloc;
+            if let Some(v) = any.downcast_ref::<alloc::vec::Vec<u8>>() {
+                return Ok(v);
+            }
+            if let Some(v) = any.downcast_ref::<alloc::string::String>() {
+                return Ok(v.as_bytes());
+            }
+        }
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    }
+
+    /// Borrow a string leaf by key.
+    ///
+    /// Supported leaf types are `&'static str` and with the `alloc` feature also `String`.
+    /// See also [`TreeAny::get_bytes()`].
+    fn get_str<K: IntoKeys>(&self, keys: K) -> Result<&str, Traversal> {
+        let any = self.ref_any_by_key(keys.into_keys())?;
+        if let Some(v) = any.downcast_ref::<&'static str>() {
+            return Ok(v);
+        }
+        #[cfg(feature = "alloc")]
+        {
+            extern crate alloc;
+            if let Some(v) = any.downcast_ref::<alloc::string::String>() {
+                return Ok(v);
+            }
+        }
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    }
+
+    /// Obtain a reference to a leaf cast to a trait object by key.
+    ///
+    /// This uses the [`crosstrait`] registry to cast the `dyn Any` leaf.
+    /// The cast target must have been registered for the concrete leaf type.
+    #[cfg(feature = "crosstrait")]
+    #[inline]
+    fn cast_ref_by_key<T: ?Sized + 'static, K: IntoKeys>(&self, keys: K) -> Result<&T, Traversal> {
+        use crosstrait::Cast;
+        self.ref_any_by_key(keys.into_keys())?
+            .cast()
+            .ok_or(Traversal::Invalid(0, "Cast failed"))
+    }
+
+    /// Obtain a mutable reference to a leaf cast to a trait object by key.
+    ///
+    /// See [`TreeAny::cast_ref_by_key()`].
+    #[cfg(feature = "crosstrait")]
+    #[inline]
+    fn cast_mut_by_key<T: ?Sized + 'static, K: IntoKeys>(
+        &mut self,
+        keys: K,
+    ) -> Result<&mut T, Traversal> {
+        use crosstrait::Cast;
+        self.mut_any_by_key(keys.into_keys())?
+            .cast()
+            .ok_or(Traversal::Invalid(0, "Cast failed"))
+    }
 }
 
 /// Serialize a leaf node by its keys.
@@ -417,6 +1605,164 @@ pub trait TreeSerialize {
     where
         K: Keys,
         S: Serializer;
+
+    /// Iterate over the leaves that are not accessible in this instance.
+    ///
+    /// This yields the key of each leaf that can not be serialized due to a
+    /// [`Traversal::Absent`] (e.g. `None` options) or [`Traversal::Access`]
+    /// (e.g. borrowed `RefCell`s, poisoned locks) error, together with that error.
+    /// Leaf values are not serialized.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Path, Traversal, TreeKey, TreeSerialize};
+    /// #[derive(TreeKey, TreeSerialize)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Option<Leaf<u16>>; 2],
+    /// };
+    /// let s = S {
+    ///     foo: 9.into(),
+    ///     bar: [Some(1.into()), None],
+    /// };
+    /// let absent: Vec<_> = s
+    ///     .absent_paths::<Path<String, '/'>, 2>()
+    ///     .map(|p| p.map(|(p, reason)| (p.into_inner(), reason)))
+    ///     .collect();
+    /// assert_eq!(absent, [Ok(("/bar/1".to_string(), Traversal::Absent(2)))]);
+    /// ```
+    ///
+    /// # Returns
+    /// An iterator over the keys and errors of the inaccessible leaves.
+    /// The `Err(usize)` variant indicates that `N` or the depth `D` do not have
+    /// sufficient capacity (see [`NodeIter`]).
+    fn absent_paths<N, const D: usize>(
+        &self,
+    ) -> impl Iterator<Item = Result<(N, Traversal), usize>> + '_
+    where
+        Self: TreeKey,
+        N: Transcode + Default,
+    {
+        Self::nodes::<Packed, D>().filter_map(move |node| {
+            let (keys, _node) = match node {
+                Ok(node) => node,
+                Err(depth) => return Some(Err(depth)),
+            };
+            match self.serialize_by_key(keys.into_keys(), Probe) {
+                Err(Error::Traversal(
+                    reason @ (Traversal::Absent(_) | Traversal::Access(_, _)),
+                )) => Some(
+                    Self::transcode(keys)
+                        .map(|(path, _node)| (path, reason))
+                        .map_err(|err| err.depth()),
+                ),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// A `Serializer` that fails on any value
+///
+/// This is used to probe for the accessibility of leaves without serializing them.
+struct Probe;
+
+#[derive(Debug)]
+pub(crate) struct ProbeError;
+
+impl core::fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Probe")
+    }
+}
+
+impl serde::ser::StdError for ProbeError {}
+
+impl serde::ser::Error for ProbeError {
+    fn custom<T: core::fmt::Display>(_msg: T) -> Self {
+        Self
+    }
+}
+
+macro_rules! probe {
+    ($($name:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(
+            #[inline]
+            fn $name(self, $(_: $arg),*) -> Result<$ret, ProbeError> {
+                Err(ProbeError)
+            }
+        )*
+    };
+}
+pub(crate) use probe;
+
+impl Serializer for Probe {
+    type Ok = ();
+    type Error = ProbeError;
+    type SerializeSeq = serde::ser::Impossible<(), ProbeError>;
+    type SerializeTuple = serde::ser::Impossible<(), ProbeError>;
+    type SerializeTupleStruct = serde::ser::Impossible<(), ProbeError>;
+    type SerializeTupleVariant = serde::ser::Impossible<(), ProbeError>;
+    type SerializeMap = serde::ser::Impossible<(), ProbeError>;
+    type SerializeStruct = serde::ser::Impossible<(), ProbeError>;
+    type SerializeStructVariant = serde::ser::Impossible<(), ProbeError>;
+
+    probe! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u8(u8) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    #[inline]
+    fn serialize_some<T: ?Sized + serde::Serialize>(self, _value: &T) -> Result<(), ProbeError> {
+        Err(ProbeError)
+    }
+
+    #[inline]
+    fn collect_str<T: ?Sized + core::fmt::Display>(self, _value: &T) -> Result<(), ProbeError> {
+        Err(ProbeError)
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: ?Sized + serde::Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<(), ProbeError> {
+        Err(ProbeError)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T: ?Sized + serde::Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), ProbeError> {
+        Err(ProbeError)
+    }
 }
 
 /// Deserialize a leaf node by its keys.
@@ -466,6 +1812,8 @@ impl<T> TreeDeserializeOwned for T where T: for<'de> TreeDeserialize<'de> {}
 // Blanket impls for refs and muts
 
 impl<T: TreeKey> TreeKey for &T {
+    const SCHEMA: Schema = T::SCHEMA;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         T::traverse_all()
@@ -482,6 +1830,8 @@ impl<T: TreeKey> TreeKey for &T {
 }
 
 impl<T: TreeKey> TreeKey for &mut T {
+    const SCHEMA: Schema = T::SCHEMA;
+
     #[inline]
     fn traverse_all<W: Walk>() -> Result<W, W::Error> {
         T::traverse_all()
@@ -546,4 +1896,13 @@ impl<T: TreeAny> TreeAny for &mut T {
     {
         (**self).mut_any_by_key(keys)
     }
+
+    #[inline]
+    fn with_any_by_key<K, F, R>(&self, keys: K, func: F) -> Result<R, Traversal>
+    where
+        K: Keys,
+        F: FnOnce(&dyn Any) -> R,
+    {
+        (**self).with_any_by_key(keys, func)
+    }
 }
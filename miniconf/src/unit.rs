@@ -0,0 +1,272 @@
+use core::{
+    any::Any,
+    fmt,
+    marker::PhantomData,
+    num::NonZero,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{de::Visitor, Deserializer, Serializer};
+
+use crate::{
+    Error, Keys, Schema, Traversal, TreeAny, TreeDeserialize, TreeKey, TreeSerialize, Walk,
+};
+
+/// Unit table for [`UnitLeaf`]
+///
+/// ```
+/// use miniconf::Unit;
+/// struct Hertz;
+/// impl Unit for Hertz {
+///     const SUFFIXES: &'static [(&'static str, f64)] =
+///         &[("Hz", 1.0), ("kHz", 1e3), ("MHz", 1e6)];
+///     const SERIALIZE: usize = 1;
+/// }
+/// ```
+pub trait Unit {
+    /// Suffixes and their scale factors with respect to the base unit
+    const SUFFIXES: &'static [(&'static str, f64)];
+
+    /// Index of the suffix in [`Unit::SUFFIXES`] used for serialization
+    const SERIALIZE: usize = 0;
+}
+
+/// Numeric value that can be scaled by a [`Unit`]
+pub trait UnitValue: Copy {
+    /// Convert to `f64`
+    fn to_f64(self) -> f64;
+
+    /// Convert from `f64`
+    ///
+    /// Returns `None` if the value is not representable.
+    /// Integers accept values within a few ulp of an integer.
+    fn from_f64(value: f64) -> Option<Self>;
+}
+
+macro_rules! impl_unit_value_float {
+    ($($t:ty)+) => {$(
+        impl UnitValue for $t {
+            #[inline]
+            fn to_f64(self) -> f64 {
+                self as _
+            }
+
+            #[inline]
+            fn from_f64(value: f64) -> Option<Self> {
+                Some(value as _)
+            }
+        }
+    )+};
+}
+impl_unit_value_float!(f32 f64);
+
+macro_rules! impl_unit_value_int {
+    ($($t:ty)+) => {$(
+        impl UnitValue for $t {
+            #[inline]
+            fn to_f64(self) -> f64 {
+                self as _
+            }
+
+            #[inline]
+            fn from_f64(value: f64) -> Option<Self> {
+                // Round to nearest (`f64::round()` requires `std`). The cast saturates.
+                let v = (value + if value < 0.0 { -0.5 } else { 0.5 }) as Self;
+                // Tolerate the rounding error of scaling (e.g. `1.001 * 1e3`)
+                let magnitude = if value < 0.0 { -value } else { value };
+                let tol = 4.0 * f64::EPSILON * magnitude.max(1.0);
+                (-tol..=tol).contains(&(v as f64 - value)).then_some(v)
+            }
+        }
+    )+};
+}
+impl_unit_value_int!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+/// Leaf with a unit suffix
+///
+/// The value is serialized as a string with the suffix [`Unit::SERIALIZE`].
+/// It is deserialized from a string with any of the suffixes in [`Unit::SUFFIXES`]
+/// (or without suffix for the base unit) and scaled to the base unit.
+/// Unknown suffixes are rejected with [`Traversal::Access`], values that are not
+/// representable in `T` with [`Traversal::Invalid`].
+///
+/// ```
+/// use miniconf::{json, Tree, Unit, UnitLeaf};
+/// struct Hertz;
+/// impl Unit for Hertz {
+///     const SUFFIXES: &'static [(&'static str, f64)] = &[("Hz", 1.0), ("kHz", 1e3)];
+///     const SERIALIZE: usize = 1;
+/// }
+/// #[derive(Tree, Default)]
+/// struct S {
+///     freq: UnitLeaf<u32, Hertz>,
+/// };
+/// let mut s = S::default();
+/// json::set(&mut s, "/freq", br#""1.5kHz""#).unwrap();
+/// assert_eq!(*s.freq, 1500);
+/// let mut buf = [0; 16];
+/// let len = json::get(&s, "/freq", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], br#""1.5kHz""#);
+/// ```
+#[repr(transparent)]
+pub struct UnitLeaf<T, U>(pub T, PhantomData<U>);
+
+impl<T, U> UnitLeaf<T, U> {
+    /// Create a new leaf
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self(value, PhantomData)
+    }
+
+    /// Extract just the inner
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, U> Deref for UnitLeaf<T, U> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, U> DerefMut for UnitLeaf<T, U> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T, U> From<T> for UnitLeaf<T, U> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Clone, U> Clone for UnitLeaf<T, U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl<T: Copy, U> Copy for UnitLeaf<T, U> {}
+
+impl<T: Default, U> Default for UnitLeaf<T, U> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug, U> fmt::Debug for UnitLeaf<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UnitLeaf").field(&self.0).finish()
+    }
+}
+
+impl<T: PartialEq, U> PartialEq for UnitLeaf<T, U> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T, U> TreeKey for UnitLeaf<T, U> {
+    const SCHEMA: Schema = Schema::Leaf;
+
+    #[inline]
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        Ok(W::leaf())
+    }
+
+    #[inline]
+    fn traverse_by_key<K, F, E>(mut keys: K, _func: F) -> Result<usize, Error<E>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+    {
+        keys.finalize()?;
+        Ok(0)
+    }
+}
+
+impl<T: UnitValue, U: Unit> TreeSerialize for UnitLeaf<T, U> {
+    #[inline]
+    fn serialize_by_key<K, S>(&self, mut keys: K, ser: S) -> Result<usize, Error<S::Error>>
+    where
+        K: Keys,
+        S: Serializer,
+    {
+        keys.finalize()?;
+        let (suffix, scale) = U::SUFFIXES[U::SERIALIZE];
+        ser.collect_str(&format_args!("{}{}", self.0.to_f64() / scale, suffix))
+            .map_err(|err| Error::Inner(0, err))?;
+        Ok(0)
+    }
+}
+
+/// Parse a string with a unit suffix and scale it to the base unit
+///
+/// Accepts borrowed, transient, and owned strings.
+/// Returns `None` for unknown suffixes.
+struct UnitVisitor<U>(PhantomData<U>);
+
+impl<U: Unit> Visitor<'_> for UnitVisitor<U> {
+    type Value = Option<f64>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a number with a unit suffix")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(U::SUFFIXES
+            .iter()
+            .chain([&("", 1.0)])
+            .find_map(|(suffix, scale)| {
+                let num: f64 = v.strip_suffix(suffix)?.trim().parse().ok()?;
+                Some(num * scale)
+            }))
+    }
+}
+
+impl<'de, T: UnitValue, U: Unit> TreeDeserialize<'de> for UnitLeaf<T, U> {
+    #[inline]
+    fn deserialize_by_key<K, D>(&mut self, mut keys: K, de: D) -> Result<usize, Error<D::Error>>
+    where
+        K: Keys,
+        D: Deserializer<'de>,
+    {
+        keys.finalize()?;
+        let value = de
+            .deserialize_str(UnitVisitor::<U>(PhantomData))
+            .map_err(|err| Error::Inner(0, err))?
+            .ok_or(Traversal::Access(0, "Unknown unit"))?;
+        self.0 = T::from_f64(value).ok_or(Traversal::Invalid(0, "Not representable"))?;
+        Ok(0)
+    }
+}
+
+impl<T: Any, U> TreeAny for UnitLeaf<T, U> {
+    #[inline]
+    fn ref_any_by_key<K>(&self, mut keys: K) -> Result<&dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        keys.finalize()?;
+        Ok(&self.0)
+    }
+
+    #[inline]
+    fn mut_any_by_key<K>(&mut self, mut keys: K) -> Result<&mut dyn Any, Traversal>
+    where
+        K: Keys,
+    {
+        keys.finalize()?;
+        Ok(&mut self.0)
+    }
+}
@@ -7,6 +7,7 @@ use crate::{KeyLookup, Packed};
 /// Metadata includes paths that may be [`crate::Traversal::Absent`] at runtime.
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Metadata {
     /// The maximum length of a path in bytes.
     ///
@@ -26,6 +27,16 @@ pub struct Metadata {
 
     /// The maximum number of bits (see [`crate::Packed`])
     pub max_bits: u32,
+
+    /// The maximum length of a single name of a named child in bytes.
+    ///
+    /// Numbered and homogeneous children (indices) are not included.
+    pub max_name_length: usize,
+
+    /// The maximum number of children of any internal node.
+    ///
+    /// This is zero for a leaf.
+    pub max_fanout: usize,
 }
 
 impl Metadata {
@@ -39,6 +50,33 @@ impl Metadata {
     }
 }
 
+/// Compile time structure of a `TreeKey` namespace.
+///
+/// This is the `const` equivalent of `Shape` and available as [`crate::TreeKey::SCHEMA`].
+/// It allows walking all nodes in `const` context (see e.g. [`crate::HashIndex`]).
+///
+/// ```
+/// use miniconf::{KeyLookup, Leaf, Schema, TreeKey};
+/// #[derive(TreeKey)]
+/// struct S {
+///     foo: Leaf<u32>,
+///     bar: [Leaf<u16>; 2],
+/// };
+/// const SCHEMA: Schema = S::SCHEMA;
+/// let Schema::Internal(KeyLookup::Named(names), children) = SCHEMA else { unreachable!() };
+/// assert_eq!(names, &["foo", "bar"]);
+/// assert_eq!(children[0], Schema::Leaf);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    /// A leaf node
+    Leaf,
+    /// An internal node with the lookup of its children and the children.
+    ///
+    /// There is a single child for [`KeyLookup::Homogeneous`].
+    Internal(KeyLookup, &'static [Schema]),
+}
+
 /// Capability to be walked through a `TreeKey` using `traverse_all()`.
 pub trait Walk: Sized {
     /// Error type for `merge()`
@@ -55,51 +93,308 @@ pub trait Walk: Sized {
     fn internal(children: &[&Self], lookup: &KeyLookup) -> Result<Self, Self::Error>;
 }
 
+impl Metadata {
+    /// Metadata of a leaf node
+    pub const LEAF: Self = Self {
+        count: NonZero::<usize>::MIN,
+        max_length: 0,
+        max_depth: 0,
+        max_bits: 0,
+        max_name_length: 0,
+        max_fanout: 0,
+    };
+}
+
+impl Schema {
+    /// Metadata about all paths below this node.
+    ///
+    /// This is the `const` equivalent of walking the node with [`Metadata`] and used
+    /// to compute [`crate::TreeKey::META`].
+    pub const fn metadata(&self) -> Metadata {
+        match self {
+            Self::Leaf => Metadata::LEAF,
+            Self::Internal(lookup, children) => {
+                let mut acc = Acc::EMPTY;
+                let mut index = 0;
+                while index < children.len() {
+                    acc = acc.child(index, &children[index].metadata(), children.len(), lookup);
+                    index += 1;
+                }
+                acc.finish()
+            }
+        }
+    }
+}
+
+/// Accumulator for `Metadata` of an internal node
+struct Acc {
+    max_length: usize,
+    max_depth: usize,
+    count: usize,
+    max_bits: u32,
+    max_name_length: usize,
+    max_fanout: usize,
+}
+
+impl Acc {
+    const EMPTY: Self = Self {
+        max_length: 0,
+        max_depth: 0,
+        count: 0,
+        max_bits: 0,
+        max_name_length: 0,
+        max_fanout: 0,
+    };
+
+    const fn child(self, index: usize, child: &Metadata, len: usize, lookup: &KeyLookup) -> Self {
+        let mut name_length = child.max_name_length;
+        let (length, n) = match lookup {
+            KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => {
+                debug_assert!(len == names.len());
+                if names[index].len() > name_length {
+                    name_length = names[index].len();
+                }
+                (names[index].len(), 1)
+            }
+            KeyLookup::Numbered(num) => {
+                debug_assert!(len == num.get());
+                let digits = match index.checked_ilog10() {
+                    Some(l) => l as usize + 1,
+                    None => 1,
+                };
+                (digits, 1)
+            }
+            KeyLookup::Homogeneous(num) => {
+                debug_assert!(len == 1);
+                (num.ilog10() as usize + 1, num.get())
+            }
+        };
+        let depth = 1 + child.max_depth;
+        let length = length + child.max_length;
+        let bits = Packed::bits_for(lookup.len().get() - 1) + child.max_bits;
+        let fanout = if lookup.len().get() > child.max_fanout {
+            lookup.len().get()
+        } else {
+            child.max_fanout
+        };
+        Self {
+            max_depth: if depth > self.max_depth {
+                depth
+            } else {
+                self.max_depth
+            },
+            max_length: if length > self.max_length {
+                length
+            } else {
+                self.max_length
+            },
+            count: self.count + n * child.count.get(),
+            max_bits: if bits > self.max_bits {
+                bits
+            } else {
+                self.max_bits
+            },
+            max_name_length: if name_length > self.max_name_length {
+                name_length
+            } else {
+                self.max_name_length
+            },
+            max_fanout: if fanout > self.max_fanout {
+                fanout
+            } else {
+                self.max_fanout
+            },
+        }
+    }
+
+    const fn finish(self) -> Metadata {
+        Metadata {
+            max_bits: self.max_bits,
+            max_depth: self.max_depth,
+            max_length: self.max_length,
+            max_name_length: self.max_name_length,
+            max_fanout: self.max_fanout,
+            count: match NonZero::new(self.count) {
+                Some(count) => count,
+                None => panic!("internal nodes must have at least one leaf"),
+            },
+        }
+    }
+}
+
 impl Walk for Metadata {
     type Error = core::convert::Infallible;
 
     #[inline]
     fn leaf() -> Self {
-        Self {
-            count: NonZero::<usize>::MIN,
-            max_length: 0,
-            max_depth: 0,
-            max_bits: 0,
-        }
+        Self::LEAF
     }
 
     #[inline]
     fn internal(children: &[&Self], lookup: &KeyLookup) -> Result<Self, Self::Error> {
-        let mut max_depth = 0;
-        let mut max_length = 0;
-        let mut count = 0;
-        let mut max_bits = 0;
-        // TODO: swap loop and match
+        let mut acc = Acc::EMPTY;
         for (index, child) in children.iter().enumerate() {
-            let (len, n) = match lookup {
-                KeyLookup::Named(names) => {
-                    debug_assert_eq!(children.len(), names.len());
-                    (names[index].len(), 1)
+            acc = acc.child(index, child, children.len(), lookup);
+        }
+        Ok(acc.finish())
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod _alloc {
+    use super::*;
+    extern crate alloc;
+    use alloc::{
+        borrow::{Cow, ToOwned},
+        boxed::Box,
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    /// Owned structure of a `TreeKey` namespace.
+    ///
+    /// This mirrors the internal node [`KeyLookup`]s and the leaves of a `TreeKey`
+    /// as obtained through `traverse_all()`.
+    ///
+    /// ```
+    /// use miniconf::{Leaf, Shape, Tree, TreeKey};
+    /// #[derive(Tree)]
+    /// struct S {
+    ///     foo: Leaf<u32>,
+    ///     bar: [Leaf<u16>; 2],
+    /// };
+    /// let shape: Shape = S::traverse_all().unwrap();
+    /// let Shape::Named(children) = &shape else { unreachable!() };
+    /// assert_eq!(children[0], ("foo".into(), Shape::Leaf));
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    pub enum Shape {
+        /// A leaf node
+        Leaf,
+        /// An internal node with named children
+        Named(Vec<(String, Shape)>),
+        /// An internal node with numbered heterogeneous children
+        Numbered(Vec<Shape>),
+        /// An internal node with a number of homogeneous children
+        Homogeneous(NonZero<usize>, Box<Shape>),
+    }
+
+    impl Shape {
+        /// The child at `index`
+        ///
+        /// All children of a homogeneous node are the same.
+        pub fn child(&self, index: usize) -> Option<&Self> {
+            match self {
+                Self::Leaf => None,
+                Self::Named(children) => children.get(index).map(|(_, child)| child),
+                Self::Numbered(children) => children.get(index),
+                Self::Homogeneous(len, child) => (index < len.get()).then_some(&**child),
+            }
+        }
+
+        /// Iterate over the index, the name, and the shape of all children.
+        ///
+        /// The name of numbered and homogeneous children is their index.
+        ///
+        /// ```
+        /// use miniconf::{Leaf, Shape, Tree, TreeKey};
+        /// #[derive(Tree)]
+        /// struct S {
+        ///     foo: Leaf<u32>,
+        ///     bar: [Leaf<u16>; 2],
+        /// };
+        /// let shape: Shape = S::traverse_all().unwrap();
+        /// let bar = shape.child(1).unwrap();
+        /// let names: Vec<_> = bar.children().map(|(i, name, _child)| (i, name)).collect();
+        /// assert_eq!(names, [(0, "0".into()), (1, "1".into())]);
+        /// ```
+        pub fn children(&self) -> impl Iterator<Item = (usize, Cow<'_, str>, &Self)> + '_ {
+            let len = match self {
+                Self::Leaf => 0,
+                Self::Named(children) => children.len(),
+                Self::Numbered(children) => children.len(),
+                Self::Homogeneous(len, _) => len.get(),
+            };
+            (0..len).map(move |index| match self {
+                Self::Named(children) => {
+                    let (name, child) = &children[index];
+                    (index, Cow::Borrowed(name.as_str()), child)
+                }
+                // Note(unwrap): index < len
+                _ => (index, index.to_string().into(), self.child(index).unwrap()),
+            })
+        }
+
+        /// Check structural compatibility ignoring the order of named children.
+        ///
+        /// Named children are matched by name regardless of their position.
+        /// Numbered and homogeneous children are compared by position and length.
+        ///
+        /// ```
+        /// use miniconf::{Leaf, Shape, Tree, TreeKey};
+        /// #[derive(Tree)]
+        /// struct A {
+        ///     foo: Leaf<u32>,
+        ///     bar: [Leaf<u16>; 2],
+        /// };
+        /// #[derive(Tree)]
+        /// struct B {
+        ///     bar: [Leaf<i8>; 2],
+        ///     foo: Leaf<f32>,
+        /// };
+        /// let a: Shape = A::traverse_all().unwrap();
+        /// let b: Shape = B::traverse_all().unwrap();
+        /// assert_ne!(a, b);
+        /// assert!(a.is_compatible_unordered(&b));
+        /// ```
+        pub fn is_compatible_unordered(&self, other: &Self) -> bool {
+            match (self, other) {
+                (Self::Leaf, Self::Leaf) => true,
+                (Self::Named(a), Self::Named(b)) => {
+                    a.len() == b.len()
+                        && a.iter().all(|(name, a)| {
+                            b.iter()
+                                .find(|(n, _)| n == name)
+                                .is_some_and(|(_, b)| a.is_compatible_unordered(b))
+                        })
+                }
+                (Self::Numbered(a), Self::Numbered(b)) => {
+                    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.is_compatible_unordered(b))
+                }
+                (Self::Homogeneous(na, a), Self::Homogeneous(nb, b)) => {
+                    na == nb && a.is_compatible_unordered(b)
                 }
-                KeyLookup::Numbered(len) => {
-                    debug_assert_eq!(children.len(), len.get());
-                    (index.checked_ilog10().unwrap_or_default() as usize + 1, 1)
+                _ => false,
+            }
+        }
+    }
+
+    impl Walk for Shape {
+        type Error = core::convert::Infallible;
+
+        #[inline]
+        fn leaf() -> Self {
+            Self::Leaf
+        }
+
+        fn internal(children: &[&Self], lookup: &KeyLookup) -> Result<Self, Self::Error> {
+            Ok(match lookup {
+                KeyLookup::Named(names) | KeyLookup::IndexNamed(names) => Self::Named(
+                    names
+                        .iter()
+                        .zip(children)
+                        .map(|(name, child)| ((*name).to_owned(), (*child).clone()))
+                        .collect(),
+                ),
+                KeyLookup::Numbered(_) => {
+                    Self::Numbered(children.iter().map(|child| (*child).clone()).collect())
                 }
                 KeyLookup::Homogeneous(len) => {
-                    debug_assert_eq!(children.len(), 1);
-                    (len.ilog10() as usize + 1, len.get())
+                    Self::Homogeneous(*len, Box::new(children[0].clone()))
                 }
-            };
-            max_depth = max_depth.max(1 + child.max_depth);
-            max_length = max_length.max(len + child.max_length);
-            count += n * child.count.get();
-            max_bits = max_bits.max(Packed::bits_for(lookup.len().get() - 1) + child.max_bits);
+            })
         }
-        Ok(Self {
-            max_bits,
-            max_depth,
-            max_length,
-            count: NonZero::new(count).unwrap(),
-        })
     }
 }
+#[cfg(feature = "alloc")]
+pub use _alloc::*;
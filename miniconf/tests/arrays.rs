@@ -1,6 +1,6 @@
 use miniconf::{
-    json, Deserialize, Error, Indices, Leaf, Metadata, Packed, Path, Serialize, Traversal, Tree,
-    TreeKey,
+    json, Deserialize, Error, Glob, Indices, Leaf, Metadata, Packed, Path, Serialize, Traversal,
+    Tree, TreeKey,
 };
 
 mod common;
@@ -127,6 +127,59 @@ fn not_found() {
     );
 }
 
+#[test]
+fn glob() {
+    let paths = |pattern| {
+        Glob::<Settings, Path<String, '/'>, 4>::new(pattern)
+            .map(|k| k.map(|(p, _node)| p.into_inner()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        paths("/aam/*/*/c"),
+        [
+            Ok("/aam/0/0/c".to_string()),
+            Ok("/aam/0/1/c".to_string()),
+            Ok("/aam/1/0/c".to_string()),
+            Ok("/aam/1/1/c".to_string())
+        ]
+    );
+    assert_eq!(
+        paths("/aam/*/1"),
+        [Ok("/aam/0/1".to_string()), Ok("/aam/1/1".to_string())]
+    );
+    assert_eq!(paths("/d/1"), [Ok("/d/1".to_string())]);
+    assert_eq!(
+        paths("/*"),
+        [Err(Traversal::Invalid(1, "Wildcard at named node"))]
+    );
+    assert_eq!(
+        paths("/am/*/*"),
+        [
+            Err(Traversal::Invalid(3, "Wildcard at named node")),
+            Err(Traversal::Invalid(3, "Wildcard at named node"))
+        ]
+    );
+    assert_eq!(
+        paths("/aam/*/0/d"),
+        [Err(Traversal::NotFound(4)), Err(Traversal::NotFound(4))]
+    );
+    assert_eq!(
+        paths("/d/*/*"),
+        [Err(Traversal::TooLong(2)), Err(Traversal::TooLong(2))]
+    );
+
+    // Leaves below the matches
+    let leaves: Vec<_> = Glob::<Settings, Packed, 4>::new("/aam/1/*")
+        .flat_map(|k| {
+            Settings::nodes::<Path<String, '/'>, 4>()
+                .root(k.unwrap().0)
+                .unwrap()
+                .map(|p| p.unwrap().0.into_inner())
+        })
+        .collect();
+    assert_eq!(leaves, ["/aam/1/0/c", "/aam/1/1/c"]);
+}
+
 #[test]
 fn metadata() {
     let m: Metadata = Settings::traverse_all().unwrap();
@@ -134,3 +187,224 @@ fn metadata() {
     assert_eq!(m.max_length("/"), "/aam/0/0/c".len());
     assert_eq!(m.count.get(), 11);
 }
+
+#[test]
+fn is_collection() {
+    #[derive(Tree)]
+    #[allow(dead_code)]
+    enum En {
+        A(Leaf<u8>),
+        B([Leaf<u8>; 2]),
+    }
+    #[derive(Tree)]
+    struct S {
+        arr: [Inner; 2],
+        nested: [[Leaf<u8>; 2]; 2],
+        tuple: (Leaf<u8>, Leaf<u8>),
+        inner: Inner,
+        leaf: Leaf<u8>,
+        opt: Option<[Leaf<u8>; 2]>,
+        e: En,
+    }
+    let is_collection = |keys| S::transcode::<(), _>(keys).map(|((), node)| node.is_collection());
+    assert_eq!(is_collection(Path::<_, '/'>::from("")), Ok(false));
+    for (path, collection) in [
+        ("/arr", true),
+        ("/arr/0", false),
+        ("/arr/0/c", false),
+        ("/nested", true),
+        ("/nested/1", true),
+        ("/nested/1/1", false),
+        ("/tuple", false),
+        ("/inner", false),
+        ("/leaf", false),
+        ("/opt", true),
+        ("/e", false),
+        ("/e/B", true),
+    ] {
+        assert_eq!(
+            is_collection(Path::<_, '/'>::from(path)),
+            Ok(collection),
+            "{path}"
+        );
+    }
+    assert_eq!(
+        is_collection(Path::<_, '/'>::from("/arr/2")),
+        Err(Traversal::NotFound(2))
+    );
+}
+
+#[test]
+fn group_index() {
+    use miniconf::GroupIndexKeys;
+
+    let mut s = Settings::default();
+    json::set_by_key(&mut s, GroupIndexKeys::new("d:1").unwrap(), b"7").unwrap();
+    assert_eq!(*s.d[1], 7);
+    let (path, node): (Path<String, '/'>, _) =
+        Settings::transcode(GroupIndexKeys::new("dm:0").unwrap()).unwrap();
+    assert_eq!((path.as_str(), node.depth()), ("/dm/0", 2));
+
+    assert_eq!(
+        json::set_by_key(&mut s, GroupIndexKeys::new("d:2").unwrap(), b"7"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(
+        json::set_by_key(&mut s, GroupIndexKeys::new("am:0").unwrap(), b"7"),
+        Err(Traversal::TooShort(2).into())
+    );
+    assert_eq!(
+        json::set_by_key(&mut s, GroupIndexKeys::new("a:0").unwrap(), b"7"),
+        Err(Traversal::TooLong(1).into())
+    );
+    for (input, msg) in [
+        ("d1", "Missing `:` separator"),
+        (":1", "Empty group"),
+        ("d:", "Malformed index"),
+        ("d:x", "Malformed index"),
+    ] {
+        assert_eq!(GroupIndexKeys::new(input), Err(Traversal::Invalid(0, msg)));
+    }
+}
+
+#[test]
+fn index_names() {
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(index_names = ["left", "right"])]
+        channels: [Inner; 2],
+        #[tree(index_names = ["x", "y", "z"])]
+        pos: [Leaf<i32>; 3],
+    }
+
+    let mut s = S::default();
+    json::set(&mut s, "/channels/right/c", b"3").unwrap();
+    json::set(&mut s, "/channels/0/c", b"4").unwrap();
+    json::set_by_key(&mut s, [1, 2], b"5").unwrap();
+    assert_eq!((*s.channels[0].c, *s.channels[1].c, *s.pos[2]), (4, 3, 5));
+    assert_eq!(
+        json::set(&mut s, "/channels/center/c", b"1"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/pos/3", b"1"),
+        Err(Traversal::NotFound(2).into())
+    );
+    // Struct fields and enum variants are not addressable by index
+    assert_eq!(
+        json::set(&mut s, "/1/0", b"1"),
+        Err(Traversal::NotFound(1).into())
+    );
+    #[derive(Tree)]
+    #[allow(dead_code)]
+    enum E {
+        A(Leaf<i32>),
+        B(Leaf<i32>),
+    }
+    let mut e = E::A(0.into());
+    assert_eq!(
+        json::set(&mut e, "/0", b"1"),
+        Err(Traversal::NotFound(1).into())
+    );
+    assert_eq!(json::set(&mut e, "/A", b"1"), Ok(1));
+    assert!(S::transcode::<(), _>(["pos"]).unwrap().1.is_collection());
+
+    assert_eq!(
+        common::paths::<S, 3>(),
+        [
+            "/channels/left/c",
+            "/channels/right/c",
+            "/pos/x",
+            "/pos/y",
+            "/pos/z"
+        ]
+    );
+    assert_eq!(S::META.max_length("/"), "/channels/right/c".len());
+}
+
+#[test]
+fn swap() {
+    use miniconf::TreeAny;
+
+    let mut s = Settings::default();
+    *s.d[0] = 3;
+    s.am[1].c = 7.into();
+    s.swap_by_key::<u8, _, _>(["d", "0"], ["d", "1"]).unwrap();
+    assert_eq!((*s.d[0], *s.d[1]), (0, 3));
+    s.swap_by_key::<u8, _, _>(["am", "1", "c"], ["d", "1"])
+        .unwrap();
+    assert_eq!((*s.am[1].c, *s.d[1]), (3, 7));
+    // Both keys resolve to the same leaf
+    s.swap_by_key::<u8, _, _>(["d", "1"], ["d", "1"]).unwrap();
+    assert_eq!(*s.d[1], 7);
+    s.swap_by_key::<u8, _, _>(["d", "1"], Path::<_, '/'>("/d/1"))
+        .unwrap();
+    assert_eq!(*s.d[1], 7);
+    // Whole `Inner` leaves
+    s.dm[0] = Inner { c: 9.into() }.into();
+    s.swap_by_key::<Inner, _, _>(["dm", "0"], ["dm", "1"])
+        .unwrap();
+    assert_eq!((*s.dm[0].c, *s.dm[1].c), (0, 9));
+    assert_eq!(
+        s.swap_by_key::<u8, _, _>(["d", "1"], ["dm", "1"]),
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    );
+    assert_eq!(
+        s.swap_by_key::<u8, _, _>(["d", "1"], ["d", "2"]),
+        Err(Traversal::NotFound(2))
+    );
+    assert_eq!(*s.d[1], 7);
+}
+
+#[test]
+fn partial_array() {
+    #[derive(Default, Tree)]
+    struct S {
+        #[tree(partial_array)]
+        b: [Leaf<i32>; 3],
+        #[tree(partial_array)]
+        c: [Inner; 2],
+    }
+
+    let mut s = S::default();
+    *s.b[2] = 9;
+    assert_eq!(
+        json::set_by_key(&mut s, ["b"], b"[1,2]").map(|(_, n)| n.depth()),
+        Ok(1)
+    );
+    assert_eq!(s.b.map(|b| *b), [1, 2, 9]);
+    json::set(&mut s, "/b", b"[]").unwrap();
+    assert_eq!(s.b.map(|b| *b), [1, 2, 9]);
+    json::set(&mut s, "/b", b"[4, 5, 6]").unwrap();
+    assert_eq!(s.b.map(|b| *b), [4, 5, 6]);
+    assert!(matches!(
+        json::set(&mut s, "/b", b"[7, 8, 9, 10]"),
+        Err(Error::Inner(1, _))
+    ));
+    assert_eq!(s.b.map(|b| *b), [4, 5, 6]);
+    assert!(json::set(&mut s, "/b", b"[7, true]").is_err());
+    assert_eq!(s.b.map(|b| *b), [4, 5, 6]);
+    assert!(json::set(&mut s, "/b", b"3").is_err());
+
+    // Elements and nodes below
+    assert_eq!(json::set(&mut s, "/b/1", b"0"), Ok(1));
+    assert_eq!(s.b.map(|b| *b), [4, 0, 6]);
+    json::set(&mut s, "/c", br#"[{"c": 3}]"#).unwrap();
+    json::set(&mut s, "/c/1/c", b"4").unwrap();
+    assert_eq!((*s.c[0].c, *s.c[1].c), (3, 4));
+    assert_eq!(
+        json::set(&mut s, "/b/3", b"0"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/c/0/d", b"0"),
+        Err(Traversal::NotFound(3).into())
+    );
+
+    // Serialization is unchanged
+    let mut buf = [0; 8];
+    assert_eq!(
+        json::get(&s, "/b", &mut buf),
+        Err(Traversal::TooShort(1).into())
+    );
+}
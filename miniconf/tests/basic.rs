@@ -1,4 +1,4 @@
-use miniconf::{Indices, Leaf, Metadata, Node, Path, Traversal, Tree, TreeKey};
+use miniconf::{Indices, IntoKeys, Leaf, Metadata, Node, Path, Traversal, Tree, TreeKey};
 mod common;
 
 #[derive(Tree, Default)]
@@ -80,3 +80,360 @@ fn cell() {
     let mut r = &c;
     common::set_get(&mut r, "", b"9");
 }
+
+#[test]
+fn locked() {
+    use core::cell::RefCell;
+    use miniconf::TreeAny;
+
+    #[derive(Tree, Default)]
+    struct S {
+        #[cfg(feature = "std")]
+        mutex: std::sync::Mutex<Leaf<i32>>,
+        cell: RefCell<[Leaf<u8>; 2]>,
+    }
+    let s = S::default();
+    *s.cell.borrow_mut()[1] = 3;
+    assert_eq!(
+        s.ref_any_by_key(["cell", "1"].into_keys()).err(),
+        Some(Traversal::Access(1, "Can't leak out of RefCell"))
+    );
+    assert_eq!(s.try_clone_by_key::<u8, _>(["cell", "1"]), Ok(3));
+    assert_eq!(
+        s.try_clone_by_key::<i8, _>(["cell", "1"]),
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    );
+    let _guard = s.cell.borrow_mut();
+    assert_eq!(
+        s.try_clone_by_key::<u8, _>(["cell", "0"]),
+        Err(Traversal::Access(1, "Borrowed"))
+    );
+    #[cfg(feature = "std")]
+    {
+        *s.mutex.lock().unwrap() = 7.into();
+        assert_eq!(s.try_clone_by_key::<i32, _>(["mutex"]), Ok(7));
+    }
+}
+
+#[test]
+fn type_id() {
+    use core::any::TypeId;
+    use core::cell::RefCell;
+    use miniconf::{Packed, TreeAny};
+
+    #[derive(Tree, Default)]
+    struct S {
+        a: Leaf<i32>,
+        b: Option<Leaf<f32>>,
+        c: RefCell<[Leaf<u8>; 2]>,
+        d: [Leaf<bool>; 2],
+    }
+    let mut s = S::default();
+    assert_eq!(s.type_id_by_key(["a"]), Ok(TypeId::of::<i32>()));
+    assert_eq!(s.type_id_by_key(["c", "1"]), Ok(TypeId::of::<u8>()));
+    assert_eq!(s.type_id_by_key(["b"]), Err(Traversal::Absent(1)));
+    s.b = Some(1.0.into());
+    assert_eq!(s.type_id_by_key(["b"]), Ok(TypeId::of::<f32>()));
+    assert_eq!(s.type_id_by_key(["d"]), Err(Traversal::TooShort(1)));
+
+    // Precompute for all leaves
+    let types: Vec<_> = S::nodes::<Packed, 2>()
+        .map(|key| s.type_id_by_key(key.unwrap().0).unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            TypeId::of::<i32>(),
+            TypeId::of::<f32>(),
+            TypeId::of::<u8>(),
+            TypeId::of::<u8>(),
+            TypeId::of::<bool>(),
+            TypeId::of::<bool>()
+        ]
+    );
+}
+
+#[test]
+fn present_typed() {
+    use core::any::TypeId;
+    use miniconf::{Path, TreeAny};
+
+    #[derive(Tree, Default)]
+    struct S {
+        a: Leaf<i32>,
+        b: Option<Leaf<f32>>,
+        c: [Leaf<u8>; 2],
+        d: Option<[Leaf<bool>; 2]>,
+    }
+    let s = S {
+        d: Some(Default::default()),
+        ..Default::default()
+    };
+    let typed: Vec<_> = s
+        .present_typed::<Path<String, '/'>, 2>()
+        .map(|p| p.map(|(p, t)| (p.into_inner(), t)).unwrap())
+        .collect();
+    assert_eq!(
+        typed,
+        [
+            ("/a".to_string(), TypeId::of::<i32>()),
+            ("/c/0".to_string(), TypeId::of::<u8>()),
+            ("/c/1".to_string(), TypeId::of::<u8>()),
+            ("/d/0".to_string(), TypeId::of::<bool>()),
+            ("/d/1".to_string(), TypeId::of::<bool>())
+        ]
+    );
+
+    // Insufficient capacity
+    assert!(s
+        .present_typed::<Path<heapless::String<2>, '/'>, 2>()
+        .any(|p| p.is_err()));
+}
+
+#[test]
+fn raw_le() {
+    use core::cell::RefCell;
+    use miniconf::TreeAny;
+
+    #[derive(Tree, Default)]
+    struct S {
+        reg: Leaf<u32>,
+        regs: RefCell<[Leaf<i16>; 2]>,
+        gain: Leaf<f32>,
+        name: Leaf<&'static str>,
+    }
+    let mut s = S::default();
+    let mut buf = [0; 8];
+
+    assert_eq!(s.set_raw_le(["reg"], &0xdead_beefu32.to_le_bytes()), Ok(4));
+    assert_eq!(*s.reg, 0xdead_beef);
+    assert_eq!(s.get_raw_le(["reg"], &mut buf), Ok(4));
+    assert_eq!(buf[..4], [0xef, 0xbe, 0xad, 0xde]);
+    assert_eq!(
+        s.get_raw_le(["reg"], &mut buf[..3]),
+        Err(Traversal::Invalid(0, "Buffer too small"))
+    );
+    assert_eq!(
+        s.set_raw_le(["reg"], &[1, 2]),
+        Err(Traversal::Invalid(0, "Incorrect length"))
+    );
+    assert_eq!(*s.reg, 0xdead_beef);
+
+    *s.regs.borrow_mut()[1] = -2;
+    assert_eq!(s.get_raw_le(["regs", "1"], &mut buf), Ok(2));
+    assert_eq!(buf[..2], [0xfe, 0xff]);
+
+    assert_eq!(s.set_raw_le(["gain"], &1.5f32.to_le_bytes()), Ok(4));
+    assert_eq!(*s.gain, 1.5);
+
+    assert_eq!(
+        s.get_raw_le(["name"], &mut buf),
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    );
+    assert_eq!(s.get_raw_le(["foo"], &mut buf), Err(Traversal::NotFound(1)));
+}
+
+#[test]
+fn get_f64() {
+    use miniconf::TreeAny;
+
+    #[derive(Tree, Default)]
+    struct S {
+        offset: Leaf<i32>,
+        mode: Leaf<u8>,
+        gain: Leaf<f32>,
+        inner: [Leaf<u64>; 1],
+        name: Leaf<&'static str>,
+    }
+    let s = S {
+        offset: (-7).into(),
+        mode: 200.into(),
+        gain: 0.25.into(),
+        inner: [(1 << 40).into()],
+        ..Default::default()
+    };
+    assert_eq!(s.get_f64(["offset"]), Ok(-7.0));
+    assert_eq!(s.get_f64(["mode"]), Ok(200.0));
+    assert_eq!(s.get_f64(["gain"]), Ok(0.25));
+    assert_eq!(s.get_f64([3, 0]), Ok((1u64 << 40) as f64));
+    assert_eq!(
+        s.get_f64(["name"]),
+        Err(Traversal::Access(0, "Not numeric"))
+    );
+    assert_eq!(s.get_f64(["inner"]), Err(Traversal::TooShort(1)));
+}
+
+#[test]
+fn case_insensitive() {
+    use miniconf::json;
+
+    #[derive(Tree, Default)]
+    #[tree(case_insensitive)]
+    struct S {
+        gain: Leaf<f32>,
+        #[tree(rename = "Mode")]
+        mode: Leaf<u8>,
+        c: Inner,
+        #[tree(index_names = ["left", "right"])]
+        arr: [Leaf<u8>; 2],
+    }
+
+    let mut s = S::default();
+    json::set(&mut s, "/GAIN", b"2.0").unwrap();
+    json::set(&mut s, "/mode", b"3").unwrap();
+    json::set(&mut s, "/C/inner", b"1.0").unwrap();
+    json::set(&mut s, "/aRR/1", b"4").unwrap();
+    assert_eq!((*s.gain, *s.mode, *s.c.inner, *s.arr[1]), (2.0, 3, 1.0, 4));
+    // Only the annotated container is case-insensitive
+    assert_eq!(
+        json::set(&mut s, "/c/INNER", b"1.0"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/arr/Left", b"1"),
+        Err(Traversal::NotFound(2).into())
+    );
+    // Canonical names are emitted
+    let (path, node) = S::transcode::<Path<String, '/'>, _>(["MODE"]).unwrap();
+    assert_eq!((path.as_str(), node), ("/Mode", Node::leaf(1)));
+}
+
+#[test]
+fn has_default() {
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(default)]
+        gain: Leaf<f32>,
+        serial: Leaf<u32>,
+        #[tree(default)]
+        c: Inner,
+        d: [Inner; 2],
+        #[tree(default)]
+        opt: Option<Inner>,
+    }
+
+    let node = |keys: &[&str]| S::transcode::<(), _>(keys).unwrap().1;
+    assert!(node(&["gain"]).has_default());
+    assert!(!node(&["serial"]).has_default());
+    assert!(node(&["c"]).has_default());
+    assert!(node(&["c", "inner"]).has_default());
+    assert!(!node(&["d", "1", "inner"]).has_default());
+    assert!(node(&["opt", "inner"]).has_default());
+    assert!(!node(&["d"]).has_default());
+
+    let defaults: Vec<_> = S::nodes::<Path<String, '/'>, 3>()
+        .map(|p| p.unwrap())
+        .filter(|(_, node)| node.has_default())
+        .map(|(path, _)| path.into_inner())
+        .collect();
+    assert_eq!(defaults, ["/gain", "/c/inner", "/opt/inner"]);
+
+    assert_eq!(S::subtree_has_defaults::<_, 3>(["c"]), Ok(true));
+    assert_eq!(S::subtree_has_defaults::<_, 3>(["opt"]), Ok(true));
+    assert_eq!(S::subtree_has_defaults::<_, 3>(["serial"]), Ok(false));
+    assert_eq!(S::subtree_has_defaults::<_, 3>(["d"]), Ok(false));
+    assert_eq!(S::subtree_has_defaults::<_, 3>([0usize; 0]), Ok(false));
+    assert_eq!(
+        S::subtree_has_defaults::<_, 3>(["foo"]),
+        Err(Traversal::NotFound(1))
+    );
+}
+
+#[test]
+fn lexicographic_order() {
+    use miniconf::json;
+
+    #[derive(Tree, Default)]
+    #[tree(order = "lexicographic")]
+    struct S {
+        gain: Leaf<f32>,
+        #[tree(rename = "Mode")]
+        mode: Leaf<u8>,
+        c: Inner,
+        arr: [Leaf<u8>; 2],
+    }
+
+    let paths: Vec<_> = S::nodes::<Path<String, '/'>, 2>()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(paths, ["/Mode", "/arr/0", "/arr/1", "/c/inner", "/gain"]);
+
+    // Indices follow the sorted order, names still resolve to their fields
+    let (idx, node) = S::transcode::<Indices<[usize; 2]>, _>(["gain"]).unwrap();
+    assert_eq!((idx.as_ref(), node), (&[3, 0][..], Node::leaf(1)));
+    let mut s = S::default();
+    json::set(&mut s, "/gain", b"2.0").unwrap();
+    json::set(&mut s, "/Mode", b"3").unwrap();
+    json::set(&mut s, "/c/inner", b"1.0").unwrap();
+    json::set_by_key(&mut s, [1, 1], b"4").unwrap();
+    assert_eq!((*s.gain, *s.mode, *s.c.inner, *s.arr[1]), (2.0, 3, 1.0, 4));
+}
+
+#[test]
+fn rename_all() {
+    use miniconf::json;
+
+    #[derive(Tree, Default)]
+    #[tree(rename_all = "kebab-case")]
+    struct S {
+        max_depth: Leaf<u8>,
+        gain: Leaf<f32>,
+        #[tree(rename = "Mode")]
+        mode: Leaf<u8>,
+        inner_config: Inner,
+    }
+
+    let paths: Vec<_> = S::nodes::<Path<String, '/'>, 2>()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(
+        paths,
+        ["/max-depth", "/gain", "/Mode", "/inner-config/inner"]
+    );
+    let mut s = S::default();
+    json::set(&mut s, "/max-depth", b"3").unwrap();
+    assert_eq!(*s.max_depth, 3);
+
+    #[allow(dead_code)]
+    #[derive(Tree)]
+    #[tree(rename_all = "camelCase", variant_names)]
+    enum E {
+        FastMode(Leaf<i32>),
+        Off,
+    }
+    assert_eq!(E::VARIANTS, ["fastMode", "off"]);
+    assert_eq!(common::paths::<E, 1>(), ["/fastMode"]);
+}
+
+#[cfg(feature = "doc")]
+#[test]
+fn doc() {
+    #[derive(Tree, Default)]
+    struct S {
+        /// The output gain
+        gain: Leaf<f32>,
+        /// Calibration
+        ///
+        /// Two points
+        cal: [Inner; 2],
+        offset: Offset,
+        undocumented: Leaf<u32>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Tree, Default)]
+    enum Offset {
+        #[default]
+        None,
+        /// Fixed offset
+        Fixed(Leaf<i32>),
+    }
+
+    assert_eq!(S::doc(["gain"]), Ok(Some("The output gain")));
+    assert_eq!(S::doc(["cal"]), Ok(Some("Calibration\n\nTwo points")));
+    assert_eq!(S::doc(["cal", "1"]), Ok(None));
+    assert_eq!(S::doc(["offset", "Fixed"]), Ok(Some("Fixed offset")));
+    assert_eq!(S::doc(["undocumented"]), Ok(None));
+    assert_eq!(S::doc([0usize; 0]), Ok(None));
+    assert_eq!(S::doc(["foo"]), Err(Traversal::NotFound(1)));
+}
@@ -0,0 +1,70 @@
+use miniconf::{cbor, json, Path, Traversal, TreeKey};
+
+#[path = "../examples/common.rs"]
+mod common;
+use common::Settings;
+
+#[test]
+fn round_trip() {
+    let mut source = Settings::default();
+    source.enable();
+    json::set(&mut source, "/foo", b"true").unwrap();
+    json::set(
+        &mut source,
+        "/enum_",
+        br#"{"C": [{"a": 1, "b": 2}, {"a": 3, "b": 4}]}"#,
+    )
+    .unwrap();
+    json::set(&mut source, "/option", b"-5").unwrap();
+    json::set(&mut source, "/array_tree2/1/b", b"-9").unwrap();
+    json::set(&mut source, "/enum_tree/B/a", b"7").unwrap();
+    json::set(&mut source, "/option_tree2/a", b"1000000").unwrap();
+
+    let mut target = Settings::default();
+    target.enable();
+    let mut buf = [0; 64];
+    for path in Settings::nodes::<Path<String, '/'>, 4>() {
+        let (path, _node) = path.unwrap();
+        match cbor::get(&source, &path, &mut buf) {
+            Ok(len) => {
+                assert_eq!(cbor::set(&mut target, &path, &buf[..len]).unwrap(), len);
+            }
+            Err(miniconf::Error::Traversal(Traversal::Absent(_))) => {}
+            e => panic!("{e:?}"),
+        }
+    }
+
+    let (mut a, mut b) = ([0; 64], [0; 64]);
+    for path in Settings::nodes::<Path<String, '/'>, 4>() {
+        let (path, _node) = path.unwrap();
+        let la = json::get(&source, &path, &mut a);
+        let lb = json::get(&target, &path, &mut b);
+        assert_eq!(
+            la.as_ref().map(|len| &a[..*len]).ok(),
+            lb.as_ref().map(|len| &b[..*len]).ok(),
+            "{}",
+            path.as_str()
+        );
+    }
+}
+
+#[test]
+fn errors() {
+    let s = Settings::default();
+    assert!(matches!(
+        cbor::get(&s, "/array_tree2/1/a", &mut [0; 0]),
+        Err(miniconf::Error::Inner(_, _))
+    ));
+    assert!(matches!(
+        cbor::get(&s, "/bar", &mut [0; 8]),
+        Err(miniconf::Error::Traversal(Traversal::NotFound(1)))
+    ));
+    let mut s = Settings::default();
+    // Text string instead of a bool
+    assert!(matches!(
+        cbor::set(&mut s, "/foo", &[0x61, b'a']),
+        Err(miniconf::Error::Inner(_, _))
+    ));
+    // Trailing data is left alone
+    assert_eq!(cbor::set(&mut s, "/foo", &[0xf5, 0x00]).unwrap(), 1);
+}
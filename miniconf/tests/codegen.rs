@@ -0,0 +1,170 @@
+use miniconf::{codegen, Leaf, Tree};
+
+#[derive(Tree, Default)]
+struct Inner {
+    val: Leaf<f32>,
+    #[tree(rename = "maxVal")]
+    max: Leaf<f32>,
+}
+
+#[derive(Tree, Default)]
+struct Settings {
+    i: [Inner; 2],
+    mode: Leaf<u8>,
+    pair: (Leaf<u8>, Option<Inner>),
+}
+
+#[test]
+fn c_header() {
+    let header = codegen::c_header::<Settings>("SETTINGS");
+    let defines: Vec<_> = header
+        .lines()
+        .filter(|line| line.starts_with("#define "))
+        .collect();
+    assert_eq!(
+        defines,
+        [
+            r#"#define SETTINGS_I_0_VAL "/i/0/val""#,
+            r#"#define SETTINGS_I_0_MAXVAL "/i/0/maxVal""#,
+            r#"#define SETTINGS_I_1_VAL "/i/1/val""#,
+            r#"#define SETTINGS_I_1_MAXVAL "/i/1/maxVal""#,
+            r#"#define SETTINGS_MODE "/mode""#,
+            r#"#define SETTINGS_PAIR_0 "/pair/0""#,
+            r#"#define SETTINGS_PAIR_1_VAL "/pair/1/val""#,
+            r#"#define SETTINGS_PAIR_1_MAXVAL "/pair/1/maxVal""#,
+        ]
+    );
+    assert!(header.contains("#pragma once\n"));
+    assert_eq!(
+        codegen::c_header::<Settings>("APP_"),
+        header.replace("SETTINGS_", "APP_")
+    );
+}
+
+#[test]
+fn dot() {
+    let dot = codegen::dot::<Settings>();
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.ends_with("}\n"));
+    for label in [
+        r#"n0 [label="/\nnamed"];"#,
+        r#"n1 [label="i\nhomogeneous"];"#,
+        r#"n2 [label="0..2\nnamed"];"#,
+        r#"n3 [label="val\nleaf"];"#,
+        r#"n4 [label="maxVal\nleaf"];"#,
+        r#"n5 [label="mode\nleaf"];"#,
+        r#"n6 [label="pair\nnumbered"];"#,
+        r#"n7 [label="0\nleaf"];"#,
+        r#"n8 [label="1\nnamed"];"#,
+    ] {
+        assert!(dot.contains(label), "{label}");
+    }
+    let edges: Vec<_> = dot.lines().filter(|line| line.contains("->")).collect();
+    assert_eq!(edges.len(), 10);
+    assert!(edges.contains(&"    n6 -> n8;"));
+}
+
+#[cfg(feature = "json-core")]
+#[test]
+fn typescript() {
+    #[derive(Tree, Default)]
+    struct Nested {
+        name: Leaf<String>,
+        enable: Leaf<bool>,
+        gains: Leaf<[f32; 2]>,
+        i: [Inner; 2],
+        opt: Option<Leaf<i32>>,
+        pair: (Leaf<u8>, Inner),
+    }
+
+    let ts = codegen::typescript::<Nested>("Nested");
+    assert_eq!(
+        ts,
+        "/* Generated by miniconf. Do not edit. */
+
+export interface Nested {
+  name: string;
+  enable: boolean;
+  gains: number[];
+  i: {
+    val: number;
+    maxVal: number;
+  }[];
+  opt?: unknown;
+  pair: [number, {
+    val: number;
+    maxVal: number;
+  }];
+}
+"
+    );
+    assert!(codegen::typescript::<[Leaf<u8>; 2]>("A").ends_with("export type A = number[];\n"));
+}
+
+#[cfg(feature = "json-core")]
+#[test]
+fn python() {
+    #[derive(Tree, Default)]
+    struct Nested {
+        name: Leaf<String>,
+        enable: Leaf<bool>,
+        gains: Leaf<[f32; 2]>,
+        i: [Inner; 2],
+        opt: Option<Leaf<i32>>,
+        pair: (Leaf<u8>, Inner),
+    }
+
+    let py = codegen::python::<Nested>("Nested");
+    assert_eq!(
+        py,
+        "# Generated by miniconf. Do not edit.
+
+from dataclasses import dataclass
+from typing import Any, Optional
+
+
+@dataclass
+class NestedI:
+    val: float
+    maxVal: float
+
+
+@dataclass
+class NestedPair1:
+    val: float
+    maxVal: float
+
+
+@dataclass
+class Nested:
+    name: str
+    enable: bool
+    gains: list[float]
+    i: list[NestedI]
+    opt: Optional[Any]
+    pair: tuple[int, NestedPair1]
+"
+    );
+    assert!(codegen::python::<[Leaf<u8>; 2]>("A").ends_with("\n\nA = list[int]\n"));
+}
+
+#[cfg(feature = "json-core")]
+#[test]
+fn python_keywords() {
+    #[derive(Tree, Default)]
+    struct Keywords {
+        class: Leaf<u8>,
+        from: Leaf<bool>,
+        lambda_: Leaf<f32>,
+    }
+
+    let py = codegen::python::<Keywords>("Keywords");
+    assert!(py.ends_with(
+        "@dataclass
+class Keywords:
+    class_: int
+    from_: bool
+    lambda_: float
+"
+    ));
+}
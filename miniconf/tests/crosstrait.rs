@@ -0,0 +1,42 @@
+use core::fmt::Debug;
+use core::ops::AddAssign;
+
+use crosstrait::register;
+use miniconf::{Leaf, Path, Traversal, Tree, TreeAny};
+
+register! { i32 => dyn AddAssign<i32> }
+register! { i32 => dyn Debug }
+
+#[derive(Default, Tree)]
+struct Inner {
+    val: Leaf<i32>,
+}
+
+#[derive(Default, Tree)]
+struct Settings {
+    a: Leaf<u32>,
+    i: [Inner; 2],
+}
+
+#[test]
+fn cast() {
+    let mut s = Settings::default();
+    let key = Path::<_, '/'>::from("/i/1/val");
+
+    let val: &mut dyn AddAssign<i32> = s.cast_mut_by_key(&key).unwrap();
+    *val += 5;
+    assert_eq!(*s.i[1].val, 5);
+
+    let val: &dyn Debug = s.cast_ref_by_key(&key).unwrap();
+    assert_eq!(format!("{val:?}"), "5");
+
+    // Not registered for `u32`
+    assert_eq!(
+        s.cast_ref_by_key::<dyn Debug, _>(&Path::<_, '/'>::from("/a"))
+            .err(),
+        Some(Traversal::Invalid(0, "Cast failed"))
+    );
+    assert!(s
+        .cast_ref_by_key::<dyn Debug, _>(&Path::<_, '/'>::from("/i"))
+        .is_err());
+}
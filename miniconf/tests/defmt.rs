@@ -0,0 +1,21 @@
+use miniconf::{Error, Metadata, Traversal};
+
+fn format<T: defmt::Format>() {}
+
+fn format_value<T: defmt::Format>(_value: T) {}
+
+#[test]
+fn implemented() {
+    format::<Traversal>();
+    format::<Error<u8>>();
+    format::<Metadata>();
+}
+
+#[test]
+fn value_errors() {
+    // `Traversal` also carries the leaf value errors (`ValueError` elsewhere)
+    format_value(Traversal::Absent(1));
+    format_value(Traversal::Access(1, "read-only"));
+    format_value(Traversal::Invalid(1, "out of range"));
+    format_value(Error::<u8>::Traversal(Traversal::Access(0, "write-only")));
+}
@@ -0,0 +1,51 @@
+use either::Either;
+use miniconf::{json, Leaf, Path, Traversal, Tree, TreeAny, TreeKey};
+
+mod common;
+use common::*;
+
+#[derive(Tree, Default)]
+struct Inner {
+    a: Leaf<u8>,
+}
+
+#[derive(Tree)]
+struct Settings {
+    e: Either<Leaf<i32>, Inner>,
+}
+
+#[test]
+fn either() {
+    assert_eq!(paths::<Settings, 3>(), ["/e/Left", "/e/Right/a"]);
+
+    let mut s = Settings {
+        e: Either::Left(3.into()),
+    };
+    set_get(&mut s, "/e/Left", b"-5");
+    assert_eq!(s.e.as_ref().left().map(|v| **v), Some(-5));
+    assert_eq!(s.ref_by_key::<i32, _>(["e", "Left"]), Ok(&-5));
+    assert_eq!(
+        json::set(&mut s, "/e/Right/a", b"1"),
+        Err(Traversal::Absent(2).into())
+    );
+
+    s.e = Either::Right(Inner::default());
+    set_get(&mut s, "/e/Right/a", b"7");
+    assert_eq!(s.e.as_ref().right().map(|v| *v.a), Some(7));
+    let mut buf = [0; 8];
+    assert_eq!(
+        json::get(&s, "/e/Left", &mut buf),
+        Err(Traversal::Absent(2).into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/e/Middle", b"1"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(
+        Settings::transcode::<Path<String, '/'>, _>(["e", "Right", "a"])
+            .unwrap()
+            .0
+            .as_str(),
+        "/e/Right/a"
+    );
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use miniconf::{
+    json, Leaf, MapElements, Packed, Path, StringChars, Traversal, Tree, TreeAny, TreeKey,
+    VecElements,
+};
+
+mod common;
+use common::*;
+
+#[derive(Tree, Default)]
+struct S {
+    vec: VecElements<Leaf<i32>, 16>,
+    whole: Leaf<Vec<i32>>,
+    name: StringChars<4>,
+}
+
+#[test]
+fn vec() {
+    let mut s = S::default();
+    s.vec.extend([1, 2, 3].map(Leaf));
+    set_get(&mut s, "/vec/2", b"9");
+    assert_eq!(*s.vec[2], 9);
+    assert_eq!(s.vec.len(), 3);
+    assert_eq!(
+        json::set(&mut s, "/vec/3", b"9"),
+        Err(Traversal::Absent(2).into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/vec/99", b"9"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/vec", b"[9]"),
+        Err(Traversal::TooShort(1).into())
+    );
+    assert_eq!(s.ref_by_key::<i32, _>(["vec", "1"]), Ok(&2));
+    assert!(S::transcode::<(), _>(["vec"]).unwrap().1.is_collection());
+    assert!(!S::transcode::<(), _>(["whole"]).unwrap().1.is_collection());
+
+    // Atomic leaf default
+    set_get(&mut s, "/whole", b"[1,2]");
+    assert_eq!(*s.whole, [1, 2]);
+}
+
+#[test]
+fn string() {
+    let mut s = S::default();
+    s.name.push_str("fµo");
+    set_get(&mut s, "/name/1", "\"ä\"".as_bytes());
+    set_get(&mut s, "/name/2", b"\"x\"");
+    assert_eq!(s.name.as_str(), "fäx");
+    assert_eq!(
+        json::set(&mut s, "/name/0", b"\"ab\""),
+        Err(Traversal::Invalid(2, "Not a single character").into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/name/3", b"\"a\""),
+        Err(Traversal::Absent(2).into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/name/4", b"\"a\""),
+        Err(Traversal::NotFound(2).into())
+    );
+}
+
+#[test]
+fn map() {
+    #[derive(Tree, Default)]
+    struct M {
+        channels: MapElements<BTreeMap<String, Inner>, 4>,
+    }
+    #[derive(Tree, Default)]
+    struct Inner {
+        gain: Leaf<i32>,
+    }
+
+    let mut s = M::default();
+    s.channels.insert("x".into(), Inner::default());
+    s.channels.insert("a".into(), Inner::default());
+    set_get(&mut s, "/channels/x/gain", b"9");
+    assert_eq!(*s.channels["x"].gain, 9);
+    assert_eq!(*s.channels["a"].gain, 0);
+    assert_eq!(
+        json::set(&mut s, "/channels/b/gain", b"9"),
+        Err(Traversal::Absent(2).into())
+    );
+    assert_eq!(s.ref_by_key::<i32, _>(["channels", "a", "gain"]), Ok(&0));
+
+    // Index by position in key order
+    let idx = s.channels.position("x").unwrap();
+    assert_eq!(idx, 1);
+    set_get(&mut s, &format!("/channels/{idx}/gain"), b"8");
+    assert_eq!(*s.channels["x"].gain, 8);
+    assert_eq!(s.channels.position("b"), None);
+    assert_eq!(
+        json::set(&mut s, "/channels/2/gain", b"9"),
+        Err(Traversal::Absent(2).into())
+    );
+    assert_eq!(
+        s.ref_by_key::<i32, _>([0usize, 4, 0]),
+        Err(Traversal::NotFound(2))
+    );
+
+    // A map key takes precedence over an index
+    s.channels.insert("0".into(), Inner::default());
+    set_get(&mut s, "/channels/0/gain", b"5");
+    assert_eq!(*s.channels["0"].gain, 5);
+    assert_eq!(*s.channels["a"].gain, 0);
+    assert!(M::transcode::<(), _>(["channels"])
+        .unwrap()
+        .1
+        .is_collection());
+    assert!(!M::transcode::<(), _>(["channels", "0"])
+        .unwrap()
+        .1
+        .is_collection());
+
+    // Present nodes follow the current entries in key order
+    let mut buf = [0; 8];
+    let present: Vec<_> = M::nodes::<Path<String, '/'>, 3>()
+        .map(|p| p.unwrap().0)
+        .filter(|p| json::get(&s, p, &mut buf).is_ok())
+        .map(|p| p.into_inner())
+        .collect();
+    assert_eq!(
+        present,
+        ["/channels/0/gain", "/channels/1/gain", "/channels/2/gain"]
+    );
+
+    #[cfg(feature = "std")]
+    {
+        let mut h = MapElements::<std::collections::HashMap<String, Leaf<i32>>, 4>::default();
+        h.insert("foo".into(), 1.into());
+        assert_eq!(h.ref_by_key::<i32, _>(["foo"]), Ok(&1));
+        assert_eq!(h.ref_by_key::<i32, _>(["bar"]), Err(Traversal::Absent(1)));
+    }
+}
+
+#[test]
+fn iter() {
+    assert_eq!(paths::<S, 2>().len(), 16 + 1 + 4);
+    let mut s = S::default();
+    s.vec.push(Leaf(5));
+    s.name.push('a');
+    let mut buf = [0; 64];
+    let present = S::nodes::<Packed, 2>()
+        .filter(|p| json::get_by_key(&s, p.unwrap().0, &mut buf).is_ok())
+        .count();
+    assert_eq!(present, 3);
+}
+
+#[test]
+fn borrow_owned() {
+    #[derive(Tree, Default)]
+    struct S {
+        s: Leaf<String>,
+        b: Leaf<Vec<u8>>,
+    }
+    let mut s = S::default();
+    json::set(&mut s, "/s", b"\"foo\"").unwrap();
+    json::set(&mut s, "/b", b"[1,2]").unwrap();
+    assert_eq!(s.get_str(["s"]), Ok("foo"));
+    assert_eq!(s.get_bytes(["s"]), Ok(&b"foo"[..]));
+    assert_eq!(s.get_bytes(["b"]), Ok(&[1, 2][..]));
+}
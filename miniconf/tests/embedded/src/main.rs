@@ -62,6 +62,10 @@ fn main() -> ! {
     *val += 5;
     assert_eq!(*s.i[1].val, 3 + 5);
 
+    let val: &mut dyn AddAssign<i32> = s.cast_mut_by_key(&key).unwrap();
+    *val += 2;
+    assert_eq!(*s.i[1].val, 3 + 5 + 2);
+
     hprintln!("success!");
 
     // exit QEMU
@@ -1,4 +1,4 @@
-use miniconf::{json, Leaf, StrLeaf, Tree};
+use miniconf::{json, Indices, Leaf, Path, StrLeaf, Tree, TreeKey};
 
 mod common;
 use common::*;
@@ -82,3 +82,153 @@ fn option() {
         [""]
     );
 }
+
+#[test]
+fn variants() {
+    #[allow(dead_code)]
+    #[derive(Tree)]
+    #[tree(variant_names)]
+    enum E {
+        None,
+        #[tree(rename = "foo")]
+        A(Leaf<i32>),
+        B(Inner),
+        #[tree(skip)]
+        C(i32),
+    }
+    assert_eq!(E::VARIANTS, ["None", "foo", "B"]);
+    assert_eq!(paths::<E, 2>(), ["/foo", "/B/a"]);
+}
+
+#[test]
+fn tag() {
+    #[derive(Default, PartialEq, Debug)]
+    struct NoTree;
+
+    #[derive(Tree, Default, PartialEq, Debug)]
+    #[tree(tag)]
+    enum Tagged {
+        #[default]
+        None,
+        #[tree(rename = "foo")]
+        A(Leaf<i32>),
+        B(Inner, #[tree(skip)] u8),
+        #[tree(skip)]
+        C(NoTree),
+    }
+
+    #[derive(Tree, Default, Debug)]
+    struct S {
+        e: Tagged,
+        o: Option<Tagged>,
+    }
+
+    let mut s = S::default();
+    set_get(&mut s, "/e", br#""foo""#);
+    assert_eq!(s.e, Tagged::A(0.into()));
+    set_get(&mut s, "/e/foo", b"3");
+    // Selecting the active variant keeps the value
+    set_get(&mut s, "/e", br#""foo""#);
+    assert_eq!(s.e, Tagged::A(3.into()));
+    set_get(&mut s, "/e", br#""B""#);
+    assert_eq!(s.e, Tagged::B(Inner::default(), 0));
+    // Transient (unescaped) names
+    #[cfg(feature = "serde_json")]
+    {
+        use miniconf::{IntoKeys, TreeDeserialize};
+        let mut de = serde_json::Deserializer::from_slice(br#""\u0066oo""#);
+        s.deserialize_by_key(["e"].into_keys(), &mut de).unwrap();
+        assert_eq!(s.e, Tagged::A(0.into()));
+        set_get(&mut s, "/e", br#""B""#);
+    }
+    set_get(&mut s, "/e/B/a", b"7");
+    set_get(&mut s, "/e", br#""None""#);
+    assert_eq!(s.e, Tagged::None);
+    assert_eq!(
+        json::set(&mut s, "/e", br#""C""#),
+        Err(miniconf::Traversal::Invalid(1, "Unknown variant").into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/e", b"1"),
+        Err(miniconf::Error::Inner(
+            1,
+            serde_json_core::de::Error::InvalidType
+        ))
+    );
+
+    let mut buf = [0; 8];
+    s.e = Tagged::C(NoTree);
+    assert_eq!(
+        json::get(&s, "/e", &mut buf),
+        Err(miniconf::Traversal::Absent(1).into())
+    );
+    assert_eq!(
+        json::get(&s, "/o", &mut buf),
+        Err(miniconf::Traversal::Absent(1).into())
+    );
+    s.o = Some(Tagged::None);
+    set_get(&mut s, "/o", br#""B""#);
+    assert_eq!(s.o, Some(Tagged::B(Inner::default(), 0)));
+
+    // The tag is not a node
+    assert_eq!(paths::<S, 3>(), ["/e/foo", "/e/B/a", "/o/foo", "/o/B/a"]);
+}
+
+#[test]
+fn variant_by_index() {
+    #[derive(Default, PartialEq, Debug)]
+    struct NoTree;
+
+    #[derive(Tree, Default, PartialEq, Debug)]
+    #[tree(variant_by = "index")]
+    enum Indexed {
+        #[default]
+        None,
+        A(Leaf<i32>),
+        #[tree(skip)]
+        C(NoTree),
+        B(Inner),
+    }
+
+    #[derive(Tree, Default, Debug)]
+    struct S {
+        e: Indexed,
+    }
+
+    let mut s = S::default();
+    let mut buf = [0; 8];
+    assert_eq!(
+        json::get(&s, "/e/0", &mut buf),
+        Err(miniconf::Traversal::Absent(2).into())
+    );
+    s.e = Indexed::A(1.into());
+    set_get(&mut s, "/e/0", b"3");
+    assert_eq!(s.e, Indexed::A(3.into()));
+    assert_eq!(
+        json::set(&mut s, "/e/1/a", b"5"),
+        Err(miniconf::Traversal::Absent(2).into())
+    );
+    s.e = Indexed::B(Inner::default());
+    set_get(&mut s, "/e/1/a", b"5");
+    // Path and integer keys agree
+    json::set_by_key(&mut s, [0usize, 1, 0], b"7").unwrap();
+    assert_eq!(s.e, Indexed::B(Inner { a: 7.into() }));
+    let (indices, _node) =
+        S::transcode::<Indices<[usize; 3]>, _>(Path::<_, '/'>::from("/e/1/a")).unwrap();
+    assert_eq!(*indices, [0, 1, 0]);
+    for path in ["/e/2", "/e/B/a"] {
+        assert_eq!(
+            json::set(&mut s, path, b"5"),
+            Err(miniconf::Traversal::NotFound(2).into()),
+            "{path}"
+        );
+    }
+
+    s.e = Indexed::C(NoTree);
+    assert_eq!(
+        json::set(&mut s, "/e/1/a", b"5"),
+        Err(miniconf::Traversal::Absent(2).into())
+    );
+
+    assert_eq!(paths::<S, 3>(), ["/e/0", "/e/1/a"]);
+}
@@ -0,0 +1,91 @@
+use miniconf::{
+    env::{self, EnvKeys},
+    Error, Leaf, Path, Traversal, Tree, TreeKey,
+};
+
+#[derive(Tree, Default)]
+struct Inner {
+    val: Leaf<i32>,
+}
+
+#[derive(Tree, Default)]
+struct Settings {
+    i: [Inner; 2],
+    gain_db: Leaf<f32>,
+    gain: Leaf<u8>,
+    name: Leaf<String>,
+    flag: Option<Leaf<bool>>,
+}
+
+#[test]
+fn keys() {
+    let (path, _node) =
+        Settings::transcode::<Path<String, '/'>, _>(EnvKeys::new("I_1_VAL")).unwrap();
+    assert_eq!(path.as_str(), "/i/1/val");
+    let (path, _node) =
+        Settings::transcode::<Path<String, '/'>, _>(EnvKeys::new("gain_DB")).unwrap();
+    assert_eq!(path.as_str(), "/gain_db");
+    let (path, _node) = Settings::transcode::<Path<String, '/'>, _>(EnvKeys::new("GAIN")).unwrap();
+    assert_eq!(path.as_str(), "/gain");
+    assert_eq!(
+        Settings::transcode::<Path<String, '/'>, _>(EnvKeys::new("I_2_VAL")),
+        Err(Traversal::NotFound(2))
+    );
+    let (path, node) = Settings::transcode::<Path<String, '/'>, _>(EnvKeys::new("I_1")).unwrap();
+    assert_eq!(path.as_str(), "/i/1");
+    assert!(!node.is_leaf());
+    let mut s = Settings::default();
+    assert_eq!(
+        env::apply_vars(&mut s, "", [("I_1", "3")]),
+        Err(Error::Traversal(Traversal::TooShort(2)))
+    );
+}
+
+#[test]
+fn apply() {
+    let mut s = Settings {
+        flag: Some(false.into()),
+        ..Default::default()
+    };
+    let vars = [
+        ("SETTINGS_I_1_VAL", "3"),
+        ("SETTINGS_GAIN_DB", "-1.5"),
+        ("SETTINGS_GAIN", "7"),
+        ("SETTINGS_NAME", "dev a"),
+        ("SETTINGS_FLAG", "true"),
+        ("PATH", "/usr/bin"),
+    ];
+    assert_eq!(env::apply_vars(&mut s, "SETTINGS_", vars), Ok(5));
+    assert_eq!(*s.i[1].val, 3);
+    assert_eq!(*s.gain_db, -1.5);
+    assert_eq!(*s.gain, 7);
+    assert_eq!(s.name.as_str(), "dev a");
+    assert_eq!(s.flag, Some(true.into()));
+
+    s.flag = None;
+    assert_eq!(
+        env::apply_vars(&mut s, "SETTINGS_", [("SETTINGS_FLAG", "false")]),
+        Err(Error::Traversal(Traversal::Absent(1)))
+    );
+    assert!(env::apply_vars(&mut s, "SETTINGS_", [("SETTINGS_GAIN", "x")]).is_err());
+}
+
+#[test]
+fn process_env() {
+    std::env::set_var("MINICONF_TEST_ENV_I_0_VAL", "-4");
+    let mut s = Settings::default();
+    assert_eq!(env::apply_prefix(&mut s, "MINICONF_TEST_ENV_"), Ok(1));
+    assert_eq!(*s.i[0].val, -4);
+}
+
+#[cfg(unix)]
+#[test]
+fn process_env_non_unicode() {
+    use std::os::unix::ffi::OsStrExt;
+    let key = std::ffi::OsStr::from_bytes(b"MINICONF_TEST_OS_\xff");
+    std::env::set_var(key, "1");
+    std::env::set_var("MINICONF_TEST_OS_GAIN", "5");
+    let mut s = Settings::default();
+    assert_eq!(env::apply_prefix(&mut s, "MINICONF_TEST_OS_"), Ok(1));
+    assert_eq!(*s.gain, 5);
+}
@@ -71,3 +71,20 @@ fn enum_flatten() {
         miniconf::Traversal::Absent(0).into()
     );
 }
+
+#[test]
+fn enum_flatten_single_variant() {
+    #[derive(Tree, PartialEq, Debug)]
+    #[tree(flatten)]
+    enum Config {
+        V1(Inner),
+    }
+    assert_eq!(paths::<Config, 1>(), ["/a"]);
+    let mut c = Config::V1(Inner::default());
+    set_get(&mut c, "/a", b"1");
+    assert_eq!(c, Config::V1(Inner { a: Leaf(1) }));
+    assert_eq!(
+        json::set(&mut c, "/V1/a", b"1").unwrap_err(),
+        miniconf::Traversal::NotFound(1).into()
+    );
+}
@@ -1,4 +1,4 @@
-use miniconf::{json, Deserialize, Leaf, Metadata, Serialize, Tree, TreeKey};
+use miniconf::{json, Deserialize, Leaf, Metadata, Path, Serialize, Tree, TreeKey};
 
 #[test]
 fn generic_type() {
@@ -95,3 +95,34 @@ fn test_depth() {
     // This does not compile as u32 does not implement TreeKey
     // S::<u32>::traverse_all::<Metadata>();
 }
+
+#[test]
+fn const_generic() {
+    #[derive(Tree)]
+    struct Buffer<const N: usize> {
+        data: [Leaf<f32>; N],
+    }
+
+    #[derive(Tree)]
+    struct Settings<T, const N: usize = 2> {
+        #[tree(index_names=["a", "b"])]
+        buf: [Buffer<N>; 2],
+        tail: T,
+    }
+
+    let mut buffer = Buffer::<3> {
+        data: Default::default(),
+    };
+    json::set(&mut buffer, "/data/2", b"3.0").unwrap();
+    assert_eq!(*buffer.data[2], 3.0);
+    let paths: Vec<_> = Buffer::<3>::nodes::<Path<String, '/'>, 2>()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(paths, ["/data/0", "/data/1", "/data/2"]);
+
+    let metadata = Settings::<Leaf<u8>>::traverse_all::<Metadata>().unwrap();
+    assert_eq!(metadata.max_depth, 4);
+    assert_eq!(metadata.count.get(), 5);
+    let metadata = Settings::<Leaf<u8>, 4>::traverse_all::<Metadata>().unwrap();
+    assert_eq!(metadata.count.get(), 9);
+}
@@ -0,0 +1,67 @@
+use miniconf::{ini, Leaf, Traversal, Tree};
+
+#[derive(Tree, Default)]
+struct Inner {
+    val: Leaf<i32>,
+}
+
+#[derive(Tree, Default)]
+struct Settings {
+    i: [Inner; 2],
+    gain: Leaf<f32>,
+    name: Leaf<String>,
+    flag: Option<Leaf<bool>>,
+}
+
+#[test]
+fn set_all() {
+    let mut s = Settings {
+        flag: Some(false.into()),
+        ..Default::default()
+    };
+    let text = r#"
+; comment
+# another comment
+gain = 1.5
+name = "dev a"
+/i/0/val = -3
+
+[i.1]
+val = 4
+[]
+flag=true
+"#;
+    assert_eq!(ini::set_all(&mut s, text), Ok(5));
+    assert_eq!(
+        (*s.gain, s.name.as_str(), *s.i[0].val, *s.i[1].val),
+        (1.5, "dev a", -3, 4)
+    );
+    assert_eq!(s.flag, Some(true.into()));
+
+    // bare string
+    assert_eq!(ini::set_all(&mut s, "name = dev b"), Ok(1));
+    assert_eq!(s.name.as_str(), "dev b");
+}
+
+#[test]
+fn reject() {
+    let mut s = Settings::default();
+    assert_eq!(
+        ini::set_all(&mut s, "gain = 2\nunknown = 1\ngain = 3"),
+        Err(Traversal::NotFound(1).into())
+    );
+    // Applied up to the error
+    assert_eq!(*s.gain, 2.0);
+    assert_eq!(
+        ini::set_all(&mut s, "[i]\n2.val = 1"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(
+        ini::set_all(&mut s, "gain"),
+        Err(Traversal::Invalid(0, "Expected `key = value`").into())
+    );
+    assert_eq!(
+        ini::set_all(&mut s, "flag = true"),
+        Err(Traversal::Absent(1).into())
+    );
+}
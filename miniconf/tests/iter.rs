@@ -1,4 +1,9 @@
-use miniconf::{Indices, Leaf, NodeIter, Path, Tree, TreeKey};
+use core::num::NonZero;
+
+use miniconf::{
+    Error, Indices, KeyLookup, Keys, Leaf, NodeIter, NodeKind, Packed, Path, Schema, Tree, TreeKey,
+    Walk,
+};
 
 mod common;
 use common::*;
@@ -113,3 +118,264 @@ fn root() {
         ["/b/0", "/b/1"]
     );
 }
+
+#[test]
+fn resume() {
+    let mut iter = Settings::nodes::<Path<String, '/'>, 3>();
+    let head: Vec<_> = iter
+        .by_ref()
+        .take(3)
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(head, ["/b/0", "/b/1", "/c/inner"]);
+
+    // Persist and restore the cursor
+    let cursor = iter.position().unwrap().into_lsb().get();
+    let cursor = Packed::from_lsb(cursor.try_into().unwrap());
+
+    let tail: Vec<_> = Settings::nodes::<Path<String, '/'>, 3>()
+        .resume(Packed::EMPTY, cursor)
+        .unwrap()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(tail, ["/d/0/inner", "/a"]);
+    let rest: Vec<_> = iter.map(|p| p.unwrap().0.into_inner()).collect();
+    assert_eq!(tail, rest);
+
+    // Sub-tree
+    let (cursor, _node) = Settings::transcode::<Packed, _>(["b", "0"]).unwrap();
+    let tail: Vec<_> = Settings::nodes::<Path<String, '/'>, 3>()
+        .resume(["b"], cursor)
+        .unwrap()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(tail, ["/b/1"]);
+    assert!(Settings::nodes::<Path<String, '/'>, 3>()
+        .resume(["c"], cursor)
+        .is_err());
+}
+
+#[test]
+fn absent_paths() {
+    use core::cell::RefCell;
+    use miniconf::{Traversal, TreeSerialize};
+
+    #[derive(Tree, Default)]
+    struct S {
+        a: Option<Inner>,
+        b: RefCell<Inner>,
+        c: [Option<Leaf<i32>>; 2],
+    }
+    let s = S {
+        c: [Some(1.into()), None],
+        ..Default::default()
+    };
+    let absent = |s: &S| {
+        s.absent_paths::<Path<String, '/'>, 2>()
+            .map(|p| p.map(|(p, reason)| (p.into_inner(), reason)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    };
+    assert_eq!(
+        absent(&s),
+        [
+            ("/a/inner".to_string(), Traversal::Absent(1)),
+            ("/c/1".to_string(), Traversal::Absent(2))
+        ]
+    );
+    let _borrow = s.b.borrow_mut();
+    assert_eq!(
+        absent(&s),
+        [
+            ("/a/inner".to_string(), Traversal::Absent(1)),
+            ("/b/inner".to_string(), Traversal::Access(1, "Borrowed")),
+            ("/c/1".to_string(), Traversal::Absent(2))
+        ]
+    );
+}
+
+#[test]
+fn filter_kind() {
+    #[derive(Tree, Default)]
+    struct S {
+        settings: Settings,
+        e: ([Leaf<u8>; 2], Leaf<i32>),
+        f: [[Leaf<u8>; 2]; 2],
+    }
+    let kind = |kind, root| -> Vec<_> {
+        S::nodes::<Path<String, '/'>, 4>()
+            .root(Path::<_, '/'>::from(root))
+            .unwrap()
+            .filter_kind(kind)
+            .map(|p| p.unwrap().0.into_inner())
+            .collect()
+    };
+    assert_eq!(
+        kind(NodeKind::Homogeneous, ""),
+        ["/settings/b", "/settings/d", "/e/0", "/f", "/f/0", "/f/1"]
+    );
+    assert_eq!(
+        kind(NodeKind::Named, ""),
+        ["/settings", "/settings/c", "/settings/d/0"]
+    );
+    assert_eq!(kind(NodeKind::Numbered, ""), ["/e"]);
+    assert_eq!(kind(NodeKind::Homogeneous, "/f"), ["/f/0", "/f/1"]);
+    assert_eq!(kind(NodeKind::Leaf, "/settings/c"), ["/settings/c/inner"]);
+    assert!(kind(NodeKind::Homogeneous, "/e/1").is_empty());
+
+    // Depth limited
+    let arrays: Vec<_> = S::nodes::<Path<String, '/'>, 2>()
+        .filter_kind(NodeKind::Homogeneous)
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(
+        arrays,
+        ["/settings/b", "/settings/d", "/e/0", "/f", "/f/0", "/f/1"]
+    );
+    let arrays: Vec<_> = S::nodes::<Path<String, '/'>, 1>()
+        .filter_kind(NodeKind::Homogeneous)
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(arrays, ["/f"]);
+}
+
+#[test]
+fn breadth_first() {
+    #[derive(Tree, Default)]
+    struct S {
+        settings: Settings,
+        e: ([Leaf<u8>; 2], Leaf<i32>),
+        f: [[Leaf<u8>; 2]; 2],
+    }
+    let bfs = |root, d: usize| -> Vec<_> {
+        let iter = S::nodes::<Path<String, '/'>, 4>()
+            .root(Path::<_, '/'>::from(root))
+            .unwrap()
+            .breadth_first();
+        let len = iter.len();
+        let paths: Vec<_> = iter
+            .map(|p| p.unwrap())
+            .inspect(|(p, n)| assert!(n.depth() <= d && p.split('/').count() == n.depth() + 1))
+            .map(|p| p.0.into_inner())
+            .collect();
+        assert_eq!(paths.len(), len);
+        paths
+    };
+    assert_eq!(
+        bfs("", 4),
+        [
+            "/settings/a",
+            "/e/1",
+            "/settings/b/0",
+            "/settings/b/1",
+            "/settings/c/inner",
+            "/e/0/0",
+            "/e/0/1",
+            "/f/0/0",
+            "/f/0/1",
+            "/f/1/0",
+            "/f/1/1",
+            "/settings/d/0/inner"
+        ]
+    );
+    assert_eq!(
+        bfs("/settings", 4),
+        [
+            "/settings/a",
+            "/settings/b/0",
+            "/settings/b/1",
+            "/settings/c/inner",
+            "/settings/d/0/inner"
+        ]
+    );
+    assert_eq!(bfs("/e/1", 4), ["/e/1"]);
+
+    // Same nodes as depth-first
+    let mut dfs: Vec<_> = S::nodes::<Path<String, '/'>, 4>()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    dfs.sort();
+    let mut all = bfs("", 4);
+    all.sort();
+    assert_eq!(all, dfs);
+
+    // Depth limited: internal nodes at the limit are returned
+    let paths: Vec<_> = S::nodes::<Path<String, '/'>, 2>()
+        .breadth_first()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/settings/b",
+            "/settings/c",
+            "/settings/d",
+            "/settings/a",
+            "/e/0",
+            "/e/1",
+            "/f/0",
+            "/f/1"
+        ]
+    );
+
+    // Identical for all transcode targets
+    let indices: Vec<_> = S::nodes::<Indices<[usize; 4]>, 4>()
+        .breadth_first()
+        .map(|p| p.unwrap())
+        .collect();
+    let packed: Vec<_> = S::nodes::<Packed, 4>()
+        .breadth_first()
+        .map(|p| p.unwrap())
+        .collect();
+    let paths: Vec<_> = S::nodes::<Path<String, '/'>, 4>()
+        .breadth_first()
+        .map(|p| p.unwrap())
+        .collect();
+    assert_eq!(indices.len(), paths.len());
+    for ((i, p), (k, n)) in indices.iter().zip(&packed).zip(&paths) {
+        assert_eq!(i.1, *n);
+        assert_eq!(p.1, *n);
+        assert_eq!(
+            S::transcode::<Path<String, '/'>, _>(&i.0[..n.depth()])
+                .unwrap()
+                .0,
+            *k
+        );
+        assert_eq!(S::transcode::<Path<String, '/'>, _>(p.0).unwrap().0, *k);
+    }
+}
+
+#[test]
+fn verify_count() {
+    assert_eq!(Settings::nodes::<(), 3>().verify_count(), 5);
+    assert_eq!(Settings::nodes::<Packed, 3>().verify_count(), 5);
+}
+
+/// Claims three children in `traverse_all()` but only has two
+struct Broken;
+
+impl TreeKey for Broken {
+    const SCHEMA: Schema = Schema::Internal(KeyLookup::homogeneous(3), &[Schema::Leaf]);
+
+    fn traverse_all<W: Walk>() -> Result<W, W::Error> {
+        W::internal(&[&W::leaf()], &KeyLookup::homogeneous(3))
+    }
+
+    fn traverse_by_key<K, F, E>(mut keys: K, mut func: F) -> Result<usize, Error<E>>
+    where
+        K: Keys,
+        F: FnMut(usize, Option<&'static str>, NonZero<usize>) -> Result<(), E>,
+    {
+        let k = KeyLookup::homogeneous(2);
+        let index = keys.next(&k)?;
+        func(index, None, k.len()).map_err(|err| Error::Inner(1, err))?;
+        keys.finalize()?;
+        Ok(1)
+    }
+}
+
+#[test]
+#[should_panic(expected = "Node count mismatch")]
+fn verify_count_mismatch() {
+    Broken::nodes::<(), 1>().verify_count();
+}
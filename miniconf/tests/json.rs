@@ -0,0 +1,851 @@
+use miniconf::{
+    json, Deserialize, JsonPointer, Leaf, Node, Serialize, Traversal, Tree, TreeKey, Unit, UnitLeaf,
+};
+
+#[derive(Tree, Default)]
+struct Inner {
+    a: Leaf<i32>,
+    b: Leaf<bool>,
+}
+
+#[derive(Tree)]
+struct Settings {
+    gain: Leaf<f32>,
+    mode: Leaf<u16>,
+    inner: Inner,
+    arr: [Leaf<u8>; 3],
+    opt: Option<Leaf<i32>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            gain: 1.5.into(),
+            mode: 3.into(),
+            inner: Inner::default(),
+            arr: Default::default(),
+            opt: Some(7.into()),
+        }
+    }
+}
+
+#[test]
+fn get_all_skip_defaults() {
+    let mut s = Settings::default();
+    let mut buf = [0; 128];
+
+    let len = json::get_all::<_, 2>(&s, &mut buf, Some(&Default::default())).unwrap();
+    assert_eq!(&buf[..len], b"{}");
+
+    *s.inner.b = true;
+    s.arr[2] = 3.into();
+    s.opt = None;
+    let len = json::get_all::<_, 2>(&s, &mut buf, Some(&Default::default())).unwrap();
+    assert_eq!(&buf[..len], br#"{"/inner/b":true,"/arr/2":3}"#);
+
+    let len = json::get_all::<_, 2>(&s, &mut buf, None).unwrap();
+    assert_eq!(
+        &buf[..len],
+        br#"{"/gain":1.5,"/mode":3,"/inner/a":0,"/inner/b":true,"/arr/0":0,"/arr/1":0,"/arr/2":3}"#
+    );
+
+    assert!(json::get_all::<_, 2>(&s, &mut buf[..20], None).is_err());
+}
+
+#[test]
+fn describe() {
+    #[derive(Tree)]
+    struct S {
+        #[tree(min = 0.0, max = 10.0)]
+        gain: Leaf<f32>,
+        #[tree(deny(serialize = "write-only"), min = -1)]
+        secret: Leaf<i32>,
+        opt: Option<Leaf<i32>>,
+        inner: [Leaf<u8>; 2],
+    }
+    impl Default for S {
+        fn default() -> Self {
+            Self {
+                gain: 2.5.into(),
+                secret: 0.into(),
+                opt: None,
+                inner: Default::default(),
+            }
+        }
+    }
+    let mut s = S::default();
+    let mut buf = [0u8; 64];
+    json::set(&mut s, "/gain", b"7").unwrap();
+    let len = json::describe(&s, "/gain", &mut buf).unwrap();
+    assert_eq!(
+        &buf[..len],
+        br#"{"type":"number","access":"rw","min":0,"max":10,"default":2.5}"#
+    );
+    let len = json::describe(&s, "/secret", &mut buf).unwrap();
+    assert_eq!(&buf[..len], br#"{"type":"number","access":"w","min":-1}"#);
+    let len = json::describe(&s, "/opt", &mut buf).unwrap();
+    assert_eq!(&buf[..len], br#"{"access":"rw"}"#);
+    assert_eq!(
+        json::describe(&s, "/inner", &mut buf),
+        Err(Traversal::TooShort(1).into())
+    );
+    assert!(json::describe(&s, "/gain", &mut buf[..20]).is_err());
+    assert!(json::describe(&s, "/gain", &mut buf[..40]).is_err());
+}
+
+#[test]
+fn set_fill() {
+    #[derive(Tree, Default)]
+    struct S {
+        arr: [Leaf<i32>; 8],
+        nested: [[Leaf<u8>; 2]; 3],
+    }
+    let mut s = S::default();
+    assert_eq!(json::set_fill(&mut s, "/arr", br#"{"fill": 7}"#), Ok(8));
+    assert_eq!(
+        json::set_fill(&mut s, "/arr", br#" { "2..5" : -1 } "#),
+        Ok(3)
+    );
+    assert_eq!(s.arr.map(|v| *v), [7, 7, -1, -1, -1, 7, 7, 7]);
+    assert_eq!(
+        json::set_fill(&mut s, "/arr", br#"{"6..9": 0}"#),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(*s.arr[6], 7);
+    assert_eq!(
+        json::set_fill(&mut s, "/arr", br#"{"3": 0}"#),
+        Err(Traversal::Invalid(0, "Invalid fill directive").into())
+    );
+    // Internal children can not be deserialized
+    assert_eq!(
+        json::set_fill(&mut s, "/nested", br#"{"1..3": [4, 5]}"#),
+        Err(Traversal::TooShort(2).into())
+    );
+    assert_eq!(
+        json::set_fill(&mut s, "/nested/1", br#"{"fill": 1}"#),
+        Ok(2)
+    );
+    assert_eq!(s.nested.map(|v| v.map(|v| *v)), [[0, 0], [1, 1], [0, 0]]);
+    assert!(json::set_fill(&mut s, "/arr/0", br#"{"fill": 1}"#).is_err());
+}
+
+#[test]
+fn get_entry() {
+    let mut s = Settings::default();
+    *s.inner.a = -4;
+    let mut buf = [0; 16];
+    assert_eq!(
+        json::get_entry(&s, "/inner/a", &mut buf),
+        Ok(("/inner/a", &b"-4"[..]))
+    );
+    assert_eq!(
+        json::get_entry(&s, "settings/gain", &mut buf),
+        Ok(("/gain", &b"1.5"[..]))
+    );
+    s.opt = None;
+    assert_eq!(
+        json::get_entry(&s, "/opt", &mut buf),
+        Err(Traversal::Absent(1).into())
+    );
+    assert_eq!(
+        json::get_entry(&s, "/inner", &mut buf),
+        Err(Traversal::TooShort(1).into())
+    );
+}
+
+#[test]
+fn set_by_key_node() {
+    let mut s = Settings::default();
+    assert_eq!(
+        json::set_by_key(&mut s, ["inner", "a"], b"5 "),
+        Ok((2, Node::leaf(2)))
+    );
+    assert_eq!(*s.inner.a, 5);
+    assert_eq!(
+        json::set_by_key(&mut s, ["mode"], b"4"),
+        Ok((1, Node::leaf(1)))
+    );
+    assert_eq!(
+        json::set_by_key(&mut s, ["inner"], b"5"),
+        Err(Traversal::TooShort(1).into())
+    );
+}
+
+#[test]
+fn get_by_key_meta() {
+    let mut s = Settings::default();
+    *s.inner.a = -4;
+    let mut buf = [0; 16];
+    let (len, node) = json::get_by_key_meta(&s, ["inner", "a"], &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"-4");
+    assert_eq!(node, Node::leaf(2));
+    assert!(node.is_leaf());
+    let (len, node) = json::get_by_key_meta(&s, ["arr", "2"], &mut buf).unwrap();
+    assert_eq!((&buf[..len], node.depth()), (&b"0"[..], 2));
+    let (len, node) = json::get_by_key_meta(&s, ["gain"], &mut buf).unwrap();
+    assert_eq!((&buf[..len], node), (&b"1.5"[..], Node::leaf(1)));
+    assert_eq!(
+        json::get_by_key_meta(&s, ["inner"], &mut buf),
+        Err(Traversal::TooShort(1).into())
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn pretty() {
+    let mut s = Settings::default();
+    *s.inner.b = true;
+    s.arr[1] = 4.into();
+    let mut buf = [0; 256];
+    let len = json::get_all_pretty(&s, &mut buf).unwrap();
+    let pretty = core::str::from_utf8(&buf[..len]).unwrap();
+    #[cfg(feature = "preserve_order")]
+    assert!(
+        pretty.starts_with("{\n  \"gain\": 1.5,\n  \"mode\": 3,\n  \"inner\": {\n    \"a\": 0,")
+    );
+    assert!(pretty.contains("\n  \"arr\": [\n    0,\n    4,\n    0\n  ],\n"));
+
+    let mut t = Settings {
+        opt: None,
+        ..Default::default()
+    };
+    // `/opt` is absent
+    assert_eq!(json::set_all(&mut t, &buf[..len]).unwrap(), 7);
+    t.opt = Some(0.into());
+    assert_eq!(json::set_all(&mut t, &buf[..len]).unwrap(), 8);
+    let mut buf2 = [0; 256];
+    let len2 = json::get_all_pretty(&t, &mut buf2).unwrap();
+    assert_eq!(&buf2[..len2], &buf[..len]);
+
+    assert!(matches!(
+        json::set_all(&mut t, br#"{"inner": [1]}"#),
+        Err(miniconf::Error::Traversal(Traversal::Invalid(
+            1,
+            "Expected object"
+        )))
+    ));
+    assert!(matches!(
+        json::set_all(&mut t, br#"{"arr": [1, 2, 3, 4]}"#),
+        Err(miniconf::Error::Traversal(Traversal::NotFound(2)))
+    ));
+    assert!(json::get_all_pretty(&s, &mut buf[..20]).is_err());
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn subtree_value() {
+    use serde_json::json;
+
+    let mut s = Settings::default();
+    s.arr[1] = 4.into();
+    *s.inner.b = true;
+    assert_eq!(
+        json::get_subtree_value(&s, "/arr").unwrap(),
+        json!([0, 4, 0])
+    );
+    assert_eq!(
+        json::get_subtree_value(&s, "/inner").unwrap(),
+        json!({"a": 0, "b": true})
+    );
+    assert_eq!(json::get_subtree_value(&s, "/arr/1").unwrap(), json!(4));
+    assert_eq!(
+        json::get_subtree_value(&s, "").unwrap(),
+        json!({"gain": 1.5, "mode": 3, "inner": {"a": 0, "b": true}, "arr": [0, 4, 0], "opt": 7})
+    );
+    s.opt = None;
+    assert!(matches!(
+        json::get_subtree_value(&s, "/opt"),
+        Err(miniconf::Error::Traversal(Traversal::Absent(1)))
+    ));
+    assert!(matches!(
+        json::get_subtree_value(&s, "/arr/3"),
+        Err(miniconf::Error::Traversal(Traversal::NotFound(2)))
+    ));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn skip_deserialize() {
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(skip_deserialize)]
+        serial: Leaf<u32>,
+        #[tree(skip_deserialize)]
+        inner: Inner,
+        gain: Leaf<f32>,
+    }
+    let mut s = S {
+        serial: 1234.into(),
+        ..Default::default()
+    };
+    let mut buf = [0; 128];
+    let len = json::get_all_pretty(&s, &mut buf).unwrap();
+    assert_eq!(
+        json::set_all(&mut s, br#"{"serial": 1, "inner": {"a": 5}, "gain": 2.0}"#).unwrap(),
+        3
+    );
+    assert_eq!((*s.serial, *s.inner.a, *s.gain), (1234, 0, 2.0));
+    *s.gain = 0.0;
+    assert_eq!(json::set_all(&mut s, &buf[..len]).unwrap(), 4);
+    assert_eq!(*s.serial, 1234);
+    assert_eq!(json::set(&mut s, "/serial", b"5"), Ok(1));
+    assert_eq!(json::set(&mut s, "/inner/b", b"true"), Ok(4));
+    assert!(!*s.inner.b);
+    assert_eq!(
+        json::set(&mut s, "/inner/c", b"5"),
+        Err(Traversal::NotFound(2).into())
+    );
+    let len = json::get(&s, "/serial", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"1234");
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn apply_ndjson() {
+    let mut s = Settings::default();
+    let stream = br#"{"path": "/gain", "value": 2.5}
+{"path": "/inner/a", "value": -4}
+
+{"path": "/arr/1", "value": 7}
+{"path": "/mode", "value": "fast"}
+{"path": "/inner/b", "value": true}
+"#;
+    let (line, err) = json::apply_ndjson(&mut s, &stream[..]).unwrap_err();
+    assert_eq!(line, 4);
+    assert!(matches!(err, miniconf::Error::Inner(1, _)));
+    // Lines up to the failing one are applied
+    assert_eq!((*s.gain, *s.inner.a, *s.arr[1]), (2.5, -4, 7));
+    assert!(!*s.inner.b);
+
+    let stream = b"{\"path\": \"/inner/b\", \"value\": true}\n{\"path\": \"/inner/b\"}\nnot json\n";
+    let (line, err) = json::apply_ndjson(&mut s, &stream[..]).unwrap_err();
+    assert_eq!(line, 1);
+    assert!(matches!(
+        err,
+        miniconf::Error::Traversal(Traversal::Invalid(0, _))
+    ));
+    assert!(*s.inner.b);
+    assert_eq!(
+        json::apply_ndjson(&mut s, &b"{\"path\": \"/opt\", \"value\": 3}"[..]).unwrap(),
+        1
+    );
+    assert_eq!(s.opt.as_deref(), Some(&3));
+}
+
+#[test]
+fn pointer() {
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(index_names=["a/b", "m~n", "c"])]
+        foo: [Leaf<u32>; 3],
+        bar: [Inner; 2],
+    }
+    let mut s = S::default();
+    assert_eq!(json::set_pointer(&mut s, "/foo/a~1b", b"1"), Ok(1));
+    assert_eq!(json::set_pointer(&mut s, "/foo/m~0n", b"2"), Ok(1));
+    assert_eq!(json::set_pointer(&mut s, "/foo/2", b"3"), Ok(1));
+    assert_eq!(json::set_pointer(&mut s, "/bar/1/a", b"4"), Ok(1));
+    assert_eq!(s.foo.map(|v| *v), [1, 2, 3]);
+    assert_eq!(*s.bar[1].a, 4);
+    let mut buf = [0; 8];
+    let len = json::get_pointer(&s, "/foo/m~0n", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"2");
+    for invalid in ["/foo/a/b", "/foo/a~2b", "/foo/m~n", "/foo/a~1b~"] {
+        assert_eq!(
+            json::set_pointer(&mut s, invalid, b"0"),
+            Err(Traversal::NotFound(2).into())
+        );
+    }
+    assert_eq!(
+        json::get_pointer(&s, "/bar", &mut buf),
+        Err(Traversal::TooShort(1).into())
+    );
+    // No index fallback for struct fields
+    assert_eq!(
+        json::set_pointer(&mut s, "/1/0/a", b"0"),
+        Err(Traversal::NotFound(1).into())
+    );
+
+    let paths: Vec<_> = S::nodes::<JsonPointer<String>, 3>()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/foo/a~1b",
+            "/foo/m~0n",
+            "/foo/c",
+            "/bar/0/a",
+            "/bar/0/b",
+            "/bar/1/a",
+            "/bar/1/b"
+        ]
+    );
+}
+
+#[test]
+fn canonical() {
+    let mut s = Settings::default();
+    *s.inner.a = -3;
+    let (mut a, mut b) = ([0; 128], [0; 128]);
+    let len = json::get_all_canonical::<_, 2>(&s, &mut a).unwrap();
+    assert_eq!(json::get_all_canonical::<_, 2>(&s, &mut b), Ok(len));
+    assert_eq!(a[..len], b[..len]);
+    assert_eq!(
+        &a[..len],
+        br#"{"/gain":1.5,"/mode":3,"/inner/a":-3,"/inner/b":false,"/arr/0":0,"/arr/1":0,"/arr/2":0,"/opt":7}"#
+    );
+    let hash = json::hash_canonical::<_, 2>(&s, &mut b).unwrap();
+    assert_eq!(json::hash_canonical::<_, 2>(&s, &mut b), Ok(hash));
+    *s.inner.a = 3;
+    assert_ne!(json::hash_canonical::<_, 2>(&s, &mut b), Ok(hash));
+    assert!(json::get_all_canonical::<_, 2>(&s, &mut a[..20]).is_err());
+}
+
+#[test]
+fn checksum_by_key() {
+    let mut s = Settings::default();
+    let mut buf = [0; 16];
+    let inner = |s: &Settings, buf: &mut [u8]| json::checksum_by_key::<_, _, 2>(s, ["inner"], buf);
+    let all = |s: &Settings, buf: &mut [u8]| {
+        json::checksum_by_key::<_, _, 2>(s, miniconf::Packed::EMPTY, buf)
+    };
+    let (i0, a0) = (inner(&s, &mut buf).unwrap(), all(&s, &mut buf).unwrap());
+    assert_ne!(i0, a0);
+    // Stable
+    assert_eq!(inner(&s, &mut buf), Ok(i0));
+    // Changes outside the root
+    *s.gain = 2.0;
+    assert_eq!(inner(&s, &mut buf), Ok(i0));
+    let a1 = all(&s, &mut buf).unwrap();
+    assert_ne!(a1, a0);
+    // Changes of each leaf below the root
+    *s.inner.a = 1;
+    let i1 = inner(&s, &mut buf).unwrap();
+    assert_ne!(i1, i0);
+    *s.inner.a = 0;
+    assert_eq!(inner(&s, &mut buf), Ok(i0));
+    *s.inner.b = true;
+    assert_ne!(inner(&s, &mut buf), Ok(i0));
+    assert_ne!(inner(&s, &mut buf), Ok(i1));
+    // Presence
+    s.opt = None;
+    assert_ne!(all(&s, &mut buf), Ok(a1));
+    // Unknown root
+    assert_eq!(
+        json::checksum_by_key::<_, _, 2>(&s, ["inner", "c"], &mut buf),
+        Err(Traversal::NotFound(2).into())
+    );
+}
+
+#[test]
+fn for_each_entry() {
+    let mut s = Settings::default();
+    *s.inner.a = -3;
+    s.opt = None;
+    let mut buf = [0; 32];
+    let mut entries = vec![];
+    let count = json::for_each_entry::<_, 2>(&s, &mut buf, |path, value| {
+        entries.push((path.to_string(), String::from_utf8(value.to_vec()).unwrap()));
+    })
+    .unwrap();
+    assert_eq!(count, 7);
+    assert_eq!(
+        entries,
+        [
+            ("/gain", "1.5"),
+            ("/mode", "3"),
+            ("/inner/a", "-3"),
+            ("/inner/b", "false"),
+            ("/arr/0", "0"),
+            ("/arr/1", "0"),
+            ("/arr/2", "0"),
+        ]
+        .map(|(p, v)| (p.to_string(), v.to_string()))
+    );
+    assert_eq!(
+        json::for_each_entry::<_, 2>(&s, &mut buf[..4], |_, _| {}),
+        Err(miniconf::Error::Finalization(
+            serde_json_core::ser::Error::BufferFull
+        ))
+    );
+}
+
+#[test]
+fn compare_and_set() {
+    let mut s = Settings::default();
+    let mut buf = [0; 16];
+    assert_eq!(
+        json::compare_and_set(&mut s, "/mode", b"3", b"4", &mut buf),
+        Ok(true)
+    );
+    assert_eq!(*s.mode, 4);
+    // Mismatch
+    assert_eq!(
+        json::compare_and_set(&mut s, "/mode", b"3", b"5", &mut buf),
+        Ok(false)
+    );
+    // Current value longer than expected
+    *s.mode = 1234;
+    assert_eq!(
+        json::compare_and_set(&mut s, "/mode", b"12", b"5", &mut buf),
+        Ok(false)
+    );
+    assert_eq!(*s.mode, 1234);
+    assert_eq!(
+        json::compare_and_set(&mut s, "/gain", b"1.5", b"2.5", &mut buf),
+        Ok(true)
+    );
+    assert_eq!(*s.gain, 2.5);
+    assert_eq!(
+        json::compare_and_set(&mut s, "/mode", b"1234", b"5", &mut buf[..3]),
+        Err(Traversal::Invalid(0, "Buffer too small").into())
+    );
+    assert_eq!(
+        json::compare_and_set(&mut s, "/foo", b"1", b"5", &mut buf),
+        Err(Traversal::NotFound(1).into())
+    );
+    s.opt = None;
+    assert_eq!(
+        json::compare_and_set(&mut s, "/opt", b"7", b"5", &mut buf),
+        Err(Traversal::Absent(1).into())
+    );
+    assert_eq!(*s.mode, 1234);
+}
+
+#[test]
+fn set_dyn() {
+    #[derive(Tree, Default)]
+    struct Plugin {
+        rate: Leaf<u32>,
+    }
+
+    let mut s = Settings::default();
+    let mut p = Plugin::default();
+    {
+        let plugins: [(&str, &mut dyn json::DynSet); 2] =
+            [("settings", &mut s), ("plugin", &mut p)];
+        json::set_dyn(plugins[0].1, "/inner/a", b"5").unwrap();
+        json::set_dyn(plugins[1].1, "/rate", b"7").unwrap();
+        assert_eq!(
+            json::set_dyn(plugins[1].1, "/foo", b"1"),
+            Err(Traversal::NotFound(1).into())
+        );
+        let key = Settings::transcode::<miniconf::Packed, _>(["mode"])
+            .unwrap()
+            .0;
+        assert_eq!(
+            json::set_dyn_by_key(plugins[0].1, key, b"9"),
+            Ok((1, Node::leaf(1)))
+        );
+    }
+    assert_eq!((*s.inner.a, *s.mode, *p.rate), (5, 9, 7));
+}
+
+#[test]
+fn secret() {
+    #[derive(Tree, Default)]
+    struct Credentials {
+        user: Leaf<u32>,
+        #[tree(secret)]
+        key: Leaf<u32>,
+    }
+    #[derive(Tree, Default)]
+    struct S {
+        gain: Leaf<f32>,
+        login: Credentials,
+        #[tree(secret)]
+        token: [Leaf<u32>; 2],
+    }
+    let mut s = S::default();
+    *s.login.user = 1;
+    *s.login.key = 42;
+    *s.token[1] = 7;
+
+    let (_, node) = S::transcode::<(), _>(["login", "key"]).unwrap();
+    assert!(node.is_leaf() && node.is_secret());
+    assert!(!S::transcode::<(), _>(["login", "user"])
+        .unwrap()
+        .1
+        .is_secret());
+    assert!(S::transcode::<(), _>(["token"]).unwrap().1.is_secret());
+    let secret: Vec<_> = S::nodes::<JsonPointer<String>, 2>()
+        .map(|p| p.unwrap())
+        .filter(|(_, node)| node.is_secret())
+        .map(|(p, _)| p.into_inner())
+        .collect();
+    assert_eq!(secret, ["/login/key", "/token/0", "/token/1"]);
+
+    let mut buf = [0; 128];
+    let len = json::get_all::<_, 2>(&s, &mut buf, None).unwrap();
+    assert_eq!(
+        &buf[..len],
+        br#"{"/gain":0.0,"/login/user":1,"/login/key":"***","/token/0":"***","/token/1":"***"}"#
+    );
+    let len = json::get_all::<_, 2>(&s, &mut buf, Some(&Default::default())).unwrap();
+    assert_eq!(
+        &buf[..len],
+        br#"{"/login/user":1,"/login/key":"***","/token/0":"***","/token/1":"***"}"#
+    );
+    let len = json::get(&s, "/login/key", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"42");
+    let len = json::get_all_canonical::<_, 2>(&s, &mut buf).unwrap();
+    assert_eq!(
+        &buf[..len],
+        br#"{"/gain":0.0,"/login/user":1,"/login/key":42,"/token/0":0,"/token/1":7}"#
+    );
+
+    #[cfg(feature = "serde_json")]
+    {
+        let len = json::get_all_pretty(&s, &mut buf).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&buf[..len]).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "gain": 0.0,
+                "login": {"user": 1, "key": "***"},
+                "token": ["***", "***"],
+            })
+        );
+        assert_eq!(
+            json::get_subtree_value(&s, "/token").unwrap(),
+            serde_json::json!([0, 7])
+        );
+    }
+}
+
+#[test]
+fn unit() {
+    struct Hertz;
+    impl Unit for Hertz {
+        const SUFFIXES: &'static [(&'static str, f64)] = &[("Hz", 1.0), ("kHz", 1e3), ("MHz", 1e6)];
+        const SERIALIZE: usize = 1;
+    }
+    #[derive(Tree, Default)]
+    struct S {
+        freq: UnitLeaf<u32, Hertz>,
+        gain: UnitLeaf<f32, Hertz>,
+    }
+    let mut s = S::default();
+    let mut buf = [0; 32];
+
+    json::set(&mut s, "/freq", br#""1kHz""#).unwrap();
+    assert_eq!(*s.freq, 1000);
+    let len = json::get(&s, "/freq", &mut buf).unwrap();
+    assert_eq!(&buf[..len], br#""1kHz""#);
+
+    for (value, expect) in [("12 Hz", 12), ("0.5MHz", 500_000), ("7", 7)] {
+        json::set(&mut s, "/freq", format!("\"{value}\"").as_bytes()).unwrap();
+        assert_eq!(*s.freq, expect);
+    }
+    json::set(&mut s, "/gain", br#""2.5kHz""#).unwrap();
+    assert_eq!(*s.gain, 2500.0);
+
+    assert_eq!(
+        json::set(&mut s, "/freq", br#""1foo""#),
+        Err(Traversal::Access(1, "Unknown unit").into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/freq", br#""1.5Hz""#),
+        Err(Traversal::Invalid(1, "Not representable").into())
+    );
+    assert_eq!(*s.freq, 7);
+
+    // Round trip through the `kHz` representation
+    for freq in 1..=2000 {
+        *s.freq = freq;
+        let len = json::get(&s, "/freq", &mut buf).unwrap();
+        *s.freq = 0;
+        json::set(&mut s, "/freq", &buf[..len]).unwrap();
+        assert_eq!(*s.freq, freq);
+    }
+
+    // Transient (unescaped) strings
+    #[cfg(feature = "serde_json")]
+    {
+        use miniconf::{IntoKeys, TreeDeserialize};
+        let mut de = serde_json::Deserializer::from_slice(br#""2\u006bHz""#);
+        s.deserialize_by_key(["freq"].into_keys(), &mut de).unwrap();
+        assert_eq!(*s.freq, 2000);
+    }
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn schema_bundle() {
+    #[derive(Tree)]
+    struct S {
+        /// Output gain
+        #[tree(min = 0.0, max = 10.0)]
+        gain: Leaf<f32>,
+        #[tree(deny(serialize = "write-only"))]
+        command: Leaf<u32>,
+        #[tree(deny(deserialize = "read-only"))]
+        serial: Leaf<u32>,
+        #[tree(secret)]
+        key: Leaf<u32>,
+        opt: Option<Leaf<i32>>,
+        inner: [Leaf<bool>; 2],
+    }
+    impl Default for S {
+        fn default() -> Self {
+            Self {
+                gain: 2.5.into(),
+                command: 0.into(),
+                serial: 7.into(),
+                key: 42.into(),
+                opt: None,
+                inner: Default::default(),
+            }
+        }
+    }
+    let s = S {
+        opt: Some(3.into()),
+        ..Default::default()
+    };
+    let bundle = json::schema_bundle(&s).unwrap();
+    let mut gain = serde_json::json!({"type": "number", "access": "rw", "minimum": 0, "maximum": 10, "default": 2.5, "optional": false, "secret": false});
+    if cfg!(feature = "doc") {
+        gain["description"] = "Output gain".into();
+    }
+    assert_eq!(
+        bundle,
+        serde_json::json!({
+            "/gain": gain,
+            "/command": {"type": "number", "access": "w", "optional": false, "secret": false},
+            "/serial": {"type": "number", "access": "r", "default": 7, "optional": false, "secret": false},
+            "/key": {"type": "number", "access": "rw", "optional": false, "secret": true},
+            "/opt": {"type": "number", "access": "rw", "optional": true, "secret": false},
+            "/inner/0": {"type": "boolean", "access": "rw", "default": false, "optional": false, "secret": false},
+            "/inner/1": {"type": "boolean", "access": "rw", "default": false, "optional": false, "secret": false},
+        })
+    );
+    #[cfg(feature = "preserve_order")]
+    {
+        let keys: Vec<_> = bundle.as_object().unwrap().keys().collect();
+        assert_eq!(
+            keys,
+            ["/gain", "/command", "/serial", "/key", "/opt", "/inner/0", "/inner/1"]
+        );
+    }
+
+    // Descriptors that can not be serialized are reported
+    #[derive(Tree, Default)]
+    struct Large {
+        text: Leaf<String>,
+    }
+    let large = Large {
+        text: "x".repeat(1 << 16).into(),
+    };
+    assert_eq!(
+        json::schema_bundle(&large),
+        Err(miniconf::Error::Inner(
+            1,
+            serde_json_core::ser::Error::BufferFull
+        ))
+    );
+}
+
+#[cfg(feature = "jsonc")]
+#[test]
+fn set_all_jsonc() {
+    let mut s = Settings::default();
+    let text = r#"{
+        // Line comment with "quotes" and a trailing comma,
+        "gain": 2.5, /* block comment */
+        "inner": {
+            "a": -3,
+            /* multi-line
+               block comment */
+            "b": true,
+        },
+        "arr": [1, 2, /* inline */ 3, ],
+    }"#;
+    assert_eq!(json::set_all_jsonc(&mut s, text).unwrap(), 6);
+    assert_eq!((*s.gain, *s.inner.a, *s.inner.b), (2.5, -3, true));
+    assert_eq!(s.arr.map(|a| *a), [1, 2, 3]);
+
+    // Comment markers and commas in strings are retained
+    #[derive(Tree, Default)]
+    struct S {
+        s: Leaf<String>,
+    }
+    let mut t = S::default();
+    json::set_all_jsonc(&mut t, r#"{"s": "a // b /* c */ ,}\",]", }"#).unwrap();
+    assert_eq!(*t.s, r#"a // b /* c */ ,}",]"#);
+
+    // Unterminated block comments are rejected
+    assert!(matches!(
+        json::set_all_jsonc(&mut s, r#"{"gain": 1.0} /* oops"#),
+        Err(miniconf::Error::Inner(0, _))
+    ));
+
+    // Strict JSON is unaffected
+    assert!(json::set_all(&mut s, br#"{"gain": 1.0,}"#).is_err());
+}
+
+#[test]
+fn set_by_key_changed() {
+    #[derive(Serialize, Deserialize, Default)]
+    struct Pair {
+        x: i32,
+        y: bool,
+    }
+    #[derive(Serialize, Deserialize, Default)]
+    struct Sparse {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        x: Option<i32>,
+        y: bool,
+    }
+    #[derive(Tree, Default)]
+    struct S {
+        pair: Leaf<Pair>,
+        sparse: Leaf<Sparse>,
+        foo: Leaf<u32>,
+    }
+    let mut s = S::default();
+    let mut buf = [0u8; 16];
+    let (_len, node, changed) =
+        json::set_by_key_changed::<_, _, 2>(&mut s, ["pair"], br#"{"x":0,"y":true}"#, &mut buf)
+            .unwrap();
+    assert_eq!(node, Node::leaf(1));
+    assert_eq!(*changed, ["y"]);
+    assert!(s.pair.y);
+
+    let (_len, _node, changed) =
+        json::set_by_key_changed::<_, _, 2>(&mut s, ["pair"], br#"{"x":0,"y":true}"#, &mut buf)
+            .unwrap();
+    assert!(changed.is_empty());
+
+    let (_len, _node, changed) =
+        json::set_by_key_changed::<_, _, 2>(&mut s, ["foo"], b"3", &mut buf).unwrap();
+    assert!(changed.is_empty());
+
+    // Fields are matched by name when they appear or disappear
+    let (_len, _node, changed) =
+        json::set_by_key_changed::<_, _, 2>(&mut s, ["sparse"], br#"{"x":1,"y":false}"#, &mut buf)
+            .unwrap();
+    assert_eq!(*changed, ["x"]);
+    let (_len, _node, changed) =
+        json::set_by_key_changed::<_, _, 2>(&mut s, ["sparse"], br#"{"y":true}"#, &mut buf)
+            .unwrap();
+    assert_eq!(*changed, ["y", "x"]);
+
+    // Fields that do not fit are reported as changed
+    let (_len, _node, changed) = json::set_by_key_changed::<_, _, 2>(
+        &mut s,
+        ["pair"],
+        br#"{"x":-1000000,"y":true}"#,
+        &mut buf[..9],
+    )
+    .unwrap();
+    assert_eq!(*changed, ["x"]);
+    let (_len, _node, changed) = json::set_by_key_changed::<_, _, 2>(
+        &mut s,
+        ["pair"],
+        br#"{"x":-1000000,"y":true}"#,
+        &mut buf[..9],
+    )
+    .unwrap();
+    assert_eq!(*changed, ["x", "y"]);
+}
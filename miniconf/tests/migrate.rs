@@ -0,0 +1,76 @@
+use miniconf::{
+    migrate::{report, Change, Schema},
+    Leaf, Tree,
+};
+
+mod v1 {
+    use super::*;
+
+    #[derive(Tree, Default)]
+    pub struct Inner {
+        pub gain: Leaf<i32>,
+        pub offset: Leaf<f32>,
+    }
+
+    #[derive(Tree, Default)]
+    pub struct Settings {
+        pub enable: Leaf<bool>,
+        pub ch: [Inner; 2],
+        pub rate: Leaf<u32>,
+        pub opt: Option<Leaf<u8>>,
+    }
+}
+
+mod v2 {
+    use super::*;
+
+    #[derive(Tree, Default)]
+    pub struct Inner {
+        pub gain: Leaf<f32>,
+        pub offset: Leaf<f32>,
+    }
+
+    #[derive(Tree, Default)]
+    pub struct Settings {
+        pub enable: Leaf<bool>,
+        pub ch: [Inner; 3],
+        pub sample_rate: Leaf<u32>,
+        pub opt: Option<Leaf<f32>>,
+    }
+}
+
+#[test]
+fn report_changes() {
+    // Persisted with the old firmware
+    let old = serde_json::to_string(&Schema::new::<v1::Settings>()).unwrap();
+    let old: Schema = serde_json::from_str(&old).unwrap();
+    assert_eq!(old.leaves["/ch/1/gain"].as_deref(), Some("integer"));
+    assert_eq!(old.leaves["/opt"], None);
+
+    let changes = report(&old, &Schema::new::<v2::Settings>());
+    let retyped = |path: &str| Change::Retyped {
+        path: path.into(),
+        old: "integer".into(),
+        new: "number".into(),
+    };
+    assert_eq!(
+        changes,
+        [
+            retyped("/ch/0/gain"),
+            retyped("/ch/1/gain"),
+            Change::Added("/ch/2/gain".into()),
+            Change::Added("/ch/2/offset".into()),
+            Change::Removed("/rate".into()),
+            Change::Added("/sample_rate".into()),
+        ]
+    );
+    assert_eq!(
+        changes.iter().map(|c| c.to_string()).collect::<Vec<_>>()[..2],
+        [
+            "~ /ch/0/gain (integer -> number)",
+            "~ /ch/1/gain (integer -> number)"
+        ]
+    );
+
+    assert_eq!(report(&old, &old), []);
+}
@@ -0,0 +1,103 @@
+use miniconf::{openapi, Leaf, Tree};
+use serde_json::json;
+
+#[derive(Tree, Default)]
+struct Inner {
+    gain: Leaf<f32>,
+    enable: Leaf<bool>,
+}
+
+#[derive(Tree)]
+struct Settings {
+    name: Leaf<String>,
+    inner: Inner,
+    values: [Leaf<i32>; 3],
+    #[tree(min = 0, max = 9)]
+    level: Leaf<i32>,
+    #[tree(deny(deserialize = "read-only"))]
+    version: Leaf<u8>,
+    pair: (Leaf<u8>, Inner),
+    opt: Option<Leaf<u8>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            name: String::from("foo").into(),
+            inner: Inner {
+                gain: 0.5.into(),
+                enable: true.into(),
+            },
+            values: [Leaf(1); 3],
+            level: Default::default(),
+            version: Default::default(),
+            pair: Default::default(),
+            opt: None,
+        }
+    }
+}
+
+#[test]
+fn nested() {
+    let inner = json!({
+        "type": "object",
+        "properties": {
+            "gain": {"type": "number", "default": 0.0},
+            "enable": {"type": "boolean", "default": false},
+        },
+        "additionalProperties": false,
+    });
+    assert_eq!(
+        openapi::components::<Settings>(),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "default": "foo"},
+                "inner": {
+                    "type": "object",
+                    "properties": {
+                        "gain": {"type": "number", "default": 0.5},
+                        "enable": {"type": "boolean", "default": true},
+                    },
+                    "additionalProperties": false,
+                },
+                "values": {
+                    "type": "array",
+                    "items": {"type": "number", "default": 1},
+                    "minItems": 3,
+                    "maxItems": 3,
+                },
+                "pair": {
+                    "type": "array",
+                    "prefixItems": [{"type": "number", "default": 0}, inner],
+                    "items": false,
+                    "minItems": 2,
+                    "maxItems": 2,
+                },
+                "level": {"type": "number", "default": 0, "minimum": 0, "maximum": 9},
+                "version": {"type": "number", "default": 0, "readOnly": true},
+                "opt": {},
+            },
+            "additionalProperties": false,
+        })
+    );
+}
+
+#[test]
+fn secret() {
+    #[derive(Tree, Default)]
+    struct S {
+        user: Leaf<u32>,
+        #[tree(secret)]
+        key: Leaf<u32>,
+    }
+    let schema = openapi::components::<S>();
+    assert_eq!(
+        schema["properties"]["user"],
+        json!({"type": "number", "default": 0})
+    );
+    assert_eq!(
+        schema["properties"]["key"],
+        json!({"type": "number", "writeOnly": true})
+    );
+}
@@ -150,3 +150,108 @@ fn array_option() {
         c: [Option<Leaf<u32>>; 1],
     }
 }
+
+#[test]
+fn presence() {
+    #[derive(Default, Tree)]
+    struct S {
+        feature: Option<Inner>,
+        opts: [Option<Leaf<i32>>; 2],
+        plain: Leaf<u32>,
+        inner: Inner,
+    }
+
+    let mut s = S {
+        feature: Some(Inner { data: 5.into() }),
+        ..Default::default()
+    };
+    let mut buf = [0; 16];
+
+    let len = json::get(&s, "/feature?", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"true");
+    set_get(&mut s, "/feature/data", b"7");
+    assert_eq!(s.feature, Some(Inner { data: 7.into() }));
+    // Re-asserting presence keeps the inner value
+    assert_eq!(json::set(&mut s, "/feature?", b"true"), Ok(4));
+    assert_eq!(s.feature, Some(Inner { data: 7.into() }));
+
+    assert_eq!(json::set(&mut s, "/feature?", b"false"), Ok(5));
+    assert_eq!(s.feature, None);
+    let len = json::get(&s, "/feature?", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"false");
+    assert_eq!(
+        json::get(&s, "/feature/data", &mut buf),
+        Err(Traversal::Absent(1).into())
+    );
+    // There is no inner value to make present
+    assert_eq!(
+        json::set(&mut s, "/feature?", b"true"),
+        Err(Traversal::Invalid(1, "Can't create absent value").into())
+    );
+    assert!(matches!(
+        json::set(&mut s, "/feature?", b"1"),
+        Err(Error::Inner(1, _))
+    ));
+
+    // Array elements
+    s.opts[1] = Some(3.into());
+    let len = json::get(&s, "/opts/1?", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"true");
+    let len = json::get(&s, "/opts/0?", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"false");
+    json::set(&mut s, "/opts/1?", b"false").unwrap();
+    assert_eq!(s.opts, [None, None]);
+
+    // Only on `Option`
+    assert_eq!(
+        json::get(&s, "/plain?", &mut buf),
+        Err(Traversal::TooLong(1).into())
+    );
+    assert_eq!(
+        json::get(&s, "/inner?/data", &mut buf),
+        Err(Traversal::TooLong(1).into())
+    );
+    assert_eq!(
+        json::get(&s, "/feature?/data", &mut buf),
+        Err(Traversal::TooLong(1).into())
+    );
+
+    // Presence leaves are not iterated
+    assert_eq!(
+        paths::<S, 2>(),
+        [
+            "/feature/data",
+            "/opts/0",
+            "/opts/1",
+            "/plain",
+            "/inner/data"
+        ]
+    );
+}
+
+#[test]
+fn get_or() {
+    let mut settings = Settings::default();
+    let mut data = [0; 10];
+
+    assert_eq!(
+        json::get_or(&settings, "/value/data", b"-1", &mut data),
+        Ok(2)
+    );
+    assert_eq!(&data[..2], b"-1");
+    assert_eq!(
+        json::get_or(&settings, "/foo", b"-1", &mut data),
+        Err(Traversal::NotFound(1).into())
+    );
+    assert_eq!(
+        json::get_or(&settings, "/value/data", b"12345678901", &mut data),
+        Err(Error::Finalization(serde_json_core::ser::Error::BufferFull))
+    );
+
+    settings.value = Some(Inner { data: 5.into() });
+    assert_eq!(
+        json::get_or(&settings, "/value/data", b"-1", &mut data),
+        Ok(1)
+    );
+    assert_eq!(&data[..1], b"5");
+}
@@ -1,5 +1,6 @@
 use miniconf::{
-    Indices, Leaf, Metadata, Node, Packed, Path, Traversal, Tree, TreeKey, TreeSerialize,
+    Indices, Keys, Leaf, Metadata, Node, Packed, PackedWide, Path, Traversal, Tree, TreeKey,
+    TreeSerialize,
 };
 
 #[derive(Tree, Default)]
@@ -170,3 +171,236 @@ fn size() {
     assert_eq!(meta.count.get(), 3usize.pow(15));
     assert_eq!(meta.max_length, 16);
 }
+
+#[test]
+fn hash() {
+    use miniconf::{Fnv1a, HashIndex};
+    assert_eq!(Fnv1a::hash("a"), 0xe40c292c);
+    let (packed, node) = Settings::find_by_hash::<2>(Fnv1a::hash("/b/1")).unwrap();
+    assert_eq!(node, Node::leaf(2));
+    assert_eq!(
+        Settings::transcode::<Path<String, '/'>, _>(packed)
+            .unwrap()
+            .0
+            .as_str(),
+        "/b/1"
+    );
+    assert_eq!(
+        Settings::find_by_hash::<2>(Fnv1a::hash("/b")),
+        Err(Traversal::NotFound(0))
+    );
+    const INDEX: HashIndex<3> = match HashIndex::new::<Settings>() {
+        Ok(index) => index,
+        Err(_) => panic!(),
+    };
+    assert_eq!(INDEX.get(Fnv1a::hash("/b/1")), Some(packed));
+    for path in Settings::nodes::<Path<String, '/'>, 2>() {
+        let (path, _node) = path.unwrap();
+        let (packed, _node) = Settings::find_by_hash::<2>(Fnv1a::hash(&path)).unwrap();
+        assert_eq!(INDEX.get(Fnv1a::hash(&path)), Some(packed));
+    }
+    assert_eq!(
+        HashIndex::<2>::new::<Settings>(),
+        Err(Traversal::Invalid(0, "Too many leaves"))
+    );
+    assert_eq!(
+        HashIndex::<4>::new::<Settings>(),
+        Err(Traversal::Invalid(0, "Too few leaves"))
+    );
+}
+
+#[test]
+fn key_table() {
+    #[derive(Tree)]
+    #[tree(key_table)]
+    struct S {
+        zeta: Leaf<i32>,
+        alpha: [Leaf<i32>; 2],
+        #[tree(rename = "mid")]
+        beta: Settings,
+        opt: Option<[Leaf<i32>; 11]>,
+    }
+    #[derive(Tree)]
+    #[tree(key_table)]
+    #[allow(dead_code)]
+    enum E {
+        B(Leaf<i32>),
+        A([Leaf<i32>; 2]),
+        #[tree(skip)]
+        C(Leaf<i32>),
+    }
+    const KEYS: &[(&str, Packed)] = S::KEYS;
+    assert!(KEYS.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_eq!(KEYS.len(), S::META.count.get());
+    assert_eq!(
+        KEYS.iter()
+            .map(|(path, _)| *path)
+            .take(6)
+            .collect::<Vec<_>>(),
+        ["/alpha/0", "/alpha/1", "/mid/a", "/mid/b/0", "/mid/b/1", "/opt/0"]
+    );
+    // Same leaves as the runtime iteration
+    let mut paths: Vec<_> = S::nodes::<Path<String, '/'>, 3>()
+        .map(|p| p.unwrap().0.into_inner())
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        KEYS.iter().map(|(path, _)| *path).collect::<Vec<_>>()
+    );
+    for (path, packed) in S::KEYS {
+        let (p, node) = S::transcode::<Packed, _>(Path::<_, '/'>::from(*path)).unwrap();
+        assert_eq!(p, *packed);
+        assert!(node.is_leaf());
+    }
+    for (path, packed) in E::KEYS {
+        assert_eq!(
+            E::transcode::<Packed, _>(Path::<_, '/'>::from(*path))
+                .unwrap()
+                .0,
+            *packed
+        );
+    }
+    assert_eq!(
+        E::KEYS.iter().map(|(path, _)| *path).collect::<Vec<_>>(),
+        ["/A/0", "/A/1", "/B"]
+    );
+}
+
+#[test]
+fn is_valid() {
+    #[derive(Tree, Default)]
+    struct Old {
+        a: Leaf<f32>,
+        b: [Leaf<f32>; 4],
+    }
+    // `a` became an array and `b` shrunk
+    #[derive(Tree, Default)]
+    struct New {
+        a: [Leaf<f32>; 2],
+        b: [Leaf<f32>; 3],
+    }
+    let key = |path| {
+        Old::transcode::<Packed, _>(Path::<_, '/'>::from(path))
+            .unwrap()
+            .0
+    };
+    assert!(Old::is_valid(key("/a")));
+    assert!(!New::is_valid(key("/a")));
+    assert!(New::is_valid(key("/b/2")));
+    assert!(!New::is_valid(key("/b/3")));
+    assert!(!New::is_valid(Packed::EMPTY));
+}
+
+#[test]
+fn transcode_all() {
+    let keys: Vec<Packed> = Settings::nodes::<Packed, 2>()
+        .map(|p| p.unwrap().0)
+        .collect();
+    let mut buf = [0; 32];
+    let (len, count) = Settings::transcode_all::<'/'>(&keys, ',', &mut buf).unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(&buf[..len], b"/a,/b/0,/b/1");
+
+    // Matches transcode() in a loop
+    let paths: Vec<_> = keys
+        .iter()
+        .map(|k| {
+            Settings::transcode::<Path<String, '.'>, _>(*k)
+                .unwrap()
+                .0
+                .into_inner()
+        })
+        .collect();
+    let (len, _count) = Settings::transcode_all::<'.'>(&keys, ';', &mut buf).unwrap();
+    assert_eq!(&buf[..len], paths.join(";").as_bytes());
+
+    // Short-circuits on exhaustion with the paths that fit
+    assert_eq!(
+        Settings::transcode_all::<'/'>(&keys, ',', &mut buf[..9]),
+        Ok((7, 2))
+    );
+    assert_eq!(
+        Settings::transcode_all::<'/'>(&keys, ',', &mut buf[..1]),
+        Ok((0, 0))
+    );
+
+    // Invalid keys are reported
+    let invalid = Packed::new_from_lsb(0b1111).unwrap();
+    assert!(Settings::transcode_all::<'/'>(&[keys[0], invalid], ',', &mut buf).is_err());
+}
+
+#[test]
+fn transcode_all_prefix() {
+    #[derive(Tree, Default)]
+    struct Inner {
+        x: Leaf<u8>,
+        y: [Leaf<u8>; 3],
+    }
+    #[derive(Tree, Default)]
+    struct Outer {
+        a: [Inner; 3],
+        #[tree(index_names = ["lo", "mid", "hi"])]
+        b: [Leaf<u8>; 3],
+        c: (Leaf<u8>, Inner),
+        d: Option<Inner>,
+    }
+    // Every key matches transcode(), alone and following any other key
+    let keys: Vec<_> = (1..1 << 9).filter_map(Packed::new_from_lsb).collect();
+    let mut buf = [0; 64];
+    for prev in Outer::nodes::<Packed, 3>().map(|p| p.unwrap().0) {
+        let (prev_path, _node) = Outer::transcode::<Path<String, '/'>, _>(prev).unwrap();
+        for key in keys.iter() {
+            let expect = Outer::transcode::<Path<String, '/'>, _>(*key)
+                .map(|(path, _node)| format!("{},{}", prev_path.as_str(), path.as_str()));
+            let ret = Outer::transcode_all::<'/'>(&[prev, *key], ',', &mut buf)
+                .map(|(len, _count)| String::from_utf8(buf[..len].to_vec()).unwrap());
+            assert_eq!(ret, expect, "{prev:?} {key:?}");
+        }
+    }
+}
+
+#[test]
+fn wide() {
+    type Cube = [[[Leaf<u8>; 8]; 8]; 8];
+    assert_eq!(Cube::traverse_all::<Metadata>().unwrap().max_bits, 9);
+    for (path, node) in Cube::nodes::<Path<String, '/'>, 3>().map(Result::unwrap) {
+        let (packed, _node) = Cube::transcode::<Packed, _>(&path).unwrap();
+        let (wide, n) = Cube::transcode::<PackedWide, _>(&path).unwrap();
+        assert_eq!(n, node);
+        assert_eq!(wide.len(), 9);
+        // Same layout
+        assert_eq!(wide.into_lsb().get(), packed.into_lsb().get() as u128);
+        assert_eq!(
+            wide.get() >> (PackedWide::BITS - Packed::BITS),
+            packed.get() as u128
+        );
+        assert_eq!(
+            Cube::transcode::<Path<String, '/'>, _>(wide).unwrap().0,
+            path
+        );
+    }
+
+    // 8 * 9 bits exceed `Packed` capacity
+    type Deep = [[[[[[[[Leaf<()>; 512]; 512]; 512]; 512]; 512]; 512]; 512]; 512];
+    let idx = [511, 0, 1, 2, 3, 4, 5, 510];
+    assert_eq!(
+        Deep::transcode::<Packed, _>(idx),
+        Err(Traversal::TooShort(8))
+    );
+    let (wide, node) = Deep::transcode::<PackedWide, _>(idx).unwrap();
+    assert_eq!(node, Node::leaf(8));
+    assert_eq!(wide.len(), 72);
+    assert_eq!(
+        Deep::transcode::<Indices<[usize; 8]>, _>(wide)
+            .unwrap()
+            .0
+             .0,
+        idx
+    );
+
+    // Chained keys
+    let (head, _node) = Deep::transcode::<PackedWide, _>(&idx[..5]).unwrap();
+    let (path, _node) = Deep::transcode::<Path<String, '/'>, _>(head.chain(&idx[5..])).unwrap();
+    assert_eq!(path.as_str(), "/511/0/1/2/3/4/5/510");
+}
@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use miniconf::{
+    Deny, Leaf, MapElements, Metadata, Schema, Shape, StrLeaf, StringChars, Tree, TreeKey,
+    VecElements, Walk,
+};
+
+#[derive(Tree)]
+struct Inner {
+    a: Leaf<i32>,
+    b: [Leaf<u8>; 2],
+}
+
+#[derive(Tree)]
+struct Settings {
+    foo: Leaf<bool>,
+    inner: Inner,
+    tuple: (Leaf<u8>, Inner),
+}
+
+#[test]
+fn compatible_unordered() {
+    #[derive(Tree)]
+    struct Inner2 {
+        b: [Leaf<u8>; 2],
+        a: Leaf<i32>,
+    }
+
+    #[derive(Tree)]
+    struct Reordered {
+        tuple: (Leaf<u8>, Inner2),
+        inner: Inner2,
+        foo: Leaf<bool>,
+    }
+
+    let shape: Shape = Settings::traverse_all().unwrap();
+    let reordered: Shape = Reordered::traverse_all().unwrap();
+    assert_ne!(shape, reordered);
+    assert!(shape.is_compatible_unordered(&shape));
+    assert!(shape.is_compatible_unordered(&reordered));
+    assert!(reordered.is_compatible_unordered(&shape));
+}
+
+#[test]
+fn incompatible() {
+    #[derive(Tree)]
+    struct Renamed {
+        foo: Leaf<bool>,
+        inner: Inner,
+        tup: (Leaf<u8>, Inner),
+    }
+
+    #[derive(Tree)]
+    struct Inner3 {
+        a: Leaf<i32>,
+        b: [Leaf<u8>; 3],
+    }
+
+    #[derive(Tree)]
+    struct Resized {
+        foo: Leaf<bool>,
+        inner: Inner3,
+        tuple: (Leaf<u8>, Inner),
+    }
+
+    #[derive(Tree)]
+    struct Swapped {
+        foo: Leaf<bool>,
+        inner: Inner,
+        tuple: (Inner, Leaf<u8>),
+    }
+
+    let shape: Shape = Settings::traverse_all().unwrap();
+    for other in [
+        Renamed::traverse_all().unwrap(),
+        Resized::traverse_all().unwrap(),
+        Swapped::traverse_all().unwrap(),
+    ] {
+        assert!(!shape.is_compatible_unordered(&other));
+        assert!(!other.is_compatible_unordered(&shape));
+    }
+}
+
+/// Walk a `Schema` like `traverse_all()` walks the `TreeKey`
+fn walk<W: Walk>(schema: &Schema) -> Result<W, W::Error> {
+    match schema {
+        Schema::Leaf => Ok(W::leaf()),
+        Schema::Internal(lookup, children) => {
+            let children: Vec<W> = children.iter().map(walk).collect::<Result<_, _>>()?;
+            W::internal(&children.iter().collect::<Vec<_>>(), lookup)
+        }
+    }
+}
+
+fn assert_schema<T: TreeKey>() {
+    assert_eq!(
+        walk::<Shape>(&T::SCHEMA).unwrap(),
+        T::traverse_all::<Shape>().unwrap()
+    );
+    assert_eq!(T::META, T::traverse_all::<Metadata>().unwrap());
+}
+
+#[test]
+fn schema_traverse_all() {
+    #[derive(Tree)]
+    #[tree(flatten)]
+    struct Flat {
+        inner: Inner,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Tree)]
+    enum Enum {
+        A(Leaf<u8>),
+        #[tree(rename = "bee")]
+        B(Inner),
+        #[tree(skip)]
+        _C,
+    }
+
+    #[derive(Tree)]
+    struct Complex {
+        settings: Settings,
+        flat: Flat,
+        e: Enum,
+        o: Option<Inner>,
+        r: Result<Leaf<u8>, Inner>,
+        range: Range<Leaf<u32>>,
+        #[tree(index_names = ["x", "y"])]
+        names: [Leaf<f32>; 2],
+        #[tree(typ = "Leaf<i32>", defer = "self.o")]
+        _d: (),
+        leaves: (StrLeaf<String>, Deny<u8>),
+        vec: VecElements<Inner, 3>,
+        map: MapElements<BTreeMap<String, Inner>, 2>,
+        chars: StringChars<4>,
+    }
+
+    assert_schema::<Leaf<u8>>();
+    assert_schema::<[[Leaf<u8>; 2]; 3]>();
+    assert_schema::<Inner>();
+    assert_schema::<Settings>();
+    assert_schema::<Flat>();
+    assert_schema::<Enum>();
+    assert_schema::<Complex>();
+}
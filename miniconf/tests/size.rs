@@ -0,0 +1,130 @@
+use miniconf::{
+    json, postcard,
+    size::{max_value_size, value_size, Json, Postcard},
+    Leaf, Path, Serialize, TreeKey, TreeSerialize,
+};
+
+#[derive(Serialize, Default, Clone, Copy)]
+struct Point {
+    x: i8,
+    y: f64,
+}
+
+#[derive(Serialize, Default, Clone, Copy)]
+struct Wrap(u16);
+
+#[derive(TreeKey, TreeSerialize, Default)]
+struct Settings {
+    a: Leaf<u32>,
+    b: Leaf<i64>,
+    c: [Leaf<f32>; 2],
+    d: Leaf<(bool, u8)>,
+    e: Leaf<Point>,
+    f: Leaf<Wrap>,
+    g: Leaf<[i16; 3]>,
+    h: Leaf<()>,
+}
+
+impl Settings {
+    fn extreme() -> Self {
+        Self {
+            a: u32::MAX.into(),
+            b: i64::MIN.into(),
+            c: [(-f32::MIN_POSITIVE).into(), f32::MIN.into()],
+            d: (false, u8::MAX).into(),
+            e: Point {
+                x: i8::MIN,
+                y: -f64::MIN_POSITIVE,
+            }
+            .into(),
+            f: Wrap(u16::MAX).into(),
+            g: [i16::MIN; 3].into(),
+            h: ().into(),
+        }
+    }
+}
+
+#[test]
+fn bounds() {
+    let s = Settings::extreme();
+    let mut buf = [0; 64];
+    for path in Settings::nodes::<Path<String, '/'>, 2>() {
+        let (path, _node) = path.unwrap();
+        let keys = Path::<_, '/'>::from(path.as_str());
+        let json_len = json::get(&s, &path, &mut buf).unwrap();
+        let bound = value_size::<Json, _, _>(&s, keys).unwrap();
+        assert!(json_len <= bound, "{} {json_len} {bound}", path.as_str());
+        let postcard_len =
+            postcard::get_by_key(&s, keys, ::postcard::ser_flavors::Slice::new(&mut buf))
+                .unwrap()
+                .len();
+        let bound = value_size::<Postcard, _, _>(&s, keys).unwrap();
+        assert!(
+            postcard_len <= bound,
+            "{} {postcard_len} {bound}",
+            path.as_str()
+        );
+    }
+    assert_eq!(value_size::<Json, _, _>(&s, ["b"]), Some(20));
+    assert_eq!(
+        value_size::<Json, _, _>(&s, ["e"]),
+        Some(2 + 4 + 4 + 1 + 4 + 24)
+    );
+    assert_eq!(value_size::<Postcard, _, _>(&s, ["g"]), Some(9));
+    assert_eq!(value_size::<Json, _, _>(&s, ["g"]), Some(4 + 3 * 6));
+    assert_eq!(max_value_size::<Json, _, 2>(&s), Some(39));
+    assert_eq!(max_value_size::<Postcard, _, 2>(&s), Some(10));
+}
+
+#[test]
+fn unbounded() {
+    #[allow(dead_code)]
+    #[derive(Serialize, Default)]
+    enum En {
+        #[default]
+        A,
+        B(u8),
+    }
+
+    #[derive(TreeKey, TreeSerialize, Default)]
+    struct S {
+        a: Leaf<u8>,
+        s: Leaf<&'static str>,
+        e: Leaf<En>,
+        o: Leaf<Option<u8>>,
+        v: Leaf<Vec<u8>>,
+        t: Option<Leaf<u8>>,
+    }
+    let s = S::default();
+    assert_eq!(value_size::<Json, _, _>(&s, ["a"]), Some(3));
+    for key in ["s", "e", "o", "v", "t"] {
+        assert_eq!(value_size::<Json, _, _>(&s, [key]), None, "{key}");
+    }
+    assert_eq!(max_value_size::<Postcard, _, 1>(&s), None);
+}
+
+#[test]
+fn options() {
+    #[derive(TreeKey, TreeSerialize, Default)]
+    struct S {
+        a: Leaf<u8>,
+        o: Leaf<Option<u16>>,
+        t: Option<Leaf<Option<i32>>>,
+    }
+    let mut s = S {
+        o: Some(0).into(),
+        ..Default::default()
+    };
+    // `Some` is bounded by the larger of `null` and the inner value
+    assert_eq!(value_size::<Json, _, _>(&s, ["o"]), Some(5));
+    assert_eq!(value_size::<Postcard, _, _>(&s, ["o"]), Some(4));
+    // Absent leaves are skipped
+    assert_eq!(value_size::<Json, _, _>(&s, ["t"]), None);
+    assert_eq!(max_value_size::<Json, _, 1>(&s), Some(5));
+    s.t = Some(Some(0).into());
+    assert_eq!(max_value_size::<Json, _, 1>(&s), Some(11));
+    assert_eq!(max_value_size::<Postcard, _, 1>(&s), Some(6));
+    // The type of the inner value of `None` is not known
+    *s.o = None;
+    assert_eq!(value_size::<Json, _, _>(&s, ["o"]), None);
+}
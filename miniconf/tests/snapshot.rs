@@ -0,0 +1,180 @@
+use miniconf::{
+    postcard::{self, LoadCount, Snapshot},
+    Leaf, Tree,
+};
+
+#[derive(Tree, Default, PartialEq, Debug)]
+struct Inner {
+    c: Leaf<i16>,
+}
+
+#[derive(Tree, Default, PartialEq, Debug)]
+struct Settings {
+    a: Leaf<u32>,
+    b: [Leaf<f32>; 2],
+    d: Option<Inner>,
+    e: Option<Inner>,
+}
+
+fn source() -> Settings {
+    Settings {
+        a: 0x1234.into(),
+        b: [1.5.into(), (-3.0).into()],
+        d: Some(Inner { c: (-7).into() }),
+        e: None,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let source = source();
+    let mut buf = [0; 64];
+    let len = Snapshot::to_bytes_crc::<_, 2>(&source, &mut buf).unwrap();
+    let mut target = Settings {
+        d: Some(Inner::default()),
+        ..Default::default()
+    };
+    assert_eq!(Snapshot::from_bytes_crc(&mut target, &buf[..len]), Ok(0));
+    assert_eq!(source, target);
+}
+
+#[test]
+fn corrupted() {
+    let source = source();
+    let mut buf = [0; 64];
+    let len = Snapshot::to_bytes_crc::<_, 2>(&source, &mut buf).unwrap();
+    // First record: key (1 byte), length (2 bytes), value (varint 0x1234: 2 bytes), CRC.
+    // Flip a bit in the first record's value
+    buf[3] ^= 0x04;
+    let mut target = Settings {
+        d: Some(Inner::default()),
+        ..Default::default()
+    };
+    assert_eq!(Snapshot::from_bytes_crc(&mut target, &buf[..len]), Ok(1));
+    assert_eq!(*target.a, 0);
+    assert_eq!(target.b, source.b);
+    assert_eq!(target.d, source.d);
+
+    // Truncated framing drops the remainder
+    let mut target = Settings {
+        d: Some(Inner::default()),
+        ..Default::default()
+    };
+    assert_eq!(
+        Snapshot::from_bytes_crc(&mut target, &buf[7..len - 1]),
+        Ok(1)
+    );
+    assert_eq!(target.b, source.b);
+}
+
+#[test]
+fn full() {
+    let mut buf = [0; 8];
+    assert!(Snapshot::to_bytes_crc::<_, 2>(&source(), &mut buf).is_err());
+}
+
+#[test]
+fn load_all_evolution() {
+    // Settings without `b` but with an additional `f`
+    #[derive(Tree, Default, PartialEq, Debug)]
+    struct Evolved {
+        f: Leaf<u8>,
+        d: Option<Inner>,
+        a: Leaf<u32>,
+        e: Option<Inner>,
+    }
+
+    let source = source();
+    let mut buf = [0; 64];
+    let len = postcard::save_all::<_, 2>(&source, &mut buf).unwrap();
+
+    let mut target = Evolved {
+        f: 3.into(),
+        ..Default::default()
+    };
+    // Extra `/b/0`, `/b/1` and absent `/d/c` skipped, `/e/c` not saved (absent)
+    let count = postcard::load_all::<_, 2>(&mut target, &buf[..len]).unwrap();
+    assert_eq!(
+        count,
+        LoadCount {
+            applied: 1,
+            skipped: 3,
+            missing: 2
+        }
+    );
+    assert_eq!(*target.f, 3);
+    assert_eq!(*target.a, 0x1234);
+    assert_eq!(target.d, None); // absent in target: skipped
+
+    // Missing key: load a blob without `/a`
+    let mut target = Settings {
+        d: Some(Inner::default()),
+        ..Default::default()
+    };
+    let evolved = Evolved {
+        f: 5.into(),
+        d: Some(Inner { c: 9.into() }),
+        a: 7.into(),
+        e: None,
+    };
+    let len = postcard::save_all::<_, 2>(&evolved, &mut buf).unwrap();
+    let count = postcard::load_all::<_, 2>(&mut target, &buf[..len]).unwrap();
+    assert_eq!((count.applied, count.skipped, count.missing), (2, 1, 3));
+    assert_eq!(*target.a, 7);
+    assert_eq!(*target.d.as_ref().unwrap().c, 9);
+    assert_eq!(target.b, [Leaf(0.0); 2]);
+
+    // Truncated
+    assert!(postcard::load_all::<_, 2>(&mut target, &buf[..len - 1]).is_err());
+
+    // Reordered fields
+    #[derive(Tree, Default, PartialEq, Debug)]
+    struct Reordered {
+        e: Option<Inner>,
+        d: Option<Inner>,
+        b: [Leaf<f32>; 2],
+        a: Leaf<u32>,
+    }
+    let len = postcard::save_all::<_, 2>(&source, &mut buf).unwrap();
+    let mut target = Reordered {
+        d: Some(Inner::default()),
+        ..Default::default()
+    };
+    let count = postcard::load_all::<_, 2>(&mut target, &buf[..len]).unwrap();
+    assert_eq!((count.applied, count.skipped, count.missing), (4, 0, 1));
+    assert_eq!(*target.a, *source.a);
+    assert_eq!(target.b, source.b);
+    assert_eq!(target.d, source.d);
+}
+
+#[test]
+fn patch() {
+    use ::postcard::ser_flavors::AllocVec;
+    use miniconf::{Packed, TreeKey};
+
+    let old = Settings {
+        d: Some(Inner::default()),
+        e: Some(Inner::default()),
+        ..Default::default()
+    };
+    let mut new = source();
+    new.e = Some(Inner { c: 3.into() });
+    // Diff of the leaves that changed from `old` to `new`
+    let patch: Vec<(Packed, Vec<u8>)> = Settings::nodes::<Packed, 2>()
+        .filter_map(|key| {
+            let (key, _node) = key.unwrap();
+            let value = postcard::get_by_key(&new, key, AllocVec::new()).ok()?;
+            let prev = postcard::get_by_key(&old, key, AllocVec::new()).ok();
+            (prev.as_ref() != Some(&value)).then_some((key, value))
+        })
+        .collect();
+    assert_eq!(patch.len(), 5);
+    let patch: Vec<_> = patch.iter().map(|(k, v)| (*k, &v[..])).collect();
+
+    let mut target = Settings { e: None, ..old };
+    // `/e/c` is absent
+    assert_eq!(Snapshot::apply_patch(&mut target, &patch), Ok(4));
+    target.e = Some(Inner::default());
+    assert_eq!(Snapshot::apply_patch(&mut target, &patch), Ok(5));
+    assert_eq!(target, new);
+}
@@ -43,10 +43,95 @@ fn structs() {
     assert_eq!(metadata.max_depth, 2);
     assert_eq!(metadata.max_length("/"), "/d/a".len());
     assert_eq!(metadata.count.get(), 4);
+    assert_eq!(Settings::META, metadata);
 
     assert_eq!(paths::<Settings, 2>(), ["/a", "/b", "/c", "/d/a"]);
 }
 
+#[test]
+fn meta_const() {
+    use core::ops::Range;
+
+    #[derive(Tree)]
+    struct Inner<T> {
+        a: Leaf<T>,
+        bcd: [Leaf<T>; 11],
+    }
+
+    #[derive(Tree)]
+    #[tree(flatten)]
+    #[allow(dead_code)]
+    enum Flat {
+        A(Inner<u8>),
+    }
+
+    #[derive(Tree)]
+    struct Settings {
+        a: (Leaf<f32>, Inner<i32>, Option<Leaf<bool>>),
+        b: Result<Leaf<u8>, Inner<u8>>,
+        r: Range<Leaf<u32>>,
+        f: Flat,
+        #[tree(typ = "Leaf<i32>", defer = "self.b")]
+        _d: (),
+        g: [[Inner<u16>; 3]; 100],
+    }
+
+    const META: Metadata = Settings::META;
+    const _: () = assert!(META.max_depth == 5);
+    assert_eq!(META, Settings::traverse_all::<Metadata>().unwrap());
+    assert_eq!(Flat::META, Flat::traverse_all::<Metadata>().unwrap());
+    assert_eq!(Flat::META, Inner::<u8>::META);
+}
+
+#[test]
+fn max_name_length() {
+    #[derive(Tree)]
+    struct Inner {
+        a: Leaf<u8>,
+        longest_name: Leaf<u8>,
+    }
+
+    #[derive(Tree)]
+    struct Settings {
+        ab: Leaf<u8>,
+        #[tree(rename = "abcdef")]
+        b: Leaf<u8>,
+        inner: [Inner; 1000],
+    }
+
+    const META: Metadata = Settings::META;
+    assert_eq!(META.max_name_length, "longest_name".len());
+    assert_eq!(META, Settings::traverse_all::<Metadata>().unwrap());
+    assert_eq!(Metadata::LEAF.max_name_length, 0);
+    assert_eq!(<[Leaf<u8>; 1000]>::META.max_name_length, 0);
+}
+
+#[test]
+fn max_fanout() {
+    #[derive(Tree)]
+    struct Wide {
+        a: Leaf<u8>,
+        b: Leaf<u8>,
+        c: Leaf<u8>,
+        d: Leaf<u8>,
+        e: [Leaf<u8>; 3],
+    }
+
+    #[derive(Tree)]
+    struct Deep {
+        a: Leaf<u8>,
+        b: [[Leaf<u8>; 2]; 7],
+        c: Wide,
+    }
+
+    assert_eq!(Metadata::LEAF.max_fanout, 0);
+    // M fields
+    assert_eq!(Wide::META.max_fanout, 5);
+    // Array of length N
+    assert_eq!(Deep::META.max_fanout, 7);
+    assert_eq!(Deep::META, Deep::traverse_all::<Metadata>().unwrap());
+}
+
 #[test]
 fn borrowed() {
     // Can't derive TreeAny
@@ -59,6 +144,37 @@ fn borrowed() {
     assert_eq!(s.a, "bar".into());
 }
 
+#[test]
+fn borrowed_str() {
+    #[derive(TreeKey, TreeDeserialize, TreeSerialize)]
+    struct S<'a> {
+        name: &'a str,
+        inner: [&'a str; 2],
+    }
+    let mut s = S {
+        name: "foo",
+        inner: ["a", "b"],
+    };
+    let data = br#""bar""#.to_vec();
+    set_get(&mut s, "/name", &data);
+    set_get(&mut s, "/inner/1", &data);
+    assert_eq!((s.name, s.inner), ("bar", ["a", "bar"]));
+
+    #[derive(Tree)]
+    struct T {
+        name: &'static str,
+        leaf: Leaf<&'static str>,
+    }
+    let mut t = T {
+        name: "foo",
+        leaf: "foo".into(),
+    };
+    json::set(&mut t, "/name", b"\"bar\"").unwrap();
+    json::set(&mut t, "/leaf", b"\"bar\"").unwrap();
+    assert_eq!((t.name, *t.leaf), ("bar", "bar"));
+    assert_eq!(t.ref_by_key::<&str, _>(["name"]), Ok(&"bar"));
+}
+
 #[test]
 fn tuple_struct() {
     #[derive(Tree, Default)]
@@ -98,3 +214,103 @@ fn deny_access() {
         Err(Traversal::Access(1, "no any"))
     ));
 }
+
+#[test]
+fn ranges() {
+    use core::ops::{RangeFull, RangeToInclusive};
+    #[derive(Tree)]
+    struct S {
+        to: RangeToInclusive<Leaf<i32>>,
+        full: RangeFull,
+    }
+    let mut s = S {
+        to: ..=Leaf(3),
+        full: ..,
+    };
+    set_get(&mut s, "/to/end", b"7");
+    assert_eq!(*s.to.end, 7);
+    set_get(&mut s, "/full", b"null");
+    let end: &mut i32 = s.mut_by_key(["to", "end"]).unwrap();
+    *end = 9;
+    assert_eq!(s.to, ..=Leaf(9));
+    assert_eq!(paths::<S, 2>(), ["/to/end", "/full"]);
+}
+
+#[test]
+fn atomic() {
+    #[derive(Tree, Serialize, Deserialize, Default, PartialEq, Debug)]
+    struct Inner {
+        a: Leaf<i32>,
+        b: [Leaf<u8>; 2],
+    }
+
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(atomic)]
+        inner: Inner,
+        other: Leaf<bool>,
+    }
+
+    let mut s = S::default();
+    assert_eq!(
+        paths::<S, 3>(),
+        ["/inner/a", "/inner/b/0", "/inner/b/1", "/other"]
+    );
+
+    // Setting the whole struct works
+    assert_eq!(
+        json::set(&mut s, "/inner", br#"{"a": 3, "b": [4, 5]}"#),
+        Ok(21)
+    );
+    assert_eq!(*s.inner.a, 3);
+    assert_eq!(*s.inner.b[1], 5);
+
+    // Inner leaves can be read
+    let mut buf = [0; 8];
+    let len = json::get(&s, "/inner/b/0", &mut buf).unwrap();
+    assert_eq!(&buf[..len], b"4");
+    let a: &i32 = s.ref_by_key(["inner", "a"]).unwrap();
+    assert_eq!(*a, 3);
+
+    // But not set
+    assert_eq!(
+        json::set(&mut s, "/inner/a", b"7"),
+        Err(Traversal::Access(1, "Atomic").into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/inner/b/1", b"7"),
+        Err(Traversal::Access(1, "Atomic").into())
+    );
+    assert_eq!(*s.inner.a, 3);
+
+    set_get(&mut s, "/other", b"true");
+}
+
+#[test]
+fn borrow_bytes() {
+    #[derive(Tree)]
+    struct S {
+        s: Leaf<&'static str>,
+        b: Leaf<&'static [u8]>,
+        i: Leaf<u32>,
+        o: Option<Leaf<&'static str>>,
+    }
+    let s = S {
+        s: Leaf("foo"),
+        b: Leaf(&[1, 2, 3]),
+        i: 7.into(),
+        o: None,
+    };
+    assert_eq!(s.get_str(["s"]), Ok("foo"));
+    assert_eq!(s.get_bytes(["s"]), Ok(&b"foo"[..]));
+    assert_eq!(s.get_bytes(["b"]), Ok(&[1, 2, 3][..]));
+    assert_eq!(
+        s.get_str(["b"]),
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    );
+    assert_eq!(
+        s.get_bytes(["i"]),
+        Err(Traversal::Invalid(0, "Incorrect type"))
+    );
+    assert_eq!(s.get_str(["o"]), Err(Traversal::Absent(1)));
+}
@@ -0,0 +1,127 @@
+use miniconf::{toml, Leaf, Traversal, Tree};
+
+#[derive(Tree, Default, PartialEq, Debug)]
+struct Inner {
+    a: Leaf<i32>,
+    b: Leaf<String>,
+}
+
+#[derive(Tree, Default, PartialEq, Debug)]
+struct Settings {
+    gain: Leaf<f32>,
+    enable: Leaf<bool>,
+    offset: Leaf<Option<i32>>,
+    inner: Inner,
+    arr: [Leaf<u8>; 3],
+    inners: [Inner; 2],
+    opt: Option<Inner>,
+    opts: [Option<Leaf<i32>>; 2],
+}
+
+#[test]
+fn round_trip() {
+    let mut s = Settings::default();
+    *s.gain = 1.5;
+    *s.inner.a = -3;
+    *s.inner.b = "foo".into();
+    s.arr = [1, 2, 3].map(Leaf);
+    *s.inners[1].b = "bar".into();
+    s.opts[1] = Some(Leaf(5));
+    let doc = toml::get_all(&s).unwrap();
+    #[cfg(not(feature = "preserve_order"))]
+    assert!(doc.starts_with("arr = [1, 2, 3]\nenable = false\ngain = 1.5\nopts = [{}, 5]\n"));
+    #[cfg(feature = "preserve_order")]
+    assert_eq!(
+        doc,
+        r#"gain = 1.5
+enable = false
+arr = [1, 2, 3]
+opts = [{}, 5]
+
+[inner]
+a = -3
+b = "foo"
+
+[[inners]]
+a = 0
+b = ""
+
+[[inners]]
+a = 0
+b = "bar"
+"#
+    );
+
+    let mut t = Settings::default();
+    // `/opts/1` is absent in `t`
+    assert_eq!(toml::set_all(&mut t, &doc), Ok(11));
+    assert_eq!(
+        t,
+        Settings {
+            opts: [None, None],
+            ..s
+        }
+    );
+}
+
+#[test]
+fn partial() {
+    let mut s = Settings {
+        opt: Some(Inner::default()),
+        ..Default::default()
+    };
+    let doc = r#"
+offset = 7
+arr = [4]
+
+[opt]
+b = "baz"
+
+[[inners]]
+
+[[inners]]
+a = 9
+"#;
+    assert_eq!(toml::set_all(&mut s, doc), Ok(4));
+    assert_eq!(*s.offset, Some(7));
+    assert_eq!(s.arr.map(|v| *v), [4, 0, 0]);
+    assert_eq!(*s.opt.as_ref().unwrap().b, "baz");
+    assert_eq!(*s.inners[1].a, 9);
+    assert!(toml::get_all(&s).unwrap().contains("offset = 7\n"));
+}
+
+#[test]
+fn mismatch() {
+    let mut s = Settings::default();
+    assert!(matches!(
+        toml::set_all(&mut s, "gain = \"x\""),
+        Err(miniconf::Error::Inner(1, _))
+    ));
+    assert!(matches!(
+        toml::set_all(&mut s, "inner = { a = true }"),
+        Err(miniconf::Error::Inner(2, _))
+    ));
+    assert_eq!(
+        toml::set_all(&mut s, "inner = 3"),
+        Err(Traversal::Invalid(1, "Expected table").into())
+    );
+    assert_eq!(
+        toml::set_all(&mut s, "arr = { a = 1 }"),
+        Err(Traversal::Invalid(1, "Expected array").into())
+    );
+    assert_eq!(
+        toml::set_all(&mut s, "foo = 1"),
+        Err(Traversal::NotFound(1).into())
+    );
+    assert!(matches!(
+        toml::set_all(&mut s, "gain = "),
+        Err(miniconf::Error::Inner(0, _))
+    ));
+    assert_eq!(s, Settings::default());
+    // Updated up to the first error
+    assert_eq!(
+        toml::set_all(&mut s, "arr = [1, 2, 3, 4]"),
+        Err(Traversal::NotFound(2).into())
+    );
+    assert_eq!(s.arr.map(|v| *v), [1, 2, 3]);
+}
@@ -0,0 +1,11 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+#[tree(case_insensitive)]
+pub struct S {
+    gain: Leaf<i32>,
+    #[tree(rename = "Gain")]
+    other: Leaf<i32>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+pub struct S {
+    #[tree(index_names = ["a", "b"])]
+    a: Leaf<i32>,
+}
+
+fn main() {}
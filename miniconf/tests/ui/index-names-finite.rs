@@ -0,0 +1,9 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+pub struct S {
+    #[tree(index_names = ["a", "b"], finite)]
+    a: [Leaf<f32>; 2],
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+#[tree(order = "reverse")]
+pub struct S {
+    gain: Leaf<i32>,
+    mode: Leaf<i32>,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use miniconf::{TreeDeserialize, TreeKey, TreeSerialize};
+
+#[derive(TreeKey, TreeSerialize, TreeDeserialize)]
+pub struct S<'a> {
+    name: &'a str,
+}
+
+fn deserialize<'de, T: TreeDeserialize<'de>>(_data: &'de [u8]) {}
+
+fn main() {
+    let data = br#""bar""#.to_vec();
+    deserialize::<S<'_>>(&data);
+}
@@ -0,0 +1,11 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+#[tree(rename_all = "snake_case")]
+pub struct S {
+    max_gain: Leaf<i32>,
+    #[tree(rename = "max_gain")]
+    gain: Leaf<i32>,
+}
+
+fn main() {}
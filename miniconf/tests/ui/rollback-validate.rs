@@ -0,0 +1,9 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+pub struct S {
+    #[tree(rollback)]
+    gain: Leaf<i32>,
+}
+
+fn main() {}
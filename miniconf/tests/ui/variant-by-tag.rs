@@ -0,0 +1,9 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+#[tree(tag, variant_by = "index")]
+pub enum E {
+    A(Leaf<i32>),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use miniconf::{Leaf, Tree};
+
+#[derive(Tree)]
+#[tree(variant_names)]
+pub struct S {
+    a: Leaf<i32>,
+}
+
+fn main() {}
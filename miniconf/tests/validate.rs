@@ -1,4 +1,5 @@
-use miniconf::{json, Leaf, Traversal, Tree};
+use miniconf::{json, IntoKeys, Leaf, Traversal, Tree, TreeDeserialize, TreeKey};
+use serde::de::IntoDeserializer;
 
 #[derive(Tree, Default)]
 struct Inner {
@@ -52,6 +53,61 @@ fn validate() {
     assert_eq!(json::set(&mut s, "/i/a", b"1.0"), Ok(3));
 }
 
+#[test]
+fn rollback() {
+    #[derive(Tree, Default, Clone, PartialEq, Debug)]
+    struct Limits {
+        lo: Leaf<i32>,
+        hi: Leaf<i32>,
+    }
+
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(validate=self.check_limits, rollback)]
+        limits: Limits,
+        #[tree(validate=self.check_gain, rollback)]
+        gain: Leaf<f32>,
+    }
+
+    impl S {
+        fn check_limits(&mut self, depth: usize) -> Result<usize, &'static str> {
+            (*self.limits.lo <= *self.limits.hi)
+                .then_some(depth)
+                .ok_or("lo > hi")
+        }
+
+        fn check_gain(&mut self, depth: usize) -> Result<usize, &'static str> {
+            (*self.gain >= 0.0).then_some(depth).ok_or("negative")
+        }
+    }
+
+    let mut s = S::default();
+    // Passing
+    json::set(&mut s, "/limits/hi", b"5").unwrap();
+    json::set(&mut s, "/limits/lo", b"2").unwrap();
+    json::set(&mut s, "/gain", b"1.5").unwrap();
+    // Failing validation of a nested leaf restores the field
+    assert_eq!(
+        json::set(&mut s, "/limits/lo", b"7"),
+        Err(Traversal::Invalid(1, "lo > hi").into())
+    );
+    assert_eq!(
+        s.limits,
+        Limits {
+            lo: 2.into(),
+            hi: 5.into()
+        }
+    );
+    assert_eq!(
+        json::set(&mut s, "/gain", b"-1"),
+        Err(Traversal::Invalid(1, "negative").into())
+    );
+    assert_eq!(*s.gain, 1.5);
+    // Deserialization errors
+    assert!(json::set(&mut s, "/limits/hi", b"true").is_err());
+    assert_eq!(*s.limits.hi, 5);
+}
+
 #[test]
 fn paging() {
     // Demonstrate and test how a variable length `Vec` can be accessed
@@ -166,3 +222,133 @@ fn locked() {
     assert_eq!(*s.val, 1);
     json::set(&mut s, "/val", b"1").unwrap_err();
 }
+
+#[test]
+fn finalize() {
+    #[derive(Default, Tree)]
+    #[tree(finalize=Self::check)]
+    struct Span {
+        start: Leaf<i32>,
+        end: Leaf<i32>,
+    }
+
+    impl Span {
+        fn check(&mut self) -> Result<(), &'static str> {
+            if *self.start <= *self.end {
+                Ok(())
+            } else {
+                // Best effort rollback
+                *self.start = *self.end;
+                Err("start > end")
+            }
+        }
+    }
+
+    #[derive(Default, Tree)]
+    struct S {
+        span: Span,
+    }
+
+    let mut s = S::default();
+    json::set(&mut s, "/span/end", b"5").unwrap();
+    json::set(&mut s, "/span/start", b"3").unwrap();
+    assert_eq!((*s.span.start, *s.span.end), (3, 5));
+    assert_eq!(
+        json::set(&mut s, "/span/start", b"7"),
+        Err(Traversal::Invalid(1, "start > end").into())
+    );
+    assert_eq!((*s.span.start, *s.span.end), (5, 5));
+    assert_eq!(json::set(&mut s, "/span/end", b"9"), Ok(1));
+}
+
+#[test]
+fn range() {
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(min = -1, max = 10)]
+        strict: Leaf<i32>,
+        #[tree(min = -1, max = 10, clamp)]
+        clamped: Leaf<i32>,
+        #[tree(max = 1.0, clamp, validate=self.check)]
+        float: Leaf<f32>,
+        #[tree(atomic, min = [Leaf(0), Leaf(1)], max = [Leaf(1), Leaf(0)])]
+        arr: [Leaf<u8>; 2],
+    }
+    impl S {
+        fn check(&mut self, depth: usize) -> Result<usize, &'static str> {
+            assert!(*self.float <= 1.0);
+            Ok(depth)
+        }
+    }
+    let (min, max) = S::range(["strict"]).unwrap();
+    assert_eq!(
+        (min.unwrap().to_string(), max.unwrap().to_string()),
+        ("-1".to_string(), "10".to_string())
+    );
+    // Not `Display`
+    assert!(matches!(S::range(["arr"]), Ok((None, None))));
+    let mut s = S::default();
+    json::set(&mut s, "/strict", b"10").unwrap();
+    assert_eq!(
+        json::set(&mut s, "/strict", b"11"),
+        Err(Traversal::Invalid(1, "Above maximum").into())
+    );
+    assert_eq!(
+        json::set(&mut s, "/strict", b"-2"),
+        Err(Traversal::Invalid(1, "Below minimum").into())
+    );
+    assert_eq!(*s.strict, 10);
+    json::set(&mut s, "/clamped", b"11").unwrap();
+    assert_eq!(*s.clamped, 10);
+    json::set(&mut s, "/clamped", b"-2").unwrap();
+    assert_eq!(*s.clamped, -1);
+    json::set(&mut s, "/float", b"3.5").unwrap();
+    assert_eq!(*s.float, 1.0);
+    json::set(&mut s, "/arr", b"[0, 3]").unwrap();
+    assert_eq!(
+        json::set(&mut s, "/arr", b"[2, 0]"),
+        Err(Traversal::Invalid(1, "Above maximum").into())
+    );
+}
+
+#[test]
+fn finite() {
+    #[derive(Tree, Default)]
+    struct S {
+        #[tree(finite)]
+        gain: Leaf<f32>,
+        #[tree(finite, max = 1.0, clamp)]
+        scale: Leaf<f64>,
+        #[tree(atomic, finite)]
+        offset: Leaf<f64>,
+    }
+    let mut s = S::default();
+    json::set(&mut s, "/gain", b"-1.5").unwrap();
+    assert_eq!(*s.gain, -1.5);
+    // f32 overflow
+    assert_eq!(
+        json::set(&mut s, "/gain", b"1e39"),
+        Err(Traversal::Access(1, "Non-finite").into())
+    );
+    assert_eq!(*s.gain, -1.5);
+    json::set(&mut s, "/scale", b"0.5").unwrap();
+
+    // Other formats can carry NaN and infinities
+    let set = |s: &mut S, key, value: f64| {
+        s.deserialize_by_key(
+            [key].into_keys(),
+            IntoDeserializer::<serde::de::value::Error>::into_deserializer(value),
+        )
+    };
+    for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        for key in ["scale", "offset"] {
+            assert_eq!(
+                set(&mut s, key, value),
+                Err(Traversal::Access(1, "Non-finite").into())
+            );
+        }
+    }
+    assert_eq!((*s.scale, *s.offset), (0.5, 0.0));
+    set(&mut s, "scale", 2.0).unwrap();
+    assert_eq!(*s.scale, 1.0);
+}
@@ -5,7 +5,7 @@ use darling::{
     FromField, FromMeta,
 };
 use proc_macro2::{Span, TokenStream};
-use quote::quote_spanned;
+use quote::{quote, quote_spanned};
 use syn::{parse_quote, spanned::Spanned};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -25,17 +25,32 @@ struct Deny {
 }
 
 #[derive(Debug, FromField, Clone)]
-#[darling(attributes(tree))]
+#[darling(attributes(tree), forward_attrs(doc), and_then=Self::parse)]
 pub struct TreeField {
     pub ident: Option<syn::Ident>,
+    pub attrs: Vec<syn::Attribute>,
     ty: syn::Type,
     pub skip: Flag,
     typ: Option<syn::Type>,
     validate: Option<syn::Expr>,
+    pub rollback: Flag,
     get: Option<syn::Expr>,
     get_mut: Option<syn::Expr>,
-    rename: Option<syn::Ident>,
+    pub rename: Option<syn::Ident>,
+    /// Name from the container `rename_all`
+    #[darling(skip)]
+    pub renamed: Option<syn::LitStr>,
     defer: Option<syn::Expr>,
+    atomic: Flag,
+    skip_deserialize: Flag,
+    partial_array: Flag,
+    secret: Flag,
+    default: Flag,
+    min: Option<syn::Expr>,
+    max: Option<syn::Expr>,
+    clamp: Flag,
+    finite: Flag,
+    index_names: Option<syn::ExprArray>,
     #[darling(default)]
     deny: Deny,
 }
@@ -44,6 +59,76 @@ uses_type_params!(TreeField, ty, typ);
 uses_lifetimes!(TreeField, ty, typ);
 
 impl TreeField {
+    fn parse(self) -> darling::Result<Self> {
+        if self.clamp.is_present() && self.min.is_none() && self.max.is_none() {
+            return Err(
+                darling::Error::custom("`clamp` requires `min` and/or `max`")
+                    .with_span(&self.clamp.span()),
+            );
+        }
+        if self.skip_deserialize.is_present()
+            && (self.atomic.is_present()
+                || self.checked()
+                || self.validate.is_some()
+                || self.index_names.is_some()
+                || self.deny.deserialize.is_some())
+        {
+            return Err(darling::Error::custom(
+                "`skip_deserialize` can not be combined with `atomic`, `min`, `max`, `finite`, `validate`, `index_names`, or `deny(deserialize)`",
+            )
+            .with_span(&self.skip_deserialize.span()));
+        }
+        if self.partial_array.is_present() {
+            if !matches!(self.typ(), syn::Type::Array(_)) {
+                return Err(
+                    darling::Error::custom("`partial_array` requires an array type")
+                        .with_span(&self.partial_array.span()),
+                );
+            }
+            if self.atomic.is_present()
+                || self.checked()
+                || self.index_names.is_some()
+                || self.skip_deserialize.is_present()
+            {
+                return Err(darling::Error::custom(
+                    "`partial_array` can not be combined with `atomic`, `min`, `max`, `finite`, `index_names`, or `skip_deserialize`",
+                )
+                .with_span(&self.partial_array.span()));
+            }
+        }
+        if self.rollback.is_present()
+            && (self.validate.is_none() || self.get_mut.is_some() || self.defer.is_some())
+        {
+            return Err(darling::Error::custom(
+                "`rollback` requires `validate` and can not be combined with `get_mut` or `defer`",
+            )
+            .with_span(&self.rollback.span()));
+        }
+        if let Some(names) = &self.index_names {
+            if !matches!(self.typ(), syn::Type::Array(_)) {
+                return Err(
+                    darling::Error::custom("`index_names` requires an array type").with_span(names),
+                );
+            }
+            if self.atomic.is_present() || self.checked() {
+                return Err(darling::Error::custom(
+                    "`index_names` can not be combined with `atomic`, `min`, `max`, or `finite`",
+                )
+                .with_span(names));
+            }
+        }
+        Ok(self)
+    }
+
+    fn ranged(&self) -> bool {
+        self.min.is_some() || self.max.is_some()
+    }
+
+    /// Whether the deserialized value is checked before assignment
+    fn checked(&self) -> bool {
+        self.ranged() || self.finite.is_present()
+    }
+
     fn span(&self) -> Span {
         self.ident
             .as_ref()
@@ -55,6 +140,56 @@ impl TreeField {
         self.typ.as_ref().unwrap_or(&self.ty)
     }
 
+    /// The type implementing the `Tree*` traits for the node below this field:
+    /// the array element type with `index_names`.
+    fn node_typ(&self) -> &syn::Type {
+        match (&self.index_names, self.typ()) {
+            (Some(_), syn::Type::Array(array)) => &array.elem,
+            (_, typ) => typ,
+        }
+    }
+
+    fn index_lookup(&self) -> Option<(TokenStream, TokenStream)> {
+        // Checked in parse(): typ is an array
+        let (Some(names), syn::Type::Array(array)) = (&self.index_names, self.typ()) else {
+            return None;
+        };
+        let len = &array.len;
+        let count = names.elems.len();
+        Some((
+            quote_spanned! { names.span()=>
+                ::miniconf::KeyLookup::IndexNamed(&#names)
+            },
+            quote_spanned! { names.span()=>
+                ::core::assert!(#len == #count, "`index_names` length must match the array length");
+            },
+        ))
+    }
+
+    fn descend(&self, call: TokenStream, mutable: bool, any: bool) -> TokenStream {
+        // Quote context is the closure receiving the field reference `item`.
+        let Some((lookup, _)) = self.index_lookup() else {
+            return call;
+        };
+        let item = if mutable {
+            quote!(&mut item[index])
+        } else {
+            quote!(&item[index])
+        };
+        let increment = if any {
+            quote!(#call.map_err(::miniconf::Traversal::increment))
+        } else {
+            quote!(::miniconf::Error::increment_result(#call))
+        };
+        quote_spanned! { self.span()=>
+            {
+                let index = ::miniconf::Keys::next(&mut keys, &#lookup)?;
+                let item = #item;
+                #increment
+            }
+        }
+    }
+
     pub fn bound(&self, traite: TreeTrait, type_set: &IdentSet) -> Option<TokenStream> {
         if self
             .uses_type_params(&Purpose::BoundImpl.into(), type_set)
@@ -69,23 +204,61 @@ impl TreeField {
                     .serialize
                     .is_none()
                     .then_some(parse_quote!(::miniconf::TreeSerialize)),
-                TreeTrait::Deserialize => self
-                    .deny
-                    .deserialize
-                    .is_none()
-                    .then_some(parse_quote!(::miniconf::TreeDeserialize<'de>)),
+                TreeTrait::Deserialize if self.skip_deserialize.is_present() => {
+                    let ty = self.typ();
+                    return Some(quote_spanned! { self.span()=>
+                        #ty: ::miniconf::TreeDeserialize<'de> + ::core::default::Default,
+                    });
+                }
+                TreeTrait::Deserialize if self.partial_array.is_present() => {
+                    return self.deny.deserialize.is_none().then(|| {
+                        // Checked in parse(): typ is an array
+                        let syn::Type::Array(array) = self.typ() else {
+                            unreachable!()
+                        };
+                        let elem = &array.elem;
+                        quote_spanned! { self.span()=>
+                            #elem: ::miniconf::TreeDeserialize<'de> + ::miniconf::Deserialize<'de>,
+                        }
+                    });
+                }
+                TreeTrait::Deserialize if self.checked() && !self.atomic.is_present() => {
+                    return self.deny.deserialize.is_none().then(|| {
+                        let ty = self.typ();
+                        quote_spanned! { self.span()=>
+                            #ty: ::core::ops::DerefMut,
+                            <#ty as ::core::ops::Deref>::Target:
+                                ::miniconf::Deserialize<'de> + ::core::cmp::PartialOrd + ::core::marker::Sized,
+                        }
+                    });
+                }
+                TreeTrait::Deserialize => {
+                    self.deny
+                        .deserialize
+                        .is_none()
+                        .then_some(if self.atomic.is_present() {
+                            parse_quote!(::miniconf::Deserialize<'de>)
+                        } else {
+                            parse_quote!(::miniconf::TreeDeserialize<'de>)
+                        })
+                }
                 TreeTrait::Any => (self.deny.ref_any.is_none() || self.deny.mut_any.is_none())
                     .then_some(parse_quote!(::miniconf::TreeAny)),
             }
             .map(|bound: syn::TraitBound| {
-                let ty = self.typ();
+                let ty = self.node_typ();
                 quote_spanned!(self.span()=> #ty: #bound,)
             })
         }
     }
 
-    pub fn name(&self) -> Option<&syn::Ident> {
-        self.rename.as_ref().or(self.ident.as_ref())
+    pub fn name(&self) -> Option<syn::LitStr> {
+        self.renamed.clone().or_else(|| {
+            self.rename
+                .as_ref()
+                .or(self.ident.as_ref())
+                .map(|name| syn::LitStr::new(&name.to_string(), name.span()))
+        })
     }
 
     fn ident_or_index(&self, i: usize) -> TokenStream {
@@ -98,15 +271,127 @@ impl TreeField {
         }
     }
 
+    /// The doc comment lines joined with newlines
+    fn doc(&self) -> Option<String> {
+        let lines: Vec<String> = self
+            .attrs
+            .iter()
+            .filter_map(|attr| match &attr.meta {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    value:
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(doc),
+                            ..
+                        }),
+                    ..
+                }) if path.is_ident("doc") => {
+                    let doc = doc.value();
+                    Some(doc.strip_prefix(' ').unwrap_or(&doc).trim_end().to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        let doc = lines.join("\n");
+        let doc = doc.trim();
+        (!doc.is_empty()).then(|| doc.to_string())
+    }
+
     pub fn traverse_by_key(&self, i: usize) -> TokenStream {
         // Quote context is a match of the field index with `traverse_by_key()` args available.
-        let typ = self.typ();
-        quote_spanned!(self.span()=> #i => <#typ as ::miniconf::TreeKey>::traverse_by_key(keys, func))
+        let typ = self.node_typ();
+        let doc = if cfg!(feature = "doc") {
+            self.doc().map(
+                |doc| quote_spanned!(self.span()=> ::miniconf::Keys::mark_doc(&mut keys, #doc);),
+            )
+        } else {
+            None
+        };
+        let secret = self
+            .secret
+            .is_present()
+            .then(|| quote_spanned!(self.span()=> ::miniconf::Keys::mark_secret(&mut keys);));
+        let default = self
+            .default
+            .is_present()
+            .then(|| quote_spanned!(self.span()=> ::miniconf::Keys::mark_default(&mut keys);));
+        let range = (self.min.is_some() || self.max.is_some()).then(|| {
+            let limit = |limit: &Option<syn::Expr>| match limit {
+                Some(limit) => quote_spanned! { limit.span()=> {
+                    #[allow(unused_imports)]
+                    use ::miniconf::{DisplayLimit as _, OtherLimit as _};
+                    (&&::miniconf::LimitProbe(&const { #limit })).limit()
+                }},
+                None => quote!(::core::option::Option::None),
+            };
+            let (min, max) = (limit(&self.min), limit(&self.max));
+            quote_spanned!(self.span()=> ::miniconf::Keys::mark_range(&mut keys, #min, #max);)
+        });
+        if let Some((lookup, check)) = self.index_lookup() {
+            quote_spanned! { self.span()=>
+                #i => {
+                    const { #check };
+                    #secret
+                    #default
+                    #doc
+                    #range
+                    ::miniconf::Keys::next(&mut keys, &#lookup)
+                        .map_err(::miniconf::Error::from)
+                        .and_then(|index| {
+                            let lookup = #lookup;
+                            func(index, lookup.lookup(index)?, lookup.len())
+                                .map_err(|err| ::miniconf::Error::Inner(1, err))?;
+                            ::miniconf::Error::increment_result(
+                                <#typ as ::miniconf::TreeKey>::traverse_by_key(keys, func)
+                            )
+                        })
+                }
+            }
+        } else {
+            quote_spanned! { self.span()=>
+                #i => {
+                    #secret
+                    #default
+                    #doc
+                    #range
+                    <#typ as ::miniconf::TreeKey>::traverse_by_key(keys, func)
+                }
+            }
+        }
+    }
+
+    pub fn schema(&self) -> TokenStream {
+        let typ = self.node_typ();
+        if let Some((lookup, _check)) = self.index_lookup() {
+            let syn::Type::Array(array) = self.typ() else {
+                unreachable!()
+            };
+            let len = &array.len;
+            quote_spanned! { self.span()=>
+                ::miniconf::Schema::Internal(#lookup, &[<#typ as ::miniconf::TreeKey>::SCHEMA; #len])
+            }
+        } else {
+            quote_spanned!(self.span()=> <#typ as ::miniconf::TreeKey>::SCHEMA)
+        }
     }
 
     pub fn traverse_all(&self) -> TokenStream {
-        let typ = self.typ();
-        quote_spanned!(self.span()=> <#typ as ::miniconf::TreeKey>::traverse_all())
+        let typ = self.node_typ();
+        if let Some((lookup, check)) = self.index_lookup() {
+            let syn::Type::Array(array) = self.typ() else {
+                unreachable!()
+            };
+            let len = &array.len;
+            quote_spanned! { self.span()=> {
+                const { #check };
+                match <#typ as ::miniconf::TreeKey>::traverse_all::<W>() {
+                    ::core::result::Result::Ok(child) => W::internal(&[&child; #len], &#lookup),
+                    err => err,
+                }
+            }}
+        } else {
+            quote_spanned!(self.span()=> <#typ as ::miniconf::TreeKey>::traverse_all())
+        }
     }
 
     fn getter(&self, i: Option<usize>) -> TokenStream {
@@ -149,6 +434,44 @@ impl TreeField {
         })
     }
 
+    fn range(&self) -> TokenStream {
+        // Quote context is the deserialized `value` before assignment.
+        let check = |limit: &Option<syn::Expr>, op: TokenStream, msg: &str| {
+            limit.as_ref().map(|limit| {
+                if self.clamp.is_present() {
+                    quote_spanned! { limit.span()=>
+                        if value #op #limit {
+                            value = #limit;
+                        }
+                    }
+                } else {
+                    quote_spanned! { limit.span()=>
+                        if value #op #limit {
+                            return ::core::result::Result::Err(::miniconf::Traversal::Invalid(0, #msg).into());
+                        }
+                    }
+                }
+            })
+        };
+        let min = check(&self.min, quote!(<), "Below minimum");
+        let max = check(&self.max, quote!(>), "Above maximum");
+        let finite = self.finite.is_present().then(|| {
+            quote_spanned! { self.finite.span()=>
+                if !value.is_finite() {
+                    return ::core::result::Result::Err(::miniconf::Traversal::Access(0, "Non-finite").into());
+                }
+            }
+        });
+        quote! {
+            #[allow(clippy::manual_clamp)]
+            {
+                #finite
+                #min
+                #max
+            }
+        }
+    }
+
     pub fn serialize_by_key(&self, i: Option<usize>) -> TokenStream {
         // Quote context is a match of the field index with `serialize_by_key()` args available.
         if let Some(s) = &self.deny.serialize {
@@ -157,29 +480,123 @@ impl TreeField {
             }
         } else {
             let getter = self.getter(i);
+            let call = self.descend(
+                quote!(::miniconf::TreeSerialize::serialize_by_key(item, keys, ser)),
+                false,
+                false,
+            );
             quote_spanned! { self.span()=>
                 #getter
-                    .and_then(|item|
-                        ::miniconf::TreeSerialize::serialize_by_key(item, keys, ser)
-                    )
+                    .and_then(|item| #call)
             }
         }
     }
 
     pub fn deserialize_by_key(&self, i: Option<usize>) -> TokenStream {
+        let call = self.deserialize_by_key_inner(i);
+        match (self.rollback.is_present(), i) {
+            (true, Some(i)) => {
+                // Restore the previous value if deserialization or validation fail
+                let ident = self.ident_or_index(i);
+                quote_spanned! { self.rollback.span()=>
+                    {
+                        let old = ::core::clone::Clone::clone(&self.#ident);
+                        let ret: ::core::result::Result<usize, ::miniconf::Error<D::Error>> = #call;
+                        if ret.is_err() {
+                            self.#ident = old;
+                        }
+                        ret
+                    }
+                }
+            }
+            _ => call,
+        }
+    }
+
+    fn deserialize_by_key_inner(&self, i: Option<usize>) -> TokenStream {
         // Quote context is a match of the field index with `deserialize_by_key()` args available.
         if let Some(s) = &self.deny.deserialize {
             quote_spanned! { self.span()=> ::core::result::Result::Err(
                 ::miniconf::Traversal::Access(0, #s).into())
             }
+        } else if self.skip_deserialize.is_present() {
+            // Deserialize into a default value and discard it
+            let typ = self.typ();
+            quote_spanned! { self.span()=>
+                ::miniconf::TreeDeserialize::<'de>::deserialize_by_key(
+                    &mut <#typ as ::core::default::Default>::default(), keys, de
+                )
+            }
+        } else if let (true, syn::Type::Array(array)) =
+            (self.partial_array.is_present(), self.typ())
+        {
+            let getter_mut = self.getter_mut(i);
+            let validator = self.validator();
+            let len = &array.len;
+            quote_spanned! { self.span()=>
+                #getter_mut
+                    .and_then(|item| {
+                        match ::miniconf::Keys::next(&mut keys, &::miniconf::KeyLookup::homogeneous(#len)) {
+                            ::core::result::Result::Ok(index) => ::miniconf::Error::increment_result(
+                                ::miniconf::TreeDeserialize::<'de>::deserialize_by_key(&mut item[index], keys, de)
+                            ),
+                            ::core::result::Result::Err(::miniconf::Traversal::TooShort(_)) => {
+                                // Deserialize a prefix sequence into the array
+                                ::miniconf::deserialize_prefix(item, de)
+                                    .map_err(|err| ::miniconf::Error::Inner(0, err))?;
+                                ::core::result::Result::Ok(0)
+                            }
+                            ::core::result::Result::Err(err) => ::core::result::Result::Err(err.into()),
+                        }
+                    })
+                    #validator
+            }
+        } else if self.atomic.is_present() || self.checked() {
+            let getter_mut = self.getter_mut(i);
+            let validator = self.validator();
+            let typ = self.typ();
+            let (finalize, value, target) = if self.atomic.is_present() {
+                (
+                    quote_spanned! { self.span()=>
+                        .or(::core::result::Result::Err(::miniconf::Traversal::Access(0, "Atomic")))
+                    },
+                    quote_spanned!(self.span()=> #typ),
+                    quote_spanned!(self.span()=> *item),
+                )
+            } else {
+                (
+                    quote!(),
+                    quote_spanned!(self.span()=> <#typ as ::core::ops::Deref>::Target),
+                    quote_spanned!(self.span()=> **item),
+                )
+            };
+            let range = self.range();
+            quote_spanned! { self.span()=>
+                #getter_mut
+                    .and_then(|item| {
+                        ::miniconf::Keys::finalize(&mut keys) #finalize?;
+                        #[allow(unused_mut)]
+                        let mut value: #value = ::miniconf::Deserialize::deserialize(de)
+                            .map_err(|err| ::miniconf::Error::Inner(0, err))?;
+                        #range
+                        #target = value;
+                        ::core::result::Result::Ok(0)
+                    })
+                    #validator
+            }
         } else {
             let getter_mut = self.getter_mut(i);
             let validator = self.validator();
+            let call = self.descend(
+                quote!(::miniconf::TreeDeserialize::<'de>::deserialize_by_key(
+                    item, keys, de
+                )),
+                true,
+                false,
+            );
             quote_spanned! { self.span()=>
                 #getter_mut
-                    .and_then(|item|
-                        ::miniconf::TreeDeserialize::<'de>::deserialize_by_key(item, keys, de)
-                    )
+                    .and_then(|item| #call)
                     #validator
             }
         }
@@ -193,9 +610,34 @@ impl TreeField {
             }
         } else {
             let getter = self.getter(i);
+            let call = self.descend(
+                quote!(::miniconf::TreeAny::ref_any_by_key(item, keys)),
+                false,
+                true,
+            );
+            quote_spanned! { self.span()=>
+                #getter
+                    .and_then(|item| #call)
+            }
+        }
+    }
+
+    pub fn with_any_by_key(&self, i: Option<usize>) -> TokenStream {
+        // Quote context is a match of the field index with `with_any_by_key()` args available.
+        if let Some(s) = &self.deny.ref_any {
+            quote_spanned! { self.span()=> ::core::result::Result::Err(
+                ::miniconf::Traversal::Access(0, #s).into())
+            }
+        } else {
+            let getter = self.getter(i);
+            let call = self.descend(
+                quote!(::miniconf::TreeAny::with_any_by_key(item, keys, func)),
+                false,
+                true,
+            );
             quote_spanned! { self.span()=>
                 #getter
-                    .and_then(|item| ::miniconf::TreeAny::ref_any_by_key(item, keys))
+                    .and_then(|item| #call)
             }
         }
     }
@@ -208,9 +650,14 @@ impl TreeField {
             }
         } else {
             let getter_mut = self.getter_mut(i);
+            let call = self.descend(
+                quote!(::miniconf::TreeAny::mut_any_by_key(item, keys)),
+                true,
+                true,
+            );
             quote_spanned! { self.span()=>
                 #getter_mut
-                    .and_then(|item| ::miniconf::TreeAny::mut_any_by_key(item, keys))
+                    .and_then(|item| #call)
             }
         }
     }
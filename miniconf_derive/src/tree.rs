@@ -6,22 +6,30 @@ use darling::{
 };
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
-use syn::{parse_quote, WhereClause};
+use syn::{parse_quote, spanned::Spanned, WhereClause};
 
 use crate::field::{TreeField, TreeTrait};
 
 #[derive(Debug, FromVariant, Clone)]
-#[darling(attributes(tree), supports(newtype, tuple, unit), and_then=Self::parse)]
+#[darling(attributes(tree), forward_attrs(doc), supports(newtype, tuple, unit), and_then=Self::parse)]
 pub struct TreeVariant {
     ident: syn::Ident,
+    attrs: Vec<syn::Attribute>,
     rename: Option<syn::Ident>,
+    /// Name from the container `rename_all`
+    #[darling(skip)]
+    renamed: Option<syn::LitStr>,
     skip: Flag,
     fields: ast::Fields<TreeField>,
+    /// Number of fields including skipped ones
+    #[darling(skip)]
+    arity: usize,
 }
 
 impl TreeVariant {
     fn parse(mut self) -> darling::Result<Self> {
         assert!(!self.fields.is_struct());
+        self.arity = self.fields.len();
         while self
             .fields
             .fields
@@ -31,12 +39,24 @@ impl TreeVariant {
         {
             self.fields.fields.pop();
         }
+        // The variant doc comment documents the variant node
+        if let Some(f) = self.fields.fields.first_mut() {
+            if !self.attrs.is_empty() {
+                f.attrs = self.attrs.clone();
+            }
+        }
         if let Some(f) = self.fields.iter().find(|f| f.skip.is_present()) {
             return Err(
                 Error::custom("Can only `skip` terminal tuple variant fields")
                     .with_span(&f.skip.span()),
             );
         }
+        if let Some(f) = self.fields.iter().find(|f| f.rollback.is_present()) {
+            return Err(
+                Error::custom("`rollback` is only supported on struct fields")
+                    .with_span(&f.rollback.span()),
+            );
+        }
         Ok(self)
     }
 
@@ -46,11 +66,62 @@ impl TreeVariant {
         self.fields.fields.first().unwrap()
     }
 
-    fn name(&self) -> &syn::Ident {
-        self.rename.as_ref().unwrap_or(&self.ident)
+    fn name(&self) -> syn::LitStr {
+        self.renamed.clone().unwrap_or_else(|| {
+            let name = self.rename.as_ref().unwrap_or(&self.ident);
+            syn::LitStr::new(&name.to_string(), name.span())
+        })
+    }
+
+    /// Construct the variant with default fields
+    fn default(&self) -> TokenStream {
+        let ident = &self.ident;
+        if self.arity == 0 {
+            quote!(Self::#ident)
+        } else {
+            let fields = (0..self.arity).map(|_| quote!(::core::default::Default::default()));
+            quote!(Self::#ident(#(#fields ,)*))
+        }
     }
 }
 
+/// Convert an identifier to the `rename_all` case `rule`
+fn rename_case(rule: &str, ident: &syn::Ident) -> Option<String> {
+    let ident = ident.to_string();
+    let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+    // Split at underscores and lower-to-upper case transitions
+    let mut words = Vec::new();
+    for part in ident.split('_').filter(|p| !p.is_empty()) {
+        let mut word = String::new();
+        let mut lower = false;
+        for c in part.chars() {
+            if c.is_uppercase() && lower {
+                words.push(core::mem::take(&mut word));
+            }
+            lower = c.is_lowercase() || c.is_ascii_digit();
+            word.extend(c.to_lowercase());
+        }
+        words.push(word);
+    }
+    let capitalize = |w: &String| {
+        let mut c = w.chars();
+        c.next()
+            .map(|f| f.to_uppercase().chain(c).collect())
+            .unwrap_or_default()
+    };
+    Some(match rule {
+        "kebab-case" => words.join("-"),
+        "snake_case" => words.join("_"),
+        "camelCase" => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+            .collect(),
+        "PascalCase" => words.iter().map(capitalize).collect(),
+        _ => return None,
+    })
+}
+
 #[derive(Debug, FromDeriveInput, Clone)]
 #[darling(attributes(tree), supports(struct_named, struct_newtype, struct_tuple, enum_newtype, enum_tuple, enum_unit), and_then=Self::parse)]
 #[darling()]
@@ -58,11 +129,83 @@ pub struct Tree {
     ident: syn::Ident,
     generics: syn::Generics,
     flatten: Flag,
+    finalize: Option<syn::Expr>,
+    key_table: Flag,
+    variant_names: Flag,
+    case_insensitive: Flag,
+    order: Option<syn::LitStr>,
+    rename_all: Option<syn::LitStr>,
+    tag: Flag,
+    variant_by: Option<syn::LitStr>,
     data: Data<TreeVariant, TreeField>,
+    /// All (including unit and skipped) enum variants
+    #[darling(skip)]
+    variants: Vec<TreeVariant>,
 }
 
 impl Tree {
     fn parse(mut self) -> darling::Result<Self> {
+        if let Some(rule) = &self.rename_all {
+            if self.flatten.is_present()
+                || matches!(&self.data, Data::Struct(fields) if !fields.style.is_struct())
+            {
+                return Err(Error::custom(
+                    "`rename_all` requires named fields/variants without `flatten`",
+                )
+                .with_span(rule));
+            }
+            let rename = |ident: &syn::Ident| {
+                rename_case(&rule.value(), ident)
+                    .map(|name| syn::LitStr::new(&name, ident.span()))
+                    .ok_or_else(|| {
+                        Error::custom(
+                            "Unknown `rename_all`, expected one of \"kebab-case\", \"snake_case\", \"camelCase\", \"PascalCase\"",
+                        )
+                        .with_span(rule)
+                    })
+            };
+            match &mut self.data {
+                Data::Struct(fields) => {
+                    for f in fields.fields.iter_mut().filter(|f| f.rename.is_none()) {
+                        // ident is Some
+                        f.renamed = Some(rename(f.ident.as_ref().unwrap())?);
+                    }
+                }
+                Data::Enum(variants) => {
+                    for v in variants.iter_mut().filter(|v| v.rename.is_none()) {
+                        v.renamed = Some(rename(&v.ident)?);
+                    }
+                }
+            }
+            let names: Vec<syn::LitStr> = match &self.data {
+                // ident is Some
+                Data::Struct(fields) => fields.iter().map(|f| f.name().unwrap()).collect(),
+                Data::Enum(variants) => variants
+                    .iter()
+                    .filter(|v| !v.skip.is_present())
+                    .map(|v| v.name())
+                    .collect(),
+            };
+            for (i, name) in names.iter().enumerate() {
+                if names[..i].iter().any(|n| n.value() == name.value()) {
+                    return Err(Error::custom(format!(
+                        "Duplicate name `{}` after `rename_all`",
+                        name.value()
+                    ))
+                    .with_span(&name.span()));
+                }
+            }
+        }
+        if self.by_index() {
+            if let Data::Enum(variants) = &self.data {
+                if let Some(rename) = variants.iter().find_map(|v| v.rename.as_ref()) {
+                    return Err(
+                        Error::custom("`rename` conflicts with `variant_by = \"index\"`")
+                            .with_span(rename),
+                    );
+                }
+            }
+        }
         match &mut self.data {
             Data::Struct(fields) => {
                 while fields
@@ -86,6 +229,7 @@ impl Tree {
                 }
             }
             Data::Enum(variants) => {
+                self.variants = variants.to_vec();
                 variants.retain(|v| !(v.skip.is_present() || v.fields.is_empty()));
                 for v in variants.iter() {
                     if v.fields.len() != 1 {
@@ -97,10 +241,100 @@ impl Tree {
                 }
             }
         }
+        if self.tag.is_present()
+            && (self.flatten.is_present() || !matches!(&self.data, Data::Enum(_)))
+        {
+            return Err(Error::custom("`tag` requires an enum without `flatten`")
+                .with_span(&self.tag.span()));
+        }
+        if let Some(by) = &self.variant_by {
+            if !["name", "index"].contains(&by.value().as_str()) {
+                return Err(
+                    Error::custom("Unknown `variant_by`, expected \"name\" or \"index\"")
+                        .with_span(by),
+                );
+            }
+            if self.flatten.is_present() || !matches!(&self.data, Data::Enum(_)) {
+                return Err(
+                    Error::custom("`variant_by` requires an enum without `flatten`").with_span(by),
+                );
+            }
+            if self.by_index()
+                && (self.tag.is_present()
+                    || self.key_table.is_present()
+                    || self.variant_names.is_present()
+                    || self.case_insensitive.is_present()
+                    || self.order.is_some()
+                    || self.rename_all.is_some())
+            {
+                return Err(Error::custom(
+                    "`variant_by = \"index\"` excludes `tag`, `key_table`, `variant_names`, `case_insensitive`, `order`, and `rename_all`",
+                )
+                .with_span(by));
+            }
+        }
         if self.flatten.is_present() && self.fields().len() != 1 {
             return Err(Error::custom("Can't flatten multiple fields/variants")
                 .with_span(&self.flatten.span()));
         }
+        if self.key_table.is_present() && !self.generics.params.is_empty() {
+            return Err(
+                Error::custom("`key_table` requires a type without generics")
+                    .with_span(&self.key_table.span()),
+            );
+        }
+        if self.variant_names.is_present() && !matches!(&self.data, Data::Enum(_)) {
+            return Err(Error::custom("`variant_names` requires an enum")
+                .with_span(&self.variant_names.span()));
+        }
+        if let Some(order) = &self.order {
+            if order.value() != "lexicographic" {
+                return Err(
+                    Error::custom("Unknown `order`, expected \"lexicographic\"").with_span(order)
+                );
+            }
+            if self.flatten.is_present()
+                || matches!(&self.data, Data::Struct(fields) if !fields.style.is_struct())
+            {
+                return Err(Error::custom(
+                    "`order` requires named fields/variants without `flatten`",
+                )
+                .with_span(order));
+            }
+            // The child index is the position: sorting reorders iteration and `Packed` keys
+            match &mut self.data {
+                // ident is Some
+                Data::Struct(fields) => fields.fields.sort_by_key(|f| f.name().unwrap().value()),
+                Data::Enum(variants) => variants.sort_by_key(|v| v.name().value()),
+            }
+        }
+        if self.case_insensitive.is_present() {
+            if self.flatten.is_present()
+                || matches!(&self.data, Data::Struct(fields) if !fields.style.is_struct())
+            {
+                return Err(Error::custom(
+                    "`case_insensitive` requires named fields/variants without `flatten`",
+                )
+                .with_span(&self.case_insensitive.span()));
+            }
+            let names: Vec<syn::LitStr> = match &self.data {
+                // ident is Some
+                Data::Struct(fields) => fields.iter().map(|f| f.name().unwrap()).collect(),
+                Data::Enum(variants) => variants.iter().map(|v| v.name()).collect(),
+            };
+            for (i, name) in names.iter().enumerate() {
+                let lower = name.value().to_ascii_lowercase();
+                if names[..i]
+                    .iter()
+                    .any(|n| n.value().to_ascii_lowercase() == lower)
+                {
+                    return Err(Error::custom(
+                        "Names must be unique ignoring case with `case_insensitive`",
+                    )
+                    .with_span(&name.span()));
+                }
+            }
+        }
         if self.fields().is_empty() {
             return Err(Error::custom("Internal nodes must have at least one leaf")
                 .with_span(&self.ident.span()));
@@ -108,6 +342,13 @@ impl Tree {
         Ok(self)
     }
 
+    /// Enum variants are selected by numeric index instead of by name
+    fn by_index(&self) -> bool {
+        self.variant_by
+            .as_ref()
+            .is_some_and(|by| by.value() == "index")
+    }
+
     fn fields(&self) -> Vec<&TreeField> {
         match &self.data {
             Data::Struct(fields) => fields.iter().collect(),
@@ -175,11 +416,71 @@ impl Tree {
             quote!(::core::result::Result::<usize, ::miniconf::Traversal>::Ok(
                 0
             ))
+        } else if self.case_insensitive.is_present() {
+            quote!(::miniconf::Keys::next_ignore_case(
+                &mut keys,
+                &Self::__MINICONF_LOOKUP
+            ))
         } else {
             quote!(::miniconf::Keys::next(&mut keys, &Self::__MINICONF_LOOKUP))
         }
     }
 
+    /// Serialize the variant name of a `tag` enum if the keys are exhausted
+    fn tag_serialize(&self) -> Option<TokenStream> {
+        self.tag.is_present().then(|| {
+            let arms = self.variants.iter().filter(|v| !v.skip.is_present()).map(|v| {
+                let ident = &v.ident;
+                let name = v.name();
+                quote!(Self::#ident { .. } => #name)
+            });
+            let skipped = self.variants.iter().any(|v| v.skip.is_present()).then(|| {
+                quote!(_ => return ::core::result::Result::Err(::miniconf::Traversal::Absent(0).into()))
+            });
+            quote! {
+                ::core::result::Result::Err(::miniconf::Traversal::TooShort(0)) => {
+                    let name = match self {
+                        #(#arms ,)*
+                        #skipped
+                    };
+                    ser.serialize_str(name).map_err(|err| ::miniconf::Error::Inner(0, err))?;
+                    return ::core::result::Result::Ok(0);
+                }
+            }
+        })
+    }
+
+    /// Select the variant of a `tag` enum by name if the keys are exhausted
+    fn tag_deserialize(&self) -> Option<TokenStream> {
+        self.tag.is_present().then(|| {
+            let variants: Vec<_> = self
+                .variants
+                .iter()
+                .filter(|v| !v.skip.is_present())
+                .collect();
+            let names = variants.iter().map(|v| v.name());
+            let arms = variants.iter().enumerate().map(|(i, v)| {
+                let ident = &v.ident;
+                let default = v.default();
+                quote! {
+                    (Self::#ident { .. }, #i) => {}
+                    (_, #i) => *self = #default
+                }
+            });
+            let ignore_case = self.case_insensitive.is_present();
+            quote! {
+                ::core::result::Result::Err(::miniconf::Traversal::TooShort(0)) => {
+                    let index = ::miniconf::deserialize_tag(&[#(#names ,)*], #ignore_case, de)?;
+                    match (&*self, index) {
+                        #(#arms ,)*
+                        _ => ::core::unreachable!(),
+                    }
+                    ::core::result::Result::Ok(0)
+                }
+            }
+        })
+    }
+
     pub fn tree_key(&self) -> TokenStream {
         let ident = &self.ident;
         let (impl_generics, ty_generics, orig_where_clause) = self.generics.split_for_impl();
@@ -193,21 +494,46 @@ impl Tree {
                     .map(|f| {
                         // ident is Some
                         let name = f.name().unwrap();
-                        quote_spanned! { name.span()=> stringify!(#name) }
+                        quote_spanned! { name.span()=> #name }
                     })
                     .collect(),
             ),
-            Data::Enum(variants) => Some(
+            Data::Enum(variants) if !self.by_index() => Some(
                 variants
                     .iter()
                     .map(|v| {
                         let name = v.name();
-                        quote_spanned! { name.span()=> stringify!(#name) }
+                        quote_spanned! { name.span()=> #name }
                     })
                     .collect(),
             ),
             _ => None,
         };
+        let key_table = self.key_table.is_present().then(|| {
+            quote! {
+                const __MINICONF_KEY_TABLE: ::miniconf::KeyTable<
+                    { <#ident as ::miniconf::TreeKey>::META.count.get() },
+                    { ::miniconf::KeyTable::len::<#ident>() },
+                > = ::miniconf::KeyTable::new::<#ident>();
+                /// Paths of all leaves and their `Packed` keys, sorted by path
+                pub const KEYS: &'static [(&'static str, ::miniconf::Packed)] =
+                    &Self::__MINICONF_KEY_TABLE.entries();
+            }
+        });
+        let variants = self.variant_names.is_present().then(|| {
+            let names = self
+                .variants
+                .iter()
+                .filter(|v| !v.skip.is_present())
+                .map(|v| {
+                    let name = v.name();
+                    quote_spanned! { name.span()=> #name }
+                });
+            quote! {
+                /// Names of the enum variants (including unit variants) in declaration order
+                pub const VARIANTS: &'static [&'static str] = &[#(#names ,)*];
+            }
+        });
         let names = match names {
             None => quote! {
                 ::miniconf::KeyLookup::Numbered(
@@ -221,10 +547,11 @@ impl Tree {
         };
         let traverse_arms = fields.iter().enumerate().map(|(i, f)| f.traverse_by_key(i));
         let index = self.index();
-        let (traverse, increment, traverse_all) = if self.flatten.is_present() {
-            (None, None, fields[0].traverse_all())
+        let (traverse, increment, traverse_all, schema) = if self.flatten.is_present() {
+            (None, None, fields[0].traverse_all(), fields[0].schema())
         } else {
             let w = fields.iter().map(|f| f.traverse_all());
+            let s = fields.iter().map(|f| f.schema());
             (
                 Some(quote! {
                     let name = Self::__MINICONF_LOOKUP.lookup(index)?;
@@ -233,6 +560,7 @@ impl Tree {
                 }),
                 Some(quote!(::miniconf::Error::increment_result)),
                 quote!(W::internal(&[#(&#w? ,)*], &Self::__MINICONF_LOOKUP)),
+                quote!(::miniconf::Schema::Internal(Self::__MINICONF_LOOKUP, &[#(#s ,)*])),
             )
         };
 
@@ -241,10 +569,14 @@ impl Tree {
             #[automatically_derived]
             impl #impl_generics #ident #ty_generics #orig_where_clause {
                 const __MINICONF_LOOKUP: ::miniconf::KeyLookup = #names;
+                #key_table
+                #variants
             }
 
             #[automatically_derived]
             impl #impl_generics ::miniconf::TreeKey for #ident #ty_generics #where_clause {
+                const SCHEMA: ::miniconf::Schema = #schema;
+
                 fn traverse_all<W: ::miniconf::Walk>() -> ::core::result::Result<W, W::Error> {
                     #traverse_all
                 }
@@ -273,6 +605,15 @@ impl Tree {
         let (mat, arms, default) = self.arms(|f, i| f.serialize_by_key(i));
         let increment =
             (!self.flatten.is_present()).then_some(quote!(::miniconf::Error::increment_result));
+        let index = match self.tag_serialize() {
+            Some(tag) => quote! {
+                match #index {
+                    #tag
+                    index => index?,
+                }
+            },
+            None => quote!(#index?),
+        };
 
         quote! {
             #[automatically_derived]
@@ -282,7 +623,7 @@ impl Tree {
                     K: ::miniconf::Keys,
                     S: ::miniconf::Serializer,
                 {
-                    let index = #index?;
+                    let index = #index;
                     #increment(match #mat {
                         #(#arms ,)*
                         _ => #default
@@ -294,7 +635,9 @@ impl Tree {
 
     pub fn tree_deserialize(&self) -> TokenStream {
         let ty_generics = self.generics.split_for_impl().1;
-        let lifetimes = self.generics.declared_lifetimes();
+        let mut lifetimes = self.generics.declared_lifetimes();
+        // Borrowed `'static` leaves require `'de: 'static`
+        lifetimes.insert(parse_quote!('static));
         let mut de: syn::LifetimeParam = parse_quote!('de);
         de.bounds.extend(
             self.fields()
@@ -310,9 +653,42 @@ impl Tree {
         let where_clause = self.bound_generics(TreeTrait::Deserialize, where_clause);
         let index = self.index();
         let ident = &self.ident;
-        let (mat, arms, default) = self.arms(|f, i| f.deserialize_by_key(i));
+        let (mut mat, arms, default) = self.arms(|f, i| f.deserialize_by_key(i));
+        if matches!(self.data, Data::Enum(_)) {
+            // Reborrow to keep `self` available for `finalize`
+            mat = quote!((&mut *self, index));
+        }
         let increment =
             (!self.flatten.is_present()).then_some(quote!(::miniconf::Error::increment_result));
+        let body = quote! {
+            #increment(match #mat {
+                #(#arms ,)*
+                _ => #default
+            })
+        };
+        let body = match self.tag_deserialize() {
+            Some(tag) => quote! {
+                match #index {
+                    #tag
+                    index => {
+                        let index = index?;
+                        #body
+                    }
+                }
+            },
+            None => quote! {{
+                let index = #index?;
+                #body
+            }},
+        };
+        let finalize = self.finalize.as_ref().map(|finalize| {
+            quote_spanned! { finalize.span()=>
+                .and_then(|depth| #finalize(self)
+                    .map(|()| depth)
+                    .map_err(|msg| ::miniconf::Traversal::Invalid(0, msg).into())
+                )
+            }
+        });
 
         quote! {
             #[automatically_derived]
@@ -322,11 +698,8 @@ impl Tree {
                     K: ::miniconf::Keys,
                     D: ::miniconf::Deserializer<'de>,
                 {
-                    let index = #index?;
-                    #increment(match #mat {
-                        #(#arms ,)*
-                        _ => #default
-                    })
+                    let ret = #body;
+                    ret #finalize
                 }
             }
         }
@@ -339,6 +712,7 @@ impl Tree {
         let ident = &self.ident;
         let (mat, ref_arms, default) = self.arms(|f, i| f.ref_any_by_key(i));
         let (_, mut_arms, _) = self.arms(|f, i| f.mut_any_by_key(i));
+        let (_, with_arms, _) = self.arms(|f, i| f.with_any_by_key(i));
         let increment = (!self.flatten.is_present())
             .then_some(quote!(.map_err(::miniconf::Traversal::increment)));
 
@@ -368,6 +742,19 @@ impl Tree {
                     };
                     ret #increment
                 }
+
+                fn with_any_by_key<K, F, R>(&self, mut keys: K, func: F) -> ::core::result::Result<R, ::miniconf::Traversal>
+                where
+                    K: ::miniconf::Keys,
+                    F: ::core::ops::FnOnce(&dyn ::core::any::Any) -> R,
+                {
+                    let index = #index?;
+                    let ret: ::core::result::Result<_, _> = match #mat {
+                        #(#with_arms ,)*
+                        _ => #default
+                    };
+                    ret #increment
+                }
             }
         }
     }
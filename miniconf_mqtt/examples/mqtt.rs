@@ -1,5 +1,5 @@
 use heapless::String;
-use miniconf::{Leaf, Tree};
+use miniconf::{Leaf, Packed, Path, Tree, TreeKey};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use std_embedded_nal::Stack;
@@ -45,19 +45,53 @@ impl Settings {
 async fn main() {
     env_logger::init();
 
+    let mut batch = |_settings: &Settings, changed: &[Packed]| {
+        let mut line = std::string::String::from("Batch:");
+        for key in changed {
+            let (path, _node) =
+                Settings::transcode::<Path<std::string::String, '/'>, _>(*key).unwrap();
+            line.push(' ');
+            line.push_str(path.as_str());
+        }
+        println!("{line}");
+    };
+
     let mut buffer = [0u8; 1024];
     let localhost: minimq::embedded_nal::IpAddr = "127.0.0.1".parse().unwrap();
 
     // Construct a settings configuration interface.
-    let mut client = miniconf_mqtt::MqttClient::<_, _, _, _, 4>::new(
-        Stack,
-        "test/id",
-        StandardClock::default(),
+    let config =
         minimq::ConfigBuilder::<minimq::broker::IpBroker>::new(localhost.into(), &mut buffer)
-            .keepalive_interval(60),
-    )
+            .keepalive_interval(60);
+    // Optionally use a custom will payload instead of clearing the alive message.
+    let will = std::env::var("MQTT_WILL").ok();
+    let mut client = match &will {
+        None => miniconf_mqtt::MqttClient::<_, _, _, _, 4>::new(
+            Stack,
+            "test/id",
+            StandardClock::default(),
+            config,
+        ),
+        Some(payload) => miniconf_mqtt::MqttClient::<_, _, _, _, 4>::with_will(
+            Stack,
+            "test/id",
+            StandardClock::default(),
+            config,
+            "test/id/alive",
+            payload.as_bytes(),
+        ),
+    }
     .unwrap();
     client.set_alive("\"hello\"");
+    client.set_max_value_length(64);
+    client.set_adopt_retained(false);
+    client.set_change_topic("test/id/changed");
+    client.set_batch_handler(&mut batch);
+    client.set_list_array(true);
+    client.set_batch_get(true);
+    client.set_schema_property(true);
+    #[cfg(feature = "bulk")]
+    client.set_bulk(true);
 
     let mut settings = Settings::default();
     while !*settings.exit {
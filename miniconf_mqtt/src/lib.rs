@@ -5,19 +5,22 @@
 #![forbid(unsafe_code)]
 //! The Minimq MQTT client for `miniconf``.
 
+#[cfg(feature = "bulk")]
+extern crate alloc;
+
 use core::fmt::Display;
 
 use heapless::{String, Vec};
 use log::{error, info, warn};
 use miniconf::{
-    json, IntoKeys, Metadata, NodeIter, Path, Traversal, TreeDeserializeOwned, TreeKey,
+    json, size, Fnv1a, IntoKeys, NodeIter, Packed, Path, Traversal, TreeDeserializeOwned, TreeKey,
     TreeSerialize,
 };
 pub use minimq;
 use minimq::{
     embedded_nal::TcpClientStack,
     embedded_time,
-    types::{Properties, SubscriptionOptions, TopicFilter},
+    types::{Properties, RetainHandling, SubscriptionOptions, TopicFilter},
     ConfigBuilder, DeferredPublication, ProtocolError, Publication, QoS,
 };
 use strum::IntoStaticStr;
@@ -35,10 +38,27 @@ const MAX_CD_LENGTH: usize = 32;
 // dumped.
 const DUMP_TIMEOUT_SECONDS: u32 = 2;
 
+// The initial and maximum delay before re-attempting a failed subscription.
+const SUBSCRIBE_BACKOFF_SECONDS: (u32, u32) = (1, 32);
+
+// The maximum number of distinct changed paths collected into a batch.
+const MAX_BATCH_LENGTH: usize = 16;
+
+// The maximum number of paths in a batched Get request.
+const MAX_GET_LENGTH: usize = 16;
+
+// The maximum length of the uncompressed bulk JSON object.
+#[cfg(feature = "bulk")]
+const MAX_BULK_LENGTH: usize = 1 << 16;
+
 const SEPARATOR: char = '/';
 
+/// Handler called with the settings and the keys of the leaves changed in a poll
+pub type BatchHandler<'a, Settings> = &'a mut dyn FnMut(&Settings, &[Packed]);
+
 /// Miniconf MQTT joint error type
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error<E> {
     /// Miniconf
     Miniconf(miniconf::Traversal),
@@ -46,6 +66,8 @@ pub enum Error<E> {
     State(sm::Error),
     /// Minimq
     Minimq(minimq::Error<E>),
+    /// Bulk dump or set (`bulk` feature)
+    Bulk(&'static str),
 }
 
 impl<E> From<sm::Error> for Error<E> {
@@ -67,7 +89,7 @@ impl<E> From<minimq::Error<E>> for Error<E> {
 }
 
 mod sm {
-    use super::DUMP_TIMEOUT_SECONDS;
+    use super::{DUMP_TIMEOUT_SECONDS, SUBSCRIBE_BACKOFF_SECONDS};
     use minimq::embedded_time::{self, duration::Extensions, Instant};
     use smlang::statemachine;
 
@@ -76,6 +98,8 @@ mod sm {
             *Connect + Connect = Alive,
             Alive + Alive = Subscribe,
             Subscribe + Subscribe / start_timeout = Wait,
+            Subscribe + Retry / start_backoff = Backoff,
+            Backoff + Tick [timed_out] = Subscribe,
             Wait + Tick [timed_out] = Init,
             Init + Multipart = Multipart,
             Multipart + Complete = Single,
@@ -87,6 +111,7 @@ mod sm {
     pub struct Context<C: embedded_time::Clock> {
         clock: C,
         timeout: Option<Instant<C>>,
+        backoff: u32,
     }
 
     impl<C: embedded_time::Clock> Context<C> {
@@ -94,6 +119,7 @@ mod sm {
             Self {
                 clock,
                 timeout: None,
+                backoff: SUBSCRIBE_BACKOFF_SECONDS.0,
             }
         }
     }
@@ -107,10 +133,19 @@ mod sm {
         }
 
         fn start_timeout(&mut self) -> Result<(), ()> {
+            // Subscribed: restart backoff for the next failure
+            self.backoff = SUBSCRIBE_BACKOFF_SECONDS.0;
             self.timeout
                 .replace(self.clock.try_now().unwrap() + DUMP_TIMEOUT_SECONDS.seconds());
             Ok(())
         }
+
+        fn start_backoff(&mut self) -> Result<(), ()> {
+            self.timeout
+                .replace(self.clock.try_now().unwrap() + self.backoff.seconds());
+            self.backoff = (self.backoff * 2).min(SUBSCRIBE_BACKOFF_SECONDS.1);
+            Ok(())
+        }
     }
 }
 
@@ -182,6 +217,145 @@ impl From<ResponseCode> for minimq::Property<'static> {
     }
 }
 
+/// Response properties: the response code and optionally the schema fingerprint
+fn response_properties(code: ResponseCode, schema: Option<&str>) -> Vec<minimq::Property<'_>, 2> {
+    let mut props = Vec::new();
+    props.push(code.into()).unwrap(); // Note(unwrap): capacity
+    if let Some(schema) = schema {
+        props
+            .push(minimq::Property::UserProperty(
+                minimq::types::Utf8String("schema"),
+                minimq::types::Utf8String(schema),
+            ))
+            .unwrap(); // Note(unwrap): capacity
+    }
+    props
+}
+
+#[cfg(feature = "bulk")]
+mod bulk {
+    use super::MAX_BULK_LENGTH;
+    use alloc::vec::Vec;
+    use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec_with_limit};
+
+    // ID1, ID2, CM (deflate), FLG (none), MTIME (none), XFL, OS (unknown)
+    const GZIP_HEADER: [u8; 10] = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+
+    fn crc32(data: &[u8]) -> u32 {
+        !data.iter().fold(!0, |crc, byte| {
+            (0..8).fold(crc ^ *byte as u32, |crc, _| {
+                (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+            })
+        })
+    }
+
+    /// Compress into a gzip member (RFC 1952)
+    pub fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::from(GZIP_HEADER);
+        out.extend(compress_to_vec(data, 6));
+        out.extend(crc32(data).to_le_bytes());
+        out.extend((data.len() as u32).to_le_bytes());
+        out
+    }
+
+    /// Decompress a gzip member without optional header fields
+    pub fn gunzip(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+        if data.len() < GZIP_HEADER.len() + 8 || data[..4] != GZIP_HEADER[..4] {
+            return Err("Unsupported gzip header");
+        }
+        let (body, trailer) =
+            data[GZIP_HEADER.len()..].split_at(data.len() - GZIP_HEADER.len() - 8);
+        let out = decompress_to_vec_with_limit(body, MAX_BULK_LENGTH)
+            .or(Err("Invalid or too large gzip payload"))?;
+        if trailer[..4] != crc32(&out).to_le_bytes()
+            || trailer[4..] != (out.len() as u32).to_le_bytes()
+        {
+            return Err("Gzip checksum mismatch");
+        }
+        Ok(out)
+    }
+
+    fn skip_whitespace(json: &[u8], mut i: usize) -> usize {
+        while matches!(json.get(i), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            i += 1;
+        }
+        i
+    }
+
+    // The index after the string starting at `i`
+    fn string_end(json: &[u8], mut i: usize) -> Result<usize, &'static str> {
+        i += 1;
+        loop {
+            match json.get(i).ok_or("Truncated bulk set")? {
+                b'\\' => i += 2,
+                b'"' => return Ok(i + 1),
+                _ => i += 1,
+            }
+        }
+    }
+
+    // The index after the value starting at `i`
+    fn value_end(json: &[u8], mut i: usize) -> Result<usize, &'static str> {
+        let mut depth = 0usize;
+        loop {
+            match json.get(i).ok_or("Truncated bulk set")? {
+                b'"' => {
+                    i = string_end(json, i)?;
+                    continue;
+                }
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' | b',' if depth == 0 => return Ok(i),
+                b'}' | b']' => depth -= 1,
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    /// Split a JSON object mapping paths to values into the paths and the raw values
+    ///
+    /// The values are validated when they are deserialized into the leaves.
+    pub fn entries(json: &[u8]) -> Result<Vec<(&str, &[u8])>, &'static str> {
+        let mut entries = Vec::new();
+        let mut i = skip_whitespace(json, 0);
+        if json.get(i) != Some(&b'{') {
+            return Err("Invalid bulk set");
+        }
+        i = skip_whitespace(json, i + 1);
+        if json.get(i) == Some(&b'}') {
+            i += 1;
+        } else {
+            loop {
+                if json.get(i) != Some(&b'"') {
+                    return Err("Invalid bulk set");
+                }
+                let end = string_end(json, i)?;
+                let (path, _) = serde_json_core::from_slice::<&str>(&json[i..end])
+                    .or(Err("Invalid bulk set path"))?;
+                i = skip_whitespace(json, end);
+                if json.get(i) != Some(&b':') {
+                    return Err("Invalid bulk set");
+                }
+                let start = skip_whitespace(json, i + 1);
+                i = value_end(json, start)?;
+                entries.push((path, json[start..i].trim_ascii_end()));
+                match json[i] {
+                    b',' => i = skip_whitespace(json, i + 1),
+                    b'}' => {
+                        i += 1;
+                        break;
+                    }
+                    _ => return Err("Invalid bulk set"),
+                }
+            }
+        }
+        if skip_whitespace(json, i) != json.len() {
+            return Err("Trailing data after bulk set");
+        }
+        Ok(entries)
+    }
+}
+
 /// MQTT settings interface.
 ///
 /// # Design
@@ -189,11 +363,60 @@ impl From<ResponseCode> for minimq::Property<'static> {
 /// where `<prefix>` is provided in the client constructor.
 ///
 /// By default it publishes its alive-ness as a `1` retained to `<prefix>/alive` and and clears it
-/// when disconnected.
+/// when disconnected. A custom will can be configured using [`MqttClient::with_will()`].
+///
+/// # Multiple settings roots
+/// Independent settings structs can be served by one client by composing them as named
+/// fields of a root struct deriving `Tree`. The first path segment (the field name)
+/// selects the settings struct: `<prefix>/settings/net/...` and `<prefix>/settings/dsp/...`
+/// below. Subscription, dispatch, listing, and dumps are handled through the common root.
+/// The individual structs remain separate types and are accessed as fields of the root.
+///
+/// ```
+/// use miniconf::{json, Leaf, Tree};
+///
+/// #[derive(Tree, Default)]
+/// struct Network {
+///     port: Leaf<u16>,
+/// }
+///
+/// #[derive(Tree, Default)]
+/// struct Dsp {
+///     gain: [Leaf<f32>; 2],
+/// }
+///
+/// #[derive(Tree, Default)]
+/// struct Roots {
+///     net: Network,
+///     dsp: Dsp,
+/// }
+///
+/// let mut buffer = [0u8; 1024];
+/// let localhost: minimq::embedded_nal::IpAddr = "127.0.0.1".parse().unwrap();
+/// let mut client = miniconf_mqtt::MqttClient::<Roots, _, _, _, 3>::new(
+///     std_embedded_nal::Stack::default(),
+///     "quartiq/application/12345",
+///     std_embedded_time::StandardClock::default(),
+///     minimq::ConfigBuilder::<minimq::broker::IpBroker>::new(localhost.into(), &mut buffer),
+/// )
+/// .unwrap();
+/// let mut roots = Roots::default();
+/// client.update(&mut roots).unwrap();
+///
+/// // A set on `<prefix>/settings/net/port` and one on `<prefix>/settings/dsp/gain/1`
+/// // are dispatched like this:
+/// json::set(&mut roots, "/net/port", b"1883").unwrap();
+/// json::set(&mut roots, "/dsp/gain/1", b"0.5").unwrap();
+/// assert_eq!((*roots.net.port, *roots.dsp.gain[1]), (1883, 0.5));
+/// ```
 ///
 /// # Limitations
 /// The client supports paths up to `MAX_TOPIC_LENGTH = 128` byte length.
 /// Re-publication timeout is fixed to `DUMP_TIMEOUT_SECONDS = 2` seconds.
+/// Batches contain up to `MAX_BATCH_LENGTH = 16` paths.
+/// Batched `Get` requests contain up to `MAX_GET_LENGTH = 16` paths.
+/// Bulk dumps and sets (see [`MqttClient::set_bulk()`], `bulk` feature) are limited
+/// to `MAX_BULK_LENGTH = 65536` bytes of uncompressed JSON.
 ///
 /// # Example
 /// ```
@@ -226,6 +449,16 @@ where
     state: sm::StateMachine<sm::Context<Clock>>,
     prefix: &'a str,
     alive: &'a str,
+    max_value_length: usize,
+    adopt_retained: bool,
+    change_topic: Option<&'a str>,
+    batch_handler: Option<BatchHandler<'a, Settings>>,
+    batch: Vec<Packed, MAX_BATCH_LENGTH>,
+    list_array: bool,
+    batch_get: bool,
+    schema: Option<String<8>>,
+    #[cfg(feature = "bulk")]
+    bulk: bool,
     pending: Multipart<Settings, Y>,
 }
 
@@ -243,23 +476,71 @@ where
     /// * `stack` - The network stack to use for communication.
     /// * `prefix` - The MQTT device prefix to use for this device
     /// * `clock` - The clock for managing the MQTT connection.
-    /// * `config` - The configuration of the MQTT client.
+    /// * `config` - The configuration of the MQTT client. This includes the MQTT keepalive
+    ///   interval (see [`ConfigBuilder::keepalive_interval()`]).
     pub fn new(
         stack: Stack,
         prefix: &'a str,
         clock: Clock,
         config: ConfigBuilder<'a, Broker>,
     ) -> Result<Self, ProtocolError> {
-        assert_eq!("/".len(), SEPARATOR.len_utf8());
-        let meta: Metadata = Settings::traverse_all().unwrap(); // Note(unwrap): infallible
-        assert!(meta.max_depth <= Y);
-        assert!(prefix.len() + "/settings".len() + meta.max_length("/") <= MAX_TOPIC_LENGTH);
-
         // Configure a will so that we can indicate whether or not we are connected.
         let mut will: String<MAX_TOPIC_LENGTH> = prefix.try_into().unwrap();
         will.push_str("/alive").unwrap();
         // Retained empty payload amounts to clearing the retained value (see MQTT spec).
-        let will = minimq::Will::new(&will, b"", &[])?
+        Self::with_will(stack, prefix, clock, config, &will, b"")
+    }
+
+    /// Construct a new MQTT settings interface with a custom will.
+    ///
+    /// The will message is published retained by the broker on unexpected disconnect.
+    /// [`MqttClient::new()`] uses an empty payload on `<prefix>/alive` to clear the
+    /// retained alive message.
+    ///
+    /// # Args
+    /// * `stack` - The network stack to use for communication.
+    /// * `prefix` - The MQTT device prefix to use for this device
+    /// * `clock` - The clock for managing the MQTT connection.
+    /// * `config` - The configuration of the MQTT client.
+    /// * `will_topic` - The full topic of the will message.
+    /// * `will_payload` - The payload of the will message.
+    ///
+    /// # Example
+    /// ```
+    /// use miniconf::{Leaf, Tree};
+    ///
+    /// #[derive(Tree, Clone, Default)]
+    /// struct Settings {
+    ///     foo: Leaf<bool>,
+    /// }
+    ///
+    /// let mut buffer = [0u8; 1024];
+    /// let localhost: minimq::embedded_nal::IpAddr = "127.0.0.1".parse().unwrap();
+    /// let mut client = miniconf_mqtt::MqttClient::<Settings, _, _, _, 1>::with_will(
+    ///     std_embedded_nal::Stack::default(),
+    ///     "quartiq/application/12345",
+    ///     std_embedded_time::StandardClock::default(),
+    ///     minimq::ConfigBuilder::<minimq::broker::IpBroker>::new(localhost.into(), &mut buffer),
+    ///     "quartiq/application/12345/status",
+    ///     br#"{"online":false}"#,
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn with_will(
+        stack: Stack,
+        prefix: &'a str,
+        clock: Clock,
+        config: ConfigBuilder<'a, Broker>,
+        will_topic: &str,
+        will_payload: &[u8],
+    ) -> Result<Self, ProtocolError> {
+        assert_eq!("/".len(), SEPARATOR.len_utf8());
+        const { assert!(Settings::META.max_depth <= Y) };
+        assert!(
+            prefix.len() + "/settings".len() + Settings::META.max_length("/") <= MAX_TOPIC_LENGTH
+        );
+
+        let will = minimq::Will::new(will_topic, will_payload, &[])?
             .retained()
             .qos(QoS::AtMostOnce);
         let config = config.autodowngrade_qos().will(will)?;
@@ -269,6 +550,16 @@ where
             state: sm::StateMachine::new(sm::Context::new(clock)),
             prefix,
             alive: "1",
+            max_value_length: usize::MAX,
+            adopt_retained: true,
+            change_topic: None,
+            batch_handler: None,
+            batch: Vec::new(),
+            list_array: false,
+            batch_get: false,
+            schema: None,
+            #[cfg(feature = "bulk")]
+            bulk: false,
             pending: Multipart::default(),
         })
     }
@@ -282,11 +573,163 @@ where
         self.alive = alive;
     }
 
+    /// Set the maximum accepted payload length for a settings value of an unbounded leaf.
+    ///
+    /// The payload length for a leaf with a bounded value size (see `miniconf::size`) is
+    /// limited by the JSON bound of that leaf. Other leaves (e.g. strings and sequences)
+    /// are limited by `max_value_length`.
+    /// Longer payloads are rejected before deserialization with a
+    /// `Payload too large for target` error response.
+    /// The default is not to limit the payload length of unbounded leaves
+    /// (beyond the MQTT buffer size).
+    pub fn set_max_value_length(&mut self, max_value_length: usize) {
+        self.max_value_length = max_value_length;
+    }
+
+    /// Set whether retained settings are applied on subscription.
+    ///
+    /// If the broker retains messages on `<prefix>/settings/...` topics, they are delivered
+    /// and applied as settings when subscribing. Disabling adoption keeps the device
+    /// the source of truth: the subscription is made with the MQTT retain handling option
+    /// to not send retained messages.
+    /// This takes effect on the next subscription (see [`MqttClient::reset()`]).
+    /// The default is to adopt retained settings.
+    pub fn set_adopt_retained(&mut self, adopt: bool) {
+        self.adopt_retained = adopt;
+    }
+
+    /// Set the topic to publish change notifications to.
+    ///
+    /// After each successful settings `Set` request, the path of the changed
+    /// setting (e.g. `/foo/bar`) is published to this (full) topic, for example `<prefix>/changed`.
+    /// The default is not to publish change notifications.
+    pub fn set_change_topic(&mut self, change_topic: &'a str) {
+        self.change_topic = Some(change_topic);
+    }
+
+    /// Set a handler to be called once for a batch of settings changes.
+    ///
+    /// All `Set` requests already received when [`MqttClient::update()`] is called
+    /// are applied in that same call. Afterwards the handler is called once with the
+    /// settings and the [`Packed`] keys of the distinct paths that were changed.
+    /// This avoids redundant recomputation when several related settings are changed
+    /// in rapid succession.
+    /// The default is not to batch.
+    ///
+    /// ```
+    /// # use miniconf::{Leaf, Packed, Path, Tree, TreeKey};
+    /// # #[derive(Tree, Clone, Default)]
+    /// # struct Settings {
+    /// #     foo: Leaf<bool>,
+    /// # }
+    /// # let mut buffer = [0u8; 1024];
+    /// # let localhost: minimq::embedded_nal::IpAddr = "127.0.0.1".parse().unwrap();
+    /// let mut handler = |_settings: &Settings, changed: &[Packed]| {
+    ///     for key in changed {
+    ///         let (path, _node) = Settings::transcode::<Path<String, '/'>, _>(*key).unwrap();
+    ///         println!("changed: {}", path.as_str());
+    ///     }
+    /// };
+    /// let mut client = miniconf_mqtt::MqttClient::<Settings, _, _, _, 1>::new(
+    ///     std_embedded_nal::Stack::default(),
+    ///     "quartiq/application/12345",
+    ///     std_embedded_time::StandardClock::default(),
+    ///     minimq::ConfigBuilder::<minimq::broker::IpBroker>::new(localhost.into(), &mut buffer),
+    /// )
+    /// .unwrap();
+    /// client.set_batch_handler(&mut handler);
+    /// ```
+    pub fn set_batch_handler(&mut self, handler: BatchHandler<'a, Settings>) {
+        self.batch_handler = Some(handler);
+    }
+
+    /// Set whether to respond to `List` requests with a single JSON array.
+    ///
+    /// If enabled, a `List` request is answered with a single `Ok` response containing
+    /// a JSON array of all paths (e.g. `["/foo","/bar/0"]`) if that fits into the
+    /// message buffer. Otherwise the response falls back to one `Continue` message
+    /// per path terminated by an empty `Ok` message.
+    /// The default is to respond with one message per path.
+    pub fn set_list_array(&mut self, list_array: bool) {
+        self.list_array = list_array;
+    }
+
+    /// Set whether to serve batched `Get` requests on `<prefix>/get`.
+    ///
+    /// If enabled, a request published to `<prefix>/get` with a JSON array of leaf paths
+    /// as payload (e.g. `["/foo","/bar/0"]`) is answered with a single response containing
+    /// a JSON object mapping the paths to their values (e.g. `{"/foo":true,"/bar/0":3}`).
+    /// The response is published to the response topic of the request and correlated with it.
+    /// Absent leaves are omitted. Any other error fails the entire request.
+    /// This takes effect on the next subscription (see [`MqttClient::reset()`]).
+    /// The default is not to serve batched `Get` requests.
+    pub fn set_batch_get(&mut self, batch_get: bool) {
+        self.batch_get = batch_get;
+    }
+
+    /// Set whether to include the schema fingerprint in responses.
+    ///
+    /// If enabled, every response and dump message carries a `schema` user property
+    /// with the hexadecimal [`Fnv1a`] fingerprint of the `Settings` schema
+    /// (the names and structure of all nodes, see the [`Fnv1a`] `Walk` implementation).
+    /// Clients can use it to detect firmware schema changes.
+    /// The default is not to include the fingerprint.
+    pub fn set_schema_property(&mut self, enable: bool) {
+        self.schema = enable.then(|| {
+            let mut s = String::new();
+            // Note(unwrap): infallible walk, 8 hex digits
+            let hash = Settings::traverse_all::<Fnv1a>().unwrap().finish();
+            core::fmt::Write::write_fmt(&mut s, format_args!("{hash:08x}")).unwrap();
+            s
+        });
+    }
+
+    /// Set whether to serve gzip-compressed bulk dumps and sets on `<prefix>/settings-bulk`.
+    ///
+    /// If enabled, an empty message with a response topic published to `<prefix>/settings-bulk`
+    /// requests a bulk dump: the JSON object of all leaves (`{"/foo":true,"/bar/0":3}`)
+    /// with secret values redacted (see `miniconf::json::for_each_entry()`)
+    /// compressed with gzip and published to the response topic with a
+    /// `ContentEncoding: gzip` user property.
+    /// Empty messages without a response topic (e.g. clearing a retained message) are ignored.
+    /// A non-empty message with that property and a gzip-compressed JSON object of paths
+    /// and values as payload sets all the given leaves in object order.
+    /// Like individual sets, each value is limited by the bound of its leaf
+    /// (see [`MqttClient::set_max_value_length()`])
+    /// (checked before any leaf is set), and each set leaf is published to the change topic
+    /// and recorded for the batch handler.
+    /// The set is not atomic: on error the leaves before the failing one remain set.
+    /// Retained messages on `<prefix>/settings-bulk` are not delivered on subscription.
+    /// This takes effect on the next subscription (see [`MqttClient::reset()`]).
+    /// The default is not to serve bulk dumps and sets.
+    #[cfg(feature = "bulk")]
+    pub fn set_bulk(&mut self, enable: bool) {
+        self.bulk = enable;
+    }
+
+    /// Publish a gzip-compressed bulk dump of all settings to `<prefix>/settings-bulk`.
+    ///
+    /// See [`MqttClient::set_bulk()`] for the format.
+    #[cfg(feature = "bulk")]
+    pub fn dump_bulk(&mut self, settings: &Settings) -> Result<(), Error<Stack::Error>> {
+        let mut topic: String<MAX_TOPIC_LENGTH> = self.prefix.try_into().unwrap();
+        topic.push_str("/settings-bulk").unwrap();
+        Self::publish_bulk(
+            settings,
+            &topic,
+            None,
+            self.schema.as_deref(),
+            self.mqtt.client(),
+        )
+    }
+
     /// Reset and restart state machine.
     ///
     /// This rests the state machine to start from the `Connect` state.
     /// This will connect (if not connected), send the alive message, subscribe,
     /// and perform the initial settings dump.
+    /// A failed subscription is re-attempted with exponential backoff from 1 s to 32 s.
+    /// If the client is not ready to send, it is re-attempted on the next update.
     pub fn reset(&mut self) {
         self.state.process_event(sm::Events::Reset).unwrap();
     }
@@ -313,11 +756,20 @@ where
                     self.state.process_event(sm::Events::Alive).unwrap();
                 }
             }
-            sm::States::Subscribe => {
-                if self.subscribe().is_ok() {
+            sm::States::Subscribe => match self.subscribe() {
+                Ok(()) => {
                     info!("Subscribed");
                     self.state.process_event(sm::Events::Subscribe).unwrap();
                 }
+                // A pending write: retry on the next update
+                Err(minimq::Error::NotReady) => {}
+                Err(err) => {
+                    warn!("Subscription failure, retrying: {err:?}");
+                    self.state.process_event(sm::Events::Retry).unwrap();
+                }
+            },
+            sm::States::Backoff => {
+                self.state.process_event(sm::Events::Tick).ok();
             }
             sm::States::Wait => {
                 self.state.process_event(sm::Events::Tick).ok();
@@ -337,7 +789,22 @@ where
             }
         }
         // All states must handle MQTT traffic.
-        self.poll(settings).map(|c| c == State::Changed)
+        let mut state = self.poll(settings)?.unwrap_or_default();
+        if self.batch_handler.is_some() {
+            // Drain the messages that are already available into the batch
+            while !self.batch.is_full() {
+                match self.poll(settings)? {
+                    Some(s) => state = state.max(s),
+                    None => break,
+                }
+            }
+            if !self.batch.is_empty() {
+                // Note(unwrap): checked above
+                (self.batch_handler.as_mut().unwrap())(settings, &self.batch);
+                self.batch.clear();
+            }
+        }
+        Ok(state == State::Changed)
     }
 
     fn alive(&mut self) -> Result<(), minimq::PubError<Stack::Error, ()>> {
@@ -356,8 +823,29 @@ where
     fn subscribe(&mut self) -> Result<(), minimq::Error<Stack::Error>> {
         let mut settings: String<MAX_TOPIC_LENGTH> = self.prefix.try_into().unwrap();
         settings.push_str("/settings/#").unwrap();
-        let opts = SubscriptionOptions::default().ignore_local_messages();
-        let topics = [TopicFilter::new(&settings).options(opts)];
+        let mut opts = SubscriptionOptions::default().ignore_local_messages();
+        if !self.adopt_retained {
+            opts = opts.retain_behavior(RetainHandling::Never);
+        }
+        let mut get: String<MAX_TOPIC_LENGTH> = self.prefix.try_into().unwrap();
+        get.push_str("/get").unwrap();
+        #[cfg(feature = "bulk")]
+        let mut bulk: String<MAX_TOPIC_LENGTH> = self.prefix.try_into().unwrap();
+        #[cfg(feature = "bulk")]
+        bulk.push_str("/settings-bulk").unwrap();
+        let mut topics: Vec<TopicFilter<'_>, 3> = Vec::new();
+        topics.push(TopicFilter::new(&settings).options(opts)).ok(); // Note(ok): capacity
+        if self.batch_get {
+            let opts = SubscriptionOptions::default().ignore_local_messages();
+            topics.push(TopicFilter::new(&get).options(opts)).ok(); // Note(ok): capacity
+        }
+        #[cfg(feature = "bulk")]
+        if self.bulk {
+            let opts = SubscriptionOptions::default()
+                .ignore_local_messages()
+                .retain_behavior(RetainHandling::Never);
+            topics.push(TopicFilter::new(&bulk).options(opts)).ok(); // Note(ok): capacity
+        }
         self.mqtt.client().subscribe(&topics, &[])
     }
 
@@ -386,7 +874,7 @@ where
                 (ResponseCode::Ok, String::new())
             };
 
-            let props = [code.into()];
+            let props = response_properties(code, self.schema.as_deref());
             let mut response = Publication::new(path.as_bytes())
                 .topic(self.pending.response_topic.as_ref().unwrap()) // Note(unwrap) checked in update()
                 .properties(&props)
@@ -408,6 +896,241 @@ where
         }
     }
 
+    fn list_array<K: IntoKeys>(
+        root: K,
+        mut buf: &mut [u8],
+    ) -> Result<usize, embedded_io::WriteFmtError<embedded_io::SliceWriteError>> {
+        let start = buf.len();
+        let mut sep = '[';
+        // Note(unwrap): root checked by caller
+        for path in Settings::nodes::<Path<String<MAX_TOPIC_LENGTH>, SEPARATOR>, Y>()
+            .root(root)
+            .unwrap()
+        {
+            let (path, node) = path.unwrap(); // Note(unwrap) checked capacity
+            debug_assert!(node.is_leaf()); // Note(assert): Iterator depth unlimited
+            write!(buf, "{sep}\"{}\"", path.0)?; // Note: names need no escaping
+            sep = ',';
+        }
+        if sep == '[' {
+            write!(buf, "[")?;
+        }
+        write!(buf, "]")?;
+        Ok(start - buf.len())
+    }
+
+    fn get_values(
+        settings: &Settings,
+        paths: &[&str],
+        buf: &mut [u8],
+    ) -> Result<usize, miniconf::Error<serde_json_core::ser::Error>> {
+        fn push(
+            buf: &mut [u8],
+            len: &mut usize,
+            data: &[u8],
+        ) -> Result<(), miniconf::Error<serde_json_core::ser::Error>> {
+            buf.get_mut(*len..*len + data.len())
+                .ok_or(miniconf::Error::Finalization(
+                    serde_json_core::ser::Error::BufferFull,
+                ))?
+                .copy_from_slice(data);
+            *len += data.len();
+            Ok(())
+        }
+
+        let mut len = 0;
+        let mut sep = b'{';
+        for path in paths {
+            let start = len;
+            push(buf, &mut len, &[sep, b'"'])?;
+            push(buf, &mut len, path.as_bytes())?; // Note: as received, escaped
+            push(buf, &mut len, b"\":")?;
+            match json::get(settings, path, &mut buf[len..]) {
+                Err(miniconf::Error::Traversal(Traversal::Absent(_))) => {
+                    len = start;
+                }
+                ret => {
+                    len += ret?;
+                    sep = b',';
+                }
+            }
+        }
+        if sep == b'{' {
+            push(buf, &mut len, b"{")?;
+        }
+        push(buf, &mut len, b"}")?;
+        Ok(len)
+    }
+
+    fn get_batch<'b>(
+        settings: &Settings,
+        payload: &[u8],
+        schema: Option<&str>,
+        request: &Properties<'b>,
+        client: &mut minimq::mqtt_client::MqttClient<'a, Stack, Clock, Broker>,
+    ) {
+        let Ok((paths, _len)) = serde_json_core::from_slice::<Vec<&str, MAX_GET_LENGTH>>(payload)
+        else {
+            Self::respond(
+                "Invalid batch Get request",
+                ResponseCode::Error,
+                schema,
+                request,
+                client,
+            )
+            .ok();
+            return;
+        };
+        let props = response_properties(ResponseCode::Ok, schema);
+        let Ok(response) = DeferredPublication::new(|buf| Self::get_values(settings, &paths, buf))
+            .reply(request)
+            .properties(&props)
+            .qos(QoS::AtLeastOnce)
+            .finish()
+        else {
+            info!("No response topic for batch Get");
+            return;
+        };
+        match client.publish(response) {
+            Err(minimq::PubError::Serialization(err)) => {
+                Self::respond(err, ResponseCode::Error, schema, request, client).ok();
+            }
+            Err(err) => {
+                info!("Batch Get failure: {err:?}");
+            }
+            Ok(()) => {}
+        }
+    }
+
+    #[cfg(feature = "bulk")]
+    fn publish_bulk(
+        settings: &Settings,
+        topic: &str,
+        request: Option<&Properties<'_>>,
+        schema: Option<&str>,
+        client: &mut minimq::mqtt_client::MqttClient<'a, Stack, Clock, Broker>,
+    ) -> Result<(), Error<Stack::Error>> {
+        let mut json = alloc::vec::Vec::new();
+        let mut scratch = alloc::vec![0; 256];
+        loop {
+            json.clear();
+            json.push(b'{');
+            let ret = json::for_each_entry::<Settings, Y>(settings, &mut scratch, |path, value| {
+                if json.len() > 1 {
+                    json.push(b',');
+                }
+                json.push(b'"');
+                json.extend_from_slice(path.as_bytes());
+                json.extend_from_slice(b"\":");
+                json.extend_from_slice(value);
+            });
+            match ret {
+                Ok(_count) if json.len() < MAX_BULK_LENGTH => break,
+                Err(
+                    miniconf::Error::Inner(_, serde_json_core::ser::Error::BufferFull)
+                    | miniconf::Error::Finalization(serde_json_core::ser::Error::BufferFull),
+                ) if scratch.len() < MAX_BULK_LENGTH => {
+                    scratch.resize(scratch.len() * 2, 0);
+                }
+                _ => return Err(Error::Bulk("Bulk dump too large")),
+            }
+        }
+        json.push(b'}');
+        let payload = bulk::gzip(&json);
+
+        let mut props: Vec<minimq::Property<'_>, 3> = response_properties(ResponseCode::Ok, schema)
+            .into_iter()
+            .collect();
+        props
+            .push(minimq::Property::UserProperty(
+                minimq::types::Utf8String("ContentEncoding"),
+                minimq::types::Utf8String("gzip"),
+            ))
+            .unwrap(); // Note(unwrap): capacity
+        let mut publication = Publication::new(&payload[..]).topic(topic);
+        if let Some(request) = request {
+            publication = publication.reply(request);
+        }
+        client
+            .publish(
+                publication
+                    .properties(&props)
+                    .qos(QoS::AtLeastOnce)
+                    .finish()
+                    .unwrap(), // Note(unwrap): has topic
+            )
+            .map_err(|err| match err {
+                minimq::PubError::Error(err) => Error::Minimq(err),
+                minimq::PubError::Serialization(()) => unreachable!(),
+            })
+    }
+
+    #[cfg(feature = "bulk")]
+    fn bulk_json(
+        payload: &[u8],
+        request: &Properties<'_>,
+    ) -> Result<alloc::vec::Vec<u8>, &'static str> {
+        let gzip = request.into_iter().any(|prop| {
+            matches!(
+                prop,
+                Ok(minimq::Property::UserProperty(
+                    minimq::types::Utf8String("ContentEncoding"),
+                    minimq::types::Utf8String("gzip"),
+                ))
+            )
+        });
+        if !gzip {
+            return Err("Unsupported content encoding");
+        }
+        bulk::gunzip(payload)
+    }
+
+    /// The maximum accepted payload length for a value of a leaf
+    fn max_length(
+        settings: &Settings,
+        path: Path<&str, SEPARATOR>,
+        max_value_length: usize,
+    ) -> usize {
+        size::value_size::<size::Json, _, _>(settings, path).unwrap_or(max_value_length)
+    }
+
+    /// Publish a change notification and record the changed leaf for the batch handler
+    fn changed(
+        settings: &Settings,
+        path: Path<&str, SEPARATOR>,
+        change_topic: Option<&str>,
+        batch_handler: &mut Option<BatchHandler<'a, Settings>>,
+        batch: &mut Vec<Packed, MAX_BATCH_LENGTH>,
+        client: &mut minimq::mqtt_client::MqttClient<'a, Stack, Clock, Broker>,
+    ) {
+        if let Some(change_topic) = change_topic {
+            client
+                .publish(
+                    Publication::new(path.0.as_bytes())
+                        .topic(change_topic)
+                        .qos(QoS::AtLeastOnce)
+                        .finish()
+                        .unwrap(), // Note(unwrap): has topic
+                )
+                .inspect_err(|err| {
+                    info!("Change notification failure: {err:?}");
+                })
+                .ok();
+        }
+        if let Some(handler) = batch_handler {
+            if let Ok((key, _node)) = Settings::transcode::<Packed, _>(path) {
+                if !batch.contains(&key) {
+                    if batch.is_full() {
+                        // Only bulk sets fill the batch within a single poll
+                        handler(settings, batch);
+                        batch.clear();
+                    }
+                    batch.push(key).ok(); // Note(ok): not full
+                }
+            }
+        }
+    }
+
     fn iter_dump(&mut self, settings: &Settings) {
         while self.mqtt.client().can_publish(QoS::AtLeastOnce) {
             let Some(path) = self.pending.iter.next() else {
@@ -424,7 +1147,7 @@ where
                 .and_then(|_| topic.push_str(&path))
                 .unwrap();
 
-            let props = [ResponseCode::Ok.into()];
+            let props = response_properties(ResponseCode::Ok, self.schema.as_deref());
             let mut response =
                 DeferredPublication::new(|buf| json::get_by_key(settings, &path, buf))
                     .topic(&topic)
@@ -446,7 +1169,7 @@ where
                         minimq::SerError::InsufficientMemory,
                     )),
                 ))) => {
-                    let props = [ResponseCode::Error.into()];
+                    let props = response_properties(ResponseCode::Error, self.schema.as_deref());
                     let mut response = Publication::new("Serialized value too large".as_bytes())
                         .topic(&topic)
                         .properties(&props)
@@ -469,6 +1192,7 @@ where
     fn respond<'b, T: Display>(
         response: T,
         code: ResponseCode,
+        schema: Option<&str>,
         request: &Properties<'b>,
         client: &mut minimq::mqtt_client::MqttClient<'a, Stack, Clock, Broker>,
     ) -> Result<
@@ -482,7 +1206,7 @@ where
                     write!(buf, "{}", response).and_then(|_| Ok(start - buf.len()))
                 })
                 .reply(request)
-                .properties(&[code.into()])
+                .properties(&response_properties(code, schema))
                 .qos(QoS::AtLeastOnce)
                 .finish()
                 .map_err(minimq::Error::from)?,
@@ -492,15 +1216,83 @@ where
             })
     }
 
-    fn poll(&mut self, settings: &mut Settings) -> Result<State, Error<Stack::Error>> {
+    fn poll(&mut self, settings: &mut Settings) -> Result<Option<State>, Error<Stack::Error>> {
         let Self {
             mqtt,
             state,
             prefix,
             pending,
+            max_value_length,
+            change_topic,
+            batch_handler,
+            batch,
+            list_array,
+            batch_get,
+            schema,
+            #[cfg(feature = "bulk")]
+            bulk,
             ..
         } = self;
+        let schema = schema.as_deref();
         mqtt.poll(|client, topic, payload, properties| {
+            #[cfg(feature = "bulk")]
+            if *bulk && topic.strip_prefix(*prefix) == Some("/settings-bulk") {
+                if payload.is_empty() {
+                    // Only dump on an explicit request, not e.g. on a retained message clear
+                    if properties.into_iter().response_topic().is_some() {
+                        if let Err(err) =
+                            Self::publish_bulk(settings, topic, Some(properties), schema, client)
+                        {
+                            info!("Bulk dump failure: {err:?}");
+                        }
+                    }
+                    return State::Unchanged;
+                }
+                let json = match Self::bulk_json(payload, properties) {
+                    Ok(json) => json,
+                    Err(msg) => {
+                        Self::respond(msg, ResponseCode::Error, schema, properties, client).ok();
+                        return State::Unchanged;
+                    }
+                };
+                let entries = match bulk::entries(&json) {
+                    Ok(entries)
+                        if entries.iter().all(|(path, value)| {
+                            value.len()
+                                <= Self::max_length(
+                                    settings,
+                                    Path::<_, SEPARATOR>::from(*path),
+                                    *max_value_length,
+                                )
+                        }) =>
+                    {
+                        entries
+                    }
+                    ret => {
+                        let msg = ret.err().unwrap_or("Payload too large for target");
+                        Self::respond(msg, ResponseCode::Error, schema, properties, client).ok();
+                        return State::Unchanged;
+                    }
+                };
+                let mut state = State::Unchanged;
+                for (path, value) in entries {
+                    let path = Path::<_, SEPARATOR>::from(path);
+                    if let Err(err) = json::set_by_key(settings, path, value) {
+                        Self::respond(err, ResponseCode::Error, schema, properties, client).ok();
+                        return state;
+                    }
+                    Self::changed(settings, path, *change_topic, batch_handler, batch, client);
+                    state = State::Changed;
+                }
+                Self::respond("OK", ResponseCode::Ok, schema, properties, client).ok();
+                return state;
+            }
+
+            if *batch_get && topic.strip_prefix(*prefix) == Some("/get") {
+                Self::get_batch(settings, payload, schema, properties, client);
+                return State::Unchanged;
+            }
+
             let Some(path) = topic
                 .strip_prefix(*prefix)
                 .and_then(|p| p.strip_prefix("/settings"))
@@ -517,7 +1309,7 @@ where
                     DeferredPublication::new(|buf| json::get_by_key(settings, path, buf))
                         .topic(topic)
                         .reply(properties)
-                        .properties(&[ResponseCode::Ok.into()])
+                        .properties(&response_properties(ResponseCode::Ok, schema))
                         .qos(QoS::AtLeastOnce)
                         .finish()
                         .unwrap(), // Note(unwrap): has topic
@@ -532,6 +1324,28 @@ where
                                 .or_else(|| {
                                     Multipart::try_from(properties)
                                         .map(|m| {
+                                            if *list_array
+                                                && m.response_topic.is_some()
+                                                && client
+                                                    .publish(
+                                                        DeferredPublication::new(|buf| {
+                                                            Self::list_array(path, buf)
+                                                        })
+                                                        .reply(properties)
+                                                        .properties(&response_properties(
+                                                            ResponseCode::Ok,
+                                                            schema,
+                                                        ))
+                                                        .qos(QoS::AtLeastOnce)
+                                                        .finish()
+                                                        .unwrap(), // Note(unwrap): has response topic
+                                                    )
+                                                    .is_ok()
+                                            {
+                                                // Complete list in a single response
+                                                return;
+                                            }
+                                            // Fall back to multipart
                                             *pending = m.root(path).unwrap(); // Note(unwrap) checked that it's TooShort but valid leaf
                                             state.process_event(sm::Events::Multipart).unwrap();
                                             // Responses come through iter_list/iter_dump
@@ -539,11 +1353,19 @@ where
                                         .err()
                                 })
                                 .map(|msg| {
-                                    Self::respond(msg, ResponseCode::Error, properties, client).ok()
+                                    Self::respond(
+                                        msg,
+                                        ResponseCode::Error,
+                                        schema,
+                                        properties,
+                                        client,
+                                    )
+                                    .ok()
                                 });
                         }
                         minimq::PubError::Serialization(err) => {
-                            Self::respond(err, ResponseCode::Error, properties, client).ok();
+                            Self::respond(err, ResponseCode::Error, schema, properties, client)
+                                .ok();
                         }
                         minimq::PubError::Error(minimq::Error::NotReady) => {
                             warn!("Not ready during Get. Discarding.");
@@ -554,33 +1376,43 @@ where
                     }
                 }
                 State::Unchanged
+            } else if payload.len() > Self::max_length(settings, path, *max_value_length) {
+                Self::respond(
+                    "Payload too large for target",
+                    ResponseCode::Error,
+                    schema,
+                    properties,
+                    client,
+                )
+                .ok();
+                State::Unchanged
             } else {
                 // Set
                 match json::set_by_key(settings, path, payload) {
                     Err(err) => {
-                        Self::respond(err, ResponseCode::Error, properties, client).ok();
+                        Self::respond(err, ResponseCode::Error, schema, properties, client).ok();
                         State::Unchanged
                     }
-                    Ok(_depth) => {
-                        Self::respond("OK", ResponseCode::Ok, properties, client).ok();
+                    Ok((_len, _node)) => {
+                        Self::respond("OK", ResponseCode::Ok, schema, properties, client).ok();
+                        Self::changed(settings, path, *change_topic, batch_handler, batch, client);
                         State::Changed
                     }
                 }
             }
         })
-        .map(Option::unwrap_or_default)
         .or_else(|err| match err {
             minimq::Error::SessionReset => {
                 warn!("Session reset");
                 self.state.process_event(sm::Events::Reset).unwrap();
-                Ok(State::Unchanged)
+                Ok(None)
             }
             other => Err(other.into()),
         })
     }
 }
 
-#[derive(Default, Copy, Clone, PartialEq, PartialOrd)]
+#[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum State {
     #[default]
     Unchanged,
@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+
+use miniconf::{Leaf, Packed, Tree};
+use miniconf_mqtt::MqttClient;
+use minimq::{broker::IpBroker, config::BufferConfig, embedded_nal::IpAddr, ConfigBuilder};
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec};
+
+mod common;
+use common::Broker;
+
+#[derive(Tree, Default)]
+struct Settings {
+    gain: Leaf<u32>,
+    #[tree(secret)]
+    token: Leaf<u32>,
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    out.extend(compress_to_vec(data, 6));
+    out.extend(crc32(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+fn gunzip(data: &[u8]) -> Vec<u8> {
+    decompress_to_vec(&data[10..data.len() - 8]).unwrap()
+}
+
+#[test]
+fn bulk() {
+    let broker = Broker::default();
+    let mut buffer = [0u8; 4096];
+    let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+    let batches = RefCell::new(vec![]);
+    let mut handler = |_: &Settings, keys: &[Packed]| batches.borrow_mut().push(keys.len());
+    let mut client = MqttClient::<Settings, _, _, _, 1>::new(
+        broker.clone(),
+        "test/id",
+        broker.clone(),
+        // Leave room for the responses and change notifications of a bulk set in flight
+        ConfigBuilder::<IpBroker>::new(localhost.into(), &mut buffer)
+            .rx_buffer(BufferConfig::Exactly(512))
+            .tx_buffer(BufferConfig::Exactly(512)),
+    )
+    .unwrap();
+    client.set_bulk(true);
+    client.set_change_topic("test/id/changed");
+    client.set_batch_handler(&mut handler);
+    client.set_max_value_length(4);
+    let mut settings = Settings {
+        gain: Leaf(1),
+        token: Leaf(42),
+    };
+    let mut update = |settings: &mut Settings| {
+        let mut changed = false;
+        for _ in 0..10 {
+            changed |= client.update(settings).unwrap();
+        }
+        changed
+    };
+
+    // Connect, subscribe, and dump
+    update(&mut settings);
+    assert_eq!(
+        broker.state().subscribed,
+        ["test/id/settings/#", "test/id/settings-bulk"]
+    );
+    broker.advance(2500);
+    update(&mut settings);
+
+    // Empty messages without a response topic (e.g. retained clears) are ignored
+    let published = broker.state().published.len();
+    broker.state().publish("test/id/settings-bulk", b"", None);
+    update(&mut settings);
+    assert_eq!(broker.state().published.len(), published);
+
+    // A requested dump redacts secrets
+    broker
+        .state()
+        .publish("test/id/settings-bulk", b"", Some("test/response"));
+    update(&mut settings);
+    let dump = gunzip(broker.state().payloads("test/response")[0]);
+    assert_eq!(dump, br#"{"/gain":1,"/token":"***"}"#);
+
+    // A bulk set notifies and batches like individual sets
+    let bulk_set = |payload: &[u8]| {
+        broker.state().publish_with(
+            "test/id/settings-bulk",
+            &gzip(payload),
+            Some("test/response"),
+            &[("ContentEncoding", "gzip")],
+        );
+    };
+    bulk_set(br#"{"/gain": 5, "/token": 7}"#);
+    assert!(update(&mut settings));
+    assert_eq!((*settings.gain, *settings.token), (5, 7));
+    assert_eq!(
+        broker.state().payloads("test/id/changed"),
+        [b"/gain".as_slice(), b"/token"]
+    );
+    assert_eq!(*batches.borrow(), [2]);
+    assert_eq!(broker.state().payloads("test/response")[1], b"OK");
+
+    // Oversized values are rejected before any leaf is set
+    bulk_set(br#"{"/gain": 6, "/token": 12345678901}"#);
+    assert!(!update(&mut settings));
+    assert_eq!((*settings.gain, *settings.token), (5, 7));
+    assert_eq!(
+        broker.state().payloads("test/response")[2],
+        b"Payload too large for target"
+    );
+}
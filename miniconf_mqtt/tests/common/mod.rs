@@ -0,0 +1,240 @@
+//! An in-memory MQTT v5 broker and clock to drive an `MqttClient` without a network.
+
+#![allow(dead_code)]
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use minimq::{
+    embedded_nal::{nb, SocketAddr, TcpClientStack, TcpError, TcpErrorKind},
+    embedded_time::{clock, fraction::Fraction, Clock, Instant},
+};
+
+#[derive(Debug)]
+pub struct MockError;
+
+impl TcpError for MockError {
+    fn kind(&self) -> TcpErrorKind {
+        TcpErrorKind::Other
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct State {
+    /// Client to broker stream
+    rx: Vec<u8>,
+    /// Broker to client stream
+    tx: VecDeque<u8>,
+    /// Current time in milliseconds
+    pub time: u64,
+    /// Topics and payloads published by the client
+    pub published: Vec<(String, Vec<u8>)>,
+    /// Topic filters subscribed by the client
+    pub subscribed: Vec<String>,
+    /// Number of SUBSCRIBE packets the client tried to send
+    pub subscribe_attempts: usize,
+    /// Number of SUBSCRIBE packet sends to fail with a network error
+    pub fail_subscribe: usize,
+    /// Number of PUBLISH packet sends to block
+    pub block_publish: usize,
+}
+
+fn varint(mut value: usize, buf: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            break;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn read_varint(buf: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0;
+    for (i, byte) in buf.iter().enumerate().take(4) {
+        value |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn read_str(buf: &[u8]) -> (String, &[u8]) {
+    let len = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+    let s = core::str::from_utf8(&buf[2..2 + len]).unwrap().to_string();
+    (s, &buf[2 + len..])
+}
+
+fn push_str(s: &str, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+impl State {
+    fn reply(&mut self, header: u8, body: &[u8]) {
+        let mut packet = vec![header];
+        varint(body.len(), &mut packet);
+        packet.extend_from_slice(body);
+        self.tx.extend(packet);
+    }
+
+    fn handle(&mut self, header: u8, body: &[u8]) {
+        match header >> 4 {
+            // CONNECT: CONNACK without session, success, no properties
+            1 => self.reply(0x20, &[0, 0, 0]),
+            // PUBLISH
+            3 => {
+                let qos = (header >> 1) & 3;
+                let (topic, mut rest) = read_str(body);
+                if qos > 0 {
+                    self.reply(0x40, &rest[..2]);
+                    rest = &rest[2..];
+                }
+                let (len, n) = read_varint(rest).unwrap();
+                self.published.push((topic, rest[n + len..].to_vec()));
+            }
+            // SUBSCRIBE: grant the requested QoS
+            8 => {
+                let (len, n) = read_varint(&body[2..]).unwrap();
+                let mut ack = body[..2].to_vec();
+                ack.push(0);
+                let mut rest = &body[2 + n + len..];
+                while !rest.is_empty() {
+                    let (filter, tail) = read_str(rest);
+                    self.subscribed.push(filter);
+                    ack.push(tail[0] & 3);
+                    rest = &tail[1..];
+                }
+                self.reply(0x90, &ack);
+            }
+            // PINGREQ
+            12 => self.reply(0xd0, &[]),
+            _ => {}
+        }
+    }
+
+    /// Publish a message from the broker to the client.
+    pub fn publish(&mut self, topic: &str, payload: &[u8], response_topic: Option<&str>) {
+        self.publish_with(topic, payload, response_topic, &[]);
+    }
+
+    /// Publish a message with user properties from the broker to the client.
+    pub fn publish_with(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        response_topic: Option<&str>,
+        user_properties: &[(&str, &str)],
+    ) {
+        let mut properties = vec![];
+        if let Some(response_topic) = response_topic {
+            properties.push(0x08);
+            push_str(response_topic, &mut properties);
+        }
+        for (key, value) in user_properties {
+            properties.push(0x26);
+            push_str(key, &mut properties);
+            push_str(value, &mut properties);
+        }
+        let mut body = vec![];
+        push_str(topic, &mut body);
+        varint(properties.len(), &mut body);
+        body.extend_from_slice(&properties);
+        body.extend_from_slice(payload);
+        self.reply(0x30, &body);
+    }
+
+    /// The payloads published by the client to a topic.
+    pub fn payloads(&self, topic: &str) -> Vec<&[u8]> {
+        self.published
+            .iter()
+            .filter(|(t, _)| t == topic)
+            .map(|(_, p)| &p[..])
+            .collect()
+    }
+}
+
+/// A handle to the broker [`State`], also acting as network stack and clock.
+#[derive(Clone, Debug, Default)]
+pub struct Broker(pub Rc<RefCell<State>>);
+
+impl Broker {
+    pub fn state(&self) -> std::cell::RefMut<'_, State> {
+        self.0.borrow_mut()
+    }
+
+    /// Advance the clock.
+    pub fn advance(&self, millis: u64) {
+        self.state().time += millis;
+    }
+}
+
+impl TcpClientStack for Broker {
+    type TcpSocket = ();
+    type Error = MockError;
+
+    fn socket(&mut self) -> Result<(), MockError> {
+        Ok(())
+    }
+
+    fn connect(&mut self, _socket: &mut (), _remote: SocketAddr) -> nb::Result<(), MockError> {
+        Ok(())
+    }
+
+    fn send(&mut self, _socket: &mut (), buffer: &[u8]) -> nb::Result<usize, MockError> {
+        let mut state = self.state();
+        match buffer.first().map(|header| header >> 4) {
+            Some(8) => {
+                state.subscribe_attempts += 1;
+                if state.fail_subscribe > 0 {
+                    state.fail_subscribe -= 1;
+                    return Err(nb::Error::Other(MockError));
+                }
+            }
+            Some(3) if state.block_publish > 0 => {
+                state.block_publish -= 1;
+                return Err(nb::Error::WouldBlock);
+            }
+            _ => {}
+        }
+        state.rx.extend_from_slice(buffer);
+        while state.rx.len() > 1 {
+            let Some((len, n)) = read_varint(&state.rx[1..]) else {
+                break;
+            };
+            if state.rx.len() < 1 + n + len {
+                break;
+            }
+            let packet: Vec<u8> = state.rx.drain(..1 + n + len).collect();
+            state.handle(packet[0], &packet[1 + n..]);
+        }
+        Ok(buffer.len())
+    }
+
+    fn receive(&mut self, _socket: &mut (), buffer: &mut [u8]) -> nb::Result<usize, MockError> {
+        let mut state = self.state();
+        if state.tx.is_empty() {
+            return Err(nb::Error::WouldBlock);
+        }
+        let len = buffer.len().min(state.tx.len());
+        for (b, t) in buffer.iter_mut().zip(state.tx.drain(..len)) {
+            *b = t;
+        }
+        Ok(len)
+    }
+
+    fn close(&mut self, _socket: ()) -> Result<(), MockError> {
+        Ok(())
+    }
+}
+
+impl Clock for Broker {
+    type T = u64;
+    const SCALING_FACTOR: Fraction = Fraction::new(1, 1000);
+
+    fn try_now(&self) -> Result<Instant<Self>, clock::Error> {
+        Ok(Instant::new(self.0.borrow().time))
+    }
+}
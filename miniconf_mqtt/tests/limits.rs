@@ -0,0 +1,68 @@
+use miniconf::{Leaf, Tree};
+use miniconf_mqtt::MqttClient;
+use minimq::{broker::IpBroker, embedded_nal::IpAddr, ConfigBuilder};
+
+mod common;
+use common::Broker;
+
+#[derive(Tree, Default)]
+struct Settings {
+    small: Leaf<u8>,
+    large: Leaf<u64>,
+    name: Leaf<heapless::String<32>>,
+}
+
+#[test]
+fn value_length() {
+    let broker = Broker::default();
+    let mut buffer = [0u8; 1024];
+    let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+    let mut client = MqttClient::<Settings, _, _, _, 1>::new(
+        broker.clone(),
+        "test/id",
+        broker.clone(),
+        ConfigBuilder::<IpBroker>::new(localhost.into(), &mut buffer),
+    )
+    .unwrap();
+    client.set_max_value_length(8);
+    let mut settings = Settings::default();
+
+    // Connect, subscribe, and dump
+    for _ in 0..10 {
+        client.update(&mut settings).unwrap();
+    }
+    broker.advance(2500);
+    for _ in 0..10 {
+        client.update(&mut settings).unwrap();
+    }
+
+    let mut set = |topic: &str, value: &[u8]| {
+        broker.state().publish(topic, value, Some("test/response"));
+        for _ in 0..10 {
+            client.update(&mut settings).unwrap();
+        }
+        broker
+            .state()
+            .payloads("test/response")
+            .last()
+            .unwrap()
+            .to_vec()
+    };
+    // Bounded leaves are limited by their bound
+    assert_eq!(
+        set("test/id/settings/small", b"0001"),
+        b"Payload too large for target"
+    );
+    assert_eq!(set("test/id/settings/small", b"255"), b"OK");
+    assert_eq!(set("test/id/settings/large", b"1234567890123"), b"OK");
+    // Unbounded leaves fall back to the maximum value length
+    assert_eq!(
+        set("test/id/settings/name", br#""too long""#),
+        b"Payload too large for target"
+    );
+    assert_eq!(set("test/id/settings/name", br#""short""#), b"OK");
+    assert_eq!(
+        (*settings.small, *settings.large, settings.name.as_str()),
+        (255, 1234567890123, "short")
+    );
+}
@@ -0,0 +1,74 @@
+use miniconf::{Leaf, Tree};
+use miniconf_mqtt::MqttClient;
+use minimq::{broker::IpBroker, embedded_nal::IpAddr, ConfigBuilder};
+
+mod common;
+use common::Broker;
+
+#[derive(Tree, Default)]
+struct Network {
+    port: Leaf<u16>,
+}
+
+#[derive(Tree, Default)]
+struct Dsp {
+    gain: [Leaf<f32>; 2],
+}
+
+#[derive(Tree, Default)]
+struct Roots {
+    net: Network,
+    dsp: Dsp,
+}
+
+#[test]
+fn two_roots() {
+    let broker = Broker::default();
+    let mut buffer = [0u8; 1024];
+    let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+    let mut client = MqttClient::<Roots, _, _, _, 3>::new(
+        broker.clone(),
+        "test/id",
+        broker.clone(),
+        ConfigBuilder::<IpBroker>::new(localhost.into(), &mut buffer),
+    )
+    .unwrap();
+    let mut roots = Roots::default();
+
+    // Connect, subscribe, and dump after the timeout
+    for _ in 0..10 {
+        client.update(&mut roots).unwrap();
+    }
+    assert_eq!(broker.state().subscribed, ["test/id/settings/#"]);
+    broker.advance(2500);
+    for _ in 0..10 {
+        client.update(&mut roots).unwrap();
+    }
+    for topic in [
+        "test/id/settings/net/port",
+        "test/id/settings/dsp/gain/0",
+        "test/id/settings/dsp/gain/1",
+    ] {
+        assert_eq!(broker.state().payloads(topic).len(), 1, "{topic}");
+    }
+
+    // Set one leaf in each root through the same client
+    for (topic, value) in [
+        ("test/id/settings/net/port", "1883"),
+        ("test/id/settings/dsp/gain/1", "0.5"),
+    ] {
+        broker
+            .state()
+            .publish(topic, value.as_bytes(), Some("test/response"));
+        let mut changed = false;
+        for _ in 0..10 {
+            changed |= client.update(&mut roots).unwrap();
+        }
+        assert!(changed);
+    }
+    assert_eq!((*roots.net.port, *roots.dsp.gain[1]), (1883, 0.5));
+    assert_eq!(
+        broker.state().payloads("test/response"),
+        [b"OK".as_slice(), b"OK"]
+    );
+}
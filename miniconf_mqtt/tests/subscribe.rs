@@ -0,0 +1,70 @@
+use miniconf::{Leaf, Tree};
+use miniconf_mqtt::MqttClient;
+use minimq::{broker::IpBroker, embedded_nal::IpAddr, ConfigBuilder};
+
+mod common;
+use common::Broker;
+
+#[derive(Tree, Default)]
+struct Settings {
+    foo: Leaf<u32>,
+}
+
+fn client<'a>(
+    broker: &Broker,
+    buffer: &'a mut [u8],
+) -> MqttClient<'a, Settings, Broker, Broker, IpBroker, 1> {
+    let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+    MqttClient::new(
+        broker.clone(),
+        "test/id",
+        broker.clone(),
+        ConfigBuilder::new(localhost.into(), buffer),
+    )
+    .unwrap()
+}
+
+#[test]
+fn retry_with_backoff() {
+    let broker = Broker::default();
+    broker.state().fail_subscribe = 1;
+    let mut buffer = [0u8; 1024];
+    let mut client = client(&broker, &mut buffer);
+    let mut settings = Settings::default();
+
+    for _ in 0..10 {
+        client.update(&mut settings).unwrap();
+    }
+    assert_eq!(broker.state().subscribe_attempts, 1);
+    assert!(broker.state().subscribed.is_empty());
+
+    // Still backing off
+    broker.advance(900);
+    for _ in 0..10 {
+        client.update(&mut settings).unwrap();
+    }
+    assert_eq!(broker.state().subscribe_attempts, 1);
+
+    broker.advance(200);
+    for _ in 0..10 {
+        client.update(&mut settings).unwrap();
+    }
+    assert_eq!(broker.state().subscribe_attempts, 2);
+    assert_eq!(broker.state().subscribed, ["test/id/settings/#"]);
+}
+
+#[test]
+fn not_ready_without_backoff() {
+    let broker = Broker::default();
+    // Block the alive message and its first flush so it is still pending when subscribing
+    broker.state().block_publish = 2;
+    let mut buffer = [0u8; 1024];
+    let mut client = client(&broker, &mut buffer);
+    let mut settings = Settings::default();
+
+    for _ in 0..10 {
+        client.update(&mut settings).unwrap();
+    }
+    assert_eq!(broker.state().payloads("test/id/alive"), [b"1"]);
+    assert_eq!(broker.state().subscribed, ["test/id/settings/#"]);
+}
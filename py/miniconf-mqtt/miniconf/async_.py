@@ -174,7 +174,11 @@ class Miniconf:
         Args:
             path: Path to the root node to list.
         """
-        return await self._do(path, response=2, **kwargs)
+        ret = await self._do(path, response=2, **kwargs)
+        if len(ret) == 1 and ret[0].startswith("["):
+            # Single JSON array response
+            return json.loads(ret[0])
+        return ret
 
     async def dump(self, path: str = "", **kwargs):
         """Dump all the paths at or below a given root into the settings namespace.
@@ -13,14 +13,18 @@ PREFIX=test
 ALIVE=$(timeout --foreground 1 mosquitto_sub -t "$PREFIX/+/alive" -h localhost -F '%p' || true)
 test "$ALIVE" = "" -o "$ALIVE" = "0"
 
+# retained settings are not adopted (the DUT would exit)
+mosquitto_pub -t "$PREFIX/id/settings/exit" -h localhost -r -m 'true'
+
 # build and start DUT
-cargo build -p miniconf_mqtt --example mqtt
-cargo run -p miniconf_mqtt --example mqtt &
+cargo build -p miniconf_mqtt --features bulk --example mqtt
+LOG=$(mktemp)
+cargo run -p miniconf_mqtt --features bulk --example mqtt > $LOG &
 DUT_PID=$!
 
 # check initial dump (9 settings)
 # 3 > DUMP_TIMEOUT_SECONDS
-DUMP=$(timeout --foreground 3 mosquitto_sub -t "$PREFIX/+/settings/#" -h localhost -F '%t' | wc -l)
+DUMP=$(timeout --foreground 3 mosquitto_sub -t "$PREFIX/+/settings/#" -h localhost -F '%t' -R | wc -l)
 test $DUMP = 9
 
 # test alive-ness
@@ -35,14 +39,115 @@ MC="python -m miniconf -b localhost -d $PREFIX/+"
 $MC '/afe/0' '/afe/0="G10"' '/afe/0=' '/afe?' '?' '/afe!'
 sleep 1  # DUMP is asynchronous
 
-# validation ok
+# LIST as a single JSON array
+LIST=$(mosquitto_rr -t "$PREFIX/id/settings/afe" -e "$PREFIX/response" -n -h localhost -W 2)
+test "$LIST" = '["/afe/0","/afe/1"]'
+
+# GET with schema fingerprint property
+mosquitto_rr -t "$PREFIX/id/settings/four" -e "$PREFIX/response" -n -h localhost -W 2 -F '%P' | grep -q "schema:[0-9a-f]\{8\}"
+
+# validation ok, with change notification
+CHANGED=$(mktemp)
+timeout --foreground 3 mosquitto_sub -t "$PREFIX/id/changed" -h localhost -C 1 -F '%p' > $CHANGED &
+SUB_PID=$!
+sleep 0.5
 $MC '/four=5'
+wait $SUB_PID
+test "$(cat $CHANGED)" = "/four"
+rm $CHANGED
+# rapid sets are handled as one batch
+python - <<EOF
+import threading
+import paho.mqtt
+from paho.mqtt.client import Client, MQTTv5
+from paho.mqtt.packettypes import PacketTypes
+from paho.mqtt.properties import Properties
+client = Client(paho.mqtt.enums.CallbackAPIVersion.VERSION2, protocol=MQTTv5)
+responses = []
+done = threading.Event()
+def on_message(client, userdata, msg):
+    responses.append(msg)
+    if len(responses) == 3:
+        done.set()
+client.on_message = on_message
+client.connect("localhost")
+client.loop_start()
+client.subscribe("$PREFIX/batch-response")
+props = Properties(PacketTypes.PUBLISH)
+props.ResponseTopic = "$PREFIX/batch-response"
+for path, value in [("values/0", "1"), ("values/1", "2"), ("inner/a", "3")]:
+    client.publish(f"$PREFIX/id/settings/{path}", value, properties=props).wait_for_publish()
+assert done.wait(2), responses
+client.disconnect()
+client.loop_stop()
+EOF
+# the batch handler is called at the end of the poll that sent the responses
+timeout --foreground 2 sh -c "until grep -qx 'Batch: /values/0 /values/1 /inner/a' $LOG; do sleep 0.1; done"
+# batched GET in a single response
+GET=$(mosquitto_rr -t "$PREFIX/id/get" -e "$PREFIX/response" -m '["/inner/a","/exit","/opt"]' -h localhost -W 2)
+test "$GET" = '{"/inner/a":3,"/exit":false}'
+# gzip-compressed bulk dump and set
+python - <<EOF
+import gzip, json, threading
+import paho.mqtt
+from paho.mqtt.client import Client, MQTTv5
+from paho.mqtt.packettypes import PacketTypes
+from paho.mqtt.properties import Properties
+client = Client(paho.mqtt.enums.CallbackAPIVersion.VERSION2, protocol=MQTTv5)
+responses = []
+received = threading.Event()
+subscribed = threading.Event()
+def on_message(client, userdata, msg):
+    responses.append(msg)
+    received.set()
+def on_subscribe(client, userdata, mid, reason_codes, properties):
+    subscribed.set()
+client.on_message = on_message
+client.on_subscribe = on_subscribe
+client.connect("localhost")
+client.loop_start()
+client.subscribe("$PREFIX/bulk-response")
+assert subscribed.wait(2)
+props = Properties(PacketTypes.PUBLISH)
+props.ResponseTopic = "$PREFIX/bulk-response"
+client.publish("$PREFIX/id/settings-bulk", b"", properties=props).wait_for_publish()
+assert received.wait(2)
+dump = responses.pop()
+assert ("ContentEncoding", "gzip") in dump.properties.UserProperty
+values = json.loads(gzip.decompress(dump.payload))
+assert values["/inner/a"] == 3 and values["/afe/1"] == "G1", values
+received.clear()
+props.UserProperty = ("ContentEncoding", "gzip")
+payload = gzip.compress(json.dumps({"/afe/1": "G100", "/values/0": 5}).encode())
+client.publish("$PREFIX/id/settings-bulk", payload, properties=props).wait_for_publish()
+assert received.wait(2)
+assert responses.pop().payload == b"OK"
+client.disconnect()
+client.loop_stop()
+EOF
+GET=$(mosquitto_rr -t "$PREFIX/id/get" -e "$PREFIX/response" -m '["/afe/1","/values/0"]' -h localhost -W 2)
+test "$GET" = '{"/afe/1":"G100","/values/0":5.0}'
 # validation error
 $MC '/four=2' && exit 1
 
+# payload too large
+$MC '/stream="'$(printf '%070d' 0)'"' && exit 1
+
 # request exit
 $MC '/exit=true'
 wait $DUT_PID
+rm $LOG
+mosquitto_pub -t "$PREFIX/id/settings/exit" -h localhost -r -n
 
+# default will clears alive
 ALIVE=$(timeout --foreground 1 mosquitto_sub -t "$PREFIX/+/alive" -h localhost -F '%p' || true)
 test "$ALIVE" = ""
+
+# custom will
+MQTT_WILL=0 cargo run -p miniconf_mqtt --features bulk --example mqtt &
+DUT_PID=$!
+timeout --foreground 10 mosquitto_sub -t "$PREFIX/id/alive" -h localhost -C 1 -F '%p' | grep -qx '"hello"'
+$MC '/exit=true'
+wait $DUT_PID
+ALIVE=$(timeout --foreground 1 mosquitto_sub -t "$PREFIX/+/alive" -h localhost -F '%p' || true)
+test "$ALIVE" = "0"